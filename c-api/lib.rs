@@ -470,6 +470,8 @@ pub extern "C" fn resvg_options_set_keep_named_groups(opt: *mut resvg_options, k
 ///
 /// Prints a warning into the log when the data is not a valid TrueType font.
 ///
+/// WOFF and WOFF2 fonts are supported as well.
+///
 /// Has no effect when the `text` feature is not enabled.
 #[no_mangle]
 #[allow(unused_variables)]
@@ -487,7 +489,7 @@ pub extern "C" fn resvg_options_load_font_data(
             &mut *opt
         };
 
-        opt.0.fontdb.load_font_data(data.to_vec())
+        usvg::WebFontsExt::load_web_font_data(&mut opt.0.fontdb, data.to_vec())
     }
 }
 
@@ -495,6 +497,8 @@ pub extern "C" fn resvg_options_load_font_data(
 ///
 /// Prints a warning into the log when the data is not a valid TrueType font.
 ///
+/// WOFF and WOFF2 fonts are supported as well.
+///
 /// Has no effect when the `text` feature is not enabled.
 ///
 /// @return #resvg_error with RESVG_OK, RESVG_ERROR_NOT_AN_UTF8_STR or RESVG_ERROR_FILE_OPEN_FAILED
@@ -516,7 +520,7 @@ pub extern "C" fn resvg_options_load_font_file(
             &mut *opt
         };
 
-        if opt.0.fontdb.load_font_file(file_path).is_ok() {
+        if usvg::WebFontsExt::load_web_font_file(&mut opt.0.fontdb, file_path).is_ok() {
             resvg_error::OK as i32
         } else {
             resvg_error::FILE_OPEN_FAILED as i32
@@ -893,6 +897,8 @@ fn convert_error(e: usvg::Error) -> resvg_error {
         usvg::Error::ElementsLimitReached => resvg_error::ELEMENTS_LIMIT_REACHED,
         usvg::Error::InvalidSize => resvg_error::INVALID_SIZE,
         usvg::Error::ParsingFailed(_) => resvg_error::PARSING_FAILED,
+        usvg::Error::InvalidBinary => resvg_error::PARSING_FAILED,
+        usvg::Error::BinaryVersionMismatch => resvg_error::PARSING_FAILED,
     }
 }
 
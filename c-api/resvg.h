@@ -363,6 +363,8 @@ void resvg_options_set_keep_named_groups(resvg_options *opt, bool keep);
  *
  * Prints a warning into the log when the data is not a valid TrueType font.
  *
+ * WOFF and WOFF2 fonts are supported as well.
+ *
  * Has no effect when the `text` feature is not enabled.
  */
 void resvg_options_load_font_data(resvg_options *opt, const char *data, uintptr_t len);
@@ -372,6 +374,8 @@ void resvg_options_load_font_data(resvg_options *opt, const char *data, uintptr_
  *
  * Prints a warning into the log when the data is not a valid TrueType font.
  *
+ * WOFF and WOFF2 fonts are supported as well.
+ *
  * Has no effect when the `text` feature is not enabled.
  *
  * @return #resvg_error with RESVG_OK, RESVG_ERROR_NOT_AN_UTF8_STR or RESVG_ERROR_FILE_OPEN_FAILED
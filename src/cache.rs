@@ -0,0 +1,364 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use usvg::FuzzyEq;
+
+/// A cache of rasterized group layers.
+///
+/// Can be passed to [`render_with_cache`](crate::render_with_cache) to reuse
+/// already rendered groups between calls. Each group layer contains the group content
+/// with filters, clipping and masking already applied, so repeated renders
+/// of an unchanged tree are mostly reduced to layers blending.
+///
+/// A layer is reused only when the group's canvas transform and the target pixmap size
+/// are the same as during the previous render. At most one layer per group is stored.
+///
+/// Since each layer can be as big as the target pixmap, the total size of layers is limited
+/// by [`RenderCache::DEFAULT_MAX_BYTES`] or by the value passed to [`RenderCache::with_max_bytes`].
+/// When the limit is reached, the least recently used layers are removed.
+///
+/// Pattern tiles are cached as well, per pattern, transform and object bounding box
+/// (when the pattern depends on it).
+///
+/// The cache doesn't track tree modifications by itself.
+/// After a node was changed, it must be passed to [`RenderCache::mark_dirty`].
+/// This includes nodes inside patterns, clip paths, masks and `feImage` subtrees.
+pub struct RenderCache {
+    keep_layers: bool,
+    max_bytes: usize,
+    clock: Cell<u64>,
+    layers: HashMap<usize, Layer>,
+    patterns: HashMap<usize, Vec<PatternTile>>,
+}
+
+pub(crate) struct Layer {
+    // Keeps the node alive, so its address cannot be reused by another node.
+    #[allow(dead_code)]
+    node: usvg::Node,
+    transform: tiny_skia::Transform,
+    size: (u32, u32),
+    pub x: i32,
+    pub y: i32,
+    pub pixmap: tiny_skia::Pixmap,
+    pub bbox: Option<usvg::PathBbox>,
+    /// Roots of clip paths, masks, patterns and `feImage` subtrees used by the layer.
+    refs: Vec<usize>,
+    last_used: Cell<u64>,
+}
+
+pub(crate) struct PatternTile {
+    #[allow(dead_code)]
+    pattern: Rc<usvg::Pattern>,
+    transform: tiny_skia::Transform,
+    bbox: usvg::PathBbox,
+    /// Roots of clip paths, masks, patterns and `feImage` subtrees used by the pattern content.
+    refs: Vec<usize>,
+    pub pixmap: tiny_skia::Pixmap,
+    pub pixmap_ts: usvg::Transform,
+    /// A device region covered by the pixmap.
+    ///
+    /// Set only when the pattern was rendered directly in device space
+    /// and not as a repeatable tile.
+    pub region: Option<tiny_skia::IntRect>,
+    /// Indicates that the pixmap pixels are aligned with the device pixels,
+    /// so the pixmap must not be resampled.
+    pub is_aligned: bool,
+}
+
+impl RenderCache {
+    /// The default limit of the total size of cached layers. 256 MiB.
+    pub const DEFAULT_MAX_BYTES: usize = 256 * 1024 * 1024;
+
+    /// Creates a new, empty cache.
+    pub fn new() -> Self {
+        Self::with_max_bytes(Self::DEFAULT_MAX_BYTES)
+    }
+
+    /// Creates a new, empty cache with a custom limit of the total size of cached layers.
+    pub fn with_max_bytes(max_bytes: usize) -> Self {
+        RenderCache {
+            keep_layers: true,
+            max_bytes,
+            clock: Cell::new(0),
+            layers: HashMap::new(),
+            patterns: HashMap::new(),
+        }
+    }
+
+    /// Creates a cache that doesn't store group layers.
+    ///
+    /// Used by a regular rendering, where only pattern tiles can be reused.
+    pub(crate) fn without_layers() -> Self {
+        RenderCache {
+            keep_layers: false,
+            ..Self::with_max_bytes(0)
+        }
+    }
+
+    /// Invalidates cached layers affected by the `node`.
+    ///
+    /// Removes layers of the `node` itself and all its ancestors,
+    /// since their content depends on the `node`.
+    ///
+    /// When the `node` is a part of a clip path, mask, pattern or `feImage` subtree,
+    /// layers and pattern tiles that reference this subtree are removed as well.
+    ///
+    /// Must be called after a node was modified or inserted, and before it was detached.
+    pub fn mark_dirty(&mut self, node: &usvg::Node) {
+        for n in node.ancestors() {
+            let key = node_key(&n);
+            self.layers.remove(&key);
+            // Patterns are indexed by their root node.
+            self.patterns.remove(&key);
+        }
+
+        let root = match node.ancestors().last() {
+            Some(v) => node_key(&v),
+            None => return,
+        };
+
+        // Ancestors' references include references of their descendants,
+        // so there is no need to propagate the invalidation further.
+        self.layers.retain(|_, layer| !layer.refs.contains(&root));
+        for tiles in self.patterns.values_mut() {
+            tiles.retain(|t| !t.refs.contains(&root));
+        }
+        self.patterns.retain(|_, tiles| !tiles.is_empty());
+    }
+
+    /// Removes all cached layers and pattern tiles.
+    pub fn clear(&mut self) {
+        self.layers.clear();
+        self.patterns.clear();
+    }
+
+    /// Returns the total size of cached layers in bytes.
+    pub fn layers_bytes(&self) -> usize {
+        self.layers.values().map(|l| l.pixmap.data().len()).sum()
+    }
+
+    /// Returns the number of cached layers and pattern tiles.
+    pub fn len(&self) -> usize {
+        self.layers.len() + self.patterns.values().map(|v| v.len()).sum::<usize>()
+    }
+
+    /// Checks that the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn layer(
+        &self,
+        node: &usvg::Node,
+        transform: tiny_skia::Transform,
+        size: (u32, u32),
+    ) -> Option<&Layer> {
+        if !self.keep_layers {
+            return None;
+        }
+
+        let layer = self.layers.get(&node_key(node))?;
+        if layer.transform == transform && layer.size == size {
+            layer.last_used.set(self.tick());
+            Some(layer)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn insert_layer(
+        &mut self,
+        node: &usvg::Node,
+        transform: tiny_skia::Transform,
+        size: (u32, u32),
+        x: i32,
+        y: i32,
+        pixmap: tiny_skia::Pixmap,
+        bbox: Option<usvg::PathBbox>,
+    ) {
+        if !self.keep_layers {
+            return;
+        }
+
+        // A layer that depends on the content outside of the current node
+        // cannot be invalidated via `mark_dirty`, therefore it should not be cached.
+        #[cfg(feature = "filter")]
+        {
+            use usvg::NodeExt;
+
+            for n in node.descendants() {
+                if let usvg::NodeKind::Group(ref g) = *n.borrow() {
+                    if g.filters
+                        .iter()
+                        .any(|f| n.filter_background_start_node(f).is_some())
+                    {
+                        return;
+                    }
+                }
+            }
+        }
+
+        let key = node_key(node);
+        self.layers.remove(&key);
+
+        let bytes = pixmap.data().len();
+        if bytes > self.max_bytes {
+            return;
+        }
+
+        // Remove the least recently used layers until the new one fits.
+        let mut total = self.layers_bytes();
+        while total + bytes > self.max_bytes {
+            let lru = match self.layers.iter().min_by_key(|(_, l)| l.last_used.get()) {
+                Some((key, _)) => *key,
+                None => break,
+            };
+
+            if let Some(layer) = self.layers.remove(&lru) {
+                total -= layer.pixmap.data().len();
+            }
+        }
+
+        let mut refs = Vec::new();
+        collect_refs(node, &mut refs);
+
+        let layer = Layer {
+            node: node.clone(),
+            transform,
+            size,
+            x,
+            y,
+            pixmap,
+            bbox,
+            refs,
+            last_used: Cell::new(self.tick()),
+        };
+
+        self.layers.insert(key, layer);
+    }
+
+    pub(crate) fn pattern_tile(
+        &self,
+        pattern: &usvg::Pattern,
+        transform: tiny_skia::Transform,
+        bbox: usvg::PathBbox,
+    ) -> Option<&PatternTile> {
+        self.patterns
+            .get(&node_key(&pattern.root))?
+            .iter()
+            .find(|t| t.transform == transform && t.bbox.fuzzy_eq(&bbox))
+    }
+
+    pub(crate) fn insert_pattern_tile(
+        &mut self,
+        pattern: &Rc<usvg::Pattern>,
+        transform: tiny_skia::Transform,
+        bbox: usvg::PathBbox,
+        pixmap: tiny_skia::Pixmap,
+        pixmap_ts: usvg::Transform,
+        region: Option<tiny_skia::IntRect>,
+        is_aligned: bool,
+    ) {
+        let mut refs = Vec::new();
+        collect_refs(&pattern.root, &mut refs);
+
+        let tiles = self.patterns.entry(node_key(&pattern.root)).or_default();
+        // Keep only one tile per bbox. Otherwise, the cache will grow on each transform change.
+        tiles.retain(|t| t.bbox.fuzzy_ne(&bbox));
+        tiles.push(PatternTile {
+            pattern: pattern.clone(),
+            transform,
+            bbox,
+            refs,
+            pixmap,
+            pixmap_ts,
+            region,
+            is_aligned,
+        });
+    }
+
+    fn tick(&self) -> u64 {
+        let time = self.clock.get() + 1;
+        self.clock.set(time);
+        time
+    }
+}
+
+impl Default for RenderCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for RenderCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RenderCache")
+            .field("layers", &self.layers.len())
+            .field("patterns", &self.patterns.len())
+            .finish()
+    }
+}
+
+/// Collects roots of clip paths, masks, patterns and `feImage` subtrees
+/// used by the `node` and its descendants, recursively.
+fn collect_refs(node: &usvg::Node, refs: &mut Vec<usize>) {
+    for n in node.descendants() {
+        match *n.borrow() {
+            usvg::NodeKind::Group(ref g) => {
+                let mut clip_path = g.clip_path.as_ref();
+                while let Some(cp) = clip_path {
+                    collect_root(&cp.root, refs);
+                    clip_path = cp.clip_path.as_ref();
+                }
+
+                let mut mask = g.mask.as_ref();
+                while let Some(m) = mask {
+                    collect_root(&m.root, refs);
+                    mask = m.mask.as_ref();
+                }
+
+                #[cfg(feature = "filter")]
+                for f in &g.filters {
+                    for fe in &f.primitives {
+                        if let usvg::filter::Kind::Image(ref img) = fe.kind {
+                            if let usvg::filter::ImageKind::Use(ref node) = img.data {
+                                collect_root(node, refs);
+                            }
+                        }
+                    }
+                }
+            }
+            usvg::NodeKind::Path(ref path) => {
+                let fill = path.fill.as_ref().map(|f| &f.paint);
+                let stroke = path.stroke.as_ref().map(|s| &s.paint);
+                for paint in fill.into_iter().chain(stroke) {
+                    if let usvg::Paint::Pattern(ref pattern) = paint {
+                        collect_root(&pattern.root, refs);
+                    }
+                }
+            }
+            usvg::NodeKind::Image(_) => {}
+        }
+    }
+}
+
+fn collect_root(root: &usvg::Node, refs: &mut Vec<usize>) {
+    let key = node_key(root);
+    if !refs.contains(&key) {
+        refs.push(key);
+        collect_refs(root, refs);
+    }
+}
+
+/// Returns the node identity.
+///
+/// `rctree` doesn't provide a way to hash nodes, so we are using the address of the node data,
+/// which is stable during the node lifetime.
+fn node_key(node: &usvg::Node) -> usize {
+    &*node.borrow() as *const usvg::NodeKind as usize
+}
@@ -4,12 +4,13 @@
 
 use usvg::{NodeExt, TransformFromBBox};
 
-use crate::{render::Canvas, ConvTransform, OptionLog};
+use crate::{render::Canvas, ConvTransform, OptionLog, RenderCache};
 
 pub fn clip(
     tree: &usvg::Tree,
     cp: &usvg::ClipPath,
     bbox: usvg::PathBbox,
+    cache: &mut RenderCache,
     canvas: &mut Canvas,
 ) -> Option<()> {
     let mut clip_pixmap = tiny_skia::Pixmap::new(canvas.pixmap.width(), canvas.pixmap.height())?;
@@ -37,11 +38,12 @@ pub fn clip(
                     tree,
                     path_node,
                     tiny_skia::BlendMode::Clear,
+                    cache,
                     &mut clip_canvas,
                 );
             }
             usvg::NodeKind::Group(ref g) => {
-                clip_group(tree, &node, g, bbox, &mut clip_canvas);
+                clip_group(tree, &node, g, bbox, cache, &mut clip_canvas);
             }
             _ => {}
         }
@@ -50,7 +52,7 @@ pub fn clip(
     }
 
     if let Some(ref cp) = cp.clip_path {
-        clip(tree, cp, bbox, canvas);
+        clip(tree, cp, bbox, cache, canvas);
     }
 
     let mut paint = tiny_skia::PixmapPaint::default();
@@ -67,11 +69,107 @@ pub fn clip(
     Some(())
 }
 
+/// Intersects the canvas clip with a simple clip path of `node`.
+///
+/// Returns `None` and leaves the canvas unchanged when the clip path cannot be represented
+/// by a single mask, i.e. it has more than one child or a child that is not a filled path.
+/// A `clip-path` on the clip path itself is allowed as long as it's simple as well.
+///
+/// Anti-aliased shapes are allowed only when they are pixel-aligned rects.
+/// Otherwise, clipping during drawing produces different edges than clipping of a rendered layer.
+pub fn intersect_clip(cp: &usvg::ClipPath, node: &usvg::Node, canvas: &mut Canvas) -> Option<()> {
+    let mut shapes = Vec::new();
+    let mut cp = Some(cp);
+    while let Some(c) = cp {
+        let shape = clip_shape(c, node, canvas.transform)?;
+        if shape.2 && pixel_rect(&shape.0).is_none() {
+            return None;
+        }
+
+        shapes.push(shape);
+        cp = c.clip_path.as_deref();
+    }
+
+    for (path, rule, anti_alias) in shapes {
+        canvas.intersect_clip_path(&path, rule, anti_alias);
+    }
+
+    Some(())
+}
+
+/// Converts a simple clip path into a path in canvas coordinates.
+fn clip_shape(
+    cp: &usvg::ClipPath,
+    node: &usvg::Node,
+    ts: tiny_skia::Transform,
+) -> Option<(tiny_skia::Path, tiny_skia::FillRule, bool)> {
+    let path = usvg::utils::simple_clip_path(cp, node)?;
+    let rule = match path.fill.as_ref()?.rule {
+        usvg::FillRule::NonZero => tiny_skia::FillRule::Winding,
+        usvg::FillRule::EvenOdd => tiny_skia::FillRule::EvenOdd,
+    };
+
+    let anti_alias = path.rendering_mode.use_shape_antialiasing();
+    let ts = ts.pre_concat(path.transform.to_native());
+    let shape = crate::path::convert_path(&path.data)?.transform(ts)?;
+    Some((shape, rule, anti_alias))
+}
+
+/// Checks that a path is an axis-aligned rect with integer coordinates.
+fn pixel_rect(path: &tiny_skia::Path) -> Option<tiny_skia::IntRect> {
+    let is_integer = |n: f32| (n - n.round()).abs() < 0.001;
+    let is_same = |a: f32, b: f32| (a - b).abs() < 0.001;
+
+    let bounds = path.bounds();
+    let (left, top, right, bottom) = (bounds.left(), bounds.top(), bounds.right(), bounds.bottom());
+    if !(is_integer(left) && is_integer(top) && is_integer(right) && is_integer(bottom)) {
+        return None;
+    }
+
+    let mut points = Vec::with_capacity(5);
+    for (i, segment) in path.segments().enumerate() {
+        match segment {
+            tiny_skia::PathSegment::MoveTo(p) if i == 0 => points.push(p),
+            tiny_skia::PathSegment::LineTo(p) => points.push(p),
+            tiny_skia::PathSegment::Close => {}
+            _ => return None,
+        }
+    }
+
+    // An explicitly closed rect.
+    if points.len() == 5 && is_same(points[0].x, points[4].x) && is_same(points[0].y, points[4].y) {
+        points.pop();
+    }
+
+    if points.len() != 4 {
+        return None;
+    }
+
+    // Each point must be a corner and each edge must be either horizontal or vertical.
+    for (i, p) in points.iter().enumerate() {
+        let next = points[(i + 1) % 4];
+        let is_corner = (is_same(p.x, left) || is_same(p.x, right))
+            && (is_same(p.y, top) || is_same(p.y, bottom));
+        let is_edge = is_same(p.x, next.x) != is_same(p.y, next.y);
+        if !is_corner || !is_edge {
+            return None;
+        }
+    }
+
+    tiny_skia::IntRect::from_ltrb(
+        left.round() as i32,
+        top.round() as i32,
+        right.round() as i32,
+        bottom.round() as i32,
+    )
+}
+
 fn clip_group(
     tree: &usvg::Tree,
     node: &usvg::Node,
     g: &usvg::Group,
     bbox: usvg::PathBbox,
+    cache: &mut RenderCache,
     canvas: &mut Canvas,
 ) -> Option<()> {
     if let Some(ref cp) = g.clip_path {
@@ -84,8 +182,8 @@ fn clip_group(
         let mut clip_canvas = Canvas::from(clip_pixmap.as_mut());
         clip_canvas.transform = canvas.transform;
 
-        draw_group_child(tree, node, &mut clip_canvas);
-        clip(tree, cp, bbox, &mut clip_canvas);
+        draw_group_child(tree, node, cache, &mut clip_canvas);
+        clip(tree, cp, bbox, cache, &mut clip_canvas);
 
         let mut paint = tiny_skia::PixmapPaint::default();
         paint.blend_mode = tiny_skia::BlendMode::Xor;
@@ -102,12 +200,23 @@ fn clip_group(
     Some(())
 }
 
-fn draw_group_child(tree: &usvg::Tree, node: &usvg::Node, canvas: &mut Canvas) {
+fn draw_group_child(
+    tree: &usvg::Tree,
+    node: &usvg::Node,
+    cache: &mut RenderCache,
+    canvas: &mut Canvas,
+) {
     if let Some(child) = node.first_child() {
         canvas.apply_transform(child.transform().to_native());
 
         if let usvg::NodeKind::Path(ref path_node) = *child.borrow() {
-            crate::path::draw(tree, path_node, tiny_skia::BlendMode::SourceOver, canvas);
+            crate::path::draw(
+                tree,
+                path_node,
+                tiny_skia::BlendMode::SourceOver,
+                cache,
+                canvas,
+            );
         }
     }
 }
@@ -0,0 +1,88 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+/// Image comparison options.
+#[derive(Clone, Copy, Debug)]
+pub struct CompareOptions {
+    /// The maximum per-channel difference that is not considered a change.
+    ///
+    /// Helps to ignore anti-aliasing and rounding differences.
+    ///
+    /// Default: 1
+    pub tolerance: u8,
+}
+
+impl Default for CompareOptions {
+    fn default() -> Self {
+        CompareOptions { tolerance: 1 }
+    }
+}
+
+/// An image comparison result.
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    /// The number of pixels that differ by more than the tolerance.
+    pub different_pixels: usize,
+
+    /// The maximum per-channel difference between images.
+    pub max_difference: u8,
+
+    /// An opaque diff image.
+    ///
+    /// Changed pixels are red and the rest is a faded grayscale copy of the first image.
+    pub diff: tiny_skia::Pixmap,
+}
+
+/// Compares two images pixel by pixel.
+///
+/// Both images must be non-premultiplied RGBA8 buffers of the specified size,
+/// like the ones produced by PNG decoders.
+///
+/// Returns `None` when a buffer length doesn't match the size or the size is zero.
+pub fn compare_images(
+    img1: &[u8],
+    img2: &[u8],
+    width: u32,
+    height: u32,
+    opt: &CompareOptions,
+) -> Option<Comparison> {
+    let len = width as usize * height as usize * 4;
+    if img1.len() != len || img2.len() != len {
+        return None;
+    }
+
+    let mut diff = tiny_skia::Pixmap::new(width, height)?;
+    let mut different_pixels = 0;
+    let mut max_difference = 0;
+    for ((c1, c2), d) in img1
+        .chunks_exact(4)
+        .zip(img2.chunks_exact(4))
+        .zip(diff.data_mut().chunks_exact_mut(4))
+    {
+        let mut pixel_difference = 0;
+        for (a, b) in c1.iter().zip(c2) {
+            pixel_difference = pixel_difference.max((*a as i32 - *b as i32).unsigned_abs() as u8);
+        }
+
+        max_difference = max_difference.max(pixel_difference);
+
+        if pixel_difference > opt.tolerance {
+            different_pixels += 1;
+            d.copy_from_slice(&[255, 0, 0, 255]);
+        } else {
+            // Blend over white, convert to grayscale and fade.
+            let alpha = c1[3] as u32;
+            let blend = |c: u8| (c as u32 * alpha + 255 * (255 - alpha)) / 255;
+            let gray = (blend(c1[0]) * 77 + blend(c1[1]) * 150 + blend(c1[2]) * 29) >> 8;
+            let gray = (192 + gray / 4) as u8;
+            d.copy_from_slice(&[gray, gray, gray, 255]);
+        }
+    }
+
+    Some(Comparison {
+        different_pixels,
+        max_difference,
+        diff,
+    })
+}
@@ -9,7 +9,7 @@ use usvg::{FuzzyZero, NodeExt, TransformFromBBox};
 
 use crate::{
     render::{Canvas, RenderState},
-    ConvTransform,
+    ConvTransform, RenderCache,
 };
 
 macro_rules! into_svgfilters_image {
@@ -954,7 +954,15 @@ fn apply_image(
             canvas.scale(sx as f32, sy as f32);
             canvas.apply_transform(node.transform().to_native());
 
-            crate::render::render_node(tree, node, &mut RenderState::Ok, &mut canvas);
+            // Layers of the referenced node should not be cached,
+            // since they would replace the ones used by the main render.
+            crate::render::render_node(
+                tree,
+                node,
+                &mut RenderState::Ok,
+                &mut RenderCache::without_layers(),
+                &mut canvas,
+            );
         }
     }
 
@@ -60,8 +60,15 @@ fn draw_svg(tree: &usvg::Tree, view_box: usvg::ViewBox, canvas: &mut Canvas) ->
     let mut sub_canvas = Canvas::from(sub_pixmap.as_mut());
     sub_canvas.transform = canvas.transform;
     sub_canvas.apply_transform(ts.to_native());
-    crate::render::render_to_canvas(tree, img_size, &mut sub_canvas);
+    crate::render::render_to_canvas(
+        tree,
+        img_size,
+        &mut crate::RenderCache::without_layers(),
+        &mut sub_canvas,
+    );
 
+    // Images are clipped inside of the current clip, which must be restored afterwards.
+    let prev_clip = clip.map(|_| canvas.clip.clone());
     if let Some(clip) = clip {
         let rr = tiny_skia::Rect::from_xywh(
             clip.x() as f32,
@@ -80,13 +87,16 @@ fn draw_svg(tree: &usvg::Tree, view_box: usvg::ViewBox, canvas: &mut Canvas) ->
         tiny_skia::Transform::identity(),
         canvas.clip.as_ref(),
     );
-    canvas.clip = None;
+
+    if let Some(clip) = prev_clip {
+        canvas.clip = clip;
+    }
 
     Some(())
 }
 
 #[cfg(feature = "raster-images")]
-mod raster_images {
+pub(crate) mod raster_images {
     use crate::render::Canvas;
 
     pub fn draw_raster(
@@ -126,6 +136,12 @@ mod raster_images {
         let mut paint = tiny_skia::Paint::default();
         paint.shader = pattern;
 
+        let prev_clip = if view_box.aspect.slice {
+            Some(canvas.clip.clone())
+        } else {
+            None
+        };
+
         if view_box.aspect.slice {
             let r = view_box.rect;
             let rect = tiny_skia::Rect::from_xywh(
@@ -141,7 +157,10 @@ mod raster_images {
         canvas
             .pixmap
             .fill_rect(rect, &paint, canvas.transform, canvas.clip.as_ref());
-        canvas.clip = None;
+
+        if let Some(clip) = prev_clip {
+            canvas.clip = clip;
+        }
 
         Some(())
     }
@@ -176,11 +195,11 @@ mod raster_images {
     }
 
     pub struct Image {
-        data: ImageData,
-        size: usvg::ScreenSize,
+        pub data: ImageData,
+        pub size: usvg::ScreenSize,
     }
 
-    enum ImageData {
+    pub enum ImageData {
         RGB(Vec<u8>),
         RGBA(Vec<u8>),
     }
@@ -270,7 +289,7 @@ mod raster_images {
     }
 
     /// Calculates an image rect depending on the provided view box.
-    fn image_rect(view_box: &usvg::ViewBox, img_size: usvg::ScreenSize) -> usvg::Rect {
+    pub fn image_rect(view_box: &usvg::ViewBox, img_size: usvg::ScreenSize) -> usvg::Rect {
         let new_size = img_size.to_size().fit_view_box(view_box);
         let (x, y) = usvg::utils::aligned_pos(
             view_box.aspect.align,
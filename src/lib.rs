@@ -17,15 +17,25 @@ pub use usvg;
 
 use usvg::NodeExt;
 
+mod cache;
 mod clip;
+mod compare;
 #[cfg(feature = "filter")]
 mod filter;
 mod image;
 mod mask;
 mod paint_server;
 mod path;
+#[cfg(feature = "pdf")]
+pub mod pdf;
+#[cfg(feature = "ps")]
+pub mod ps;
 mod render;
+#[cfg(any(feature = "pdf", feature = "ps"))]
+mod vector;
 
+pub use crate::cache::RenderCache;
+pub use crate::compare::{compare_images, CompareOptions, Comparison};
 pub use crate::render::trim_transparency;
 
 trait OptionLog {
@@ -83,17 +93,40 @@ pub fn render(
     fit_to: usvg::FitTo,
     transform: tiny_skia::Transform,
     pixmap: tiny_skia::PixmapMut,
+) -> Option<()> {
+    render_with_cache(
+        tree,
+        fit_to,
+        transform,
+        &mut RenderCache::without_layers(),
+        pixmap,
+    )
+}
+
+/// Renders an SVG to pixmap using a layers cache.
+///
+/// The same as [`render`], but rendered group layers will be stored in the `cache`
+/// and reused by the subsequent calls.
+/// Useful when the same tree is rendered multiple times with small changes.
+///
+/// All modified nodes must be passed to [`RenderCache::mark_dirty`] before rendering.
+pub fn render_with_cache(
+    tree: &usvg::Tree,
+    fit_to: usvg::FitTo,
+    transform: tiny_skia::Transform,
+    cache: &mut RenderCache,
+    pixmap: tiny_skia::PixmapMut,
 ) -> Option<()> {
     let size = fit_to.fit_to(tree.size.to_screen_size())?;
     let mut canvas = render::Canvas::from(pixmap);
     canvas.apply_transform(transform);
-    render::render_to_canvas(tree, size, &mut canvas);
+    render::render_to_canvas(tree, size, cache, &mut canvas);
     Some(())
 }
 
 /// Renders an SVG node to pixmap.
 ///
-/// If `fit_to` differs from `node.calculate_bbox()`,
+/// If `fit_to` differs from `node.calculate_visual_bbox()`,
 /// SVG would be scaled accordingly.
 ///
 /// `transform` will be used as a root transform.
@@ -105,7 +138,7 @@ pub fn render_node(
     transform: tiny_skia::Transform,
     pixmap: tiny_skia::PixmapMut,
 ) -> Option<()> {
-    let node_bbox = if let Some(bbox) = node.calculate_bbox().and_then(|r| r.to_rect()) {
+    let node_bbox = if let Some(bbox) = node.calculate_visual_bbox().and_then(|r| r.to_rect()) {
         bbox
     } else {
         log::warn!("Node '{}' has zero size.", node.id());
@@ -126,6 +159,7 @@ pub fn render_node(
         vbox,
         size,
         &mut render::RenderState::Ok,
+        &mut RenderCache::without_layers(),
         &mut canvas,
     );
     Some(())
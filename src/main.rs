@@ -2,16 +2,20 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::collections::{HashMap, HashSet};
+use std::panic::AssertUnwindSafe;
 use std::path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
-use usvg::NodeExt;
+use usvg::{NodeExt, WebFontsExt};
 
 macro_rules! timed {
     ($args:expr, $name:expr, $task:expr) => {
         if $args.perf {
             let now = std::time::Instant::now();
             let res = $task;
-            println!(
+            eprintln!(
                 "{}: {:.2}ms",
                 $name,
                 now.elapsed().as_micros() as f64 / 1000.0
@@ -48,6 +52,14 @@ fn process() -> Result<(), String> {
         }
     }
 
+    if args.batch {
+        return process_batch(&args);
+    }
+
+    render_file(&args)
+}
+
+fn render_file(args: &Args) -> Result<(), String> {
     let svg_data = timed!(args, "Reading", {
         if args.in_svg == "-" {
             use std::io::Read;
@@ -63,10 +75,20 @@ fn process() -> Result<(), String> {
         }
     });
 
+    // Get input file absolute directory.
+    let input_dir = std::fs::canonicalize(&args.in_svg)
+        .ok()
+        .and_then(|p| p.parent().map(|p| p.to_path_buf()));
+
+    let mut opt = args.usvg.to_ref();
+    if opt.resources_dir.is_none() {
+        opt.resources_dir = input_dir.as_deref();
+    }
+
     let tree = timed!(
         args,
         "Parsing",
-        usvg::Tree::from_data(&svg_data, &args.usvg.to_ref()).map_err(|e| e.to_string())
+        usvg::Tree::from_data(&svg_data, &opt).map_err(|e| e.to_string())
     )?;
 
     if args.query_all {
@@ -78,13 +100,32 @@ fn process() -> Result<(), String> {
         dump_svg(&tree, dump_path)?;
     }
 
-    let out_png = match args.out_png {
+    if let Some(ref reference) = args.compare {
+        return compare_file(args, &tree, reference);
+    }
+
+    let out_path = match args.out_path {
         Some(ref path) => path.clone(),
         None => return Ok(()),
     };
 
-    // Render.
-    render_svg(args, &tree, &out_png)
+    if args.export_all_ids {
+        return export_all_ids(args, &tree, &out_path);
+    }
+
+    let data = match args.format {
+        OutputFormat::Pdf => save_pdf(args, &tree)?,
+        OutputFormat::Ps | OutputFormat::Eps => save_ps(args, &tree)?,
+        OutputFormat::Ico | OutputFormat::Icns => {
+            timed!(args, "Rendering", render_icon(args, &tree))?
+        }
+        _ => {
+            let img = render_svg(args, &tree)?;
+            timed!(args, "Encoding", encode_raster(args, &img))?
+        }
+    };
+
+    timed!(args, "Saving", write_output(&out_path, &data))
 }
 
 const HELP: &str = "\
@@ -93,10 +134,23 @@ resvg is an SVG rendering application.
 USAGE:
   resvg [OPTIONS] <in-svg> <out-png>  # from file to file
   resvg [OPTIONS] - <out-png>         # from stdin to file
+  resvg [OPTIONS] <in-svg> -          # from file to stdout
+  resvg [OPTIONS] --batch <in> <dir>  # many files to a directory
+  resvg [OPTIONS] --compare <png> <in-svg> [<diff-png>]
 
   resvg in.svg out.png
   resvg -z 4 in.svg out.png
+  resvg --quality 80 in.svg out.jpg
+  resvg --format pdf in.svg out.pdf
+  resvg --format eps in.svg out.eps
+  resvg --format ppm in.svg -
+  resvg --sizes 16,32,48,256 in.svg out.ico
   resvg --query-all in.svg
+  resvg --export-all-ids --export-id-pattern 'icon-*' -w 32 in.svg out/
+  resvg --batch -w 64 icons/ out/
+  resvg --batch --format pdf 'icons/**/*.svg' out/
+  resvg --batch manifest.txt out/
+  resvg --compare ref.png --tolerance 2 in.svg diff.png
 
 OPTIONS:
       --help                    Prints this help
@@ -105,10 +159,34 @@ OPTIONS:
   -w, --width LENGTH            Sets the width in pixels
   -h, --height LENGTH           Sets the height in pixels
   -z, --zoom FACTOR             Zooms the image by a factor
-      --dpi DPI                 Sets the resolution
+      --dpi DPI                 Sets the resolution.
+                                Also stored in PNG and JPEG metadata
                                 [default: 96] [possible values: 10..4000]
   --background COLOR            Sets the background color
                                 Examples: red, #fff, #fff000
+  --format FORMAT               Sets the output format.
+                                Detected by the output file extension when not set.
+                                'rgba' is raw non-premultiplied pixels without a header.
+                                JPEG and PPM are filled with the background color or white.
+                                JPEG requires the `jpeg` build feature.
+                                PDF and PostScript output ignores size, background
+                                and export options. Requires the `pdf` and `ps`
+                                build features
+                                [default: png] [possible values: png, jpeg, rgba, pam,
+                                ppm, ico, icns, pdf, ps, eps]
+  --sizes LIST                  Sets a comma-separated list of ICO and ICNS icon sizes.
+                                Each size is rendered separately. ICO supports sizes
+                                up to 256, ICNS supports 16, 32, 64, 128, 256, 512, 1024
+                                [default: 16,24,32,48,64,128,256 for ICO,
+                                16,32,64,128,256,512,1024 for ICNS]
+  --quality QUALITY             Sets the JPEG quality
+                                [default: 90] [possible values: 1..100]
+  --bit-depth DEPTH             Sets the bits per channel of PNG, PAM, PPM and RGBA output.
+                                Rendering is always done with 8 bits per channel,
+                                so 16 bit output has the same precision
+                                [default: 8] [possible values: 8, 16]
+  --png-compression LEVEL       Sets the PNG compression level
+                                [default: default] [possible values: fast, default, best]
 
   --languages LANG              Sets a comma-separated list of languages that
                                 will be used during the 'systemLanguage'
@@ -149,6 +227,7 @@ OPTIONS:
   --monospace-family FAMILY     Sets the 'monospace' font family
                                 [default: Courier New]
   --use-font-file PATH          Load a specified font file into the fonts database.
+                                WOFF and WOFF2 files are supported as well.
                                 Will be used during text to path conversion.
                                 This option can be set multiple times
   --use-fonts-dir PATH          Loads all fonts from the specified directory
@@ -165,6 +244,12 @@ OPTIONS:
 
   --query-all                   Queries all valid SVG ids with bounding boxes
   --export-id ID                Renders an object only with a specified ID
+  --export-all-ids              Renders each object with an ID into a separate file.
+                                The output path is a directory and files are named
+                                after IDs. A numeric suffix is added when file names
+                                collide. Supports only raster formats
+  --export-id-pattern PATTERN   Renders only IDs matching a pattern during --export-all-ids.
+                                Supports '*' and '?' wildcards
   --export-area-page            Use an image size instead of an object size during ID exporting
 
   --export-area-drawing         Use drawing's tight bounding box instead of image size.
@@ -174,6 +259,30 @@ OPTIONS:
   --quiet                       Disables warnings
   --dump-svg PATH               Saves the preprocessed SVG into the selected file
 
+  --batch                       Renders many files using the same options and fonts.
+                                The input can be a directory, a glob pattern
+                                or a manifest file. Outputs are written into
+                                the output directory with the --format extension,
+                                keeping the input directory structure.
+                                Fails when two files have the same output path.
+                                A manifest has one file per line:
+                                `in.svg [out.png] [-w N] [-h N] [-z N] [--background C]`
+                                Paths are relative to the manifest directory
+                                and to the output directory respectively.
+                                Failed files are reported and skipped
+  --jobs COUNT                  Sets the number of rendering threads in batch mode
+                                [default: number of CPUs with the `parallel`
+                                build feature, 1 otherwise]
+
+  --compare PNG                 Renders an SVG and compares it with a reference PNG.
+                                Prints the number of different pixels and fails
+                                when it's not zero. The output file is optional
+                                and is used to save a PNG diff image with
+                                different pixels highlighted in red
+  --tolerance VALUE             Sets the maximum per-channel difference that is
+                                not considered a change during --compare
+                                [default: 1] [possible values: 0..255]
+
 ARGS:
   <in-svg>                      Input file
   <out-png>                     Output file
@@ -186,6 +295,11 @@ struct CliArgs {
     zoom: Option<f32>,
     dpi: u32,
     background: Option<svgtypes::Color>,
+    format: Option<OutputFormat>,
+    quality: u8,
+    bit_depth: u8,
+    png_compression: png::Compression,
+    sizes: Option<Vec<u32>>,
 
     languages: Vec<String>,
     shape_rendering: usvg::ShapeRendering,
@@ -207,6 +321,8 @@ struct CliArgs {
 
     query_all: bool,
     export_id: Option<String>,
+    export_all_ids: bool,
+    export_id_pattern: Option<String>,
     export_area_page: bool,
 
     export_area_drawing: bool,
@@ -215,6 +331,12 @@ struct CliArgs {
     quiet: bool,
     dump_svg: Option<String>,
 
+    batch: bool,
+    jobs: Option<usize>,
+
+    compare: Option<path::PathBuf>,
+    tolerance: Option<u8>,
+
     input: String,
     output: Option<path::PathBuf>,
 }
@@ -238,6 +360,17 @@ fn collect_args() -> Result<CliArgs, pico_args::Error> {
         zoom: input.opt_value_from_fn(["-z", "--zoom"], parse_zoom)?,
         dpi: input.opt_value_from_fn("--dpi", parse_dpi)?.unwrap_or(96),
         background: input.opt_value_from_str("--background")?,
+        format: input.opt_value_from_str("--format")?,
+        quality: input
+            .opt_value_from_fn("--quality", parse_quality)?
+            .unwrap_or(90),
+        bit_depth: input
+            .opt_value_from_fn("--bit-depth", parse_bit_depth)?
+            .unwrap_or(8),
+        png_compression: input
+            .opt_value_from_fn("--png-compression", parse_png_compression)?
+            .unwrap_or(png::Compression::Default),
+        sizes: input.opt_value_from_fn("--sizes", parse_sizes)?,
 
         languages: input
             .opt_value_from_fn("--languages", parse_languages)?
@@ -271,6 +404,8 @@ fn collect_args() -> Result<CliArgs, pico_args::Error> {
 
         query_all: input.contains("--query-all"),
         export_id: input.opt_value_from_str("--export-id")?,
+        export_all_ids: input.contains("--export-all-ids"),
+        export_id_pattern: input.opt_value_from_str("--export-id-pattern")?,
         export_area_page: input.contains("--export-area-page"),
 
         export_area_drawing: input.contains("--export-area-drawing"),
@@ -279,6 +414,12 @@ fn collect_args() -> Result<CliArgs, pico_args::Error> {
         quiet: input.contains("--quiet"),
         dump_svg: input.opt_value_from_str("--dump-svg")?,
 
+        batch: input.contains("--batch"),
+        jobs: input.opt_value_from_fn("--jobs", parse_jobs)?,
+
+        compare: input.opt_value_from_str("--compare")?,
+        tolerance: input.opt_value_from_str("--tolerance")?,
+
         input: input.free_from_str()?,
         output: input.opt_free_from_str()?,
     })
@@ -314,6 +455,69 @@ fn parse_zoom(s: &str) -> Result<f32, String> {
     }
 }
 
+fn parse_quality(s: &str) -> Result<u8, String> {
+    let n: u8 = s.parse().map_err(|_| "invalid number")?;
+
+    if (1..=100).contains(&n) {
+        Ok(n)
+    } else {
+        Err("quality out of bounds".to_string())
+    }
+}
+
+fn parse_bit_depth(s: &str) -> Result<u8, String> {
+    match s {
+        "8" => Ok(8),
+        "16" => Ok(16),
+        _ => Err("invalid bit depth".to_string()),
+    }
+}
+
+fn parse_png_compression(s: &str) -> Result<png::Compression, String> {
+    match s {
+        "fast" => Ok(png::Compression::Fast),
+        "default" => Ok(png::Compression::Default),
+        "best" => Ok(png::Compression::Best),
+        _ => Err("invalid compression level".to_string()),
+    }
+}
+
+fn parse_sizes(s: &str) -> Result<Vec<u32>, String> {
+    let mut sizes = Vec::new();
+    for size in s.split(',') {
+        let n: u32 = size.trim().parse().map_err(|_| "invalid size")?;
+        if !(1..=1024).contains(&n) {
+            return Err("icon size out of bounds".to_string());
+        }
+
+        if !sizes.contains(&n) {
+            sizes.push(n);
+        }
+    }
+
+    Ok(sizes)
+}
+
+fn parse_jobs(s: &str) -> Result<usize, String> {
+    let n: usize = s.parse().map_err(|_| "invalid number")?;
+
+    if n > 0 {
+        Ok(n)
+    } else {
+        Err("COUNT cannot be zero".to_string())
+    }
+}
+
+#[cfg(feature = "parallel")]
+fn default_jobs() -> usize {
+    num_cpus::get()
+}
+
+#[cfg(not(feature = "parallel"))]
+fn default_jobs() -> usize {
+    1
+}
+
 fn parse_font_size(s: &str) -> Result<u32, String> {
     let n: u32 = s.parse().map_err(|_| "invalid number")?;
 
@@ -337,19 +541,99 @@ fn parse_languages(s: &str) -> Result<Vec<String>, String> {
     Ok(langs)
 }
 
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum OutputFormat {
+    Png,
+    Jpeg,
+    Rgba,
+    Pam,
+    Ppm,
+    Ico,
+    Icns,
+    Pdf,
+    Ps,
+    Eps,
+}
+
+impl OutputFormat {
+    fn from_path(path: &path::Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_lowercase();
+        match ext.as_str() {
+            "jpg" => Some(OutputFormat::Jpeg),
+            "raw" => Some(OutputFormat::Rgba),
+            _ => ext.parse().ok(),
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Png => "png",
+            OutputFormat::Jpeg => "jpg",
+            OutputFormat::Rgba => "rgba",
+            OutputFormat::Pam => "pam",
+            OutputFormat::Ppm => "ppm",
+            OutputFormat::Ico => "ico",
+            OutputFormat::Icns => "icns",
+            OutputFormat::Pdf => "pdf",
+            OutputFormat::Ps => "ps",
+            OutputFormat::Eps => "eps",
+        }
+    }
+
+    fn is_vector(self) -> bool {
+        matches!(
+            self,
+            OutputFormat::Pdf | OutputFormat::Ps | OutputFormat::Eps
+        )
+    }
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "png" => Ok(OutputFormat::Png),
+            "jpeg" => Ok(OutputFormat::Jpeg),
+            "rgba" => Ok(OutputFormat::Rgba),
+            "pam" => Ok(OutputFormat::Pam),
+            "ppm" => Ok(OutputFormat::Ppm),
+            "ico" => Ok(OutputFormat::Ico),
+            "icns" => Ok(OutputFormat::Icns),
+            "pdf" => Ok(OutputFormat::Pdf),
+            "ps" => Ok(OutputFormat::Ps),
+            "eps" => Ok(OutputFormat::Eps),
+            _ => Err("invalid format".to_string()),
+        }
+    }
+}
+
+#[derive(Clone)]
 struct Args {
     in_svg: String,
-    out_png: Option<path::PathBuf>,
+    out_path: Option<path::PathBuf>,
     query_all: bool,
     export_id: Option<String>,
+    export_all_ids: bool,
+    export_id_pattern: Option<String>,
     export_area_page: bool,
     export_area_drawing: bool,
     dump: Option<path::PathBuf>,
     perf: bool,
     quiet: bool,
-    usvg: usvg::Options,
+    batch: bool,
+    jobs: usize,
+    compare: Option<path::PathBuf>,
+    tolerance: u8,
+    usvg: Arc<usvg::Options>,
     fit_to: usvg::FitTo,
     background: Option<svgtypes::Color>,
+    format: OutputFormat,
+    #[cfg_attr(not(feature = "jpeg"), allow(dead_code))]
+    quality: u8,
+    bit_depth: u8,
+    png_compression: png::Compression,
+    sizes: Vec<u32>,
 }
 
 fn parse_args() -> Result<Args, String> {
@@ -372,59 +656,167 @@ fn parse_args() -> Result<Args, String> {
         }
     }
 
-    if !args.query_all && args.output.is_none() {
+    if args.batch {
+        if args.query_all {
+            return Err("--query-all cannot be used in batch mode".to_string());
+        }
+
+        if args.output.is_none() || args.output.as_deref() == Some(path::Path::new("-")) {
+            return Err("an output directory must be set in batch mode".to_string());
+        }
+
+        if args.dump_svg.is_some() {
+            eprintln!("Warning: --dump-svg has no effect in batch mode.");
+            args.dump_svg = None;
+        }
+    } else if args.jobs.is_some() {
+        eprintln!("Warning: --jobs has no effect without --batch.");
+    }
+
+    if args.compare.is_some() {
+        if args.batch || args.query_all || args.export_all_ids {
+            return Err(
+                "--compare cannot be used with --batch, --query-all or --export-all-ids"
+                    .to_string(),
+            );
+        }
+
+        if args.output.as_deref() == Some(path::Path::new("-")) {
+            return Err("--compare cannot write a diff image to stdout".to_string());
+        }
+
+        if args.format.is_some() {
+            eprintln!("Warning: --format has no effect with --compare.");
+            args.format = None;
+        }
+    } else if args.tolerance.is_some() {
+        eprintln!("Warning: --tolerance has no effect without --compare.");
+    }
+
+    if !args.query_all && args.compare.is_none() && args.output.is_none() {
         return Err("<out-png> must be set".to_string());
     }
 
     if args.input == "-" && args.resources_dir.is_none() {
-        println!("Warning: Make sure to set --resources-dir when reading SVG from stdin.");
+        eprintln!("Warning: Make sure to set --resources-dir when reading SVG from stdin.");
+    }
+
+    if args.export_all_ids {
+        if args.batch || args.query_all || args.export_id.is_some() {
+            return Err(
+                "--export-all-ids cannot be used with --batch, --query-all or --export-id"
+                    .to_string(),
+            );
+        }
+
+        if args.output.as_deref() == Some(path::Path::new("-")) {
+            return Err("--export-all-ids requires an output directory".to_string());
+        }
+    } else if args.export_id_pattern.is_some() {
+        eprintln!("Warning: --export-id-pattern has no effect without --export-all-ids.");
+    }
+
+    let export_any_id = args.export_id.is_some() || args.export_all_ids;
+
+    if args.export_area_page && !export_any_id {
+        eprintln!("Warning: --export-area-page has no effect without --export-id.");
+    }
+
+    if args.export_area_drawing && export_any_id {
+        eprintln!("Warning: --export-area-drawing has no effect when --export-id is set.");
+    }
+
+    // Batch and all IDs output paths are directories and a diff image is always PNG.
+    let format = args
+        .format
+        .or_else(
+            || match args.batch || args.export_all_ids || args.compare.is_some() {
+                true => None,
+                false => args.output.as_deref().and_then(OutputFormat::from_path),
+            },
+        )
+        .unwrap_or(OutputFormat::Png);
+
+    if args.export_all_ids
+        && (format.is_vector() || matches!(format, OutputFormat::Ico | OutputFormat::Icns))
+    {
+        return Err("--export-all-ids supports only raster formats".to_string());
+    }
+
+    if format.is_vector() && (args.export_id.is_some() || args.export_area_drawing) {
+        eprintln!("Warning: export options have no effect on vector output.");
     }
 
-    if args.export_area_page && args.export_id.is_none() {
-        println!("Warning: --export-area-page has no effect without --export-id.");
+    if format == OutputFormat::Jpeg && args.bit_depth != 8 {
+        eprintln!("Warning: JPEG supports only 8 bits per channel.");
     }
 
-    if args.export_area_drawing && args.export_id.is_some() {
-        println!("Warning: --export-area-drawing has no effect when --export-id is set.");
+    let sizes = match format {
+        OutputFormat::Ico => {
+            let sizes = args
+                .sizes
+                .take()
+                .unwrap_or_else(|| vec![16, 24, 32, 48, 64, 128, 256]);
+            let (sizes, skipped): (Vec<_>, Vec<_>) = sizes.into_iter().partition(|n| *n <= 256);
+            for n in skipped {
+                eprintln!("Warning: ICO doesn't support the {} size.", n);
+            }
+            sizes
+        }
+        OutputFormat::Icns => {
+            let sizes = args
+                .sizes
+                .take()
+                .unwrap_or_else(|| vec![16, 32, 64, 128, 256, 512, 1024]);
+            let (sizes, skipped): (Vec<_>, Vec<_>) = sizes
+                .into_iter()
+                .partition(|n| ICNS_TYPES.iter().any(|(_, size)| size == n));
+            for n in skipped {
+                eprintln!("Warning: ICNS doesn't support the {} size.", n);
+            }
+            sizes
+        }
+        _ => {
+            if args.sizes.is_some() {
+                eprintln!("Warning: --sizes has effect only on ICO and ICNS output.");
+            }
+            Vec::new()
+        }
+    };
+
+    if matches!(format, OutputFormat::Ico | OutputFormat::Icns) {
+        if sizes.is_empty() {
+            return Err("no valid icon sizes".to_string());
+        }
+
+        if args.export_id.is_some() || args.export_area_drawing {
+            eprintln!("Warning: export options have no effect on icon output.");
+        }
     }
 
     let in_svg = args.input.clone();
-    let out_png = args.output.clone();
+    let out_path = args.output.clone();
 
     let dump = args.dump_svg.as_ref().map(|v| v.into());
     let export_id = args.export_id.as_ref().map(|v| v.to_string());
 
     // We don't have to keep named groups when we don't need them
     // because it will slow down rendering.
-    let keep_named_groups = args.query_all || export_id.is_some();
-
-    let mut fit_to = usvg::FitTo::Original;
-    let mut default_size = usvg::Size::new(100.0, 100.0).unwrap();
-    if let (Some(w), Some(h)) = (args.width, args.height) {
-        default_size = usvg::Size::new(w as f64, h as f64).unwrap();
-        fit_to = usvg::FitTo::Size(w, h);
-    } else if let Some(w) = args.width {
-        default_size = usvg::Size::new(w as f64, 100.0).unwrap();
-        fit_to = usvg::FitTo::Width(w);
-    } else if let Some(h) = args.height {
-        default_size = usvg::Size::new(100.0, h as f64).unwrap();
-        fit_to = usvg::FitTo::Height(h);
-    } else if let Some(z) = args.zoom {
-        fit_to = usvg::FitTo::Zoom(z);
-    }
-
-    let resources_dir = match args.resources_dir {
-        Some(v) => Some(v),
-        None => {
-            // Get input file absolute directory.
-            std::fs::canonicalize(&in_svg)
-                .ok()
-                .and_then(|p| p.parent().map(|p| p.to_path_buf()))
-        }
+    let keep_named_groups = args.query_all || export_id.is_some() || args.export_all_ids;
+
+    let fit_to = make_fit_to(args.width, args.height, args.zoom);
+    let default_size = match fit_to {
+        usvg::FitTo::Size(w, h) => usvg::Size::new(w as f64, h as f64).unwrap(),
+        usvg::FitTo::Width(w) => usvg::Size::new(w as f64, 100.0).unwrap(),
+        usvg::FitTo::Height(h) => usvg::Size::new(100.0, h as f64).unwrap(),
+        _ => usvg::Size::new(100.0, 100.0).unwrap(),
     };
 
+    let jobs = args.jobs.unwrap_or_else(default_jobs);
+
+    // When not set, the input file directory is used instead.
     let usvg = usvg::Options {
-        resources_dir,
+        resources_dir: args.resources_dir,
         dpi: args.dpi as f64,
         font_family: args
             .font_family
@@ -438,25 +830,49 @@ fn parse_args() -> Result<Args, String> {
         keep_named_groups,
         default_size,
         fontdb,
+        font_fallback: usvg::FontFallback::default(),
+        synthetic_bold: true,
+        synthetic_oblique: true,
         image_href_resolver: usvg::ImageHrefResolver::default(),
     };
 
     Ok(Args {
         in_svg,
-        out_png,
+        out_path,
         query_all: args.query_all,
         export_id,
+        export_all_ids: args.export_all_ids,
+        export_id_pattern: args.export_id_pattern,
         export_area_page: args.export_area_page,
         export_area_drawing: args.export_area_drawing,
         dump,
         perf: args.perf,
         quiet: args.quiet,
-        usvg,
+        batch: args.batch,
+        jobs,
+        compare: args.compare,
+        tolerance: args.tolerance.unwrap_or(1),
+        usvg: Arc::new(usvg),
         fit_to,
         background: args.background,
+        format,
+        quality: args.quality,
+        bit_depth: args.bit_depth,
+        png_compression: args.png_compression,
+        sizes,
     })
 }
 
+fn make_fit_to(width: Option<u32>, height: Option<u32>, zoom: Option<f32>) -> usvg::FitTo {
+    match (width, height, zoom) {
+        (Some(w), Some(h), _) => usvg::FitTo::Size(w, h),
+        (Some(w), None, _) => usvg::FitTo::Width(w),
+        (None, Some(h), _) => usvg::FitTo::Height(h),
+        (None, None, Some(z)) => usvg::FitTo::Zoom(z),
+        (None, None, None) => usvg::FitTo::Original,
+    }
+}
+
 fn load_fonts(args: &mut CliArgs) -> usvg::fontdb::Database {
     let mut fontdb = usvg::fontdb::Database::new();
     if !args.skip_system_fonts {
@@ -464,7 +880,7 @@ fn load_fonts(args: &mut CliArgs) -> usvg::fontdb::Database {
     }
 
     for path in &args.font_files {
-        if let Err(e) = fontdb.load_font_file(path) {
+        if let Err(e) = fontdb.load_web_font_file(path) {
             log::warn!("Failed to load '{}' cause {}.", path.display(), e);
         }
     }
@@ -485,6 +901,422 @@ fn load_fonts(args: &mut CliArgs) -> usvg::fontdb::Database {
     fontdb
 }
 
+fn process_batch(args: &Args) -> Result<(), String> {
+    let jobs = collect_jobs(args)?;
+    if jobs.is_empty() {
+        return Err("no SVG files found".to_string());
+    }
+
+    // Each thread parses its own tree, since `usvg::Tree` cannot be shared,
+    // but the options and the fonts database are loaded only once.
+    let total = jobs.len();
+    let jobs = Arc::new(jobs);
+    let next = Arc::new(AtomicUsize::new(0));
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut threads = Vec::new();
+    for _ in 0..args.jobs.min(total) {
+        let jobs = jobs.clone();
+        let next = next.clone();
+        let tx = tx.clone();
+        threads.push(std::thread::spawn(move || {
+            while let Some(job) = jobs.get(next.fetch_add(1, Ordering::Relaxed)) {
+                // The options are never modified, so it's safe to reuse them after a panic.
+                let res = std::panic::catch_unwind(AssertUnwindSafe(|| render_batch_file(job)))
+                    .unwrap_or_else(|_| Err("rendering panicked".to_string()));
+
+                if let Err(e) = res {
+                    // The receiver lives until all threads are joined.
+                    let _ = tx.send(format!("{}: {}", job.in_svg, e));
+                }
+            }
+        }));
+    }
+
+    // Otherwise, the loop below would never end.
+    drop(tx);
+
+    let mut failed = 0;
+    for e in rx {
+        eprintln!("Error: {}.", e);
+        failed += 1;
+    }
+
+    for thread in threads {
+        // Panics are caught inside the thread already.
+        let _ = thread.join();
+    }
+
+    match failed {
+        0 => Ok(()),
+        n => Err(format!("failed to render {} of {} files", n, total)),
+    }
+}
+
+fn render_batch_file(args: &Args) -> Result<(), String> {
+    if let Some(dir) = args.out_path.as_deref().and_then(|p| p.parent()) {
+        std::fs::create_dir_all(dir).map_err(|_| format!("failed to create {:?}", dir))?;
+    }
+
+    render_file(args)
+}
+
+/// Expands the batch input into a list of per-file arguments.
+fn collect_jobs(args: &Args) -> Result<Vec<Args>, String> {
+    // Checked by `parse_args`.
+    let out_dir = args.out_path.as_deref().unwrap();
+    let input = path::Path::new(&args.in_svg);
+
+    let make_job = |in_svg: &path::Path, rel_path: &path::Path| {
+        let mut job = args.clone();
+        job.in_svg = in_svg.to_string_lossy().into_owned();
+        job.out_path = Some(out_dir.join(rel_path.with_extension(args.format.extension())));
+        job
+    };
+
+    let mut jobs = Vec::new();
+    if input.is_dir() {
+        for file in collect_svg_files(input) {
+            // Unwrap is safe, because all files are inside the input directory.
+            let rel_path = file.strip_prefix(input).unwrap();
+            jobs.push(make_job(&file, rel_path));
+        }
+    } else if args.in_svg.contains(&['*', '?'][..]) {
+        // Search only inside the longest path prefix without wildcards.
+        let mut base = path::PathBuf::new();
+        let mut components = input.components().peekable();
+        while let Some(c) =
+            components.next_if(|c| !c.as_os_str().to_string_lossy().contains(&['*', '?'][..]))
+        {
+            base.push(c);
+        }
+
+        let pattern: Vec<char> = components
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("/")
+            .chars()
+            .collect();
+
+        let walk_dir = if base.as_os_str().is_empty() {
+            path::Path::new(".")
+        } else {
+            &base
+        };
+        for file in collect_files(walk_dir) {
+            // Unwrap is safe, because all files are inside the base directory.
+            let rel_path = file.strip_prefix(walk_dir).unwrap();
+            let rel_str: Vec<char> = rel_path
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+                .join("/")
+                .chars()
+                .collect();
+
+            if glob_match(&pattern, &rel_str) {
+                jobs.push(make_job(&file, rel_path));
+            }
+        }
+    } else if input.is_file() {
+        let text = std::fs::read_to_string(input)
+            .map_err(|_| "failed to read the manifest file".to_string())?;
+        let base = input.parent().unwrap_or_else(|| path::Path::new(""));
+
+        for (idx, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let job = parse_manifest_line(line, base, &make_job)
+                .map_err(|e| format!("manifest line {}: {}", idx + 1, e))?;
+            jobs.push(job);
+        }
+    } else {
+        return Err(
+            "batch input must be a directory, a glob pattern or a manifest file".to_string(),
+        );
+    }
+
+    // Do not let files silently overwrite each other.
+    let mut outputs = HashMap::new();
+    for job in &jobs {
+        // Unwrap is safe, because `make_job` always sets an output path.
+        let out_path = job.out_path.as_deref().unwrap();
+        if let Some(prev) = outputs.insert(out_path, &job.in_svg) {
+            return Err(format!(
+                "'{}' and '{}' would be both rendered to {:?}",
+                prev, job.in_svg, out_path
+            ));
+        }
+    }
+
+    Ok(jobs)
+}
+
+/// Parses a manifest line like `in.svg [out.png] [-w N] [-h N] [-z N] [--background C]`.
+///
+/// Size options replace the global ones, when set.
+fn parse_manifest_line(
+    line: &str,
+    base: &path::Path,
+    make_job: &dyn Fn(&path::Path, &path::Path) -> Args,
+) -> Result<Args, String> {
+    let mut tokens = line.split_whitespace();
+    // Unwrap is safe, because empty lines are skipped.
+    let in_path = path::Path::new(tokens.next().unwrap());
+    let in_svg = base.join(in_path);
+
+    let mut output = None;
+    let mut width = None;
+    let mut height = None;
+    let mut zoom = None;
+    let mut background = None;
+    while let Some(token) = tokens.next() {
+        let mut value = || {
+            tokens
+                .next()
+                .ok_or_else(|| format!("{} requires a value", token))
+        };
+
+        match token {
+            "-w" | "--width" => width = Some(parse_length(value()?)?),
+            "-h" | "--height" => height = Some(parse_length(value()?)?),
+            "-z" | "--zoom" => zoom = Some(parse_zoom(value()?)?),
+            "--background" => {
+                background = Some(value()?.parse().map_err(|_| "invalid background color")?)
+            }
+            _ if token.starts_with('-') => return Err(format!("unknown option '{}'", token)),
+            _ if output.is_none() => output = Some(path::PathBuf::from(token)),
+            _ => return Err(format!("unexpected argument '{}'", token)),
+        }
+    }
+
+    // Keep the input directory structure, unless the path leaves the manifest directory.
+    let is_nested = in_path
+        .components()
+        .all(|c| matches!(c, path::Component::Normal(_) | path::Component::CurDir));
+    let rel_path = if is_nested {
+        in_path
+    } else {
+        in_svg
+            .file_name()
+            .map(path::Path::new)
+            .ok_or_else(|| "invalid input file".to_string())?
+    };
+
+    let mut job = make_job(&in_svg, rel_path);
+    if let (Some(output), Some(out_dir)) =
+        (output, job.out_path.as_deref().and_then(|p| p.parent()))
+    {
+        job.out_path = Some(out_dir.join(output));
+    }
+
+    if width.is_some() || height.is_some() || zoom.is_some() {
+        job.fit_to = make_fit_to(width, height, zoom);
+    }
+
+    if background.is_some() {
+        job.background = background;
+    }
+
+    Ok(job)
+}
+
+/// Recursively collects all SVG and SVGZ files in a directory.
+fn collect_svg_files(dir: &path::Path) -> Vec<path::PathBuf> {
+    let mut files = collect_files(dir);
+    files.retain(|path| {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+        matches!(ext.as_deref(), Some("svg") | Some("svgz"))
+    });
+    files
+}
+
+/// Recursively collects all files in a directory in a stable order.
+fn collect_files(dir: &path::Path) -> Vec<path::PathBuf> {
+    fn walk(dir: &path::Path, files: &mut Vec<path::PathBuf>) {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(v) => v,
+            Err(_) => {
+                log::warn!("Failed to read the {:?} directory.", dir);
+                return;
+            }
+        };
+
+        let mut paths: Vec<_> = entries.flatten().map(|e| e.path()).collect();
+        paths.sort();
+        for path in paths {
+            if path.is_dir() {
+                walk(&path, files);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    walk(dir, &mut files);
+    files
+}
+
+/// Matches a `/`-separated path against a glob pattern.
+///
+/// `?` and `*` do not match `/`, while `**` matches any number of directories.
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['*', '*', '/', rest @ ..] => (0..=text.len())
+            .filter(|&i| i == 0 || text[i - 1] == '/')
+            .any(|i| glob_match(rest, &text[i..])),
+        ['*', '*', rest @ ..] => (0..=text.len()).any(|i| glob_match(rest, &text[i..])),
+        ['*', rest @ ..] => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != '/')
+            .any(|i| glob_match(rest, &text[i..])),
+        ['?', rest @ ..] => match text {
+            [c, text @ ..] => *c != '/' && glob_match(rest, text),
+            [] => false,
+        },
+        [p, rest @ ..] => match text {
+            [c, text @ ..] => c == p && glob_match(rest, text),
+            [] => false,
+        },
+    }
+}
+
+/// Renders each node with a matching ID into a separate file inside `out_dir`.
+///
+/// Nodes that cannot be rendered are reported and skipped.
+fn export_all_ids(args: &Args, tree: &usvg::Tree, out_dir: &path::Path) -> Result<(), String> {
+    std::fs::create_dir_all(out_dir).map_err(|_| format!("failed to create {:?}", out_dir))?;
+
+    let pattern: Option<Vec<char>> = args.export_id_pattern.as_ref().map(|p| p.chars().collect());
+
+    // Lowercase, since file systems can be case-insensitive.
+    let mut used_names = HashSet::new();
+    let mut count = 0;
+    for node in tree.root.descendants() {
+        let id = node.id().to_string();
+        if id.is_empty() {
+            continue;
+        }
+
+        if let Some(ref pattern) = pattern {
+            if !glob_match(pattern, &id.chars().collect::<Vec<_>>()) {
+                continue;
+            }
+        }
+
+        // IDs can contain any characters, but not all of them are allowed in file names.
+        let file_name: String = id
+            .chars()
+            .map(|c| match c {
+                '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+                c if c.is_control() => '_',
+                c => c,
+            })
+            .collect();
+
+        // Different IDs can have the same file name after sanitizing, like `a:b` and `a_b`.
+        let mut unique_name = file_name.clone();
+        let mut suffix = 1;
+        while !used_names.insert(unique_name.to_lowercase()) {
+            suffix += 1;
+            unique_name = format!("{}-{}", file_name, suffix);
+        }
+
+        let out_path = out_dir.join(format!("{}.{}", unique_name, args.format.extension()));
+
+        let mut job = args.clone();
+        job.export_id = Some(id);
+        let res = render_svg(&job, tree)
+            .and_then(|img| timed!(args, "Encoding", encode_raster(&job, &img)))
+            .and_then(|data| timed!(args, "Saving", write_output(&out_path, &data)));
+
+        match res {
+            Ok(()) => count += 1,
+            Err(e) => eprintln!("Warning: failed to export '{}' cause {}.", node.id(), e),
+        }
+    }
+
+    if count == 0 {
+        return Err("no IDs were exported".to_string());
+    }
+
+    Ok(())
+}
+
+fn compare_file(args: &Args, tree: &usvg::Tree, reference: &path::Path) -> Result<(), String> {
+    let img = render_svg(args, tree)?;
+    let (expected, width, height) = load_png(reference)?;
+    if (width, height) != (img.width(), img.height()) {
+        return Err(format!(
+            "rendered image size {}x{} doesn't match the reference size {}x{}",
+            img.width(),
+            img.height(),
+            width,
+            height
+        ));
+    }
+
+    let opt = resvg::CompareOptions {
+        tolerance: args.tolerance,
+    };
+    let comparison = resvg::compare_images(&expected, &to_rgba(&img, 8), width, height, &opt)
+        .ok_or_else(|| "failed to compare images".to_string())?;
+
+    println!("{}", comparison.different_pixels);
+
+    if let Some(ref path) = args.out_path {
+        let data = comparison.diff.encode_png().map_err(|e| e.to_string())?;
+        write_output(path, &data)?;
+    }
+
+    if comparison.different_pixels != 0 {
+        return Err(format!(
+            "{} of {} pixels are different",
+            comparison.different_pixels,
+            width * height
+        ));
+    }
+
+    Ok(())
+}
+
+/// Loads a PNG as non-premultiplied RGBA8.
+fn load_png(path: &path::Path) -> Result<(Vec<u8>, u32, u32), String> {
+    let file = std::fs::File::open(path).map_err(|_| format!("failed to open {:?}", path))?;
+    let mut decoder = png::Decoder::new(std::io::BufReader::new(file));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|e| e.to_string())?;
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data).map_err(|e| e.to_string())?;
+    data.truncate(info.buffer_size());
+
+    let channels = match info.color_type {
+        png::ColorType::Rgba => return Ok((data, info.width, info.height)),
+        png::ColorType::Rgb => 3,
+        png::ColorType::Grayscale => 1,
+        png::ColorType::GrayscaleAlpha => 2,
+        png::ColorType::Indexed => return Err("indexed PNG is not supported".to_string()),
+    };
+
+    let mut rgba = Vec::with_capacity(data.len() / channels * 4);
+    for c in data.chunks_exact(channels) {
+        match *c {
+            [r, g, b] => rgba.extend_from_slice(&[r, g, b, 255]),
+            [gray] => rgba.extend_from_slice(&[gray, gray, gray, 255]),
+            [gray, alpha] => rgba.extend_from_slice(&[gray, gray, gray, alpha]),
+            _ => unreachable!(),
+        }
+    }
+
+    Ok((rgba, info.width, info.height))
+}
+
 fn query_all(tree: &usvg::Tree) -> Result<(), String> {
     let mut count = 0;
     for node in tree.root.descendants() {
@@ -536,7 +1368,7 @@ fn dump_svg(_: &usvg::Tree, _: &path::Path) -> Result<(), String> {
     Ok(())
 }
 
-fn render_svg(args: Args, tree: &usvg::Tree, out_png: &path::Path) -> Result<(), String> {
+fn render_svg(args: &Args, tree: &usvg::Tree) -> Result<tiny_skia::Pixmap, String> {
     let now = std::time::Instant::now();
 
     let img = if let Some(ref id) = args.export_id {
@@ -546,7 +1378,7 @@ fn render_svg(args: Args, tree: &usvg::Tree, out_png: &path::Path) -> Result<(),
         };
 
         let bbox = node
-            .calculate_bbox()
+            .calculate_visual_bbox()
             .and_then(|r| r.to_rect())
             .ok_or_else(|| "node has zero size".to_string())?;
 
@@ -646,17 +1478,298 @@ fn render_svg(args: Args, tree: &usvg::Tree, out_png: &path::Path) -> Result<(),
     };
 
     if args.perf {
-        println!(
+        eprintln!(
             "Rendering: {:.2}ms",
             now.elapsed().as_micros() as f64 / 1000.0
         );
     }
 
-    timed!(
+    Ok(img)
+}
+
+fn encode_raster(args: &Args, img: &tiny_skia::Pixmap) -> Result<Vec<u8>, String> {
+    let (w, h) = (img.width(), img.height());
+    match args.format {
+        OutputFormat::Png => encode_png(args, img, args.bit_depth),
+        OutputFormat::Jpeg => encode_jpeg(args, img),
+        OutputFormat::Rgba => Ok(to_rgba(img, args.bit_depth)),
+        OutputFormat::Pam => {
+            let mut data = format!(
+                "P7\nWIDTH {}\nHEIGHT {}\nDEPTH 4\nMAXVAL {}\nTUPLTYPE RGB_ALPHA\nENDHDR\n",
+                w,
+                h,
+                max_value(args.bit_depth)
+            )
+            .into_bytes();
+            data.extend_from_slice(&to_rgba(img, args.bit_depth));
+            Ok(data)
+        }
+        OutputFormat::Ppm => {
+            let mut data = format!("P6\n{} {}\n{}\n", w, h, max_value(args.bit_depth)).into_bytes();
+            data.extend_from_slice(&to_rgb(args, img, args.bit_depth));
+            Ok(data)
+        }
+        OutputFormat::Ico
+        | OutputFormat::Icns
+        | OutputFormat::Pdf
+        | OutputFormat::Ps
+        | OutputFormat::Eps => unreachable!(),
+    }
+}
+
+fn encode_png(args: &Args, img: &tiny_skia::Pixmap, bit_depth: u8) -> Result<Vec<u8>, String> {
+    let mut data = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut data, img.width(), img.height());
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_compression(args.png_compression);
+        if bit_depth == 16 {
+            encoder.set_depth(png::BitDepth::Sixteen);
+        } else {
+            encoder.set_depth(png::BitDepth::Eight);
+        }
+
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+
+        // pHYs stores pixels per meter.
+        let ppm = (args.usvg.dpi / 0.0254).round() as u32;
+        let mut phys = Vec::with_capacity(9);
+        phys.extend_from_slice(&ppm.to_be_bytes());
+        phys.extend_from_slice(&ppm.to_be_bytes());
+        phys.push(1);
+        writer
+            .write_chunk(png::chunk::pHYs, &phys)
+            .map_err(|e| e.to_string())?;
+
+        writer
+            .write_image_data(&to_rgba(img, bit_depth))
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(data)
+}
+
+#[cfg(feature = "jpeg")]
+fn encode_jpeg(args: &Args, img: &tiny_skia::Pixmap) -> Result<Vec<u8>, String> {
+    use std::convert::TryFrom;
+
+    let too_big = |_| "the image is too big for JPEG".to_string();
+    let w = u16::try_from(img.width()).map_err(too_big)?;
+    let h = u16::try_from(img.height()).map_err(too_big)?;
+
+    let mut data = Vec::new();
+    let mut encoder = jpeg_encoder::Encoder::new(&mut data, args.quality);
+    let dpi = args.usvg.dpi as u16;
+    encoder.set_density(jpeg_encoder::Density::Inch { x: dpi, y: dpi });
+    encoder
+        .encode(&to_rgb(args, img, 8), w, h, jpeg_encoder::ColorType::Rgb)
+        .map_err(|e| e.to_string())?;
+
+    Ok(data)
+}
+
+#[cfg(not(feature = "jpeg"))]
+fn encode_jpeg(_: &Args, _: &tiny_skia::Pixmap) -> Result<Vec<u8>, String> {
+    Err("the jpeg feature is not enabled".to_string())
+}
+
+/// ICNS PNG entry types and their sizes.
+///
+/// The same size can be stored multiple times, for normal and Retina displays.
+const ICNS_TYPES: &[(&[u8; 4], u32)] = &[
+    (b"icp4", 16),
+    (b"icp5", 32),
+    (b"ic11", 32),
+    (b"icp6", 64),
+    (b"ic12", 64),
+    (b"ic07", 128),
+    (b"ic08", 256),
+    (b"ic13", 256),
+    (b"ic09", 512),
+    (b"ic14", 512),
+    (b"ic10", 1024),
+];
+
+/// Renders each icon size into a PNG and packs them into an ICO or ICNS container.
+fn render_icon(args: &Args, tree: &usvg::Tree) -> Result<Vec<u8>, String> {
+    let mut entries = Vec::with_capacity(args.sizes.len());
+    for &size in &args.sizes {
+        let fit_size = usvg::FitTo::Size(size, size)
+            .fit_to(tree.size.to_screen_size())
+            .ok_or_else(|| "target size is zero".to_string())?;
+
+        // Unwrap is safe, because `size` is always positive.
+        let mut pixmap = tiny_skia::Pixmap::new(size, size).unwrap();
+        if let Some(background) = args.background {
+            pixmap.fill(svg_to_skia_color(background));
+        }
+
+        // Icons are always square, therefore other images are centered.
+        let ts = tiny_skia::Transform::from_translate(
+            ((size - fit_size.width()) / 2) as f32,
+            ((size - fit_size.height()) / 2) as f32,
+        );
+        resvg::render(tree, usvg::FitTo::Size(size, size), ts, pixmap.as_mut());
+
+        entries.push((size, encode_png(args, &pixmap, 8)?));
+    }
+
+    if args.format == OutputFormat::Ico {
+        Ok(write_ico(&entries))
+    } else {
+        Ok(write_icns(&entries))
+    }
+}
+
+fn write_ico(entries: &[(u32, Vec<u8>)]) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(&0u16.to_le_bytes()); // reserved
+    data.extend_from_slice(&1u16.to_le_bytes()); // icon type
+    data.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+
+    let mut offset = 6 + 16 * entries.len() as u32;
+    for (size, png) in entries {
+        // 256 is stored as 0.
+        let dim = if *size >= 256 { 0 } else { *size as u8 };
+        data.push(dim); // width
+        data.push(dim); // height
+        data.push(0); // palette size
+        data.push(0); // reserved
+        data.extend_from_slice(&1u16.to_le_bytes()); // color planes
+        data.extend_from_slice(&32u16.to_le_bytes()); // bits per pixel
+        data.extend_from_slice(&(png.len() as u32).to_le_bytes());
+        data.extend_from_slice(&offset.to_le_bytes());
+        offset += png.len() as u32;
+    }
+
+    for (_, png) in entries {
+        data.extend_from_slice(png);
+    }
+
+    data
+}
+
+fn write_icns(entries: &[(u32, Vec<u8>)]) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend_from_slice(b"icns");
+    data.extend_from_slice(&[0; 4]); // file length, patched later
+
+    for (kind, kind_size) in ICNS_TYPES {
+        if let Some((_, png)) = entries.iter().find(|(size, _)| size == kind_size) {
+            data.extend_from_slice(*kind);
+            data.extend_from_slice(&(png.len() as u32 + 8).to_be_bytes());
+            data.extend_from_slice(png);
+        }
+    }
+
+    let len = (data.len() as u32).to_be_bytes();
+    data[4..8].copy_from_slice(&len);
+    data
+}
+
+fn max_value(bit_depth: u8) -> u32 {
+    if bit_depth == 16 {
+        65535
+    } else {
+        255
+    }
+}
+
+/// Writes a sample, scaling it to 16 bits when needed.
+///
+/// Rendering is done with 8 bits per channel, so 16 bit samples are simply padded:
+/// `v * 257` is exactly `v` stored in both bytes. This is the correct scaling,
+/// but no precision is gained.
+///
+/// 16 bit samples are big endian, which is what PNG, PAM and PPM expect.
+fn push_sample(data: &mut Vec<u8>, v: u8, bit_depth: u8) {
+    data.push(v);
+    if bit_depth == 16 {
+        data.push(v);
+    }
+}
+
+/// Returns non-premultiplied RGBA samples.
+fn to_rgba(img: &tiny_skia::Pixmap, bit_depth: u8) -> Vec<u8> {
+    let mut data = Vec::with_capacity(img.data().len() * bit_depth as usize / 8);
+    for p in img.pixels() {
+        let c = p.demultiply();
+        for v in &[c.red(), c.green(), c.blue(), c.alpha()] {
+            push_sample(&mut data, *v, bit_depth);
+        }
+    }
+
+    data
+}
+
+/// Returns RGB samples of an image filled with the background color or white.
+fn to_rgb(args: &Args, img: &tiny_skia::Pixmap, bit_depth: u8) -> Vec<u8> {
+    let background = args
+        .background
+        .map(svg_to_skia_color)
+        .unwrap_or(tiny_skia::Color::WHITE);
+
+    // Unwrap is safe, because `img` size is already valid.
+    let mut flat = tiny_skia::Pixmap::new(img.width(), img.height()).unwrap();
+    flat.fill(background);
+    flat.draw_pixmap(
+        0,
+        0,
+        img.as_ref(),
+        &tiny_skia::PixmapPaint::default(),
+        tiny_skia::Transform::default(),
+        None,
+    );
+
+    let mut data = Vec::with_capacity(flat.data().len() / 4 * 3 * bit_depth as usize / 8);
+    for p in flat.pixels() {
+        let c = p.demultiply();
+        for v in &[c.red(), c.green(), c.blue()] {
+            push_sample(&mut data, *v, bit_depth);
+        }
+    }
+
+    data
+}
+
+fn write_output(path: &path::Path, data: &[u8]) -> Result<(), String> {
+    if path == path::Path::new("-") {
+        use std::io::Write;
+        std::io::stdout()
+            .write_all(data)
+            .map_err(|_| "failed to write to the stdout".to_string())
+    } else {
+        std::fs::write(path, data).map_err(|_| format!("failed to write a file {:?}", path))
+    }
+}
+
+#[cfg(feature = "pdf")]
+fn save_pdf(args: &Args, tree: &usvg::Tree) -> Result<Vec<u8>, String> {
+    Ok(timed!(
         args,
-        "Saving",
-        img.save_png(out_png).map_err(|e| e.to_string())
-    )
+        "Rendering",
+        resvg::pdf::convert(tree, &resvg::pdf::Options::default())
+    ))
+}
+
+#[cfg(not(feature = "pdf"))]
+fn save_pdf(_: &Args, _: &usvg::Tree) -> Result<Vec<u8>, String> {
+    Err("the pdf feature is not enabled".to_string())
+}
+
+#[cfg(feature = "ps")]
+fn save_ps(args: &Args, tree: &usvg::Tree) -> Result<Vec<u8>, String> {
+    let opt = resvg::ps::Options {
+        eps: args.format == OutputFormat::Eps,
+        ..resvg::ps::Options::default()
+    };
+
+    Ok(timed!(args, "Rendering", resvg::ps::convert(tree, &opt)))
+}
+
+#[cfg(not(feature = "ps"))]
+fn save_ps(_: &Args, _: &usvg::Tree) -> Result<Vec<u8>, String> {
+    Err("the ps feature is not enabled".to_string())
 }
 
 fn svg_to_skia_color(color: svgtypes::Color) -> tiny_skia::Color {
@@ -6,13 +6,14 @@ use usvg::TransformFromBBox;
 
 use crate::{
     render::{Canvas, RenderState},
-    ConvTransform,
+    ConvTransform, RenderCache,
 };
 
 pub fn mask(
     tree: &usvg::Tree,
     mask: &usvg::Mask,
     bbox: usvg::PathBbox,
+    cache: &mut RenderCache,
     canvas: &mut Canvas,
 ) -> Option<()> {
     let bbox = if mask.units == usvg::Units::ObjectBoundingBox
@@ -55,7 +56,13 @@ pub fn mask(
             mask_canvas.apply_transform(usvg::Transform::from_bbox(bbox).to_native());
         }
 
-        crate::render::render_group(tree, &mask.root, &mut RenderState::Ok, &mut mask_canvas);
+        crate::render::render_group(
+            tree,
+            &mask.root,
+            &mut RenderState::Ok,
+            cache,
+            &mut mask_canvas,
+        );
     }
 
     {
@@ -64,7 +71,7 @@ pub fn mask(
     }
 
     if let Some(ref mask) = mask.mask {
-        self::mask(tree, mask, bbox.to_path_bbox(), canvas);
+        self::mask(tree, mask, bbox.to_path_bbox(), cache, canvas);
     }
 
     let mut paint = tiny_skia::PixmapPaint::default();
@@ -2,11 +2,16 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::f64::consts::SQRT_2;
+use std::ops::Range;
+use std::rc::Rc;
+
 use usvg::TransformFromBBox;
 
 use crate::{
+    cache::PatternTile,
     render::{Canvas, RenderState},
-    ConvTransform, OptionLog,
+    ConvTransform, OptionLog, RenderCache,
 };
 
 pub fn fill(
@@ -16,10 +21,9 @@ pub fn fill(
     path: &tiny_skia::Path,
     anti_alias: bool,
     blend_mode: tiny_skia::BlendMode,
+    cache: &mut RenderCache,
     canvas: &mut Canvas,
 ) -> Option<()> {
-    let pattern_pixmap;
-
     let mut paint = tiny_skia::Paint::default();
 
     let opacity = fill.opacity;
@@ -34,11 +38,9 @@ pub fn fill(
             prepare_radial(rg, opacity, bbox, &mut paint);
         }
         usvg::Paint::Pattern(ref pattern) => {
-            let global_ts = usvg::Transform::from_native(canvas.transform);
-            let (patt_pix, patt_ts) = prepare_pattern_pixmap(tree, pattern, &global_ts, bbox)?;
-
-            pattern_pixmap = patt_pix;
-            paint.shader = prepare_pattern(&pattern_pixmap, patt_ts, opacity);
+            let region = device_region(path, 0.0, canvas);
+            let tile = pattern_tile(tree, pattern, canvas.transform, bbox, region, cache)?;
+            paint.shader = prepare_pattern(tile, opacity);
         }
     }
 
@@ -65,10 +67,9 @@ pub fn stroke(
     path: &tiny_skia::Path,
     anti_alias: bool,
     blend_mode: tiny_skia::BlendMode,
+    cache: &mut RenderCache,
     canvas: &mut Canvas,
 ) -> Option<()> {
-    let pattern_pixmap;
-
     let mut paint = tiny_skia::Paint::default();
     let mut props = tiny_skia::Stroke::default();
 
@@ -85,11 +86,11 @@ pub fn stroke(
                 prepare_radial(rg, opacity, bbox, &mut paint);
             }
             usvg::Paint::Pattern(ref pattern) => {
-                let global_ts = usvg::Transform::from_native(canvas.transform);
-                let (patt_pix, patt_ts) = prepare_pattern_pixmap(tree, pattern, &global_ts, bbox)?;
-
-                pattern_pixmap = patt_pix;
-                paint.shader = prepare_pattern(&pattern_pixmap, patt_ts, opacity);
+                // A rough stroke outline extent, which includes miter joins and square caps.
+                let outset = stroke.width.get() * stroke.miterlimit.get().max(SQRT_2) / 2.0;
+                let region = device_region(path, outset as f32, canvas);
+                let tile = pattern_tile(tree, pattern, canvas.transform, bbox, region, cache)?;
+                paint.shader = prepare_pattern(tile, opacity);
             }
         }
 
@@ -239,21 +240,411 @@ fn prepare_radial(
     Some(())
 }
 
-fn prepare_pattern_pixmap(
+/// The maximum number of pattern cells that can be rendered directly in device space.
+///
+/// Each cell of a rotated or skewed pattern is rendered separately,
+/// therefore patterns with more cells are rendered using a resampled tile pixmap instead.
+const MAX_DEVICE_CELLS: i64 = 4096;
+
+/// The maximum number of cells along each axis of a device-aligned tile.
+const MAX_ALIGNED_TILE_CELLS: i64 = 16;
+
+/// The maximum size of a device-aligned tile along each axis.
+const MAX_ALIGNED_TILE_SIZE: u32 = 4096;
+
+/// The maximum relative difference between the exact and the rounded device-aligned tile size.
+///
+/// A tile is repeated with the rounded period, so cells are shifted by at most
+/// 0.01px per 1000px.
+const MAX_ALIGNED_TILE_ERROR: f64 = 0.00001;
+
+/// Returns a pattern pixmap from the cache, rendering it first when needed.
+///
+/// When the pattern is axis-aligned in device space, a tile that is aligned with the device
+/// pixels is rendered once and repeated without resampling.
+/// When the pattern is rotated or skewed, the pattern is rendered directly in device space
+/// for the provided `region`, since a tile pixmap would be resampled and become blurry.
+/// Otherwise, a single tile is rendered and then resampled.
+fn pattern_tile<'a>(
     tree: &usvg::Tree,
-    pattern: &usvg::Pattern,
-    global_ts: &usvg::Transform,
+    pattern: &Rc<usvg::Pattern>,
+    global_ts: tiny_skia::Transform,
     bbox: usvg::PathBbox,
-) -> Option<(tiny_skia::Pixmap, usvg::Transform)> {
-    let r = if pattern.units == usvg::Units::ObjectBoundingBox {
+    region: Option<tiny_skia::IntRect>,
+    cache: &'a mut RenderCache,
+) -> Option<&'a PatternTile> {
+    // Pattern content doesn't depend on the object bbox when only user space units are used,
+    // so such a pattern can be shared between all objects that reference it.
+    let key_bbox = if pattern.units == usvg::Units::ObjectBoundingBox
+        || (pattern.content_units == usvg::Units::ObjectBoundingBox && pattern.view_box.is_none())
+    {
+        bbox
+    } else {
+        usvg::PathBbox::new(0.0, 0.0, 1.0, 1.0).unwrap()
+    };
+
+    let is_skewed = is_skewed(pattern, global_ts);
+    let region = region.filter(|_| is_skewed);
+    let cached = cache
+        .pattern_tile(pattern, global_ts, key_bbox)
+        .map(|tile| tile.region);
+    let is_valid = match (cached, region) {
+        (Some(None), _) => true,
+        (Some(Some(cached)), Some(region)) => cached.contains(&region),
+        _ => false,
+    };
+
+    if !is_valid {
+        let ts = usvg::Transform::from_native(global_ts);
+
+        // Pattern content is rendered for each cell separately,
+        // so caching its group layers would only push out the layers of the main tree.
+        let mut content_cache = RenderCache::without_layers();
+
+        let mut layer = None;
+        if let Some(region) = region {
+            // Extend the previously rendered region, so the layer could be reused
+            // by all objects filled with this pattern.
+            let region = match cached {
+                Some(Some(cached)) => union_rect(cached, region),
+                _ => Some(region),
+            };
+
+            if let Some(region) = region {
+                layer = prepare_pattern_layer(tree, pattern, &ts, bbox, region, &mut content_cache)
+                    .map(|(pixmap, pixmap_ts)| (pixmap, pixmap_ts, Some(region), true));
+            }
+        } else if !is_skewed {
+            layer = prepare_aligned_pattern_tile(tree, pattern, &ts, bbox, &mut content_cache)
+                .map(|(pixmap, pixmap_ts)| (pixmap, pixmap_ts, None, true));
+        }
+
+        let (pixmap, pixmap_ts, region, is_aligned) = match layer {
+            Some(v) => v,
+            None => {
+                let (pixmap, pixmap_ts) =
+                    prepare_pattern_pixmap(tree, pattern, &ts, bbox, &mut content_cache)?;
+                (pixmap, pixmap_ts, None, false)
+            }
+        };
+
+        cache.insert_pattern_tile(
+            pattern, global_ts, key_bbox, pixmap, pixmap_ts, region, is_aligned,
+        );
+    }
+
+    cache.pattern_tile(pattern, global_ts, key_bbox)
+}
+
+/// Checks that pattern tiles are not axis-aligned in device space.
+fn is_skewed(pattern: &usvg::Pattern, global_ts: tiny_skia::Transform) -> bool {
+    use usvg::FuzzyZero;
+
+    let mut ts = usvg::Transform::from_native(global_ts);
+    ts.append(&pattern.transform);
+    !(ts.b.is_fuzzy_zero() && ts.c.is_fuzzy_zero())
+}
+
+/// Returns the device space region covered by a path.
+fn device_region(
+    path: &tiny_skia::Path,
+    outset: f32,
+    canvas: &Canvas,
+) -> Option<tiny_skia::IntRect> {
+    let b = path.bounds();
+    let rect = tiny_skia::Rect::from_ltrb(
+        b.left() - outset,
+        b.top() - outset,
+        b.right() + outset,
+        b.bottom() + outset,
+    )?;
+    let rect = tiny_skia::PathBuilder::from_rect(rect)
+        .transform(canvas.transform)?
+        .bounds()
+        .round_out()?;
+
+    let canvas_rect =
+        tiny_skia::IntRect::from_xywh(0, 0, canvas.pixmap.width(), canvas.pixmap.height())?;
+    rect.intersect(&canvas_rect)
+}
+
+fn union_rect(r1: tiny_skia::IntRect, r2: tiny_skia::IntRect) -> Option<tiny_skia::IntRect> {
+    tiny_skia::IntRect::from_ltrb(
+        r1.left().min(r2.left()),
+        r1.top().min(r2.top()),
+        r1.right().max(r2.right()),
+        r1.bottom().max(r2.bottom()),
+    )
+}
+
+/// Returns a pattern tile rect in user space.
+fn pattern_rect(pattern: &usvg::Pattern, bbox: usvg::PathBbox) -> Option<usvg::Rect> {
+    if pattern.units == usvg::Units::ObjectBoundingBox {
         let bbox = bbox
             .to_rect()
             .log_none(|| log::warn!("Pattern on zero-sized shapes is not allowed."))?;
 
-        pattern.rect.bbox_transform(bbox)
+        Some(pattern.rect.bbox_transform(bbox))
     } else {
-        pattern.rect
+        Some(pattern.rect)
+    }
+}
+
+/// Returns a pattern content transform relative to a tile.
+fn pattern_content_transform(
+    pattern: &usvg::Pattern,
+    rect: usvg::Rect,
+    bbox: usvg::PathBbox,
+) -> usvg::Transform {
+    if let Some(vbox) = pattern.view_box {
+        usvg::utils::view_box_to_transform(vbox.rect, vbox.aspect, rect.size())
+    } else if pattern.content_units == usvg::Units::ObjectBoundingBox {
+        // 'Note that this attribute has no effect if attribute `viewBox` is specified.'
+
+        // We don't use Transform::from_bbox(bbox) because `x` and `y` should be
+        // ignored for some reasons...
+        usvg::Transform::new_scale(bbox.width(), bbox.height())
+    } else {
+        usvg::Transform::default()
+    }
+}
+
+/// Renders all pattern cells that intersect `region` directly in device space.
+///
+/// Unlike a tile pixmap, the result doesn't need any resampling,
+/// so skewed and rotated patterns stay sharp.
+fn prepare_pattern_layer(
+    tree: &usvg::Tree,
+    pattern: &usvg::Pattern,
+    global_ts: &usvg::Transform,
+    bbox: usvg::PathBbox,
+    region: tiny_skia::IntRect,
+    cache: &mut RenderCache,
+) -> Option<(tiny_skia::Pixmap, usvg::Transform)> {
+    let r = pattern_rect(pattern, bbox)?;
+
+    let mut ts = *global_ts;
+    ts.append(&pattern.transform);
+
+    let cells = visible_cells(ts.to_native(), r, region)?;
+    let mut layer = tiny_skia::Pixmap::new(region.width(), region.height())?;
+    render_pattern_cells(tree, pattern, r, bbox, ts, cells, region, &mut layer, cache);
+
+    // The layer is in device space, so the shader transform must undo the canvas transform.
+    let ts = global_ts
+        .to_native()
+        .invert()?
+        .pre_translate(region.x() as f32, region.y() as f32);
+
+    Some((layer, usvg::Transform::from_native(ts)))
+}
+
+/// Renders a repeatable tile of an axis-aligned pattern, which is aligned with the device pixels.
+///
+/// When the cell size in device space is fractional, several cells are combined into a single tile,
+/// so the tile size is an integer and the tile can be repeated without resampling.
+fn prepare_aligned_pattern_tile(
+    tree: &usvg::Tree,
+    pattern: &usvg::Pattern,
+    global_ts: &usvg::Transform,
+    bbox: usvg::PathBbox,
+    cache: &mut RenderCache,
+) -> Option<(tiny_skia::Pixmap, usvg::Transform)> {
+    let r = pattern_rect(pattern, bbox)?;
+
+    let mut ts = *global_ts;
+    ts.append(&pattern.transform);
+
+    let cols = cells_per_tile(r.width() * ts.a.abs())?;
+    let rows = cells_per_tile(r.height() * ts.d.abs())?;
+    let width = (r.width() * ts.a.abs() * cols as f64).round() as u32;
+    let height = (r.height() * ts.d.abs() * rows as f64).round() as u32;
+    if width > MAX_ALIGNED_TILE_SIZE || height > MAX_ALIGNED_TILE_SIZE {
+        return None;
+    }
+
+    let mut tile = tiny_skia::Pixmap::new(width, height)?;
+
+    // Start the tile at the pixel that contains the pattern origin,
+    // so the subpixel offset of the cells is preserved.
+    let (x, y) = ts.apply(r.x(), r.y());
+    let tile_rect =
+        tiny_skia::IntRect::from_xywh(x.floor() as i32, y.floor() as i32, width, height)?;
+
+    // Cells that are only partially inside the tile are rendered as well,
+    // so anti-aliased edges are the same on each side of the tile.
+    let cells = visible_cells(ts.to_native(), r, tile_rect)?;
+    render_pattern_cells(
+        tree, pattern, r, bbox, ts, cells, tile_rect, &mut tile, cache,
+    );
+
+    let ts = global_ts
+        .to_native()
+        .invert()?
+        .pre_translate(tile_rect.x() as f32, tile_rect.y() as f32);
+
+    Some((tile, usvg::Transform::from_native(ts)))
+}
+
+/// Returns the minimal number of cells of `size` device pixels,
+/// which have an integer size together.
+///
+/// Cells are not snapped to the device pixels. Only the tile size is rounded,
+/// and only when the rounding error is below `MAX_ALIGNED_TILE_ERROR`.
+fn cells_per_tile(size: f64) -> Option<i64> {
+    if !size.is_finite() || size < 1.0 {
+        return None;
+    }
+
+    (1..=MAX_ALIGNED_TILE_CELLS).find(|n| {
+        let size = size * *n as f64;
+        (size - size.round()).abs() < size * MAX_ALIGNED_TILE_ERROR
+    })
+}
+
+/// Returns pattern columns and rows that intersect a device space `region`.
+///
+/// `ts` maps the pattern space onto the device.
+/// Returns `None` when there are more than `MAX_DEVICE_CELLS` cells.
+fn visible_cells(
+    ts: tiny_skia::Transform,
+    r: usvg::Rect,
+    region: tiny_skia::IntRect,
+) -> Option<(Range<i64>, Range<i64>)> {
+    // Map the region into the pattern space to find out which cells are visible.
+    let mut points = [
+        tiny_skia::Point::from_xy(region.left() as f32, region.top() as f32),
+        tiny_skia::Point::from_xy(region.right() as f32, region.top() as f32),
+        tiny_skia::Point::from_xy(region.right() as f32, region.bottom() as f32),
+        tiny_skia::Point::from_xy(region.left() as f32, region.bottom() as f32),
+    ];
+    ts.invert()?.map_points(&mut points);
+
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+    for p in &points {
+        min_x = min_x.min(p.x as f64);
+        min_y = min_y.min(p.y as f64);
+        max_x = max_x.max(p.x as f64);
+        max_y = max_y.max(p.y as f64);
+    }
+
+    let min_col = ((min_x - r.x()) / r.width()).floor() as i64;
+    let max_col = ((max_x - r.x()) / r.width()).ceil() as i64;
+    let min_row = ((min_y - r.y()) / r.height()).floor() as i64;
+    let max_row = ((max_y - r.y()) / r.height()).ceil() as i64;
+    if (max_col - min_col).saturating_mul(max_row - min_row) > MAX_DEVICE_CELLS {
+        return None;
+    }
+
+    Some((min_col..max_col, min_row..max_row))
+}
+
+/// Renders pattern cells onto `layer`, which covers a device space `region`.
+///
+/// `ts` maps the pattern space onto the device.
+fn render_pattern_cells(
+    tree: &usvg::Tree,
+    pattern: &usvg::Pattern,
+    r: usvg::Rect,
+    bbox: usvg::PathBbox,
+    ts: usvg::Transform,
+    (cols, rows): (Range<i64>, Range<i64>),
+    region: tiny_skia::IntRect,
+    layer: &mut tiny_skia::Pixmap,
+    cache: &mut RenderCache,
+) {
+    let content_ts = pattern_content_transform(pattern, r, bbox);
+    let tile_rect = match tiny_skia::Rect::from_xywh(0.0, 0.0, r.width() as f32, r.height() as f32)
+    {
+        Some(v) => v,
+        None => return,
     };
+    let tile_path = tiny_skia::PathBuilder::from_rect(tile_rect);
+
+    for row in rows {
+        for col in cols.clone() {
+            let mut cell_ts = ts;
+            cell_ts.translate(
+                r.x() + col as f64 * r.width(),
+                r.y() + row as f64 * r.height(),
+            );
+            let cell_ts = cell_ts.to_native();
+
+            let cell_rect = match tile_path
+                .clone()
+                .transform(cell_ts)
+                .and_then(|p| p.bounds().round_out())
+                .and_then(|r| r.intersect(&region))
+            {
+                Some(v) => v,
+                None => continue,
+            };
+
+            let offset_ts =
+                tiny_skia::Transform::from_translate(-cell_rect.x() as f32, -cell_rect.y() as f32);
+
+            let mut pixmap = match tiny_skia::Pixmap::new(cell_rect.width(), cell_rect.height()) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            {
+                let mut canvas = Canvas::from(pixmap.as_mut());
+                canvas.apply_transform(offset_ts.pre_concat(cell_ts));
+                canvas.apply_transform(content_ts.to_native());
+                crate::render::render_group(
+                    tree,
+                    &pattern.root,
+                    &mut RenderState::Ok,
+                    cache,
+                    &mut canvas,
+                );
+            }
+
+            // Clip the content to the cell.
+            // Anti-aliased edges of the neighbor cells are summed up, so there are no seams.
+            let mut mask = match tiny_skia::Pixmap::new(cell_rect.width(), cell_rect.height()) {
+                Some(v) => v,
+                None => continue,
+            };
+            let mut paint = tiny_skia::Paint::default();
+            paint.set_color_rgba8(0, 0, 0, 255);
+            mask.fill_rect(tile_rect, &paint, offset_ts.pre_concat(cell_ts), None);
+            pixmap.draw_pixmap(
+                0,
+                0,
+                mask.as_ref(),
+                &tiny_skia::PixmapPaint {
+                    blend_mode: tiny_skia::BlendMode::DestinationIn,
+                    ..tiny_skia::PixmapPaint::default()
+                },
+                tiny_skia::Transform::identity(),
+                None,
+            );
+
+            layer.draw_pixmap(
+                cell_rect.x() - region.x(),
+                cell_rect.y() - region.y(),
+                pixmap.as_ref(),
+                &tiny_skia::PixmapPaint {
+                    blend_mode: tiny_skia::BlendMode::Plus,
+                    ..tiny_skia::PixmapPaint::default()
+                },
+                tiny_skia::Transform::identity(),
+                None,
+            );
+        }
+    }
+}
+
+fn prepare_pattern_pixmap(
+    tree: &usvg::Tree,
+    pattern: &usvg::Pattern,
+    global_ts: &usvg::Transform,
+    bbox: usvg::PathBbox,
+    cache: &mut RenderCache,
+) -> Option<(tiny_skia::Pixmap, usvg::Transform)> {
+    let r = pattern_rect(pattern, bbox)?;
 
     let mut ts2 = global_ts.clone();
     ts2.append(&pattern.transform);
@@ -264,18 +655,15 @@ fn prepare_pattern_pixmap(
     let mut canvas = Canvas::from(pixmap.as_mut());
 
     canvas.scale(sx as f32, sy as f32);
-    if let Some(vbox) = pattern.view_box {
-        let ts = usvg::utils::view_box_to_transform(vbox.rect, vbox.aspect, r.size());
-        canvas.apply_transform(ts.to_native());
-    } else if pattern.content_units == usvg::Units::ObjectBoundingBox {
-        // 'Note that this attribute has no effect if attribute `viewBox` is specified.'
-
-        // We don't use Transform::from_bbox(bbox) because `x` and `y` should be
-        // ignored for some reasons...
-        canvas.scale(bbox.width() as f32, bbox.height() as f32);
-    }
-
-    crate::render::render_group(tree, &pattern.root, &mut RenderState::Ok, &mut canvas);
+    canvas.apply_transform(pattern_content_transform(pattern, r, bbox).to_native());
+
+    crate::render::render_group(
+        tree,
+        &pattern.root,
+        &mut RenderState::Ok,
+        cache,
+        &mut canvas,
+    );
 
     let mut ts = usvg::Transform::default();
     ts.append(&pattern.transform);
@@ -285,16 +673,161 @@ fn prepare_pattern_pixmap(
     Some((pixmap, ts))
 }
 
-fn prepare_pattern(
-    pixmap: &tiny_skia::Pixmap,
-    ts: usvg::Transform,
-    opacity: usvg::Opacity,
-) -> tiny_skia::Shader {
+fn prepare_pattern(tile: &PatternTile, opacity: usvg::Opacity) -> tiny_skia::Shader {
+    let mode = if tile.region.is_some() {
+        tiny_skia::SpreadMode::Pad
+    } else {
+        tiny_skia::SpreadMode::Repeat
+    };
+
+    // A device-aligned pixmap must not be resampled.
+    let quality = if tile.is_aligned {
+        tiny_skia::FilterQuality::Nearest
+    } else {
+        tiny_skia::FilterQuality::Bicubic
+    };
+
     tiny_skia::Pattern::new(
-        pixmap.as_ref(),
-        tiny_skia::SpreadMode::Repeat,
-        tiny_skia::FilterQuality::Bicubic,
+        tile.pixmap.as_ref(),
+        mode,
+        quality,
         opacity.get() as f32,
-        ts.to_native(),
+        tile.pixmap_ts.to_native(),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cells_per_tile_1() {
+        assert_eq!(cells_per_tile(15.0), Some(1));
+        assert_eq!(cells_per_tile(10.5), Some(2));
+        assert_eq!(cells_per_tile(28.8), Some(5));
+        assert_eq!(cells_per_tile(22.0 / 3.0), Some(3));
+    }
+
+    #[test]
+    fn cells_per_tile_2() {
+        assert_eq!(cells_per_tile(std::f64::consts::PI), None);
+        assert_eq!(cells_per_tile(0.5), None);
+        assert_eq!(cells_per_tile(f64::NAN), None);
+    }
+
+    #[test]
+    fn visible_cells_1() {
+        let r = usvg::Rect::new(0.0, 0.0, 1.0, 1.0).unwrap();
+        let region = tiny_skia::IntRect::from_xywh(0, 0, 64, 64).unwrap();
+        let cells = visible_cells(tiny_skia::Transform::identity(), r, region).unwrap();
+        assert_eq!(cells, (0..64, 0..64));
+    }
+
+    #[test]
+    fn visible_cells_2() {
+        // One more column than allowed.
+        let r = usvg::Rect::new(0.0, 0.0, 1.0, 1.0).unwrap();
+        let region = tiny_skia::IntRect::from_xywh(0, 0, 65, 64).unwrap();
+        assert_eq!(
+            visible_cells(tiny_skia::Transform::identity(), r, region),
+            None
+        );
+    }
+
+    #[test]
+    fn visible_cells_3() {
+        // Rotated cells are mapped via the region bbox.
+        let r = usvg::Rect::new(0.0, 0.0, 10.0, 10.0).unwrap();
+        let region = tiny_skia::IntRect::from_xywh(0, 0, 20, 20).unwrap();
+        let ts = tiny_skia::Transform::from_row(0.0, 1.0, -1.0, 0.0, 20.0, 0.0);
+        let cells = visible_cells(ts, r, region).unwrap();
+        assert_eq!(cells, (0..2, 0..2));
+    }
+
+    #[test]
+    fn visible_cells_4() {
+        // A tiny tile under a huge transform.
+        let r = usvg::Rect::new(0.0, 0.0, 0.001, 0.001).unwrap();
+        let region = tiny_skia::IntRect::from_xywh(0, 0, 1000, 1000).unwrap();
+        let ts = tiny_skia::Transform::from_scale(1000.0, 1000.0);
+        assert_eq!(visible_cells(ts, r, region), None);
+    }
+
+    fn render_tile(pattern_attrs: &str, scale: f32, check: impl Fn(&PatternTile)) {
+        let svg = format!(
+            "<svg viewBox='0 0 200 200' xmlns='http://www.w3.org/2000/svg'>
+                <pattern id='patt1' patternUnits='userSpaceOnUse' {}>
+                    <rect width='5' height='5' fill='green'/>
+                </pattern>
+                <rect width='200' height='200' fill='url(#patt1)'/>
+            </svg>",
+            pattern_attrs
+        );
+        let tree = usvg::Tree::from_str(&svg, &usvg::Options::default().to_ref()).unwrap();
+        let node = tree.root.last_child().unwrap();
+        let pattern = match *node.borrow() {
+            usvg::NodeKind::Path(ref path) => match path.fill.as_ref().unwrap().paint {
+                usvg::Paint::Pattern(ref pattern) => pattern.clone(),
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+
+        let bbox = usvg::PathBbox::new(0.0, 0.0, 200.0, 200.0).unwrap();
+        let size = (200.0 * scale) as u32;
+        let region = tiny_skia::IntRect::from_xywh(0, 0, size, size);
+        let ts = tiny_skia::Transform::from_scale(scale, scale);
+        let mut cache = RenderCache::without_layers();
+        check(pattern_tile(&tree, &pattern, ts, bbox, region, &mut cache).unwrap());
+    }
+
+    #[test]
+    fn pattern_tile_skewed() {
+        render_tile(
+            "width='10' height='10' patternTransform='skewX(20)'",
+            1.5,
+            |tile| {
+                assert_eq!(tile.region, tiny_skia::IntRect::from_xywh(0, 0, 300, 300));
+                assert!(tile.is_aligned);
+            },
+        );
+    }
+
+    #[test]
+    fn pattern_tile_skewed_too_many_cells() {
+        // 4000x4000 cells. Fallbacks to a resampled tile.
+        render_tile(
+            "width='0.05' height='0.05' patternTransform='skewX(20)'",
+            1.5,
+            |tile| {
+                assert_eq!(tile.region, None);
+                assert!(!tile.is_aligned);
+            },
+        );
+    }
+
+    #[test]
+    fn pattern_tile_aligned() {
+        // A 15px cell at a half pixel offset.
+        render_tile("x='5' width='10' height='10'", 1.5, |tile| {
+            assert_eq!(tile.region, None);
+            assert!(tile.is_aligned);
+            assert_eq!((tile.pixmap.width(), tile.pixmap.height()), (15, 15));
+        });
+
+        // A 28.8px cell requires 5 cells per tile.
+        render_tile("width='19.2' height='10'", 1.5, |tile| {
+            assert!(tile.is_aligned);
+            assert_eq!((tile.pixmap.width(), tile.pixmap.height()), (144, 15));
+        });
+    }
+
+    #[test]
+    fn pattern_tile_not_aligned() {
+        // A pi-sized cell cannot be aligned with the device pixels.
+        render_tile("width='3.14159265' height='10'", 1.0, |tile| {
+            assert_eq!(tile.region, None);
+            assert!(!tile.is_aligned);
+        });
+    }
+}
@@ -2,12 +2,13 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use crate::render::Canvas;
+use crate::{render::Canvas, RenderCache};
 
 pub fn draw(
     tree: &usvg::Tree,
     path: &usvg::Path,
     blend_mode: tiny_skia::BlendMode,
+    cache: &mut RenderCache,
     canvas: &mut Canvas,
 ) -> Option<usvg::PathBbox> {
     let bbox = path.data.bbox();
@@ -24,15 +25,15 @@ pub fn draw(
 
     let antialias = path.rendering_mode.use_shape_antialiasing();
 
-    let fill_path = |canvas| {
+    let fill_path = |cache, canvas| {
         if let Some(ref fill) = path.fill {
             crate::paint_server::fill(
-                tree, fill, style_bbox, &skia_path, antialias, blend_mode, canvas,
+                tree, fill, style_bbox, &skia_path, antialias, blend_mode, cache, canvas,
             );
         }
     };
 
-    let stroke_path = |canvas| {
+    let stroke_path = |cache, canvas| {
         if path.stroke.is_some() {
             crate::paint_server::stroke(
                 tree,
@@ -41,23 +42,24 @@ pub fn draw(
                 &skia_path,
                 antialias,
                 blend_mode,
+                cache,
                 canvas,
             );
         }
     };
 
     if path.paint_order == usvg::PaintOrder::FillAndStroke {
-        fill_path(canvas);
-        stroke_path(canvas);
+        fill_path(cache, canvas);
+        stroke_path(cache, canvas);
     } else {
-        stroke_path(canvas);
-        fill_path(canvas);
+        stroke_path(cache, canvas);
+        fill_path(cache, canvas);
     }
 
     bbox
 }
 
-fn convert_path(path: &usvg::PathData) -> Option<tiny_skia::Path> {
+pub fn convert_path(path: &usvg::PathData) -> Option<tiny_skia::Path> {
     let mut pb = tiny_skia::PathBuilder::new();
     for seg in path.segments() {
         match seg {
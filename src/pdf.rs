@@ -0,0 +1,1091 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! PDF output.
+
+use std::fmt::{self, Write};
+
+use usvg::{NodeExt, TransformFromBBox};
+
+use crate::vector::*;
+use crate::OptionLog;
+
+/// PDF output options.
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// The resolution used to rasterize content that cannot be represented in PDF,
+    /// i.e. filters.
+    ///
+    /// Default: 300
+    pub raster_dpi: f64,
+
+    /// Compresses streams using the Deflate algorithm.
+    ///
+    /// Default: true
+    pub compress: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            raster_dpi: 300.0,
+            compress: true,
+        }
+    }
+}
+
+/// Converts a tree into a single page PDF document.
+///
+/// Groups with opacity, clip paths and masks are written as transparency groups
+/// and soft masks, gradients as shadings, patterns as tiling patterns
+/// and raster images as image XObjects. Only filters are rasterized.
+///
+/// SVG user units are mapped to PDF points assuming 96 DPI.
+pub fn convert(tree: &usvg::Tree, opt: &Options) -> Vec<u8> {
+    let mut w = Writer::new(opt);
+
+    let catalog_ref = w.reserve();
+    let pages_ref = w.reserve();
+    let page_ref = w.reserve();
+
+    let width = tree.size.width() * PT_PER_PX;
+    let height = tree.size.height() * PT_PER_PX;
+
+    // PDF uses a bottom-left origin.
+    let mut page_ts = usvg::Transform::new(PT_PER_PX, 0.0, 0.0, -PT_PER_PX, 0.0, height);
+    page_ts.append(&usvg::utils::view_box_to_transform(
+        tree.view_box.rect,
+        tree.view_box.aspect,
+        tree.size,
+    ));
+
+    let mut content = Content::new(page_ts);
+    content.view = usvg::PathBbox::new(0.0, 0.0, width, height);
+    let mut ts = page_ts;
+    ts.append(&tree.root.transform());
+    write_group_children(tree, &mut w, &tree.root, ts, &mut content);
+
+    let resources = content.resources.to_dict();
+    let content_ref = w.add_stream("", content.ops.into_bytes(), true);
+
+    w.set(
+        catalog_ref,
+        format!("<< /Type /Catalog /Pages {} >>", RefDisplay(pages_ref)),
+    );
+    w.set(
+        pages_ref,
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count 1 >>",
+            RefDisplay(page_ref)
+        ),
+    );
+    w.set(
+        page_ref,
+        format!(
+            "<< /Type /Page /Parent {} /MediaBox [0 0 {} {}] /Resources {} /Contents {} \
+             /Group << /Type /Group /S /Transparency /CS /DeviceRGB >> >>",
+            RefDisplay(pages_ref),
+            Num(width),
+            Num(height),
+            resources,
+            RefDisplay(content_ref),
+        ),
+    );
+
+    w.finish(catalog_ref)
+}
+
+type Ref = usize;
+
+struct Writer<'a> {
+    opt: &'a Options,
+    // Object bodies. Object number is an index plus one.
+    objects: Vec<Vec<u8>>,
+}
+
+impl<'a> Writer<'a> {
+    fn new(opt: &'a Options) -> Self {
+        Writer {
+            opt,
+            objects: Vec::new(),
+        }
+    }
+
+    fn reserve(&mut self) -> Ref {
+        self.objects.push(Vec::new());
+        self.objects.len()
+    }
+
+    fn set<T: Into<Vec<u8>>>(&mut self, id: Ref, body: T) {
+        self.objects[id - 1] = body.into();
+    }
+
+    fn add<T: Into<Vec<u8>>>(&mut self, body: T) -> Ref {
+        self.objects.push(body.into());
+        self.objects.len()
+    }
+
+    /// Adds a stream object.
+    ///
+    /// `dict` contains additional stream dictionary entries.
+    fn add_stream(&mut self, dict: &str, data: Vec<u8>, compress: bool) -> Ref {
+        let (data, filter) = if compress && self.opt.compress {
+            (
+                miniz_oxide::deflate::compress_to_vec_zlib(&data, 6),
+                " /Filter /FlateDecode",
+            )
+        } else {
+            (data, "")
+        };
+
+        let mut body =
+            format!("<< {}{} /Length {} >>\nstream\n", dict, filter, data.len()).into_bytes();
+        body.extend_from_slice(&data);
+        body.extend_from_slice(b"\nendstream");
+        self.add(body)
+    }
+
+    fn finish(self, catalog_ref: Ref) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(b"%PDF-1.7\n%\xE2\xE3\xCF\xD3\n");
+
+        let mut offsets = Vec::with_capacity(self.objects.len());
+        for (i, body) in self.objects.iter().enumerate() {
+            offsets.push(data.len());
+            data.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+            data.extend_from_slice(body);
+            data.extend_from_slice(b"\nendobj\n");
+        }
+
+        let xref_offset = data.len();
+        let mut xref = format!("xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1);
+        for offset in offsets {
+            // Each entry must be exactly 20 bytes long.
+            let _ = writeln!(xref, "{:010} 00000 n ", offset);
+        }
+
+        let _ = write!(
+            xref,
+            "trailer\n<< /Size {} /Root {} >>\nstartxref\n{}\n%%EOF\n",
+            self.objects.len() + 1,
+            RefDisplay(catalog_ref),
+            xref_offset
+        );
+        data.extend_from_slice(xref.as_bytes());
+
+        data
+    }
+}
+
+/// A content stream with its resources.
+struct Content {
+    /// Maps the current subtree root coordinates into the stream's default coordinates.
+    ///
+    /// All elements are drawn directly in the default coordinates, so all streams
+    /// that are referenced by this one (transparency groups and soft masks)
+    /// share the same coordinate system.
+    base: usvg::Transform,
+    /// A visible area in the stream's default coordinates, when known.
+    ///
+    /// Used to limit rasterized regions.
+    view: Option<usvg::PathBbox>,
+    ops: String,
+    resources: Resources,
+}
+
+impl Content {
+    fn new(base: usvg::Transform) -> Self {
+        Content {
+            base,
+            view: None,
+            ops: String::new(),
+            resources: Resources::default(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Resources {
+    ext_g_states: Vec<Ref>,
+    patterns: Vec<Ref>,
+    shadings: Vec<Ref>,
+    x_objects: Vec<Ref>,
+}
+
+impl Resources {
+    fn add_ext_g_state(&mut self, r: Ref) -> String {
+        self.ext_g_states.push(r);
+        format!("Gs{}", self.ext_g_states.len())
+    }
+
+    fn add_pattern(&mut self, r: Ref) -> String {
+        self.patterns.push(r);
+        format!("P{}", self.patterns.len())
+    }
+
+    fn add_shading(&mut self, r: Ref) -> String {
+        self.shadings.push(r);
+        format!("Sh{}", self.shadings.len())
+    }
+
+    fn add_x_object(&mut self, r: Ref) -> String {
+        self.x_objects.push(r);
+        format!("X{}", self.x_objects.len())
+    }
+
+    fn to_dict(&self) -> String {
+        fn write_list(s: &mut String, key: &str, prefix: &str, list: &[Ref]) {
+            if list.is_empty() {
+                return;
+            }
+
+            let _ = write!(s, " /{} <<", key);
+            for (i, r) in list.iter().enumerate() {
+                let _ = write!(s, " /{}{} {}", prefix, i + 1, RefDisplay(*r));
+            }
+            s.push_str(" >>");
+        }
+
+        let mut s = String::from("<<");
+        write_list(&mut s, "ExtGState", "Gs", &self.ext_g_states);
+        write_list(&mut s, "Pattern", "P", &self.patterns);
+        write_list(&mut s, "Shading", "Sh", &self.shadings);
+        write_list(&mut s, "XObject", "X", &self.x_objects);
+        s.push_str(" >>");
+        s
+    }
+}
+
+fn write_group_children(
+    tree: &usvg::Tree,
+    w: &mut Writer,
+    parent: &usvg::Node,
+    ts: usvg::Transform,
+    c: &mut Content,
+) {
+    for node in parent.children() {
+        let mut node_ts = ts;
+        node_ts.append(&node.transform());
+        write_node(tree, w, &node, node_ts, c);
+    }
+}
+
+fn write_node(
+    tree: &usvg::Tree,
+    w: &mut Writer,
+    node: &usvg::Node,
+    ts: usvg::Transform,
+    c: &mut Content,
+) {
+    match *node.borrow() {
+        usvg::NodeKind::Path(ref path) => {
+            write_path(tree, w, path, ts, c);
+        }
+        usvg::NodeKind::Image(ref img) => {
+            write_image(w, img, ts, c);
+        }
+        usvg::NodeKind::Group(ref g) => {
+            write_group(tree, w, node, g, ts, c);
+        }
+    }
+}
+
+fn write_path(
+    tree: &usvg::Tree,
+    w: &mut Writer,
+    path: &usvg::Path,
+    ts: usvg::Transform,
+    c: &mut Content,
+) {
+    if path.visibility != usvg::Visibility::Visible || path.data.is_empty() {
+        return;
+    }
+
+    // `usvg` guaranties that path without a bbox will not use
+    // a paint server with ObjectBoundingBox,
+    // so we can pass whatever rect we want, because it will not be used anyway.
+    let bbox = path
+        .data
+        .bbox()
+        .unwrap_or_else(|| usvg::PathBbox::new(0.0, 0.0, 1.0, 1.0).unwrap());
+
+    let fill_path = |w: &mut Writer, c: &mut Content| {
+        if let Some(ref fill) = path.fill {
+            let mut ops = String::new();
+            if set_paint(
+                tree,
+                w,
+                &fill.paint,
+                fill.opacity,
+                bbox,
+                bbox,
+                ts,
+                true,
+                c,
+                &mut ops,
+            )
+            .is_none()
+            {
+                return;
+            }
+
+            // Paint operators must be set before the transform, since soft masks
+            // are defined in the stream coordinates.
+            c.ops.push_str("q\n");
+            c.ops.push_str(&ops);
+            let _ = writeln!(c.ops, "{} cm", TsDisplay(ts));
+            write_path_data(&path.data, &mut c.ops);
+            c.ops.push_str(if fill.rule == usvg::FillRule::NonZero {
+                "f\n"
+            } else {
+                "f*\n"
+            });
+            c.ops.push_str("Q\n");
+        }
+    };
+
+    let stroke_path = |w: &mut Writer, c: &mut Content| {
+        if let Some(ref stroke) = path.stroke {
+            let stroke_bbox = path
+                .data
+                .stroke_bbox_with_transform(usvg::Transform::default(), stroke)
+                .unwrap_or(bbox);
+
+            let mut ops = String::new();
+            if set_paint(
+                tree,
+                w,
+                &stroke.paint,
+                stroke.opacity,
+                bbox,
+                stroke_bbox,
+                ts,
+                false,
+                c,
+                &mut ops,
+            )
+            .is_none()
+            {
+                return;
+            }
+
+            c.ops.push_str("q\n");
+            c.ops.push_str(&ops);
+            let _ = writeln!(c.ops, "{} cm", TsDisplay(ts));
+            write_stroke_props(stroke, &mut c.ops);
+            write_path_data(&path.data, &mut c.ops);
+            c.ops.push_str("S\nQ\n");
+        }
+    };
+
+    if path.paint_order == usvg::PaintOrder::FillAndStroke {
+        fill_path(w, c);
+        stroke_path(w, c);
+    } else {
+        stroke_path(w, c);
+        fill_path(w, c);
+    }
+}
+
+fn write_stroke_props(stroke: &usvg::Stroke, s: &mut String) {
+    let cap = match stroke.linecap {
+        usvg::LineCap::Butt => 0,
+        usvg::LineCap::Round => 1,
+        usvg::LineCap::Square => 2,
+    };
+
+    let join = match stroke.linejoin {
+        usvg::LineJoin::Miter => 0,
+        usvg::LineJoin::Round => 1,
+        usvg::LineJoin::Bevel => 2,
+    };
+
+    let _ = writeln!(
+        s,
+        "{} w {} J {} j {} M",
+        Num(stroke.width.get()),
+        cap,
+        join,
+        Num(stroke.miterlimit.get())
+    );
+
+    if let Some(ref list) = stroke.dasharray {
+        s.push('[');
+        for (i, n) in list.iter().enumerate() {
+            if i != 0 {
+                s.push(' ');
+            }
+            let _ = write!(s, "{}", Num(*n));
+        }
+        let _ = writeln!(s, "] {} d", Num(stroke.dashoffset as f64));
+    }
+}
+
+/// Writes operators that set a fill or a stroke paint.
+///
+/// `bbox` is the object bbox, while `paint_bbox` is the area that will be painted.
+fn set_paint(
+    tree: &usvg::Tree,
+    w: &mut Writer,
+    paint: &usvg::Paint,
+    opacity: usvg::Opacity,
+    bbox: usvg::PathBbox,
+    paint_bbox: usvg::PathBbox,
+    ts: usvg::Transform,
+    is_fill: bool,
+    c: &mut Content,
+    ops: &mut String,
+) -> Option<()> {
+    let (color_op, pattern_op) = if is_fill {
+        ("rg", "/Pattern cs")
+    } else {
+        ("RG", "/Pattern CS")
+    };
+    let scn = if is_fill { "scn" } else { "SCN" };
+
+    let mut smask = None;
+    match paint {
+        usvg::Paint::Color(color) => {
+            let _ = writeln!(
+                ops,
+                "{} {} {} {}",
+                Num(color.red as f64 / 255.0),
+                Num(color.green as f64 / 255.0),
+                Num(color.blue as f64 / 255.0),
+                color_op
+            );
+        }
+        usvg::Paint::LinearGradient(ref lg) => {
+            let gradient = Gradient::linear(lg, bbox, paint_bbox)?;
+            let (pattern, mask) = write_gradient(w, &gradient, ts, paint_bbox);
+            let name = c.resources.add_pattern(pattern);
+            let _ = writeln!(ops, "{} /{} {}", pattern_op, name, scn);
+            smask = mask;
+        }
+        usvg::Paint::RadialGradient(ref rg) => {
+            let gradient = Gradient::radial(rg, bbox, paint_bbox)?;
+            let (pattern, mask) = write_gradient(w, &gradient, ts, paint_bbox);
+            let name = c.resources.add_pattern(pattern);
+            let _ = writeln!(ops, "{} /{} {}", pattern_op, name, scn);
+            smask = mask;
+        }
+        usvg::Paint::Pattern(ref pattern) => {
+            let pattern = write_pattern(tree, w, pattern, bbox, ts)?;
+            let name = c.resources.add_pattern(pattern);
+            let _ = writeln!(ops, "{} /{} {}", pattern_op, name, scn);
+        }
+    }
+
+    if opacity != usvg::Opacity::ONE || smask.is_some() {
+        let mut dict = String::from("<< /Type /ExtGState");
+        if opacity != usvg::Opacity::ONE {
+            let key = if is_fill { "ca" } else { "CA" };
+            let _ = write!(dict, " /{} {}", key, Num(opacity.get()));
+        }
+
+        if let Some(smask) = smask {
+            let _ = write!(dict, " /SMask {}", RefDisplay(smask));
+        }
+        dict.push_str(" >>");
+
+        let name = c.resources.add_ext_g_state(w.add(dict));
+        let _ = writeln!(ops, "/{} gs", name);
+    }
+
+    Some(())
+}
+
+/// Writes a gradient as a shading pattern.
+///
+/// Returns the pattern and an optional soft mask for gradients with a stop opacity.
+fn write_gradient(
+    w: &mut Writer,
+    g: &Gradient,
+    ts: usvg::Transform,
+    paint_bbox: usvg::PathBbox,
+) -> (Ref, Option<Ref>) {
+    let mut matrix = ts;
+    matrix.append(&g.transform);
+
+    let shading = w.add(g.shading(false));
+    let pattern = w.add(format!(
+        "<< /Type /Pattern /PatternType 2 /Shading {} /Matrix [{}] >>",
+        RefDisplay(shading),
+        TsDisplay(matrix)
+    ));
+
+    let mut smask = None;
+    if has_stop_opacity(&g.stops) {
+        // A luminosity mask with the same geometry, but with stops opacity as a color.
+        let alpha_shading = w.add(g.shading(true));
+        let mut content = Content::new(ts);
+        let name = content.resources.add_shading(alpha_shading);
+        let _ = writeln!(content.ops, "q\n{} cm\n/{} sh\nQ", TsDisplay(matrix), name);
+
+        let bbox = paint_bbox.transform(&ts).unwrap_or(paint_bbox);
+        let form = write_form(w, content, bbox, "/DeviceGray");
+        smask = Some(w.add(format!(
+            "<< /Type /Mask /S /Luminosity /G {} >>",
+            RefDisplay(form)
+        )));
+    }
+
+    (pattern, smask)
+}
+
+fn write_pattern(
+    tree: &usvg::Tree,
+    w: &mut Writer,
+    pattern: &usvg::Pattern,
+    bbox: usvg::PathBbox,
+    ts: usvg::Transform,
+) -> Option<Ref> {
+    let r = if pattern.units == usvg::Units::ObjectBoundingBox {
+        let bbox = bbox
+            .to_rect()
+            .log_none(|| log::warn!("Pattern on zero-sized shapes is not allowed."))?;
+
+        pattern.rect.bbox_transform(bbox)
+    } else {
+        pattern.rect
+    };
+
+    let content_ts = if let Some(vbox) = pattern.view_box {
+        usvg::utils::view_box_to_transform(vbox.rect, vbox.aspect, r.size())
+    } else if pattern.content_units == usvg::Units::ObjectBoundingBox {
+        // 'Note that this attribute has no effect if attribute `viewBox` is specified.'
+
+        // We don't use Transform::from_bbox(bbox) because `x` and `y` should be
+        // ignored for some reasons...
+        usvg::Transform::new_scale(bbox.width(), bbox.height())
+    } else {
+        usvg::Transform::default()
+    };
+
+    // Pattern content is written in the pattern cell coordinates.
+    let mut content = Content::new(content_ts);
+    write_group_children(tree, w, &pattern.root, content_ts, &mut content);
+
+    let mut matrix = ts;
+    matrix.append(&pattern.transform);
+    matrix.translate(r.x(), r.y());
+
+    let dict = format!(
+        "/Type /Pattern /PatternType 1 /PaintType 1 /TilingType 1 /BBox [0 0 {w} {h}] \
+         /XStep {w} /YStep {h} /Matrix [{}] /Resources {}",
+        TsDisplay(matrix),
+        content.resources.to_dict(),
+        w = Num(r.width()),
+        h = Num(r.height()),
+    );
+
+    Some(w.add_stream(&dict, content.ops.into_bytes(), true))
+}
+
+fn write_group(
+    tree: &usvg::Tree,
+    w: &mut Writer,
+    node: &usvg::Node,
+    g: &usvg::Group,
+    ts: usvg::Transform,
+    c: &mut Content,
+) -> Option<()> {
+    #[cfg(feature = "filter")]
+    if !g.filters.is_empty() {
+        return write_raster_node(tree, w, node, c);
+    }
+
+    if g.opacity == usvg::Opacity::ONE && g.clip_path.is_none() && g.mask.is_none() {
+        write_group_children(tree, w, node, ts, c);
+        return Some(());
+    }
+
+    // A bbox in the group's user space, which is used by `objectBoundingBox` units.
+    let bbox = usvg::utils::object_bbox(node);
+
+    // The group is not visible when its visual bbox is empty.
+    let form_bbox = node.calculate_visual_bbox()?.transform(&c.base)?;
+
+    let mut content = Content::new(c.base);
+    content.view = c.view;
+    write_group_children(tree, w, node, ts, &mut content);
+    let mut form = write_form(w, content, form_bbox, "/DeviceRGB");
+
+    if let Some(ref mask) = g.mask {
+        let smask = write_mask(tree, w, mask, bbox, ts, form_bbox)?;
+        form = wrap_form(w, form, smask, form_bbox);
+    }
+
+    let mut clip_ops = String::new();
+    if let Some(ref cp) = g.clip_path {
+        match simple_clip_ops(cp, node, ts) {
+            Some(ops) => clip_ops = ops,
+            None => {
+                let smask = write_clip_mask(w, cp, node, ts, form_bbox)?;
+                form = wrap_form(w, form, smask, form_bbox);
+            }
+        }
+    }
+
+    c.ops.push_str("q\n");
+    c.ops.push_str(&clip_ops);
+    if g.opacity != usvg::Opacity::ONE {
+        let gs = w.add(format!(
+            "<< /Type /ExtGState /ca {0} /CA {0} >>",
+            Num(g.opacity.get())
+        ));
+        let name = c.resources.add_ext_g_state(gs);
+        let _ = writeln!(c.ops, "/{} gs", name);
+    }
+
+    let name = c.resources.add_x_object(form);
+    let _ = writeln!(c.ops, "/{} Do\nQ", name);
+
+    Some(())
+}
+
+/// Writes a transparency group form.
+fn write_form(w: &mut Writer, content: Content, bbox: usvg::PathBbox, color_space: &str) -> Ref {
+    let dict = format!(
+        "/Type /XObject /Subtype /Form /BBox [{} {} {} {}] \
+         /Group << /Type /Group /S /Transparency /CS {} >> /Resources {}",
+        Num(bbox.left()),
+        Num(bbox.top()),
+        Num(bbox.right()),
+        Num(bbox.bottom()),
+        color_space,
+        content.resources.to_dict()
+    );
+
+    w.add_stream(&dict, content.ops.into_bytes(), true)
+}
+
+/// Wraps a form into another one with the soft mask applied.
+fn wrap_form(w: &mut Writer, form: Ref, smask: Ref, bbox: usvg::PathBbox) -> Ref {
+    let mut content = Content::new(usvg::Transform::default());
+    let gs = w.add(format!(
+        "<< /Type /ExtGState /SMask {} >>",
+        RefDisplay(smask)
+    ));
+    let gs_name = content.resources.add_ext_g_state(gs);
+    let form_name = content.resources.add_x_object(form);
+    let _ = writeln!(content.ops, "q\n/{} gs\n/{} Do\nQ", gs_name, form_name);
+    write_form(w, content, bbox, "/DeviceRGB")
+}
+
+/// Returns clipping operators for clip paths that consist of a single path.
+fn simple_clip_ops(cp: &usvg::ClipPath, node: &usvg::Node, ts: usvg::Transform) -> Option<String> {
+    let (data, rule) = simple_clip_path(cp, node, ts)?;
+
+    let mut ops = String::new();
+    write_path_data(&data, &mut ops);
+    ops.push_str(if rule == usvg::FillRule::NonZero {
+        "W n\n"
+    } else {
+        "W* n\n"
+    });
+    Some(ops)
+}
+
+/// Writes a clip path as a luminosity soft mask.
+fn write_clip_mask(
+    w: &mut Writer,
+    cp: &usvg::ClipPath,
+    node: &usvg::Node,
+    ts: usvg::Transform,
+    form_bbox: usvg::PathBbox,
+) -> Option<Ref> {
+    let mut clip_ts = ts;
+    clip_ts.append(&usvg::utils::clip_path_transform(cp, node)?);
+
+    let mut content = Content::new(clip_ts);
+    write_clip_children(w, &cp.root, clip_ts, form_bbox, &mut content);
+    let mut form = write_form(w, content, form_bbox, "/DeviceRGB");
+
+    if let Some(ref cp) = cp.clip_path {
+        let smask = write_clip_mask(w, cp, node, ts, form_bbox)?;
+        form = wrap_form(w, form, smask, form_bbox);
+    }
+
+    Some(w.add(format!(
+        "<< /Type /Mask /S /Luminosity /G {} >>",
+        RefDisplay(form)
+    )))
+}
+
+/// Writes clip path children filled with white.
+fn write_clip_children(
+    w: &mut Writer,
+    parent: &usvg::Node,
+    ts: usvg::Transform,
+    form_bbox: usvg::PathBbox,
+    c: &mut Content,
+) {
+    for node in parent.children() {
+        let mut node_ts = ts;
+        node_ts.append(&node.transform());
+
+        match *node.borrow() {
+            usvg::NodeKind::Path(ref path) => {
+                let fill = match path.fill {
+                    Some(ref fill) => fill,
+                    None => continue,
+                };
+
+                if path.visibility != usvg::Visibility::Visible || path.data.is_empty() {
+                    continue;
+                }
+
+                let _ = writeln!(c.ops, "q\n{} cm\n1 g", TsDisplay(node_ts));
+                write_path_data(&path.data, &mut c.ops);
+                c.ops.push_str(if fill.rule == usvg::FillRule::NonZero {
+                    "f\nQ\n"
+                } else {
+                    "f*\nQ\n"
+                });
+            }
+            usvg::NodeKind::Group(ref g) => {
+                if let Some(ref cp) = g.clip_path {
+                    // A clip path child with its own clip path.
+                    let mut content = Content::new(c.base);
+                    write_clip_children(w, &node, node_ts, form_bbox, &mut content);
+                    let form = write_form(w, content, form_bbox, "/DeviceRGB");
+
+                    let smask = match write_clip_mask(w, cp, &node, node_ts, form_bbox) {
+                        Some(v) => v,
+                        None => continue,
+                    };
+
+                    let form = wrap_form(w, form, smask, form_bbox);
+                    let name = c.resources.add_x_object(form);
+                    let _ = writeln!(c.ops, "/{} Do", name);
+                } else {
+                    write_clip_children(w, &node, node_ts, form_bbox, c);
+                }
+            }
+            usvg::NodeKind::Image(_) => {}
+        }
+    }
+}
+
+/// Writes a mask as a luminosity soft mask.
+fn write_mask(
+    tree: &usvg::Tree,
+    w: &mut Writer,
+    mask: &usvg::Mask,
+    bbox: Option<usvg::PathBbox>,
+    ts: usvg::Transform,
+    form_bbox: usvg::PathBbox,
+) -> Option<Ref> {
+    let bbox = if mask.units == usvg::Units::ObjectBoundingBox
+        || mask.content_units == usvg::Units::ObjectBoundingBox
+    {
+        Some(
+            bbox.and_then(|r| r.to_rect())
+                .log_none(|| log::warn!("Masking of zero-sized shapes is not allowed."))?,
+        )
+    } else {
+        None
+    };
+
+    let rect = match bbox {
+        Some(bbox) if mask.units == usvg::Units::ObjectBoundingBox => {
+            mask.rect.bbox_transform(bbox)
+        }
+        _ => mask.rect,
+    };
+
+    let mut content_ts = ts;
+    if let Some(bbox) = bbox {
+        if mask.content_units == usvg::Units::ObjectBoundingBox {
+            content_ts.append(&usvg::Transform::from_bbox(bbox));
+        }
+    }
+
+    let mut content = Content::new(content_ts);
+    content.ops.push_str("q\n");
+    let mut rect_data = usvg::PathData::from_rect(rect);
+    rect_data.transform(ts);
+    write_path_data(&rect_data, &mut content.ops);
+    content.ops.push_str("W n\n");
+    write_group_children(tree, w, &mask.root, content_ts, &mut content);
+    content.ops.push_str("Q\n");
+    let mut form = write_form(w, content, form_bbox, "/DeviceRGB");
+
+    if let Some(ref mask) = mask.mask {
+        let bbox = bbox.map(|r| r.to_path_bbox());
+        let smask = write_mask(tree, w, mask, bbox, ts, form_bbox)?;
+        form = wrap_form(w, form, smask, form_bbox);
+    }
+
+    Some(w.add(format!(
+        "<< /Type /Mask /S /Luminosity /G {} >>",
+        RefDisplay(form)
+    )))
+}
+
+/// Rasterizes a node, which cannot be represented in PDF.
+#[cfg(feature = "filter")]
+fn write_raster_node(
+    tree: &usvg::Tree,
+    w: &mut Writer,
+    node: &usvg::Node,
+    c: &mut Content,
+) -> Option<()> {
+    let (pixmap, bbox) = rasterize_node(tree, node, c.base, c.view, w.opt.raster_dpi, &[])?;
+    let image = write_pixmap(w, &pixmap, true);
+    let name = c.resources.add_x_object(image);
+
+    // The default coordinates have a bottom-left origin, just like the image space.
+    let ts = usvg::Transform::new(bbox.width(), 0.0, 0.0, bbox.height(), bbox.x(), bbox.y());
+    let _ = writeln!(c.ops, "q\n{} cm\n/{} Do\nQ", TsDisplay(ts), name);
+
+    Some(())
+}
+
+/// Returns a transform that maps an image XObject into `rect`.
+///
+/// Image space has a bottom-left origin, while we are using a top-left one.
+#[cfg(feature = "raster-images")]
+fn image_transform(rect: usvg::Rect) -> usvg::Transform {
+    usvg::Transform::new(
+        rect.width(),
+        0.0,
+        0.0,
+        -rect.height(),
+        rect.x(),
+        rect.y() + rect.height(),
+    )
+}
+
+/// Writes a premultiplied pixmap as an image with a soft mask.
+#[cfg(feature = "filter")]
+fn write_pixmap(w: &mut Writer, pixmap: &tiny_skia::Pixmap, interpolate: bool) -> Ref {
+    let mut rgb = Vec::with_capacity(pixmap.width() as usize * pixmap.height() as usize * 3);
+    let mut alpha = Vec::with_capacity(pixmap.width() as usize * pixmap.height() as usize);
+    for p in pixmap.pixels() {
+        let c = p.demultiply();
+        rgb.extend_from_slice(&[c.red(), c.green(), c.blue()]);
+        alpha.push(c.alpha());
+    }
+
+    write_image_data(
+        w,
+        pixmap.width(),
+        pixmap.height(),
+        rgb,
+        Some(alpha),
+        interpolate,
+    )
+}
+
+#[cfg(any(feature = "filter", feature = "raster-images"))]
+fn write_image_data(
+    w: &mut Writer,
+    width: u32,
+    height: u32,
+    rgb: Vec<u8>,
+    alpha: Option<Vec<u8>>,
+    interpolate: bool,
+) -> Ref {
+    let smask = alpha.filter(|a| a.iter().any(|a| *a != 255)).map(|alpha| {
+        let dict = format!(
+            "/Type /XObject /Subtype /Image /Width {} /Height {} \
+             /ColorSpace /DeviceGray /BitsPerComponent 8 /Interpolate {}",
+            width, height, interpolate
+        );
+        w.add_stream(&dict, alpha, true)
+    });
+
+    let mut dict = format!(
+        "/Type /XObject /Subtype /Image /Width {} /Height {} \
+         /ColorSpace /DeviceRGB /BitsPerComponent 8 /Interpolate {}",
+        width, height, interpolate
+    );
+    if let Some(smask) = smask {
+        let _ = write!(dict, " /SMask {}", RefDisplay(smask));
+    }
+
+    w.add_stream(&dict, rgb, true)
+}
+
+fn write_image(w: &mut Writer, img: &usvg::Image, ts: usvg::Transform, c: &mut Content) {
+    if img.visibility != usvg::Visibility::Visible {
+        return;
+    }
+
+    match img.kind {
+        usvg::ImageKind::SVG(ref subtree) => {
+            write_svg_image(w, subtree, img.view_box, ts, c);
+        }
+        #[cfg(feature = "raster-images")]
+        _ => {
+            write_raster_image(w, img, ts, c);
+        }
+        #[cfg(not(feature = "raster-images"))]
+        _ => {
+            log::warn!("Images decoding was disabled by a build feature.");
+        }
+    }
+}
+
+#[cfg(feature = "raster-images")]
+fn write_raster_image(
+    w: &mut Writer,
+    img: &usvg::Image,
+    ts: usvg::Transform,
+    c: &mut Content,
+) -> Option<()> {
+    use crate::image::raster_images;
+
+    let interpolate = img.rendering_mode != usvg::ImageRendering::OptimizeSpeed;
+
+    let (image, size) = match img.kind {
+        usvg::ImageKind::JPEG(ref data) => write_jpeg(w, data, interpolate)?,
+        usvg::ImageKind::PNG(ref data) => {
+            let image = raster_images::read_png(data)
+                .log_none(|| log::warn!("Failed to decode a PNG image."))?;
+            (write_decoded_image(w, &image, interpolate), image.size)
+        }
+        usvg::ImageKind::GIF(ref data) => {
+            let image = raster_images::read_gif(data)
+                .log_none(|| log::warn!("Failed to decode a GIF image."))?;
+            (write_decoded_image(w, &image, interpolate), image.size)
+        }
+        usvg::ImageKind::SVG(_) => return None,
+    };
+
+    let name = c.resources.add_x_object(image);
+
+    let _ = writeln!(c.ops, "q\n{} cm", TsDisplay(ts));
+    if img.view_box.aspect.slice {
+        let r = img.view_box.rect;
+        let _ = writeln!(
+            c.ops,
+            "{} {} {} {} re W n",
+            Num(r.x()),
+            Num(r.y()),
+            Num(r.width()),
+            Num(r.height())
+        );
+    }
+
+    let rect = raster_images::image_rect(&img.view_box, size);
+    let _ = writeln!(
+        c.ops,
+        "{} cm\n/{} Do\nQ",
+        TsDisplay(image_transform(rect)),
+        name
+    );
+
+    Some(())
+}
+
+/// Embeds a JPEG image as is.
+#[cfg(feature = "raster-images")]
+fn write_jpeg(w: &mut Writer, data: &[u8], interpolate: bool) -> Option<(Ref, usvg::ScreenSize)> {
+    let mut decoder = jpeg_decoder::Decoder::new(data);
+    decoder
+        .read_info()
+        .ok()
+        .log_none(|| log::warn!("Failed to decode a JPEG image."))?;
+    let info = decoder.info()?;
+
+    let color_space = match info.pixel_format {
+        jpeg_decoder::PixelFormat::RGB24 => "/DeviceRGB",
+        jpeg_decoder::PixelFormat::L8 => "/DeviceGray",
+        _ => {
+            log::warn!("Unsupported JPEG pixel format.");
+            return None;
+        }
+    };
+
+    let dict = format!(
+        "/Type /XObject /Subtype /Image /Width {} /Height {} \
+         /ColorSpace {} /BitsPerComponent 8 /Interpolate {} /Filter /DCTDecode",
+        info.width, info.height, color_space, interpolate
+    );
+
+    let size = usvg::ScreenSize::new(info.width as u32, info.height as u32)?;
+    Some((w.add_stream(&dict, data.to_vec(), false), size))
+}
+
+#[cfg(feature = "raster-images")]
+fn write_decoded_image(
+    w: &mut Writer,
+    image: &crate::image::raster_images::Image,
+    interpolate: bool,
+) -> Ref {
+    use crate::image::raster_images::ImageData;
+
+    let (rgb, alpha) = match image.data {
+        ImageData::RGB(ref data) => (data.clone(), None),
+        ImageData::RGBA(ref data) => {
+            let mut rgb = Vec::with_capacity(data.len() / 4 * 3);
+            let mut alpha = Vec::with_capacity(data.len() / 4);
+            for p in data.chunks(4) {
+                rgb.extend_from_slice(&p[0..3]);
+                alpha.push(p[3]);
+            }
+
+            (rgb, Some(alpha))
+        }
+    };
+
+    write_image_data(
+        w,
+        image.size.width(),
+        image.size.height(),
+        rgb,
+        alpha,
+        interpolate,
+    )
+}
+
+fn write_svg_image(
+    w: &mut Writer,
+    tree: &usvg::Tree,
+    view_box: usvg::ViewBox,
+    ts: usvg::Transform,
+    c: &mut Content,
+) -> Option<()> {
+    let img_size = tree.size.to_screen_size();
+    let (img_ts, clip) = usvg::utils::view_box_to_transform_with_clip(&view_box, img_size);
+
+    let mut sub_ts = ts;
+    sub_ts.append(&img_ts);
+    sub_ts.append(&usvg::utils::view_box_to_transform(
+        tree.view_box.rect,
+        tree.view_box.aspect,
+        img_size.to_size(),
+    ));
+
+    let bbox = tree.root.calculate_visual_bbox()?.transform(&sub_ts)?;
+
+    let mut content = Content::new(sub_ts);
+    let mut root_ts = sub_ts;
+    root_ts.append(&tree.root.transform());
+    write_group_children(tree, w, &tree.root, root_ts, &mut content);
+    let form = write_form(w, content, bbox, "/DeviceRGB");
+    let name = c.resources.add_x_object(form);
+
+    c.ops.push_str("q\n");
+    if let Some(clip) = clip {
+        let mut data = usvg::PathData::from_rect(clip);
+        data.transform(ts);
+        write_path_data(&data, &mut c.ops);
+        c.ops.push_str("W n\n");
+    }
+    let _ = writeln!(c.ops, "/{} Do\nQ", name);
+
+    Some(())
+}
+
+struct RefDisplay(Ref);
+
+impl fmt::Display for RefDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} 0 R", self.0)
+    }
+}
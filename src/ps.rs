@@ -0,0 +1,692 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! PostScript and EPS output.
+
+use std::fmt::Write;
+
+use usvg::NodeExt;
+
+use crate::vector::*;
+
+/// PostScript output options.
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// The resolution used to rasterize content that cannot be represented in PostScript,
+    /// i.e. filters, masks and transparency.
+    ///
+    /// Default: 300
+    pub raster_dpi: f64,
+
+    /// Produces an Encapsulated PostScript file instead of a PostScript document.
+    ///
+    /// Default: false
+    pub eps: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            raster_dpi: 300.0,
+            eps: false,
+        }
+    }
+}
+
+/// Converts a tree into a single page PostScript Level 3 document.
+///
+/// Paths, gradients, clip paths with a single shape and images are written as is.
+/// Since PostScript doesn't support transparency, elements with opacity, masks,
+/// filters, patterns, complex clip paths and images with semi-transparent pixels
+/// are rasterized together with the content below them, i.e. flattened.
+/// Areas without any content stay transparent via a 1-bit mask.
+///
+/// SVG user units are mapped to PostScript points assuming 96 DPI.
+pub fn convert(tree: &usvg::Tree, opt: &Options) -> Vec<u8> {
+    let width = tree.size.width() * PT_PER_PX;
+    let height = tree.size.height() * PT_PER_PX;
+
+    // PostScript uses a bottom-left origin.
+    let mut page_ts = usvg::Transform::new(PT_PER_PX, 0.0, 0.0, -PT_PER_PX, 0.0, height);
+    page_ts.append(&usvg::utils::view_box_to_transform(
+        tree.view_box.rect,
+        tree.view_box.aspect,
+        tree.size,
+    ));
+
+    let mut w = Writer {
+        opt,
+        view: usvg::PathBbox::new(0.0, 0.0, width, height),
+        ops: String::new(),
+    };
+
+    write_header(&mut w, width, height);
+
+    let doc = Document {
+        tree,
+        base: page_ts,
+        parent: None,
+    };
+
+    let mut ts = page_ts;
+    ts.append(&tree.root.transform());
+    write_group_children(&doc, &mut w, &tree.root, ts);
+
+    w.ops
+        .push_str("grestore\nend\nshowpage\n%%Trailer\n%%EOF\n");
+    w.ops.into_bytes()
+}
+
+struct Writer<'a> {
+    opt: &'a Options,
+    /// A visible area in the default coordinates.
+    view: Option<usvg::PathBbox>,
+    ops: String,
+}
+
+/// A tree being written.
+///
+/// Nested SVG images are separate trees, but the content below them
+/// is still required to flatten transparency.
+struct Document<'a> {
+    tree: &'a usvg::Tree,
+    /// Maps the tree root coordinates into the default coordinates.
+    base: usvg::Transform,
+    /// A parent document and an image node that contains the current one.
+    parent: Option<(&'a Document<'a>, usvg::Node)>,
+}
+
+fn write_header(w: &mut Writer, width: f64, height: f64) {
+    if w.opt.eps {
+        w.ops.push_str("%!PS-Adobe-3.0 EPSF-3.0\n");
+    } else {
+        w.ops.push_str("%!PS-Adobe-3.0\n");
+    }
+
+    let _ = writeln!(w.ops, "%%Creator: resvg {}", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(
+        w.ops,
+        "%%BoundingBox: 0 0 {} {}",
+        width.ceil() as i64,
+        height.ceil() as i64
+    );
+    let _ = writeln!(
+        w.ops,
+        "%%HiResBoundingBox: 0 0 {} {}",
+        Num(width),
+        Num(height)
+    );
+    w.ops.push_str("%%LanguageLevel: 3\n");
+    if !w.opt.eps {
+        w.ops.push_str("%%Pages: 1\n");
+    }
+    w.ops.push_str("%%EndComments\n");
+
+    // Short path operators, which are shared with the PDF output.
+    w.ops.push_str(
+        "%%BeginProlog\n\
+         /resvg 4 dict def\n\
+         resvg begin\n\
+         /m { moveto } bind def\n\
+         /l { lineto } bind def\n\
+         /c { curveto } bind def\n\
+         /h { closepath } bind def\n\
+         end\n\
+         %%EndProlog\n",
+    );
+
+    if !w.opt.eps {
+        let _ = writeln!(
+            w.ops,
+            "%%BeginSetup\n<< /PageSize [{} {}] >> setpagedevice\n%%EndSetup\n%%Page: 1 1",
+            Num(width),
+            Num(height)
+        );
+    }
+
+    w.ops.push_str("resvg begin\ngsave\n");
+}
+
+fn write_group_children(doc: &Document, w: &mut Writer, parent: &usvg::Node, ts: usvg::Transform) {
+    for node in parent.children() {
+        let mut node_ts = ts;
+        node_ts.append(&node.transform());
+
+        match *node.borrow() {
+            usvg::NodeKind::Path(ref path) => {
+                write_path(doc, w, &node, path, node_ts);
+            }
+            usvg::NodeKind::Image(ref img) => {
+                write_image(doc, w, &node, img, node_ts);
+            }
+            usvg::NodeKind::Group(ref g) => {
+                write_group(doc, w, &node, g, node_ts);
+            }
+        }
+    }
+}
+
+fn write_group(
+    doc: &Document,
+    w: &mut Writer,
+    node: &usvg::Node,
+    g: &usvg::Group,
+    ts: usvg::Transform,
+) {
+    #[cfg(feature = "filter")]
+    let has_filters = !g.filters.is_empty();
+    #[cfg(not(feature = "filter"))]
+    let has_filters = false;
+
+    if g.opacity != usvg::Opacity::ONE || g.mask.is_some() || has_filters {
+        write_raster_node(doc, w, node);
+        return;
+    }
+
+    let clip = match g.clip_path {
+        Some(ref cp) => match simple_clip_path(cp, node, ts) {
+            Some(v) => Some(v),
+            None => {
+                write_raster_node(doc, w, node);
+                return;
+            }
+        },
+        None => None,
+    };
+
+    if let Some((data, rule)) = clip {
+        w.ops.push_str("gsave\nnewpath\n");
+        write_path_data(&data, &mut w.ops);
+        w.ops.push_str(if rule == usvg::FillRule::NonZero {
+            "clip\n"
+        } else {
+            "eoclip\n"
+        });
+        write_group_children(doc, w, node, ts);
+        w.ops.push_str("grestore\n");
+    } else {
+        write_group_children(doc, w, node, ts);
+    }
+}
+
+fn write_path(
+    doc: &Document,
+    w: &mut Writer,
+    node: &usvg::Node,
+    path: &usvg::Path,
+    ts: usvg::Transform,
+) {
+    if path.visibility != usvg::Visibility::Visible || path.data.is_empty() {
+        return;
+    }
+
+    let fill_paint = path.fill.as_ref().map(|f| (&f.paint, f.opacity));
+    let stroke_paint = path.stroke.as_ref().map(|s| (&s.paint, s.opacity));
+    let needs_raster = fill_paint
+        .into_iter()
+        .chain(stroke_paint)
+        .any(|(paint, opacity)| !is_opaque_paint(paint, opacity));
+    if needs_raster {
+        write_raster_node(doc, w, node);
+        return;
+    }
+
+    // `usvg` guaranties that path without a bbox will not use
+    // a paint server with ObjectBoundingBox,
+    // so we can pass whatever rect we want, because it will not be used anyway.
+    let bbox = path
+        .data
+        .bbox()
+        .unwrap_or_else(|| usvg::PathBbox::new(0.0, 0.0, 1.0, 1.0).unwrap());
+
+    if path.paint_order == usvg::PaintOrder::FillAndStroke {
+        write_fill(w, path, bbox, ts);
+        write_stroke(w, path, bbox, ts);
+    } else {
+        write_stroke(w, path, bbox, ts);
+        write_fill(w, path, bbox, ts);
+    }
+}
+
+/// Checks that a paint can be represented without transparency.
+fn is_opaque_paint(paint: &usvg::Paint, opacity: usvg::Opacity) -> bool {
+    if opacity != usvg::Opacity::ONE {
+        return false;
+    }
+
+    match paint {
+        usvg::Paint::Color(_) => true,
+        usvg::Paint::LinearGradient(ref lg) => !has_stop_opacity(&lg.stops),
+        usvg::Paint::RadialGradient(ref rg) => !has_stop_opacity(&rg.stops),
+        usvg::Paint::Pattern(_) => false,
+    }
+}
+
+fn write_fill(w: &mut Writer, path: &usvg::Path, bbox: usvg::PathBbox, ts: usvg::Transform) {
+    let fill = match path.fill {
+        Some(ref fill) => fill,
+        None => return,
+    };
+
+    let gradient = match fill.paint {
+        usvg::Paint::LinearGradient(ref lg) => Gradient::linear(lg, bbox, bbox),
+        usvg::Paint::RadialGradient(ref rg) => Gradient::radial(rg, bbox, bbox),
+        _ => None,
+    };
+
+    let is_even_odd = fill.rule == usvg::FillRule::EvenOdd;
+    let _ = writeln!(w.ops, "gsave\n[{}] concat\nnewpath", TsDisplay(ts));
+    write_path_data(&path.data, &mut w.ops);
+    match fill.paint {
+        usvg::Paint::Color(color) => {
+            write_color(color, &mut w.ops);
+            w.ops
+                .push_str(if is_even_odd { "eofill\n" } else { "fill\n" });
+        }
+        _ => {
+            if let Some(gradient) = gradient {
+                w.ops
+                    .push_str(if is_even_odd { "eoclip\n" } else { "clip\n" });
+                write_shading(&gradient, &mut w.ops);
+            }
+        }
+    }
+    w.ops.push_str("grestore\n");
+}
+
+fn write_stroke(w: &mut Writer, path: &usvg::Path, bbox: usvg::PathBbox, ts: usvg::Transform) {
+    let stroke = match path.stroke {
+        Some(ref stroke) => stroke,
+        None => return,
+    };
+
+    let stroke_bbox = path
+        .data
+        .stroke_bbox_with_transform(usvg::Transform::default(), stroke)
+        .unwrap_or(bbox);
+
+    let gradient = match stroke.paint {
+        usvg::Paint::LinearGradient(ref lg) => Gradient::linear(lg, bbox, stroke_bbox),
+        usvg::Paint::RadialGradient(ref rg) => Gradient::radial(rg, bbox, stroke_bbox),
+        _ => None,
+    };
+
+    let _ = writeln!(w.ops, "gsave\n[{}] concat", TsDisplay(ts));
+    write_stroke_props(stroke, &mut w.ops);
+    w.ops.push_str("newpath\n");
+    write_path_data(&path.data, &mut w.ops);
+    match stroke.paint {
+        usvg::Paint::Color(color) => {
+            write_color(color, &mut w.ops);
+            w.ops.push_str("stroke\n");
+        }
+        _ => {
+            if let Some(gradient) = gradient {
+                // `strokepath` converts a stroke into a fillable outline.
+                w.ops.push_str("strokepath clip\n");
+                write_shading(&gradient, &mut w.ops);
+            }
+        }
+    }
+    w.ops.push_str("grestore\n");
+}
+
+fn write_color(color: usvg::Color, s: &mut String) {
+    let _ = writeln!(
+        s,
+        "{} {} {} setrgbcolor",
+        Num(color.red as f64 / 255.0),
+        Num(color.green as f64 / 255.0),
+        Num(color.blue as f64 / 255.0)
+    );
+}
+
+fn write_stroke_props(stroke: &usvg::Stroke, s: &mut String) {
+    let cap = match stroke.linecap {
+        usvg::LineCap::Butt => 0,
+        usvg::LineCap::Round => 1,
+        usvg::LineCap::Square => 2,
+    };
+
+    let join = match stroke.linejoin {
+        usvg::LineJoin::Miter => 0,
+        usvg::LineJoin::Round => 1,
+        usvg::LineJoin::Bevel => 2,
+    };
+
+    let _ = writeln!(
+        s,
+        "{} setlinewidth {} setlinecap {} setlinejoin {} setmiterlimit",
+        Num(stroke.width.get()),
+        cap,
+        join,
+        Num(stroke.miterlimit.get())
+    );
+
+    if let Some(ref list) = stroke.dasharray {
+        s.push('[');
+        for (i, n) in list.iter().enumerate() {
+            if i != 0 {
+                s.push(' ');
+            }
+            let _ = write!(s, "{}", Num(*n));
+        }
+        let _ = writeln!(s, "] {} setdash", Num(stroke.dashoffset as f64));
+    }
+}
+
+/// Fills the current clipping region with a gradient.
+///
+/// Expects the path's user space to be the current one.
+fn write_shading(g: &Gradient, s: &mut String) {
+    let _ = writeln!(
+        s,
+        "newpath\n[{}] concat\n{} shfill",
+        TsDisplay(g.transform),
+        g.shading(false)
+    );
+}
+
+/// Rasterizes a node, which cannot be represented in PostScript.
+///
+/// PostScript doesn't support transparency, so the node is rendered together with
+/// the already written content below it and the result is drawn on top of it.
+fn write_raster_node(doc: &Document, w: &mut Writer, node: &usvg::Node) -> Option<()> {
+    // Parent documents are rendered first.
+    let mut backdrop = Vec::new();
+    let mut curr = Some((doc, node.clone()));
+    while let Some((d, until)) = curr {
+        backdrop.push(Backdrop {
+            tree: d.tree,
+            until,
+            base: d.base,
+        });
+        curr = d.parent.as_ref().map(|(p, n)| (*p, n.clone()));
+    }
+    backdrop.reverse();
+
+    let (pixmap, rect) = rasterize_node(
+        doc.tree,
+        node,
+        doc.base,
+        w.view,
+        w.opt.raster_dpi,
+        &backdrop,
+    )?;
+
+    // Pixels without any content are masked out, while other pixels
+    // are blended with a white page.
+    let mut rgb = Vec::with_capacity(pixmap.width() as usize * pixmap.height() as usize * 3);
+    let mut alpha = Vec::with_capacity(pixmap.width() as usize * pixmap.height() as usize);
+    for p in pixmap.pixels() {
+        let a = p.alpha();
+        rgb.extend_from_slice(&[
+            p.red() + (255 - a),
+            p.green() + (255 - a),
+            p.blue() + (255 - a),
+        ]);
+        alpha.push(if a == 0 { 0 } else { 255 });
+    }
+
+    // The default coordinates have a bottom-left origin, while the first row is at the top.
+    let _ = writeln!(
+        w.ops,
+        "gsave\n{} {} translate {} {} scale",
+        Num(rect.x()),
+        Num(rect.bottom()),
+        Num(rect.width()),
+        Num(-rect.height())
+    );
+    write_image_data(
+        pixmap.width(),
+        pixmap.height(),
+        &rgb,
+        Some(&alpha),
+        true,
+        &mut w.ops,
+    );
+    w.ops.push_str("grestore\n");
+
+    Some(())
+}
+
+/// Writes an `image` operator followed by the image data.
+///
+/// The image is mapped into a unit square. PostScript doesn't support soft masks,
+/// therefore `alpha` must contain only fully opaque or fully transparent pixels.
+fn write_image_data(
+    width: u32,
+    height: u32,
+    rgb: &[u8],
+    alpha: Option<&[u8]>,
+    interpolate: bool,
+    s: &mut String,
+) {
+    let image_dict = format!(
+        "/ImageType 1 /Width {w} /Height {h} /BitsPerComponent 8 \
+         /ImageMatrix [{w} 0 0 {h} 0 0] /Interpolate {}",
+        interpolate,
+        w = width,
+        h = height
+    );
+    let source = "/DataSource currentfile /ASCII85Decode filter /FlateDecode filter";
+
+    s.push_str("/DeviceRGB setcolorspace\n");
+    let data = match alpha.filter(|a| a.iter().any(|a| *a != 255)) {
+        Some(alpha) => {
+            // A mask sample precedes the color samples of each pixel
+            // and only its high-order bit is used.
+            let _ = writeln!(
+                s,
+                "<< /ImageType 3 /InterleaveType 1\n\
+                 /DataDict << {} /Decode [0 1 0 1 0 1] {} >>\n\
+                 /MaskDict << /ImageType 1 /Width {} /Height {} /BitsPerComponent 8 \
+                 /ImageMatrix [{} 0 0 {} 0 0] /Decode [1 0] >>\n\
+                 >> image",
+                image_dict, source, width, height, width, height
+            );
+
+            let mut data = Vec::with_capacity(rgb.len() + alpha.len());
+            for (c, a) in rgb.chunks(3).zip(alpha) {
+                data.push(*a);
+                data.extend_from_slice(c);
+            }
+            data
+        }
+        None => {
+            let _ = writeln!(
+                s,
+                "<< {} /Decode [0 1 0 1 0 1] {} >> image",
+                image_dict, source
+            );
+            rgb.to_vec()
+        }
+    };
+
+    let data = miniz_oxide::deflate::compress_to_vec_zlib(&data, 6);
+    write_ascii85(&data, s);
+}
+
+/// Encodes data using ASCII base-85, which is followed by the `~>` EOD marker.
+fn write_ascii85(data: &[u8], s: &mut String) {
+    const LINE_LEN: usize = 75;
+
+    let mut line_len = 0;
+    let mut push = |s: &mut String, c: char| {
+        s.push(c);
+        line_len += 1;
+        if line_len == LINE_LEN {
+            s.push('\n');
+            line_len = 0;
+        }
+    };
+
+    for chunk in data.chunks(4) {
+        let mut bytes = [0u8; 4];
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        let mut n = u32::from_be_bytes(bytes);
+
+        if n == 0 && chunk.len() == 4 {
+            push(s, 'z');
+            continue;
+        }
+
+        let mut digits = [0u8; 5];
+        for d in digits.iter_mut().rev() {
+            *d = (n % 85) as u8 + b'!';
+            n /= 85;
+        }
+
+        // A partial group is written as `len + 1` characters.
+        for d in &digits[..chunk.len() + 1] {
+            push(s, *d as char);
+        }
+    }
+
+    s.push_str("~>\n");
+}
+
+fn write_image(
+    doc: &Document,
+    w: &mut Writer,
+    node: &usvg::Node,
+    img: &usvg::Image,
+    ts: usvg::Transform,
+) {
+    if img.visibility != usvg::Visibility::Visible {
+        return;
+    }
+
+    match img.kind {
+        usvg::ImageKind::SVG(ref subtree) => {
+            write_svg_image(doc, w, node, subtree, img.view_box, ts);
+        }
+        #[cfg(feature = "raster-images")]
+        _ => {
+            write_raster_image(doc, w, node, img, ts);
+        }
+        #[cfg(not(feature = "raster-images"))]
+        _ => {
+            log::warn!("Images decoding was disabled by a build feature.");
+        }
+    }
+}
+
+#[cfg(feature = "raster-images")]
+fn write_raster_image(
+    doc: &Document,
+    w: &mut Writer,
+    node: &usvg::Node,
+    img: &usvg::Image,
+    ts: usvg::Transform,
+) -> Option<()> {
+    use crate::image::raster_images::{self, ImageData};
+    use crate::OptionLog;
+
+    let image = match img.kind {
+        usvg::ImageKind::JPEG(ref data) => raster_images::read_jpeg(data)
+            .log_none(|| log::warn!("Failed to decode a JPEG image."))?,
+        usvg::ImageKind::PNG(ref data) => raster_images::read_png(data)
+            .log_none(|| log::warn!("Failed to decode a PNG image."))?,
+        usvg::ImageKind::GIF(ref data) => raster_images::read_gif(data)
+            .log_none(|| log::warn!("Failed to decode a GIF image."))?,
+        usvg::ImageKind::SVG(_) => return None,
+    };
+
+    let (rgb, alpha) = match image.data {
+        ImageData::RGB(data) => (data, None),
+        ImageData::RGBA(data) => {
+            let mut rgb = Vec::with_capacity(data.len() / 4 * 3);
+            let mut alpha = Vec::with_capacity(data.len() / 4);
+            for p in data.chunks(4) {
+                rgb.extend_from_slice(&p[0..3]);
+                alpha.push(p[3]);
+            }
+
+            (rgb, Some(alpha))
+        }
+    };
+
+    // A 1-bit mask is enough only for fully opaque and fully transparent pixels.
+    if let Some(ref alpha) = alpha {
+        if alpha.iter().any(|a| *a != 0 && *a != 255) {
+            return write_raster_node(doc, w, node);
+        }
+    }
+
+    let _ = writeln!(w.ops, "gsave\n[{}] concat", TsDisplay(ts));
+    if img.view_box.aspect.slice {
+        let r = img.view_box.rect;
+        let _ = writeln!(
+            w.ops,
+            "{} {} {} {} rectclip",
+            Num(r.x()),
+            Num(r.y()),
+            Num(r.width()),
+            Num(r.height())
+        );
+    }
+
+    let r = raster_images::image_rect(&img.view_box, image.size);
+    let _ = writeln!(
+        w.ops,
+        "{} {} translate {} {} scale",
+        Num(r.x()),
+        Num(r.y()),
+        Num(r.width()),
+        Num(r.height())
+    );
+    write_image_data(
+        image.size.width(),
+        image.size.height(),
+        &rgb,
+        alpha.as_deref(),
+        img.rendering_mode != usvg::ImageRendering::OptimizeSpeed,
+        &mut w.ops,
+    );
+    w.ops.push_str("grestore\n");
+
+    Some(())
+}
+
+fn write_svg_image(
+    doc: &Document,
+    w: &mut Writer,
+    node: &usvg::Node,
+    tree: &usvg::Tree,
+    view_box: usvg::ViewBox,
+    ts: usvg::Transform,
+) {
+    let img_size = tree.size.to_screen_size();
+    let (img_ts, clip) = usvg::utils::view_box_to_transform_with_clip(&view_box, img_size);
+
+    let mut sub_ts = ts;
+    sub_ts.append(&img_ts);
+    sub_ts.append(&usvg::utils::view_box_to_transform(
+        tree.view_box.rect,
+        tree.view_box.aspect,
+        img_size.to_size(),
+    ));
+
+    w.ops.push_str("gsave\n");
+    if let Some(clip) = clip {
+        let mut data = usvg::PathData::from_rect(clip);
+        data.transform(ts);
+        w.ops.push_str("newpath\n");
+        write_path_data(&data, &mut w.ops);
+        w.ops.push_str("clip\n");
+    }
+
+    // Nodes of the subtree are relative to its own root.
+    let sub_doc = Document {
+        tree,
+        base: sub_ts,
+        parent: Some((doc, node.clone())),
+    };
+    let mut root_ts = sub_ts;
+    root_ts.append(&tree.root.transform());
+    write_group_children(&sub_doc, w, &tree.root, root_ts);
+
+    w.ops.push_str("grestore\n");
+}
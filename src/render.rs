@@ -6,7 +6,7 @@ use std::convert::TryInto;
 
 use usvg::{FuzzyEq, NodeExt};
 
-use crate::ConvTransform;
+use crate::{ConvTransform, RenderCache};
 
 pub struct Canvas<'a> {
     pub pixmap: tiny_skia::PixmapMut<'a>,
@@ -37,18 +37,36 @@ impl Canvas<'_> {
         self.transform = self.transform.pre_concat(ts);
     }
 
+    /// Intersects the current clip with a rect in user coordinates.
     pub fn set_clip_rect(&mut self, rect: tiny_skia::Rect) {
         let path = tiny_skia::PathBuilder::from_rect(rect);
         if let Some(path) = path.transform(self.transform) {
-            let mut clip = tiny_skia::ClipMask::new();
-            clip.set_path(
-                self.pixmap.width(),
-                self.pixmap.height(),
-                &path,
-                tiny_skia::FillRule::Winding,
-                true,
-            );
-            self.clip = Some(clip);
+            self.intersect_clip_path(&path, tiny_skia::FillRule::Winding, true);
+        }
+    }
+
+    /// Intersects the current clip with a path in canvas coordinates.
+    pub fn intersect_clip_path(
+        &mut self,
+        path: &tiny_skia::Path,
+        rule: tiny_skia::FillRule,
+        anti_alias: bool,
+    ) {
+        let (width, height) = (self.pixmap.width(), self.pixmap.height());
+        match self.clip {
+            Some(ref mut clip) => {
+                // Fails only when the path is outside the canvas.
+                // In which case, `set_path` simply resets the mask.
+                if clip.intersect_path(path, rule, anti_alias).is_none() {
+                    let _ = clip.set_path(width, height, path, rule, anti_alias);
+                }
+            }
+            None => {
+                // The mask stays empty when the path is outside the canvas.
+                let mut clip = tiny_skia::ClipMask::new();
+                let _ = clip.set_path(width, height, path, rule, anti_alias);
+                self.clip = Some(clip);
+            }
         }
     }
 }
@@ -65,13 +83,19 @@ pub(crate) enum RenderState {
     BackgroundFinished,
 }
 
-pub(crate) fn render_to_canvas(tree: &usvg::Tree, img_size: usvg::ScreenSize, canvas: &mut Canvas) {
+pub(crate) fn render_to_canvas(
+    tree: &usvg::Tree,
+    img_size: usvg::ScreenSize,
+    cache: &mut RenderCache,
+    canvas: &mut Canvas,
+) {
     render_node_to_canvas(
         tree,
         &tree.root,
         tree.view_box,
         img_size,
         &mut RenderState::Ok,
+        cache,
         canvas,
     );
 }
@@ -82,6 +106,7 @@ pub(crate) fn render_node_to_canvas(
     view_box: usvg::ViewBox,
     img_size: usvg::ScreenSize,
     state: &mut RenderState,
+    cache: &mut RenderCache,
     canvas: &mut Canvas,
 ) {
     apply_viewbox_transform(view_box, img_size, canvas);
@@ -91,7 +116,7 @@ pub(crate) fn render_node_to_canvas(
     let ts = node.abs_transform();
 
     canvas.apply_transform(ts.to_native());
-    render_node(tree, node, state, canvas);
+    render_node(tree, node, state, cache, canvas);
     canvas.transform = curr_ts;
 }
 
@@ -109,14 +134,15 @@ pub(crate) fn render_node(
     tree: &usvg::Tree,
     node: &usvg::Node,
     state: &mut RenderState,
+    cache: &mut RenderCache,
     canvas: &mut Canvas,
 ) -> Option<usvg::PathBbox> {
     match *node.borrow() {
         usvg::NodeKind::Path(ref path) => {
-            crate::path::draw(tree, path, tiny_skia::BlendMode::SourceOver, canvas)
+            crate::path::draw(tree, path, tiny_skia::BlendMode::SourceOver, cache, canvas)
         }
         usvg::NodeKind::Image(ref img) => Some(crate::image::draw(img, canvas)),
-        usvg::NodeKind::Group(ref g) => render_group_impl(tree, node, g, state, canvas),
+        usvg::NodeKind::Group(ref g) => render_group_impl(tree, node, g, state, cache, canvas),
     }
 }
 
@@ -124,6 +150,7 @@ pub(crate) fn render_group(
     tree: &usvg::Tree,
     parent: &usvg::Node,
     state: &mut RenderState,
+    cache: &mut RenderCache,
     canvas: &mut Canvas,
 ) -> Option<usvg::PathBbox> {
     let curr_ts = canvas.transform;
@@ -144,7 +171,7 @@ pub(crate) fn render_group(
 
         canvas.apply_transform(node.transform().to_native());
 
-        let bbox = render_node(tree, &node, state, canvas);
+        let bbox = render_node(tree, &node, state, cache, canvas);
         if let Some(bbox) = bbox {
             if let Some(bbox) = bbox.transform(&node.transform()) {
                 g_bbox = g_bbox.expand(bbox);
@@ -168,15 +195,51 @@ fn render_group_impl(
     node: &usvg::Node,
     g: &usvg::Group,
     state: &mut RenderState,
+    cache: &mut RenderCache,
     canvas: &mut Canvas,
 ) -> Option<usvg::PathBbox> {
-    let mut sub_pixmap = tiny_skia::Pixmap::new(canvas.pixmap.width(), canvas.pixmap.height())?;
     let curr_ts = canvas.transform;
+    let canvas_size = (canvas.pixmap.width(), canvas.pixmap.height());
+
+    // Cached layers are already filtered, clipped and masked,
+    // so only the group opacity should be applied.
+    // Background rendering for filters must not use layers, since they're postprocessed.
+    let is_cacheable = *state == RenderState::Ok;
+    if is_cacheable {
+        if let Some(layer) = cache.layer(node, curr_ts, canvas_size) {
+            draw_layer(g, layer.x, layer.y, layer.pixmap.as_ref(), canvas);
+            return layer.bbox;
+        }
+    }
 
+    #[cfg(feature = "filter")]
+    let has_filters = !g.filters.is_empty();
+    #[cfg(not(feature = "filter"))]
+    let has_filters = false;
+
+    // Simple clip paths are applied while the children are drawn,
+    // instead of clipping the rendered layer afterwards.
+    // Filters must be applied before clipping and the background rendering
+    // must not clip the group that contains the last node,
+    // so both are clipped afterwards.
+    // A mask is rendered onto a layer trimmed to the unclipped content,
+    // therefore masked groups are clipped afterwards as well.
+    let draw_clip = match g.clip_path {
+        Some(ref cp) if *state == RenderState::Ok && !has_filters && g.mask.is_none() => Some(cp),
+        _ => None,
+    };
+
+    let mut sub_pixmap = tiny_skia::Pixmap::new(canvas_size.0, canvas_size.1)?;
+
+    let mut is_clipped = false;
     let bbox = {
         let mut sub_canvas = Canvas::from(sub_pixmap.as_mut());
         sub_canvas.transform = curr_ts;
-        render_group(tree, node, state, &mut sub_canvas)
+        if let Some(cp) = draw_clip {
+            is_clipped = crate::clip::intersect_clip(cp, node, &mut sub_canvas).is_some();
+        }
+
+        render_group(tree, node, state, cache, &mut sub_canvas)
     };
 
     // At this point, `sub_pixmap` has probably the same size as the viewbox.
@@ -214,7 +277,7 @@ fn render_group_impl(
             sub_pixmap.as_ref(),
             &paint,
             tiny_skia::Transform::identity(),
-            None,
+            canvas.clip.as_ref(),
         );
         return bbox;
     }
@@ -226,8 +289,10 @@ fn render_group_impl(
         let bbox = bbox.and_then(|r| r.to_rect());
         let ts = usvg::Transform::from_native(curr_ts);
         let background = prepare_filter_background(tree, node, filter, &sub_pixmap);
-        let fill_paint = prepare_filter_fill_paint(tree, node, filter, bbox, ts, &sub_pixmap);
-        let stroke_paint = prepare_filter_stroke_paint(tree, node, filter, bbox, ts, &sub_pixmap);
+        let fill_paint =
+            prepare_filter_fill_paint(tree, node, filter, bbox, ts, cache, &sub_pixmap);
+        let stroke_paint =
+            prepare_filter_stroke_paint(tree, node, filter, bbox, ts, cache, &sub_pixmap);
         crate::filter::apply(
             filter,
             bbox,
@@ -242,21 +307,31 @@ fn render_group_impl(
 
     // Clipping and masking can be done only for objects with a valid bbox.
     if let Some(bbox) = bbox {
-        if let Some(ref clip_path) = g.clip_path {
+        if let (Some(clip_path), false) = (&g.clip_path, is_clipped) {
             let mut sub_canvas = Canvas::from(sub_pixmap.as_mut());
             sub_canvas.translate(-tx as f32, -ty as f32);
             sub_canvas.apply_transform(curr_ts);
-            crate::clip::clip(tree, clip_path, bbox, &mut sub_canvas);
+            crate::clip::clip(tree, clip_path, bbox, cache, &mut sub_canvas);
         }
 
         if let Some(ref mask) = g.mask {
             let mut sub_canvas = Canvas::from(sub_pixmap.as_mut());
             sub_canvas.translate(-tx as f32, -ty as f32);
             sub_canvas.apply_transform(curr_ts);
-            crate::mask::mask(tree, mask, bbox, &mut sub_canvas);
+            crate::mask::mask(tree, mask, bbox, cache, &mut sub_canvas);
         }
     }
 
+    draw_layer(g, tx, ty, sub_pixmap.as_ref(), canvas);
+
+    if is_cacheable && *state == RenderState::Ok {
+        cache.insert_layer(node, curr_ts, canvas_size, tx, ty, sub_pixmap, bbox);
+    }
+
+    bbox
+}
+
+fn draw_layer(g: &usvg::Group, x: i32, y: i32, layer: tiny_skia::PixmapRef, canvas: &mut Canvas) {
     let mut paint = tiny_skia::PixmapPaint::default();
     paint.quality = tiny_skia::FilterQuality::Nearest;
     if g.opacity != usvg::Opacity::ONE {
@@ -264,15 +339,13 @@ fn render_group_impl(
     }
 
     canvas.pixmap.draw_pixmap(
-        tx,
-        ty,
-        sub_pixmap.as_ref(),
+        x,
+        y,
+        layer,
         &paint,
         tiny_skia::Transform::identity(),
-        None,
+        canvas.clip.as_ref(),
     );
-
-    bbox
 }
 
 /// Removes transparent borders from the image leaving only a tight bbox content.
@@ -406,6 +479,7 @@ fn prepare_filter_background(
         tree.view_box,
         img_size,
         &mut state,
+        &mut RenderCache::without_layers(),
         &mut canvas,
     );
 
@@ -426,6 +500,7 @@ fn prepare_filter_fill_paint(
     filter: &usvg::filter::Filter,
     bbox: Option<usvg::Rect>,
     ts: usvg::Transform,
+    cache: &mut RenderCache,
     pixmap: &tiny_skia::Pixmap,
 ) -> Option<tiny_skia::Pixmap> {
     let region = crate::filter::calc_region(filter, bbox, &ts, pixmap).ok()?;
@@ -451,6 +526,7 @@ fn prepare_filter_fill_paint(
                 &path,
                 true,
                 tiny_skia::BlendMode::SourceOver,
+                cache,
                 &mut sub_canvas,
             );
         }
@@ -467,6 +543,7 @@ fn prepare_filter_stroke_paint(
     filter: &usvg::filter::Filter,
     bbox: Option<usvg::Rect>,
     ts: usvg::Transform,
+    cache: &mut RenderCache,
     pixmap: &tiny_skia::Pixmap,
 ) -> Option<tiny_skia::Pixmap> {
     let region = crate::filter::calc_region(filter, bbox, &ts, pixmap).ok()?;
@@ -492,6 +569,7 @@ fn prepare_filter_stroke_paint(
                 &path,
                 true,
                 tiny_skia::BlendMode::SourceOver,
+                cache,
                 &mut sub_canvas,
             );
         }
@@ -0,0 +1,473 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Common code for vector output formats.
+
+use std::fmt::{self, Write};
+
+#[cfg(any(feature = "filter", feature = "ps"))]
+use usvg::NodeExt;
+
+use crate::ConvTransform;
+
+/// SVG pixels are 1/96 of an inch, while PDF and PostScript points are 1/72.
+pub const PT_PER_PX: f64 = 72.0 / 96.0;
+
+/// The maximum number of pixels in a rasterized region.
+#[cfg(any(feature = "filter", feature = "ps"))]
+const MAX_RASTER_PIXELS: f64 = 4096.0 * 4096.0;
+
+/// The maximum number of gradient periods for the `reflect` and `repeat` spread methods.
+const MAX_GRADIENT_PERIODS: i64 = 64;
+
+/// Writes path data using `m`, `l`, `c` and `h` operators.
+///
+/// PostScript output defines them as procedures.
+pub fn write_path_data(data: &usvg::PathData, s: &mut String) {
+    for seg in data.segments() {
+        let _ = match seg {
+            usvg::PathSegment::MoveTo { x, y } => writeln!(s, "{} {} m", Num(x), Num(y)),
+            usvg::PathSegment::LineTo { x, y } => writeln!(s, "{} {} l", Num(x), Num(y)),
+            usvg::PathSegment::CurveTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            } => writeln!(
+                s,
+                "{} {} {} {} {} {} c",
+                Num(x1),
+                Num(y1),
+                Num(x2),
+                Num(y2),
+                Num(x),
+                Num(y)
+            ),
+            usvg::PathSegment::ClosePath => writeln!(s, "h"),
+        };
+    }
+}
+
+/// A gradient resolved into a PDF/PostScript shading.
+pub struct Gradient {
+    /// `ShadingType` 2 or 3.
+    pub kind: u8,
+    pub coords: Vec<f64>,
+    /// Gradient periods range. Equals to `[0, 1]` for the `pad` spread method.
+    pub domain: (i64, i64),
+    pub spread_method: usvg::SpreadMethod,
+    /// A transform from the gradient space to the object's user space.
+    pub transform: usvg::Transform,
+    pub stops: Vec<usvg::Stop>,
+}
+
+impl Gradient {
+    pub fn linear(
+        g: &usvg::LinearGradient,
+        bbox: usvg::PathBbox,
+        paint_bbox: usvg::PathBbox,
+    ) -> Option<Self> {
+        let transform = usvg::utils::gradient_transform(g, Some(bbox))?;
+
+        // Find out how many periods are needed to cover the painted area.
+        let (dx, dy) = (g.x2 - g.x1, g.y2 - g.y1);
+        let len2 = dx * dx + dy * dy;
+        let domain = gradient_domain(g.spread_method, transform, paint_bbox, |x, y| {
+            if len2 == 0.0 {
+                0.0
+            } else {
+                ((x - g.x1) * dx + (y - g.y1) * dy) / len2
+            }
+        });
+
+        let (t0, t1) = (domain.0 as f64, domain.1 as f64);
+        Some(Gradient {
+            kind: 2,
+            coords: vec![
+                g.x1 + dx * t0,
+                g.y1 + dy * t0,
+                g.x1 + dx * t1,
+                g.y1 + dy * t1,
+            ],
+            domain,
+            spread_method: g.spread_method,
+            transform,
+            stops: g.stops.clone(),
+        })
+    }
+
+    pub fn radial(
+        g: &usvg::RadialGradient,
+        bbox: usvg::PathBbox,
+        paint_bbox: usvg::PathBbox,
+    ) -> Option<Self> {
+        let transform = usvg::utils::gradient_transform(g, Some(bbox))?;
+
+        // An upper bound of the gradient position, which is good enough for our needs.
+        let r = g.r.get();
+        let focal_dist = ((g.fx - g.cx).powi(2) + (g.fy - g.cy).powi(2)).sqrt();
+        let domain = gradient_domain(g.spread_method, transform, paint_bbox, |x, y| {
+            (((x - g.cx).powi(2) + (y - g.cy).powi(2)).sqrt() + focal_dist) / r
+        });
+        let domain = (0, domain.1.max(1));
+
+        let t1 = domain.1 as f64;
+        Some(Gradient {
+            kind: 3,
+            coords: vec![
+                g.fx,
+                g.fy,
+                0.0,
+                g.fx + (g.cx - g.fx) * t1,
+                g.fy + (g.cy - g.fy) * t1,
+                r * t1,
+            ],
+            domain,
+            spread_method: g.spread_method,
+            transform,
+            stops: g.stops.clone(),
+        })
+    }
+
+    /// Returns a shading dictionary.
+    ///
+    /// When `alpha` is set, stops opacity is used as a gray color.
+    pub fn shading(&self, alpha: bool) -> String {
+        let color_space = if alpha { "/DeviceGray" } else { "/DeviceRGB" };
+        let stops_func = stops_function(&self.stops, alpha);
+
+        let (t0, t1) = self.domain;
+        let func = if t1 - t0 == 1 && t0 == 0 {
+            stops_func
+        } else {
+            // Gradient periods are stitched together.
+            let mut functions = String::new();
+            let mut bounds = String::new();
+            let mut encode = String::new();
+            for t in t0..t1 {
+                functions.push_str(&stops_func);
+                functions.push(' ');
+
+                if t != t0 {
+                    let _ = write!(bounds, "{} ", t);
+                }
+
+                if self.spread_method == usvg::SpreadMethod::Reflect && t.rem_euclid(2) == 1 {
+                    encode.push_str("1 0 ");
+                } else {
+                    encode.push_str("0 1 ");
+                }
+            }
+
+            format!(
+                "<< /FunctionType 3 /Domain [{} {}] /Functions [{}] /Bounds [{}] /Encode [{}] >>",
+                t0,
+                t1,
+                functions.trim_end(),
+                bounds.trim_end(),
+                encode.trim_end()
+            )
+        };
+
+        let mut coords = String::new();
+        for (i, n) in self.coords.iter().enumerate() {
+            if i != 0 {
+                coords.push(' ');
+            }
+            let _ = write!(coords, "{}", Num(*n));
+        }
+
+        format!(
+            "<< /ShadingType {} /ColorSpace {} /Coords [{}] /Domain [{} {}] /Function {} \
+             /Extend [true true] >>",
+            self.kind, color_space, coords, t0, t1, func
+        )
+    }
+}
+
+/// Calculates gradient periods that cover `paint_bbox`.
+///
+/// `pos` returns a gradient position for a point in the gradient space.
+fn gradient_domain<F: Fn(f64, f64) -> f64>(
+    spread_method: usvg::SpreadMethod,
+    transform: usvg::Transform,
+    paint_bbox: usvg::PathBbox,
+    pos: F,
+) -> (i64, i64) {
+    if spread_method == usvg::SpreadMethod::Pad {
+        return (0, 1);
+    }
+
+    let (sx, kx, ky, sy, tx, ty) = match transform.to_native().invert() {
+        Some(ts) => (ts.sx, ts.kx, ts.ky, ts.sy, ts.tx, ts.ty),
+        None => return (0, 1),
+    };
+
+    let mut min = f64::MAX;
+    let mut max = f64::MIN;
+    for &(x, y) in &[
+        (paint_bbox.left(), paint_bbox.top()),
+        (paint_bbox.right(), paint_bbox.top()),
+        (paint_bbox.right(), paint_bbox.bottom()),
+        (paint_bbox.left(), paint_bbox.bottom()),
+    ] {
+        let (x, y) = (x as f32, y as f32);
+        let gx = (sx * x + kx * y + tx) as f64;
+        let gy = (ky * x + sy * y + ty) as f64;
+        let t = pos(gx, gy);
+        min = min.min(t);
+        max = max.max(t);
+    }
+
+    let mut t0 = min.floor() as i64;
+    let mut t1 = (max.ceil() as i64).max(t0 + 1);
+    if t1 - t0 > MAX_GRADIENT_PERIODS {
+        log::warn!("Gradient has too many periods. The last one will be padded.");
+        t0 = t0.max(-MAX_GRADIENT_PERIODS / 2);
+        t1 = t0 + MAX_GRADIENT_PERIODS;
+    }
+
+    (t0, t1)
+}
+
+/// Checks that at least one stop is not opaque.
+pub fn has_stop_opacity(stops: &[usvg::Stop]) -> bool {
+    stops.iter().any(|s| s.opacity != usvg::Opacity::ONE)
+}
+
+/// Converts gradient stops into a function with a `[0, 1]` domain.
+fn stops_function(stops: &[usvg::Stop], alpha: bool) -> String {
+    let color = |stop: &usvg::Stop| {
+        if alpha {
+            format!("{}", Num(stop.opacity.get()))
+        } else {
+            format!(
+                "{} {} {}",
+                Num(stop.color.red as f64 / 255.0),
+                Num(stop.color.green as f64 / 255.0),
+                Num(stop.color.blue as f64 / 255.0)
+            )
+        }
+    };
+
+    let interpolate = |s1: &usvg::Stop, s2: &usvg::Stop| {
+        format!(
+            "<< /FunctionType 2 /Domain [0 1] /C0 [{}] /C1 [{}] /N 1 >>",
+            color(s1),
+            color(s2)
+        )
+    };
+
+    // `usvg` guarantees at least two stops.
+    let (first, last) = match (stops.first(), stops.last()) {
+        (Some(first), Some(last)) if stops.len() > 1 => (first, last),
+        (Some(first), _) => return interpolate(first, first),
+        _ => return interpolate_empty(alpha),
+    };
+
+    // Zero-length intervals are skipped, which produces sharp color transitions.
+    let mut functions = Vec::new();
+    let mut bounds = Vec::new();
+    if first.offset.get() > 0.0 {
+        functions.push(interpolate(first, first));
+        bounds.push(first.offset.get());
+    }
+
+    for pair in stops.windows(2) {
+        let (s1, s2) = (&pair[0], &pair[1]);
+        if s2.offset.get() > s1.offset.get() {
+            functions.push(interpolate(s1, s2));
+            bounds.push(s2.offset.get());
+        }
+    }
+
+    if last.offset.get() < 1.0 {
+        functions.push(interpolate(last, last));
+        bounds.push(1.0);
+    }
+
+    if functions.is_empty() {
+        return interpolate(last, last);
+    }
+
+    if functions.len() == 1 {
+        return functions.remove(0);
+    }
+
+    // The last bound is the domain end.
+    bounds.pop();
+
+    let mut s = String::from("<< /FunctionType 3 /Domain [0 1] /Functions [");
+    s.push_str(&functions.join(" "));
+    s.push_str("] /Bounds [");
+    for (i, b) in bounds.iter().enumerate() {
+        if i != 0 {
+            s.push(' ');
+        }
+        let _ = write!(s, "{}", Num(*b));
+    }
+    s.push_str("] /Encode [");
+    s.push_str(&vec!["0 1"; functions.len()].join(" "));
+    s.push_str("] >>");
+    s
+}
+
+fn interpolate_empty(alpha: bool) -> String {
+    let c = if alpha { "0" } else { "0 0 0" };
+    format!(
+        "<< /FunctionType 2 /Domain [0 1] /C0 [{}] /C1 [{}] /N 1 >>",
+        c, c
+    )
+}
+
+/// Returns a clip path that consists of a single filled path.
+///
+/// The path is already transformed by `ts`, the clip path transform and its units.
+/// Returns `None` for all other clip paths, which cannot be represented by a single
+/// clipping path.
+pub fn simple_clip_path(
+    cp: &usvg::ClipPath,
+    node: &usvg::Node,
+    ts: usvg::Transform,
+) -> Option<(usvg::PathData, usvg::FillRule)> {
+    if cp.clip_path.is_some() {
+        return None;
+    }
+
+    let path = usvg::utils::simple_clip_path(cp, node)?;
+    let mut clip_ts = ts;
+    clip_ts.append(&path.transform);
+
+    // The path is transformed manually, because the current transform
+    // must stay unchanged for the clipped content.
+    let mut data = (*path.data).clone();
+    data.transform(clip_ts);
+    Some((data, path.fill?.rule))
+}
+
+/// Content that was already painted below a rasterized node.
+#[cfg(any(feature = "filter", feature = "ps"))]
+pub struct Backdrop<'a> {
+    /// A tree which root is rendered.
+    pub tree: &'a usvg::Tree,
+    /// A node that stops the rendering. Not rendered itself.
+    pub until: usvg::Node,
+    /// Maps the tree root into the default coordinates.
+    pub base: usvg::Transform,
+}
+
+/// Renders a node into a pixmap in the output's default coordinates.
+///
+/// `base` maps the node's subtree root into the default coordinates
+/// and `view` limits the rendered region. Returns the pixmap and its region
+/// in the default coordinates.
+///
+/// Each `backdrop` is rendered in order before the node, which allows flattening
+/// transparency for outputs that don't support it.
+/// Only the node region is rendered.
+///
+/// The default coordinates have a bottom-left origin, so the first pixmap row
+/// is at the top of the region.
+#[cfg(any(feature = "filter", feature = "ps"))]
+pub fn rasterize_node(
+    tree: &usvg::Tree,
+    node: &usvg::Node,
+    base: usvg::Transform,
+    view: Option<usvg::PathBbox>,
+    dpi: f64,
+    backdrop: &[Backdrop],
+) -> Option<(tiny_skia::Pixmap, usvg::Rect)> {
+    let mut bbox = node.calculate_visual_bbox()?.transform(&base)?;
+    if let Some(view) = view {
+        bbox = bbox.intersect(view)?;
+    }
+    let bbox = bbox.to_rect()?;
+
+    let mut scale = dpi / 72.0;
+    let area = bbox.width() * bbox.height() * scale * scale;
+    if area > MAX_RASTER_PIXELS {
+        log::warn!("Rasterized region is too big. The resolution will be reduced.");
+        scale *= (MAX_RASTER_PIXELS / area).sqrt();
+    }
+
+    let width = (bbox.width() * scale).ceil().max(1.0) as u32;
+    let height = (bbox.height() * scale).ceil().max(1.0) as u32;
+
+    let mut pixmap = tiny_skia::Pixmap::new(width, height)?;
+    {
+        let mut canvas = crate::render::Canvas::from(pixmap.as_mut());
+        canvas.scale(
+            (width as f64 / bbox.width()) as f32,
+            -(height as f64 / bbox.height()) as f32,
+        );
+        canvas.translate(-bbox.x() as f32, -bbox.bottom() as f32);
+        let region_ts = canvas.transform;
+
+        for b in backdrop {
+            canvas.transform = region_ts;
+            canvas.apply_transform(b.base.to_native());
+            canvas.apply_transform(b.tree.root.transform().to_native());
+            crate::render::render_node(
+                b.tree,
+                &b.tree.root,
+                &mut crate::render::RenderState::RenderUntil(b.until.clone()),
+                &mut crate::RenderCache::without_layers(),
+                &mut canvas,
+            );
+        }
+
+        canvas.transform = region_ts;
+        canvas.apply_transform(base.to_native());
+        canvas.apply_transform(node.abs_transform().to_native());
+        crate::render::render_node(
+            tree,
+            node,
+            &mut crate::render::RenderState::Ok,
+            &mut crate::RenderCache::without_layers(),
+            &mut canvas,
+        );
+    }
+
+    Some((pixmap, bbox))
+}
+
+/// Formats a number in a compact form, since PDF and PostScript don't support exponents.
+pub struct Num(pub f64);
+
+impl fmt::Display for Num {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let n = if self.0.is_finite() { self.0 } else { 0.0 };
+        if n.fract() == 0.0 && n.abs() < 1e15 {
+            write!(f, "{}", n as i64)
+        } else {
+            let s = format!("{:.6}", n);
+            let s = s.trim_end_matches('0').trim_end_matches('.');
+            if s == "-0" {
+                write!(f, "0")
+            } else {
+                write!(f, "{}", s)
+            }
+        }
+    }
+}
+
+pub struct TsDisplay(pub usvg::Transform);
+
+impl fmt::Display for TsDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ts = &self.0;
+        write!(
+            f,
+            "{} {} {} {} {} {}",
+            Num(ts.a),
+            Num(ts.b),
+            Num(ts.c),
+            Num(ts.d),
+            Num(ts.e),
+            Num(ts.f)
+        )
+    }
+}
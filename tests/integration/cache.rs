@@ -0,0 +1,170 @@
+const SVG: &str = "
+<svg id='svg1' viewBox='0 0 200 200' xmlns='http://www.w3.org/2000/svg'>
+    <clipPath id='clip1'>
+        <circle cx='100' cy='100' r='70'/>
+    </clipPath>
+    <pattern id='patt1' patternUnits='userSpaceOnUse' width='20' height='20'>
+        <rect id='rect1' width='10' height='10' fill='green'/>
+    </pattern>
+    <filter id='filter1'>
+        <feGaussianBlur stdDeviation='4'/>
+    </filter>
+    <g id='g1' opacity='0.5' clip-path='url(#clip1)'>
+        <rect id='rect2' x='20' y='20' width='160' height='160' fill='url(#patt1)'/>
+    </g>
+    <g id='g2' filter='url(#filter1)'>
+        <rect id='rect3' x='60' y='60' width='80' height='80' fill='blue'/>
+    </g>
+</svg>
+";
+
+fn render(tree: &usvg::Tree, cache: Option<&mut resvg::RenderCache>) -> tiny_skia::Pixmap {
+    let mut pixmap = tiny_skia::Pixmap::new(200, 200).unwrap();
+    let ts = tiny_skia::Transform::default();
+    match cache {
+        Some(cache) => {
+            resvg::render_with_cache(tree, usvg::FitTo::Original, ts, cache, pixmap.as_mut())
+        }
+        None => resvg::render(tree, usvg::FitTo::Original, ts, pixmap.as_mut()),
+    }
+    .unwrap();
+    pixmap
+}
+
+fn parse() -> usvg::Tree {
+    let opt = usvg::Options {
+        keep_named_groups: true,
+        ..usvg::Options::default()
+    };
+    usvg::Tree::from_str(SVG, &opt.to_ref()).unwrap()
+}
+
+#[test]
+fn reuse_layers() {
+    let tree = parse();
+    let mut cache = resvg::RenderCache::new();
+
+    let expected = render(&tree, None);
+    assert!(render(&tree, Some(&mut cache)) == expected);
+    assert!(!cache.is_empty());
+    assert!(render(&tree, Some(&mut cache)) == expected);
+}
+
+#[test]
+fn invalidate_dirty_node() {
+    let tree = parse();
+    let mut cache = resvg::RenderCache::new();
+    render(&tree, Some(&mut cache));
+
+    let node = tree.node_by_id("rect3").unwrap();
+    if let usvg::NodeKind::Path(ref mut path) = *node.borrow_mut() {
+        path.fill = Some(usvg::Fill::from_paint(usvg::Paint::Color(
+            usvg::Color::new_rgb(255, 0, 0),
+        )));
+    }
+
+    // Without invalidation, the old layer is used.
+    assert!(render(&tree, Some(&mut cache)) != render(&tree, None));
+
+    cache.mark_dirty(&node);
+    assert!(render(&tree, Some(&mut cache)) == render(&tree, None));
+}
+
+#[test]
+fn invalidate_referenced_pattern() {
+    let tree = parse();
+    let mut cache = resvg::RenderCache::new();
+    render(&tree, Some(&mut cache));
+
+    // `rect1` is inside the pattern, which is used by the `g1` layer.
+    let node = tree
+        .node_by_id("rect2")
+        .and_then(|n| match *n.borrow() {
+            usvg::NodeKind::Path(ref path) => match path.fill.as_ref()?.paint {
+                usvg::Paint::Pattern(ref pattern) => Some(pattern.root.clone()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .and_then(|root| root.descendants().find(|n| n.borrow().id() == "rect1"))
+        .unwrap();
+    if let usvg::NodeKind::Path(ref mut path) = *node.borrow_mut() {
+        path.fill = Some(usvg::Fill::from_paint(usvg::Paint::Color(
+            usvg::Color::new_rgb(255, 0, 0),
+        )));
+    }
+
+    // Without invalidation, the old layer is used.
+    assert!(render(&tree, Some(&mut cache)) != render(&tree, None));
+
+    cache.mark_dirty(&node);
+    assert!(render(&tree, Some(&mut cache)) == render(&tree, None));
+}
+
+#[test]
+fn invalidate_referenced_clip_path() {
+    let tree = parse();
+    let mut cache = resvg::RenderCache::new();
+    render(&tree, Some(&mut cache));
+
+    let node = tree
+        .node_by_id("g1")
+        .and_then(|n| match *n.borrow() {
+            usvg::NodeKind::Group(ref g) => g.clip_path.as_ref().map(|cp| cp.root.clone()),
+            _ => None,
+        })
+        .and_then(|root| root.first_child())
+        .unwrap();
+    if let usvg::NodeKind::Path(ref mut path) = *node.borrow_mut() {
+        path.transform = usvg::Transform::new_translate(20.0, 0.0);
+    }
+
+    // Without invalidation, the old layer is used.
+    assert!(render(&tree, Some(&mut cache)) != render(&tree, None));
+
+    cache.mark_dirty(&node);
+    assert!(render(&tree, Some(&mut cache)) == render(&tree, None));
+}
+
+#[test]
+fn evict_least_recently_used_layers() {
+    let tree = parse();
+    // 200x200 RGBA layers, so only a single layer fits.
+    let mut cache = resvg::RenderCache::with_max_bytes(200 * 200 * 4);
+
+    let expected = render(&tree, None);
+    assert!(render(&tree, Some(&mut cache)) == expected);
+    assert!(cache.layers_bytes() <= 200 * 200 * 4);
+    assert!(render(&tree, Some(&mut cache)) == expected);
+
+    let mut cache = resvg::RenderCache::with_max_bytes(0);
+    assert!(render(&tree, Some(&mut cache)) == expected);
+    assert_eq!(cache.layers_bytes(), 0);
+}
+
+#[test]
+fn pattern_content_layers_are_not_cached() {
+    let svg = "
+    <svg viewBox='0 0 200 200' xmlns='http://www.w3.org/2000/svg'>
+        <pattern id='patt1' patternUnits='userSpaceOnUse' width='20' height='20'
+                 patternTransform='skewX(20)'>
+            <g opacity='0.5'>
+                <rect width='10' height='10' fill='green'/>
+            </g>
+        </pattern>
+        <g id='g1' opacity='0.5'>
+            <rect x='20' y='20' width='160' height='160' fill='url(#patt1)'/>
+        </g>
+    </svg>
+    ";
+
+    let len = |svg: &str| {
+        let tree = usvg::Tree::from_str(svg, &usvg::Options::default().to_ref()).unwrap();
+        let mut cache = resvg::RenderCache::new();
+        assert!(render(&tree, Some(&mut cache)) == render(&tree, None));
+        cache.len()
+    };
+
+    // Only the pattern tile is added.
+    assert_eq!(len(svg), len(&svg.replace("url(#patt1)", "green")) + 1);
+}
@@ -0,0 +1,389 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const SVG: &str = "<svg xmlns='http://www.w3.org/2000/svg' width='20' height='10'>\
+    <rect width='20' height='10' fill='green'/>\
+</svg>";
+
+/// Creates an empty temporary directory unique to the test.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("resvg-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write_svg(path: &Path) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, SVG).unwrap();
+}
+
+fn resvg(args: &[&Path]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_resvg"))
+        .arg("--skip-system-fonts")
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stderr(output: &std::process::Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn batch_directory() {
+    let dir = temp_dir("batch-directory");
+    write_svg(&dir.join("in/a/x.svg"));
+    write_svg(&dir.join("in/b/x.svg"));
+    write_svg(&dir.join("in/y.svg"));
+    std::fs::write(dir.join("in/notes.txt"), "").unwrap();
+
+    let output = resvg(&[
+        Path::new("--batch"),
+        Path::new("--jobs"),
+        Path::new("2"),
+        &dir.join("in"),
+        &dir.join("out"),
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    // The input directory structure is preserved.
+    for name in &["a/x.png", "b/x.png", "y.png"] {
+        let data = std::fs::read(dir.join("out").join(name)).unwrap();
+        assert!(data.starts_with(b"\x89PNG"));
+    }
+    assert!(!dir.join("out/notes.png").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn batch_glob() {
+    let dir = temp_dir("batch-glob");
+    write_svg(&dir.join("in/a/x.svg"));
+    write_svg(&dir.join("in/a/b/y.svg"));
+    write_svg(&dir.join("in/z.svg"));
+
+    let output = resvg(&[
+        Path::new("--batch"),
+        &dir.join("in/**/*.svg"),
+        &dir.join("out"),
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(dir.join("out/a/x.png").exists());
+    assert!(dir.join("out/a/b/y.png").exists());
+    assert!(dir.join("out/z.png").exists());
+
+    let output = resvg(&[
+        Path::new("--batch"),
+        &dir.join("in/a/*.svg"),
+        &dir.join("out2"),
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(dir.join("out2/x.png").exists());
+    assert!(!dir.join("out2/b").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn batch_manifest() {
+    let dir = temp_dir("batch-manifest");
+    write_svg(&dir.join("in/a/x.svg"));
+    write_svg(&dir.join("in/b/x.svg"));
+    std::fs::write(
+        dir.join("in/manifest.txt"),
+        "# Comment\n\
+         a/x.svg -w 40\n\
+         b/x.svg custom.png\n",
+    )
+    .unwrap();
+
+    let output = resvg(&[
+        Path::new("--batch"),
+        &dir.join("in/manifest.txt"),
+        &dir.join("out"),
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    // Only the width is stored in the PNG header.
+    let data = std::fs::read(dir.join("out/a/x.png")).unwrap();
+    assert_eq!(&data[16..20], &40u32.to_be_bytes());
+    assert!(dir.join("out/b/custom.png").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn batch_output_collision() {
+    let dir = temp_dir("batch-collision");
+    write_svg(&dir.join("in/x.svg"));
+    write_svg(&dir.join("in/x.svgz"));
+
+    let output = resvg(&[Path::new("--batch"), &dir.join("in"), &dir.join("out")]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("would be both rendered to"));
+    assert!(!dir.join("out").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn batch_failed_file() {
+    let dir = temp_dir("batch-failed");
+    write_svg(&dir.join("in/a.svg"));
+    std::fs::write(dir.join("in/b.svg"), "not an svg").unwrap();
+
+    let output = resvg(&[Path::new("--batch"), &dir.join("in"), &dir.join("out")]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("failed to render 1 of 2 files"));
+    // Other files are still rendered.
+    assert!(dir.join("out/a.png").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Renders a 20x10 image with a green left half and a transparent right half.
+fn render_half(name: &str, args: &[&str]) -> Vec<u8> {
+    let dir = temp_dir(name);
+    std::fs::write(
+        dir.join("in.svg"),
+        "<svg xmlns='http://www.w3.org/2000/svg' width='20' height='10'>\
+            <rect width='10' height='10' fill='green'/>\
+        </svg>",
+    )
+    .unwrap();
+
+    let mut cmd_args: Vec<&Path> = args.iter().map(Path::new).collect();
+    let in_svg = dir.join("in.svg");
+    let out = dir.join("out");
+    cmd_args.push(&in_svg);
+    cmd_args.push(&out);
+    let output = resvg(&cmd_args);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let data = std::fs::read(&out).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    data
+}
+
+#[test]
+fn pam_output() {
+    let data = render_half("pam", &["--format", "pam"]);
+    let header = b"P7\nWIDTH 20\nHEIGHT 10\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n";
+    assert!(data.starts_with(header));
+    let pixels = &data[header.len()..];
+    assert_eq!(pixels.len(), 20 * 10 * 4);
+    assert_eq!(&pixels[0..4], &[0, 128, 0, 255]);
+    assert_eq!(&pixels[19 * 4..20 * 4], &[0, 0, 0, 0]);
+
+    let data = render_half("pam-16", &["--format", "pam", "--bit-depth", "16"]);
+    let header = b"P7\nWIDTH 20\nHEIGHT 10\nDEPTH 4\nMAXVAL 65535\nTUPLTYPE RGB_ALPHA\nENDHDR\n";
+    assert!(data.starts_with(header));
+    let pixels = &data[header.len()..];
+    assert_eq!(pixels.len(), 20 * 10 * 8);
+    assert_eq!(&pixels[0..8], &[0, 0, 128, 128, 0, 0, 255, 255]);
+}
+
+#[test]
+fn ppm_output() {
+    let data = render_half("ppm", &["--format", "ppm"]);
+    let header = b"P6\n20 10\n255\n";
+    assert!(data.starts_with(header));
+    let pixels = &data[header.len()..];
+    assert_eq!(pixels.len(), 20 * 10 * 3);
+    assert_eq!(&pixels[0..3], &[0, 128, 0]);
+    // Filled with white by default.
+    assert_eq!(&pixels[19 * 3..20 * 3], &[255, 255, 255]);
+
+    let data = render_half(
+        "ppm-background",
+        &["--format", "ppm", "--background", "red"],
+    );
+    assert_eq!(&data[header.len() + 19 * 3..][..3], &[255, 0, 0]);
+}
+
+#[test]
+fn rgba_output() {
+    let data = render_half("rgba", &["--format", "rgba"]);
+    assert_eq!(data.len(), 20 * 10 * 4);
+    assert_eq!(&data[0..4], &[0, 128, 0, 255]);
+}
+
+/// Returns the data of the first PNG chunk with the specified type.
+fn png_chunk<'a>(data: &'a [u8], kind: &[u8]) -> Option<&'a [u8]> {
+    let mut offset = 8;
+    while offset + 8 <= data.len() {
+        let mut len = [0; 4];
+        len.copy_from_slice(&data[offset..offset + 4]);
+        let len = u32::from_be_bytes(len) as usize;
+        if &data[offset + 4..offset + 8] == kind {
+            return Some(&data[offset + 8..offset + 8 + len]);
+        }
+
+        offset += 12 + len;
+    }
+
+    None
+}
+
+#[test]
+fn png_phys() {
+    let data = render_half("png-phys", &["--format", "png", "--dpi", "300"]);
+    // 300 DPI is 11811 pixels per meter.
+    let mut phys = Vec::new();
+    phys.extend_from_slice(&11811u32.to_be_bytes());
+    phys.extend_from_slice(&11811u32.to_be_bytes());
+    phys.push(1);
+    assert_eq!(png_chunk(&data, b"pHYs"), Some(&phys[..]));
+
+    // IHDR bit depth.
+    assert_eq!(png_chunk(&data, b"IHDR").unwrap()[8], 8);
+    let data = render_half("png-16", &["--format", "png", "--bit-depth", "16"]);
+    assert_eq!(png_chunk(&data, b"IHDR").unwrap()[8], 16);
+}
+
+#[cfg(feature = "jpeg")]
+#[test]
+fn jpeg_output() {
+    let data = render_half("jpeg", &["--format", "jpeg", "--dpi", "300"]);
+    assert!(data.starts_with(&[0xFF, 0xD8]));
+    assert!(data.ends_with(&[0xFF, 0xD9]));
+
+    // The JFIF header stores the DPI.
+    let jfif = data.windows(5).position(|w| w == b"JFIF\0").unwrap();
+    let density = &data[jfif + 7..jfif + 12];
+    assert_eq!(density, &[1, 1, 44, 1, 44]);
+}
+
+#[cfg(not(feature = "jpeg"))]
+#[test]
+fn jpeg_output() {
+    let dir = temp_dir("jpeg");
+    write_svg(&dir.join("in.svg"));
+    let output = resvg(&[&dir.join("in.svg"), &dir.join("out.jpg")]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("the jpeg feature is not enabled"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(not(feature = "pdf"))]
+#[test]
+fn pdf_output() {
+    let dir = temp_dir("pdf");
+    write_svg(&dir.join("in.svg"));
+    let output = resvg(&[&dir.join("in.svg"), &dir.join("out.pdf")]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("the pdf feature is not enabled"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+fn read_u16_le(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn read_u32_le(data: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&data[offset..offset + 4]);
+    u32::from_le_bytes(bytes)
+}
+
+fn read_u32_be(data: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&data[offset..offset + 4]);
+    u32::from_be_bytes(bytes)
+}
+
+/// Returns PNG width and height.
+fn png_size(data: &[u8]) -> (u32, u32) {
+    let ihdr = png_chunk(data, b"IHDR").unwrap();
+    (read_u32_be(ihdr, 0), read_u32_be(ihdr, 4))
+}
+
+#[test]
+fn ico_output() {
+    let data = render_half("ico", &["--format", "ico", "--sizes", "16,32,256"]);
+
+    assert_eq!(read_u16_le(&data, 0), 0); // reserved
+    assert_eq!(read_u16_le(&data, 2), 1); // type
+    assert_eq!(read_u16_le(&data, 4), 3); // count
+
+    // Images go right after the directory, one after another.
+    let mut expected_offset = 6 + 16 * 3;
+    for (idx, size) in [16, 32, 256].iter().enumerate() {
+        let entry = &data[6 + 16 * idx..6 + 16 * (idx + 1)];
+        // 256 is stored as 0.
+        let dim = if *size == 256 { 0 } else { *size as u8 };
+        assert_eq!(&entry[0..4], &[dim, dim, 0, 0]);
+        assert_eq!(read_u16_le(entry, 4), 1); // color planes
+        assert_eq!(read_u16_le(entry, 6), 32); // bits per pixel
+
+        let len = read_u32_le(entry, 8) as usize;
+        let offset = read_u32_le(entry, 12) as usize;
+        assert_eq!(offset, expected_offset);
+        assert_eq!(png_size(&data[offset..offset + len]), (*size, *size));
+        expected_offset += len;
+    }
+
+    assert_eq!(expected_offset, data.len());
+}
+
+#[test]
+fn icns_output() {
+    let data = render_half("icns", &["--format", "icns", "--sizes", "16,32,256"]);
+
+    assert_eq!(&data[0..4], b"icns");
+    assert_eq!(read_u32_be(&data, 4) as usize, data.len());
+
+    let mut entries = Vec::new();
+    let mut offset = 8;
+    while offset < data.len() {
+        let kind = &data[offset..offset + 4];
+        // The length includes the entry header.
+        let len = read_u32_be(&data, offset + 4) as usize;
+        let png = &data[offset + 8..offset + len];
+        entries.push((String::from_utf8_lossy(kind).into_owned(), png_size(png).0));
+        offset += len;
+    }
+
+    assert_eq!(offset, data.len());
+    assert_eq!(
+        entries,
+        vec![
+            ("icp4".to_string(), 16),
+            ("icp5".to_string(), 32),
+            ("ic11".to_string(), 32),
+            ("ic08".to_string(), 256),
+            ("ic13".to_string(), 256),
+        ]
+    );
+}
+
+#[test]
+fn export_all_ids_collisions() {
+    let dir = temp_dir("export-all-ids");
+    std::fs::write(
+        dir.join("in.svg"),
+        "<svg xmlns='http://www.w3.org/2000/svg' width='20' height='10'>\
+            <rect id='a:b' width='10' height='10' fill='green'/>\
+            <rect id='a_b' x='10' width='5' height='10' fill='green'/>\
+            <rect id='A_B' x='15' width='5' height='5' fill='green'/>\
+        </svg>",
+    )
+    .unwrap();
+
+    let output = resvg(&[
+        Path::new("--export-all-ids"),
+        &dir.join("in.svg"),
+        &dir.join("out"),
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    // Each ID has its own file.
+    let size = |name: &str| png_size(&std::fs::read(dir.join("out").join(name)).unwrap());
+    assert_eq!(size("a_b.png"), (10, 10));
+    assert_eq!(size("a_b-2.png"), (5, 10));
+    assert_eq!(size("A_B-3.png"), (5, 5));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
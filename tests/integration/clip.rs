@@ -0,0 +1,132 @@
+fn render(svg: &str, cache: Option<&mut resvg::RenderCache>) -> tiny_skia::Pixmap {
+    let tree = usvg::Tree::from_str(svg, &usvg::Options::default().to_ref()).unwrap();
+    let size = tree.size.to_screen_size();
+    let mut pixmap = tiny_skia::Pixmap::new(size.width(), size.height()).unwrap();
+    let ts = tiny_skia::Transform::default();
+    match cache {
+        Some(cache) => {
+            resvg::render_with_cache(&tree, usvg::FitTo::Original, ts, cache, pixmap.as_mut())
+        }
+        None => resvg::render(&tree, usvg::FitTo::Original, ts, pixmap.as_mut()),
+    }
+    .unwrap();
+    pixmap
+}
+
+fn alpha(pixmap: &tiny_skia::Pixmap, x: u32, y: u32) -> u8 {
+    pixmap.pixel(x, y).unwrap().alpha()
+}
+
+#[test]
+fn draw_clip_matches_layer_clip() {
+    // The second shape is outside the canvas, so `clip2` is the same as `clip1`,
+    // but it's not a single shape and the group layer is clipped afterwards.
+    let svg = |clip_id| {
+        format!(
+            "
+    <svg viewBox='0 0 200 200' xmlns='http://www.w3.org/2000/svg'>
+        <clipPath id='clip1'>
+            <circle cx='100' cy='100' r='70' shape-rendering='crispEdges'/>
+        </clipPath>
+        <clipPath id='clip2'>
+            <circle cx='100' cy='100' r='70' shape-rendering='crispEdges'/>
+            <rect x='-20' y='-20' width='10' height='10' shape-rendering='crispEdges'/>
+        </clipPath>
+        <clipPath id='clip3'>
+            <rect x='20' y='40' width='150' height='100'/>
+        </clipPath>
+        <clipPath id='clip4'>
+            <rect x='20' y='40' width='150' height='100'/>
+            <rect x='-20' y='-20' width='10' height='10'/>
+        </clipPath>
+        <rect x='0' y='0' width='200' height='100' fill='blue'/>
+        <g clip-path='url(#{})'>
+            <rect x='20' y='20' width='160' height='160' fill='green' stroke='black'/>
+            <circle cx='100' cy='100' r='40' fill='none' stroke='red' stroke-width='20'
+                    opacity='0.5'/>
+        </g>
+    </svg>
+    ",
+            clip_id
+        )
+    };
+
+    for (draw_clip, layer_clip) in &[("clip1", "clip2"), ("clip3", "clip4")] {
+        let direct = render(&svg(draw_clip), None);
+        let layer = render(&svg(layer_clip), None);
+        assert!(direct == layer);
+        assert!(render(&svg(draw_clip), Some(&mut resvg::RenderCache::new())) == direct);
+    }
+}
+
+#[test]
+fn rect_clip() {
+    let svg = "
+    <svg width='40' height='40' xmlns='http://www.w3.org/2000/svg'>
+        <clipPath id='clip1'>
+            <rect x='10' y='10' width='20' height='20'/>
+        </clipPath>
+        <g clip-path='url(#clip1)' opacity='0.5'>
+            <circle cx='20' cy='20' r='30' fill='green'/>
+        </g>
+    </svg>
+    ";
+
+    let pixmap = render(svg, None);
+    assert_eq!(alpha(&pixmap, 10, 10), 128);
+    assert_eq!(alpha(&pixmap, 29, 29), 128);
+    assert_eq!(alpha(&pixmap, 9, 10), 0);
+    assert_eq!(alpha(&pixmap, 10, 9), 0);
+    assert_eq!(alpha(&pixmap, 30, 29), 0);
+    assert_eq!(alpha(&pixmap, 29, 30), 0);
+}
+
+#[test]
+fn nested_clip_paths() {
+    let svg = "
+    <svg width='40' height='40' xmlns='http://www.w3.org/2000/svg'>
+        <clipPath id='clip1'>
+            <rect x='0' y='0' width='40' height='20'/>
+        </clipPath>
+        <clipPath id='clip2' clip-path='url(#clip1)'>
+            <rect x='0' y='0' width='20' height='40'/>
+        </clipPath>
+        <g clip-path='url(#clip2)'>
+            <rect x='0' y='0' width='40' height='40' fill='green'/>
+        </g>
+    </svg>
+    ";
+
+    let pixmap = render(svg, None);
+    assert_eq!(alpha(&pixmap, 5, 5), 255);
+    assert_eq!(alpha(&pixmap, 25, 5), 0);
+    assert_eq!(alpha(&pixmap, 5, 25), 0);
+    assert_eq!(alpha(&pixmap, 25, 25), 0);
+}
+
+#[test]
+fn clip_is_kept_around_images() {
+    // A 10x20 green rect, which is sliced into a 40x40 image, i.e. clipped by the image viewport.
+    let svg = "
+    <svg width='40' height='40' xmlns='http://www.w3.org/2000/svg'>
+        <clipPath id='clip1'>
+            <circle cx='20' cy='20' r='10'/>
+        </clipPath>
+        <g clip-path='url(#clip1)'>
+            <image width='40' height='40' preserveAspectRatio='xMidYMid slice'
+                   href='data:image/svg+xml;base64,PHN2ZyB4bWxucz0naHR0cDovL3d3dy53My5vcmcvMjAwMC9zdmcnIHdpZHRoPScxMCcgaGVpZ2h0PScyMCc+PHJlY3Qgd2lkdGg9JzEwJyBoZWlnaHQ9JzIwJyBmaWxsPSdncmVlbicvPjwvc3ZnPg=='/>
+            <rect x='0' y='0' width='5' height='5' fill='blue'/>
+        </g>
+        <rect x='35' y='35' width='5' height='5' fill='blue'/>
+    </svg>
+    ";
+
+    let pixmap = render(svg, None);
+    assert_eq!(alpha(&pixmap, 20, 20), 255);
+    // Inside of the image, but outside of the group clip.
+    assert_eq!(alpha(&pixmap, 2, 20), 0);
+    // The group clip is restored after the image.
+    assert_eq!(alpha(&pixmap, 2, 2), 0);
+    // And removed after the group.
+    assert_eq!(alpha(&pixmap, 37, 37), 255);
+}
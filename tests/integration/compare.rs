@@ -0,0 +1,33 @@
+#[test]
+fn identical() {
+    let img = [10, 20, 30, 255, 0, 0, 0, 0];
+    let res = resvg::compare_images(&img, &img, 2, 1, &resvg::CompareOptions::default()).unwrap();
+    assert_eq!(res.different_pixels, 0);
+    assert_eq!(res.max_difference, 0);
+    assert_eq!(res.diff.width(), 2);
+    assert_eq!(res.diff.height(), 1);
+}
+
+#[test]
+fn tolerance() {
+    let img1 = [10, 20, 30, 255, 0, 0, 0, 0, 100, 100, 100, 255];
+    let img2 = [11, 20, 30, 255, 0, 0, 0, 5, 100, 100, 100, 255];
+
+    let res = resvg::compare_images(&img1, &img2, 3, 1, &resvg::CompareOptions::default()).unwrap();
+    assert_eq!(res.different_pixels, 1);
+    assert_eq!(res.max_difference, 5);
+    assert_eq!(&res.diff.data()[4..8], &[255, 0, 0, 255]);
+    assert_ne!(&res.diff.data()[0..4], &[255, 0, 0, 255]);
+
+    let opt = resvg::CompareOptions { tolerance: 5 };
+    let res = resvg::compare_images(&img1, &img2, 3, 1, &opt).unwrap();
+    assert_eq!(res.different_pixels, 0);
+}
+
+#[test]
+fn size_mismatch() {
+    let img = [0; 8];
+    let opt = resvg::CompareOptions::default();
+    assert!(resvg::compare_images(&img, &img, 1, 1, &opt).is_none());
+    assert!(resvg::compare_images(&img, &img[..4], 2, 1, &opt).is_none());
+}
@@ -1,6 +1,14 @@
 use once_cell::sync::Lazy;
 use rgb::FromSlice;
 
+mod cache;
+mod cli;
+mod clip;
+mod compare;
+#[cfg(feature = "pdf")]
+mod pdf;
+#[cfg(feature = "ps")]
+mod ps;
 #[rustfmt::skip]
 mod render;
 
@@ -47,26 +55,21 @@ pub fn render(name: &str) -> usize {
     svgfilters::demultiply_alpha(rgba.as_mut_slice().as_rgba_mut());
 
     let expected_data = load_png(&png_path);
-    assert_eq!(expected_data.len(), rgba.len());
-
-    let mut pixels_d = 0;
-    for (a, b) in expected_data
-        .as_slice()
-        .as_rgba()
-        .iter()
-        .zip(rgba.as_rgba())
-    {
-        if is_pix_diff(*a, *b) {
-            pixels_d += 1;
-        }
-    }
+    let comparison = resvg::compare_images(
+        &expected_data,
+        rgba.as_slice(),
+        size.width(),
+        size.height(),
+        &resvg::CompareOptions::default(),
+    )
+    .unwrap();
 
     // Save diff if needed.
-    // if pixels_d != 0 {
-    //     gen_diff(&name, &expected_data, rgba.as_slice()).unwrap();
+    // if comparison.different_pixels != 0 {
+    //     comparison.diff.save_png(format!("tests/{}-diff.png", name)).unwrap();
     // }
 
-    pixels_d
+    comparison.different_pixels
 }
 
 fn load_png(path: &str) -> Vec<u8> {
@@ -111,39 +114,3 @@ fn load_png(path: &str) -> Vec<u8> {
         }
     }
 }
-
-// TODO: remove
-fn is_pix_diff(c1: rgb::RGBA8, c2: rgb::RGBA8) -> bool {
-    (c1.r as i32 - c2.r as i32).abs() > 1
-        || (c1.g as i32 - c2.g as i32).abs() > 1
-        || (c1.b as i32 - c2.b as i32).abs() > 1
-        || (c1.a as i32 - c2.a as i32).abs() > 1
-}
-
-#[allow(dead_code)]
-fn gen_diff(name: &str, img1: &[u8], img2: &[u8]) -> Result<(), png::EncodingError> {
-    assert_eq!(img1.len(), img2.len());
-
-    let mut img3 = Vec::with_capacity((img1.len() as f32 * 0.75).round() as usize);
-    for (a, b) in img1.as_rgba().iter().zip(img2.as_rgba()) {
-        if is_pix_diff(*a, *b) {
-            img3.push(255);
-            img3.push(0);
-            img3.push(0);
-        } else {
-            img3.push(255);
-            img3.push(255);
-            img3.push(255);
-        }
-    }
-
-    let path = std::path::PathBuf::from(format!("tests/{}-diff.png", name));
-    let file = std::fs::File::create(path)?;
-    let ref mut w = std::io::BufWriter::new(file);
-
-    let mut encoder = png::Encoder::new(w, IMAGE_SIZE, IMAGE_SIZE);
-    encoder.set_color(png::ColorType::Rgb);
-    encoder.set_depth(png::BitDepth::Eight);
-    let mut writer = encoder.write_header()?;
-    writer.write_image_data(&img3)
-}
@@ -0,0 +1,93 @@
+const SVG: &str = "
+<svg id='svg1' viewBox='0 0 200 200' xmlns='http://www.w3.org/2000/svg'>
+    <linearGradient id='lg1'>
+        <stop offset='0' stop-color='green'/>
+        <stop offset='1' stop-color='blue' stop-opacity='0.5'/>
+    </linearGradient>
+    <mask id='mask1'>
+        <rect x='40' y='40' width='120' height='120' fill='white'/>
+    </mask>
+    <filter id='filter1'>
+        <feGaussianBlur stdDeviation='4'/>
+    </filter>
+    <g opacity='0.5' mask='url(#mask1)'>
+        <rect x='20' y='20' width='160' height='160' fill='url(#lg1)'/>
+    </g>
+    <g filter='url(#filter1)'>
+        <rect x='60' y='60' width='80' height='80' fill='blue'/>
+    </g>
+</svg>
+";
+
+fn convert() -> Vec<u8> {
+    let tree = usvg::Tree::from_str(SVG, &usvg::Options::default().to_ref()).unwrap();
+    let opt = resvg::pdf::Options {
+        raster_dpi: 72.0,
+        compress: false,
+    };
+    resvg::pdf::convert(&tree, &opt)
+}
+
+fn convert_to_string() -> String {
+    String::from_utf8_lossy(&convert()).to_string()
+}
+
+#[test]
+fn xref_offsets() {
+    let pdf = convert();
+    assert!(pdf.starts_with(b"%PDF-1.7"));
+    assert!(pdf.ends_with(b"%%EOF\n"));
+
+    // The trailer is plain text.
+    let trailer_start = pdf.len() - 64;
+    let trailer = std::str::from_utf8(&pdf[trailer_start..]).unwrap();
+    let start: usize = trailer
+        .split("startxref\n")
+        .nth(1)
+        .unwrap()
+        .lines()
+        .next()
+        .unwrap()
+        .parse()
+        .unwrap();
+
+    let xref = std::str::from_utf8(&pdf[start..]).unwrap();
+    assert!(xref.starts_with("xref\n"));
+
+    // Skip the section header and the free entry.
+    let entries = xref.lines().skip(3).take_while(|l| l.ends_with(" n "));
+    for (i, line) in entries.enumerate() {
+        let offset: usize = line[..10].parse().unwrap();
+        assert!(pdf[offset..].starts_with(format!("{} 0 obj\n", i + 1).as_bytes()));
+    }
+}
+
+#[test]
+fn page_size() {
+    assert!(convert_to_string().contains("/MediaBox [0 0 150 150]"));
+}
+
+#[test]
+fn gradient_as_shading() {
+    let pdf = convert_to_string();
+    assert!(pdf.contains("/PatternType 2"));
+    assert!(pdf.contains("/ShadingType 2 /ColorSpace /DeviceRGB"));
+    // Stop opacity is written as a separate shading.
+    assert!(pdf.contains("/ShadingType 2 /ColorSpace /DeviceGray"));
+}
+
+#[test]
+fn group_as_transparency_group() {
+    let pdf = convert_to_string();
+    assert!(pdf.contains("/Subtype /Form"));
+    assert!(pdf.contains("/S /Transparency"));
+    assert!(pdf.contains("/S /Luminosity"));
+    assert!(pdf.contains("/ca 0.5 /CA 0.5"));
+}
+
+#[test]
+fn filter_is_rasterized() {
+    let pdf = convert_to_string();
+    assert_eq!(pdf.matches("/Subtype /Image").count(), 2); // RGB and alpha
+    assert!(pdf.contains("/ColorSpace /DeviceGray /BitsPerComponent 8"));
+}
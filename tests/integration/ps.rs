@@ -0,0 +1,160 @@
+const SVG: &str = "
+<svg id='svg1' viewBox='0 0 200 200' xmlns='http://www.w3.org/2000/svg'>
+    <radialGradient id='rg1'>
+        <stop offset='0' stop-color='green'/>
+        <stop offset='1' stop-color='blue'/>
+    </radialGradient>
+    <clipPath id='clip1'>
+        <rect x='20' y='20' width='120' height='120'/>
+    </clipPath>
+    <g clip-path='url(#clip1)'>
+        <circle cx='100' cy='100' r='80' fill='url(#rg1)' fill-rule='evenodd'
+                stroke='black' stroke-dasharray='5 3'/>
+    </g>
+    <rect x='60' y='60' width='80' height='80' fill='blue' opacity='0.5'/>
+</svg>
+";
+
+fn convert(eps: bool) -> String {
+    let tree = usvg::Tree::from_str(SVG, &usvg::Options::default().to_ref()).unwrap();
+    let opt = resvg::ps::Options {
+        raster_dpi: 72.0,
+        eps,
+    };
+    String::from_utf8(resvg::ps::convert(&tree, &opt)).unwrap()
+}
+
+#[test]
+fn eps_header() {
+    let ps = convert(true);
+    assert!(ps.starts_with("%!PS-Adobe-3.0 EPSF-3.0\n"));
+    assert!(ps.contains("%%BoundingBox: 0 0 150 150\n"));
+    assert!(ps.contains("%%LanguageLevel: 3\n"));
+    assert!(!ps.contains("setpagedevice"));
+    assert!(ps.ends_with("%%EOF\n"));
+}
+
+#[test]
+fn ps_header() {
+    let ps = convert(false);
+    assert!(ps.starts_with("%!PS-Adobe-3.0\n"));
+    assert!(ps.contains("<< /PageSize [150 150] >> setpagedevice"));
+}
+
+#[test]
+fn gradient_as_shading() {
+    let ps = convert(true);
+    assert!(ps.contains("eoclip\nnewpath"));
+    assert!(ps.contains("/ShadingType 3"));
+    assert!(ps.contains("shfill"));
+}
+
+#[test]
+fn stroke_and_clip() {
+    let ps = convert(true);
+    assert!(ps.contains("[5 3] 0 setdash"));
+    assert_eq!(ps.matches("\nclip\n").count(), 1);
+}
+
+#[test]
+fn transparency_is_rasterized() {
+    let ps = convert(true);
+    // The rect is flattened with the content below it and covers it completely,
+    // so no mask is needed.
+    assert!(ps.contains("<< /ImageType 1 /Width 60 /Height 60"));
+    assert!(!ps.contains("/ImageType 3"));
+    assert_eq!(ps.matches("~>\n").count(), 1);
+}
+
+#[test]
+fn balanced_gsave() {
+    let ps = convert(true);
+    let words: Vec<_> = ps.split_whitespace().collect();
+    let saves = words.iter().filter(|w| **w == "gsave").count();
+    let restores = words.iter().filter(|w| **w == "grestore").count();
+    assert_eq!(saves, restores);
+}
+
+/// Decodes the ASCII85 and Flate encoded data of the first masked image.
+fn masked_image_data(ps: &str) -> Vec<u8> {
+    let start = ps.find("/ImageType 3").unwrap();
+    let start = start + ps[start..].find("image\n").unwrap() + 6;
+    let end = start + ps[start..].find("~>").unwrap();
+
+    let mut data = Vec::new();
+    let mut group = Vec::new();
+    for c in ps[start..end].bytes().filter(|c| !c.is_ascii_whitespace()) {
+        if c == b'z' {
+            data.extend_from_slice(&[0; 4]);
+            continue;
+        }
+
+        group.push(c - b'!');
+        if group.len() == 5 {
+            let n = group.iter().fold(0u32, |n, d| n * 85 + *d as u32);
+            data.extend_from_slice(&n.to_be_bytes());
+            group.clear();
+        }
+    }
+
+    if !group.is_empty() {
+        let len = group.len() - 1;
+        group.resize(5, 84);
+        let n = group.iter().fold(0u32, |n, d| n * 85 + *d as u32);
+        data.extend_from_slice(&n.to_be_bytes()[..len]);
+    }
+
+    miniz_oxide::inflate::decompress_to_vec_zlib(&data).unwrap()
+}
+
+#[test]
+fn transparency_is_flattened() {
+    let svg = "
+    <svg viewBox='0 0 200 100' width='200' height='100' xmlns='http://www.w3.org/2000/svg'>
+        <rect width='100' height='100' fill='green'/>
+        <circle cx='100' cy='50' r='50' fill='blue' opacity='0.3'/>
+    </svg>
+    ";
+
+    let tree = usvg::Tree::from_str(svg, &usvg::Options::default().to_ref()).unwrap();
+    let opt = resvg::ps::Options {
+        raster_dpi: 72.0,
+        eps: true,
+    };
+    let ps = String::from_utf8(resvg::ps::convert(&tree, &opt)).unwrap();
+
+    // 100px are 75pt, which are 75 pixels at 72 DPI.
+    assert!(ps.contains("/Width 75 /Height 75"));
+    let data = masked_image_data(&ps);
+    assert_eq!(data.len(), 75 * 75 * 4);
+
+    // Each pixel is a mask sample followed by RGB.
+    let pixel = |x: usize, y: usize| {
+        let idx = (y * 75 + x) * 4;
+        [data[idx], data[idx + 1], data[idx + 2], data[idx + 3]]
+    };
+
+    // Outside the circle and the green rect.
+    assert_eq!(pixel(74, 0)[0], 0);
+
+    // Above the green rect.
+    let over_green = pixel(10, 37);
+    assert_eq!(over_green[0], 255);
+    assert_eq!(over_green[1], 0);
+    assert!((over_green[2] as i32 - 90).abs() <= 1);
+    assert!((over_green[3] as i32 - 77).abs() <= 1);
+
+    // Above the page, which is white.
+    let over_page = pixel(60, 37);
+    assert_eq!(over_page[0], 255);
+    assert!((over_page[1] as i32 - 179).abs() <= 1);
+    assert!((over_page[2] as i32 - 179).abs() <= 1);
+    assert_eq!(over_page[3], 255);
+}
+
+#[test]
+fn default_is_ps() {
+    let tree = usvg::Tree::from_str(SVG, &usvg::Options::default().to_ref()).unwrap();
+    let ps = resvg::ps::convert(&tree, &resvg::ps::Options::default());
+    assert!(ps.starts_with(b"%!PS-Adobe-3.0\n"));
+}
@@ -239,6 +239,7 @@ use crate::render;
 #[test] fn a_font_weight_012() { assert_eq!(render("a-font-weight-012"), 0); }
 #[test] fn a_glyph_orientation_horizontal_001() { assert_eq!(render("a-glyph-orientation-horizontal-001"), 0); }
 #[test] fn a_glyph_orientation_vertical_001() { assert_eq!(render("a-glyph-orientation-vertical-001"), 0); }
+#[test] fn a_glyph_orientation_vertical_002() { assert_eq!(render("a-glyph-orientation-vertical-002"), 0); }
 #[test] fn a_image_rendering_001() { assert_eq!(render("a-image-rendering-001"), 0); }
 #[test] fn a_image_rendering_002() { assert_eq!(render("a-image-rendering-002"), 0); }
 #[test] fn a_image_rendering_003() { assert_eq!(render("a-image-rendering-003"), 0); }
@@ -414,6 +415,7 @@ use crate::render;
 #[test] fn a_text_anchor_011() { assert_eq!(render("a-text-anchor-011"), 0); }
 #[test] fn a_text_anchor_012() { assert_eq!(render("a-text-anchor-012"), 0); }
 #[test] fn a_text_anchor_013() { assert_eq!(render("a-text-anchor-013"), 0); }
+#[test] fn a_text_anchor_014() { assert_eq!(render("a-text-anchor-014"), 0); }
 #[test] fn a_text_decoration_001() { assert_eq!(render("a-text-decoration-001"), 0); }
 #[test] fn a_text_decoration_002() { assert_eq!(render("a-text-decoration-002"), 0); }
 #[test] fn a_text_decoration_003() { assert_eq!(render("a-text-decoration-003"), 0); }
@@ -433,6 +435,10 @@ use crate::render;
 #[test] fn a_text_decoration_017() { assert_eq!(render("a-text-decoration-017"), 0); }
 #[test] fn a_text_decoration_018() { assert_eq!(render("a-text-decoration-018"), 0); }
 #[test] fn a_text_decoration_019() { assert_eq!(render("a-text-decoration-019"), 0); }
+#[test] fn a_text_orientation_001() { assert_eq!(render("a-text-orientation-001"), 0); }
+#[test] fn a_text_orientation_002() { assert_eq!(render("a-text-orientation-002"), 0); }
+#[test] fn a_text_orientation_003() { assert_eq!(render("a-text-orientation-003"), 0); }
+#[test] fn a_text_orientation_004() { assert_eq!(render("a-text-orientation-004"), 0); }
 #[test] fn a_text_rendering_001() { assert_eq!(render("a-text-rendering-001"), 0); }
 #[test] fn a_text_rendering_002() { assert_eq!(render("a-text-rendering-002"), 0); }
 #[test] fn a_text_rendering_003() { assert_eq!(render("a-text-rendering-003"), 0); }
@@ -467,6 +473,9 @@ use crate::render;
 #[test] fn a_transform_018() { assert_eq!(render("a-transform-018"), 0); }
 #[test] fn a_transform_019() { assert_eq!(render("a-transform-019"), 0); }
 #[test] fn a_unicode_bidi_001() { assert_eq!(render("a-unicode-bidi-001"), 0); }
+#[test] fn a_unicode_bidi_002() { assert_eq!(render("a-unicode-bidi-002"), 0); }
+#[test] fn a_unicode_bidi_003() { assert_eq!(render("a-unicode-bidi-003"), 0); }
+#[test] fn a_unicode_bidi_004() { assert_eq!(render("a-unicode-bidi-004"), 0); }
 #[test] fn a_visibility_001() { assert_eq!(render("a-visibility-001"), 0); }
 #[test] fn a_visibility_002() { assert_eq!(render("a-visibility-002"), 0); }
 #[test] fn a_visibility_003() { assert_eq!(render("a-visibility-003"), 0); }
@@ -1439,6 +1448,10 @@ use crate::render;
 #[test] fn e_textPath_042() { assert_eq!(render("e-textPath-042"), 0); }
 #[test] fn e_textPath_043() { assert_eq!(render("e-textPath-043"), 0); }
 #[test] fn e_textPath_044() { assert_eq!(render("e-textPath-044"), 0); }
+#[test] fn e_textPath_045() { assert_eq!(render("e-textPath-045"), 0); }
+#[test] fn e_textPath_046() { assert_eq!(render("e-textPath-046"), 0); }
+#[test] fn e_textPath_047() { assert_eq!(render("e-textPath-047"), 0); }
+#[test] fn e_textPath_048() { assert_eq!(render("e-textPath-048"), 0); }
 #[test] fn e_tref_001() { assert_eq!(render("e-tref-001"), 0); }
 #[test] fn e_tref_002() { assert_eq!(render("e-tref-002"), 0); }
 #[test] fn e_tref_003() { assert_eq!(render("e-tref-003"), 0); }
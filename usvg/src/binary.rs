@@ -0,0 +1,1781 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! A compact binary representation of `Tree`.
+//!
+//! The layout is:
+//!
+//! - `USVG` magic
+//! - `u32` format version
+//! - `usvg` version string
+//! - a tree: size, viewbox, defs and the root node
+//!
+//! Paint servers, clip paths, masks and filters are stored in a defs table
+//! before the nodes that reference them. Elements inside defs can reference
+//! only preceding elements, which is always possible since the tree has no cycles.
+//!
+//! All numbers are little-endian. Strings and lists are prefixed by a `u32` length.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::*;
+
+const MAGIC: &[u8; 4] = b"USVG";
+
+/// Must be increased on each layout change.
+const FORMAT_VERSION: u32 = 1;
+
+/// A nodes nesting limit, which prevents a stack overflow on malformed data.
+const MAX_DEPTH: u32 = 1024;
+
+pub(crate) fn write(tree: &Tree) -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(MAGIC);
+    buf.write_u32(FORMAT_VERSION);
+    buf.write_str(env!("CARGO_PKG_VERSION"));
+    write_tree(tree, &mut buf);
+    buf
+}
+
+pub(crate) fn read(data: &[u8]) -> Result<Tree, Error> {
+    let mut r = Reader {
+        data,
+        pos: 0,
+        defs: Vec::new(),
+        depth: 0,
+    };
+
+    if r.bytes(MAGIC.len()).ok() != Some(MAGIC) {
+        return Err(Error::InvalidBinary);
+    }
+
+    let format_version = r.u32()?;
+    let version = r.string()?;
+    if format_version != FORMAT_VERSION || version != env!("CARGO_PKG_VERSION") {
+        return Err(Error::BinaryVersionMismatch);
+    }
+
+    let tree = r.tree()?;
+    if r.pos != data.len() {
+        return Err(Error::InvalidBinary);
+    }
+
+    Ok(tree)
+}
+
+#[derive(Clone)]
+enum Def {
+    Paint(Paint),
+    ClipPath(Rc<ClipPath>),
+    Mask(Rc<Mask>),
+    #[cfg(feature = "filter")]
+    Filter(Rc<filter::Filter>),
+}
+
+impl Def {
+    fn key(&self) -> usize {
+        match self {
+            Def::Paint(Paint::Color(_)) => 0,
+            Def::Paint(Paint::LinearGradient(ref lg)) => Rc::as_ptr(lg) as usize,
+            Def::Paint(Paint::RadialGradient(ref rg)) => Rc::as_ptr(rg) as usize,
+            Def::Paint(Paint::Pattern(ref patt)) => Rc::as_ptr(patt) as usize,
+            Def::ClipPath(ref cp) => Rc::as_ptr(cp) as usize,
+            Def::Mask(ref mask) => Rc::as_ptr(mask) as usize,
+            #[cfg(feature = "filter")]
+            Def::Filter(ref filter) => Rc::as_ptr(filter) as usize,
+        }
+    }
+}
+
+mod def_tag {
+    pub const LINEAR_GRADIENT: u8 = 0;
+    pub const RADIAL_GRADIENT: u8 = 1;
+    pub const PATTERN: u8 = 2;
+    pub const CLIP_PATH: u8 = 3;
+    pub const MASK: u8 = 4;
+    #[cfg(feature = "filter")]
+    pub const FILTER: u8 = 5;
+}
+
+mod node_tag {
+    pub const GROUP: u8 = 0;
+    pub const PATH: u8 = 1;
+    pub const IMAGE: u8 = 2;
+}
+
+/// Collects defs, so each element goes after the elements it references.
+#[derive(Default)]
+struct DefsCollector {
+    defs: Vec<Def>,
+    indices: HashMap<usize, u32>,
+}
+
+impl DefsCollector {
+    fn push(&mut self, def: Def) {
+        self.indices.insert(def.key(), self.defs.len() as u32);
+        self.defs.push(def);
+    }
+
+    fn contains(&self, def: &Def) -> bool {
+        self.indices.contains_key(&def.key())
+    }
+
+    fn collect_nodes(&mut self, root: &Node) {
+        for node in root.descendants() {
+            match *node.borrow() {
+                NodeKind::Group(ref g) => {
+                    if let Some(ref cp) = g.clip_path {
+                        self.collect_clip_path(cp);
+                    }
+
+                    if let Some(ref mask) = g.mask {
+                        self.collect_mask(mask);
+                    }
+
+                    #[cfg(feature = "filter")]
+                    {
+                        for filter in &g.filters {
+                            self.collect_filter(filter);
+                        }
+
+                        if let Some(ref paint) = g.filter_fill {
+                            self.collect_paint(paint);
+                        }
+
+                        if let Some(ref paint) = g.filter_stroke {
+                            self.collect_paint(paint);
+                        }
+                    }
+                }
+                NodeKind::Path(ref path) => {
+                    if let Some(ref fill) = path.fill {
+                        self.collect_paint(&fill.paint);
+                    }
+
+                    if let Some(ref stroke) = path.stroke {
+                        self.collect_paint(&stroke.paint);
+                    }
+                }
+                // Nested trees have their own defs.
+                NodeKind::Image(_) => {}
+            }
+        }
+    }
+
+    fn collect_paint(&mut self, paint: &Paint) {
+        let def = Def::Paint(paint.clone());
+        if matches!(paint, Paint::Color(_)) || self.contains(&def) {
+            return;
+        }
+
+        if let Paint::Pattern(ref patt) = paint {
+            self.collect_nodes(&patt.root);
+        }
+
+        self.push(def);
+    }
+
+    fn collect_clip_path(&mut self, cp: &Rc<ClipPath>) {
+        let def = Def::ClipPath(cp.clone());
+        if self.contains(&def) {
+            return;
+        }
+
+        if let Some(ref cp) = cp.clip_path {
+            self.collect_clip_path(cp);
+        }
+
+        self.collect_nodes(&cp.root);
+        self.push(def);
+    }
+
+    fn collect_mask(&mut self, mask: &Rc<Mask>) {
+        let def = Def::Mask(mask.clone());
+        if self.contains(&def) {
+            return;
+        }
+
+        if let Some(ref mask) = mask.mask {
+            self.collect_mask(mask);
+        }
+
+        self.collect_nodes(&mask.root);
+        self.push(def);
+    }
+
+    #[cfg(feature = "filter")]
+    fn collect_filter(&mut self, filter: &Rc<filter::Filter>) {
+        let def = Def::Filter(filter.clone());
+        if self.contains(&def) {
+            return;
+        }
+
+        for fe in &filter.primitives {
+            if let filter::Kind::Image(ref img) = fe.kind {
+                if let filter::ImageKind::Use(ref node) = img.data {
+                    self.collect_nodes(node);
+                }
+            }
+        }
+
+        self.push(def);
+    }
+}
+
+fn write_tree(tree: &Tree, buf: &mut Vec<u8>) {
+    let mut collector = DefsCollector::default();
+    collector.collect_nodes(&tree.root);
+
+    let mut w = Writer {
+        buf,
+        indices: collector.indices,
+    };
+
+    w.buf.write_f64(tree.size.width());
+    w.buf.write_f64(tree.size.height());
+    w.write_view_box(&tree.view_box);
+
+    w.buf.write_len(collector.defs.len());
+    for def in &collector.defs {
+        w.write_def(def);
+    }
+
+    w.write_node(&tree.root);
+}
+
+struct Writer<'a> {
+    buf: &'a mut Vec<u8>,
+    indices: HashMap<usize, u32>,
+}
+
+impl Writer<'_> {
+    fn write_ref(&mut self, def: Def) {
+        // Cannot panic, because all defs were collected beforehand.
+        let idx = self.indices[&def.key()];
+        self.buf.write_u32(idx);
+    }
+
+    fn write_def(&mut self, def: &Def) {
+        match def {
+            Def::Paint(Paint::Color(_)) => unreachable!(),
+            Def::Paint(Paint::LinearGradient(ref lg)) => {
+                self.buf.write_u8(def_tag::LINEAR_GRADIENT);
+                self.buf.write_str(&lg.id);
+                self.buf.write_f64(lg.x1);
+                self.buf.write_f64(lg.y1);
+                self.buf.write_f64(lg.x2);
+                self.buf.write_f64(lg.y2);
+                self.write_base_gradient(&lg.base);
+            }
+            Def::Paint(Paint::RadialGradient(ref rg)) => {
+                self.buf.write_u8(def_tag::RADIAL_GRADIENT);
+                self.buf.write_str(&rg.id);
+                self.buf.write_f64(rg.cx);
+                self.buf.write_f64(rg.cy);
+                self.buf.write_f64(rg.r.get());
+                self.buf.write_f64(rg.fx);
+                self.buf.write_f64(rg.fy);
+                self.write_base_gradient(&rg.base);
+            }
+            Def::Paint(Paint::Pattern(ref patt)) => {
+                self.buf.write_u8(def_tag::PATTERN);
+                self.buf.write_str(&patt.id);
+                self.buf.write_u8(patt.units.to_tag());
+                self.buf.write_u8(patt.content_units.to_tag());
+                self.buf.write_transform(patt.transform);
+                self.buf.write_rect(patt.rect);
+                self.buf.write_bool(patt.view_box.is_some());
+                if let Some(ref vb) = patt.view_box {
+                    self.write_view_box(vb);
+                }
+                self.write_node(&patt.root);
+            }
+            Def::ClipPath(ref cp) => {
+                self.buf.write_u8(def_tag::CLIP_PATH);
+                self.buf.write_str(&cp.id);
+                self.buf.write_u8(cp.units.to_tag());
+                self.buf.write_transform(cp.transform);
+                self.buf.write_bool(cp.clip_path.is_some());
+                if let Some(ref cp) = cp.clip_path {
+                    self.write_ref(Def::ClipPath(cp.clone()));
+                }
+                self.write_node(&cp.root);
+            }
+            Def::Mask(ref mask) => {
+                self.buf.write_u8(def_tag::MASK);
+                self.buf.write_str(&mask.id);
+                self.buf.write_u8(mask.units.to_tag());
+                self.buf.write_u8(mask.content_units.to_tag());
+                self.buf.write_rect(mask.rect);
+                self.buf.write_bool(mask.mask.is_some());
+                if let Some(ref mask) = mask.mask {
+                    self.write_ref(Def::Mask(mask.clone()));
+                }
+                self.write_node(&mask.root);
+            }
+            #[cfg(feature = "filter")]
+            Def::Filter(ref filter) => {
+                self.buf.write_u8(def_tag::FILTER);
+                self.write_filter(filter);
+            }
+        }
+    }
+
+    fn write_base_gradient(&mut self, g: &BaseGradient) {
+        self.buf.write_u8(g.units.to_tag());
+        self.buf.write_transform(g.transform);
+        self.buf.write_u8(g.spread_method.to_tag());
+        self.buf.write_len(g.stops.len());
+        for stop in &g.stops {
+            self.buf.write_f64(stop.offset.get());
+            self.buf.write_color(stop.color);
+            self.buf.write_f64(stop.opacity.get());
+        }
+    }
+
+    fn write_view_box(&mut self, vb: &ViewBox) {
+        self.buf.write_rect(vb.rect);
+        self.buf.write_u8(vb.aspect.align.to_tag());
+        self.buf.write_bool(vb.aspect.defer);
+        self.buf.write_bool(vb.aspect.slice);
+    }
+
+    fn write_paint(&mut self, paint: &Paint) {
+        match paint {
+            Paint::Color(c) => {
+                self.buf.write_u8(0);
+                self.buf.write_color(*c);
+            }
+            _ => {
+                self.buf.write_u8(1);
+                self.write_ref(Def::Paint(paint.clone()));
+            }
+        }
+    }
+
+    fn write_node(&mut self, node: &Node) {
+        match *node.borrow() {
+            NodeKind::Group(ref g) => {
+                self.buf.write_u8(node_tag::GROUP);
+                self.buf.write_str(&g.id);
+                self.buf.write_transform(g.transform);
+                self.buf.write_f64(g.opacity.get());
+
+                self.buf.write_bool(g.clip_path.is_some());
+                if let Some(ref cp) = g.clip_path {
+                    self.write_ref(Def::ClipPath(cp.clone()));
+                }
+
+                self.buf.write_bool(g.mask.is_some());
+                if let Some(ref mask) = g.mask {
+                    self.write_ref(Def::Mask(mask.clone()));
+                }
+
+                #[cfg(feature = "filter")]
+                {
+                    self.buf.write_len(g.filters.len());
+                    for filter in &g.filters {
+                        self.write_ref(Def::Filter(filter.clone()));
+                    }
+
+                    for paint in &[&g.filter_fill, &g.filter_stroke] {
+                        self.buf.write_bool(paint.is_some());
+                        if let Some(ref paint) = paint {
+                            self.write_paint(paint);
+                        }
+                    }
+                }
+
+                #[cfg(not(feature = "filter"))]
+                {
+                    self.buf.write_len(0);
+                    self.buf.write_bool(false);
+                    self.buf.write_bool(false);
+                }
+
+                match g.enable_background {
+                    None => self.buf.write_u8(0),
+                    Some(EnableBackground(None)) => self.buf.write_u8(1),
+                    Some(EnableBackground(Some(rect))) => {
+                        self.buf.write_u8(2);
+                        self.buf.write_rect(rect);
+                    }
+                }
+
+                self.buf.write_len(node.children().count());
+                for child in node.children() {
+                    self.write_node(&child);
+                }
+            }
+            NodeKind::Path(ref path) => {
+                self.buf.write_u8(node_tag::PATH);
+                self.buf.write_str(&path.id);
+                self.buf.write_transform(path.transform);
+                self.buf.write_u8(path.visibility.to_tag());
+
+                self.buf.write_bool(path.fill.is_some());
+                if let Some(ref fill) = path.fill {
+                    self.write_paint(&fill.paint);
+                    self.buf.write_f64(fill.opacity.get());
+                    self.buf.write_u8(fill.rule.to_tag());
+                }
+
+                self.buf.write_bool(path.stroke.is_some());
+                if let Some(ref stroke) = path.stroke {
+                    self.write_paint(&stroke.paint);
+                    self.buf.write_bool(stroke.dasharray.is_some());
+                    if let Some(ref list) = stroke.dasharray {
+                        self.buf.write_f64_list(list);
+                    }
+                    self.buf.write_f32(stroke.dashoffset);
+                    self.buf.write_f64(stroke.miterlimit.get());
+                    self.buf.write_f64(stroke.opacity.get());
+                    self.buf.write_f64(stroke.width.get());
+                    self.buf.write_u8(stroke.linecap.to_tag());
+                    self.buf.write_u8(stroke.linejoin.to_tag());
+                }
+
+                self.buf.write_u8(path.paint_order.to_tag());
+                self.buf.write_u8(path.rendering_mode.to_tag());
+
+                self.buf.write_bool(path.text_bbox.is_some());
+                if let Some(rect) = path.text_bbox {
+                    self.buf.write_rect(rect);
+                }
+
+                self.buf.write_len(path.data.len());
+                for seg in path.data.segments() {
+                    match seg {
+                        PathSegment::MoveTo { x, y } => {
+                            self.buf.write_u8(0);
+                            self.buf.write_f64(x);
+                            self.buf.write_f64(y);
+                        }
+                        PathSegment::LineTo { x, y } => {
+                            self.buf.write_u8(1);
+                            self.buf.write_f64(x);
+                            self.buf.write_f64(y);
+                        }
+                        PathSegment::CurveTo {
+                            x1,
+                            y1,
+                            x2,
+                            y2,
+                            x,
+                            y,
+                        } => {
+                            self.buf.write_u8(2);
+                            for n in &[x1, y1, x2, y2, x, y] {
+                                self.buf.write_f64(*n);
+                            }
+                        }
+                        PathSegment::ClosePath => self.buf.write_u8(3),
+                    }
+                }
+            }
+            NodeKind::Image(ref img) => {
+                self.buf.write_u8(node_tag::IMAGE);
+                self.buf.write_str(&img.id);
+                self.buf.write_transform(img.transform);
+                self.buf.write_u8(img.visibility.to_tag());
+                self.write_view_box(&img.view_box);
+                self.buf.write_u8(img.rendering_mode.to_tag());
+                self.write_image_kind(&img.kind);
+            }
+        }
+    }
+
+    fn write_image_kind(&mut self, kind: &ImageKind) {
+        match kind {
+            ImageKind::JPEG(ref data) => {
+                self.buf.write_u8(0);
+                self.buf.write_bytes(data);
+            }
+            ImageKind::PNG(ref data) => {
+                self.buf.write_u8(1);
+                self.buf.write_bytes(data);
+            }
+            ImageKind::GIF(ref data) => {
+                self.buf.write_u8(2);
+                self.buf.write_bytes(data);
+            }
+            ImageKind::SVG(ref tree) => {
+                self.buf.write_u8(3);
+                write_tree(tree, self.buf);
+            }
+        }
+    }
+
+    #[cfg(feature = "filter")]
+    fn write_filter(&mut self, filter: &filter::Filter) {
+        use filter::Kind;
+
+        self.buf.write_str(&filter.id);
+        self.buf.write_u8(filter.units.to_tag());
+        self.buf.write_u8(filter.primitive_units.to_tag());
+        self.buf.write_rect(filter.rect);
+
+        self.buf.write_len(filter.primitives.len());
+        for fe in &filter.primitives {
+            for n in &[fe.x, fe.y, fe.width, fe.height] {
+                self.buf.write_bool(n.is_some());
+                if let Some(n) = n {
+                    self.buf.write_f64(*n);
+                }
+            }
+
+            self.buf.write_u8(fe.color_interpolation.to_tag());
+            self.buf.write_str(&fe.result);
+
+            match fe.kind {
+                Kind::Blend(ref fe) => {
+                    self.buf.write_u8(0);
+                    self.buf.write_input(&fe.input1);
+                    self.buf.write_input(&fe.input2);
+                    self.buf.write_u8(fe.mode.to_tag());
+                }
+                Kind::ColorMatrix(ref fe) => {
+                    self.buf.write_u8(1);
+                    self.buf.write_input(&fe.input);
+                    match fe.kind {
+                        filter::ColorMatrixKind::Matrix(ref values) => {
+                            self.buf.write_u8(0);
+                            self.buf.write_f64_list(values);
+                        }
+                        filter::ColorMatrixKind::Saturate(n) => {
+                            self.buf.write_u8(1);
+                            self.buf.write_f64(n.get());
+                        }
+                        filter::ColorMatrixKind::HueRotate(n) => {
+                            self.buf.write_u8(2);
+                            self.buf.write_f64(n);
+                        }
+                        filter::ColorMatrixKind::LuminanceToAlpha => self.buf.write_u8(3),
+                    }
+                }
+                Kind::ComponentTransfer(ref fe) => {
+                    self.buf.write_u8(2);
+                    self.buf.write_input(&fe.input);
+                    for func in &[&fe.func_r, &fe.func_g, &fe.func_b, &fe.func_a] {
+                        self.buf.write_transfer_function(func);
+                    }
+                }
+                Kind::Composite(ref fe) => {
+                    self.buf.write_u8(3);
+                    self.buf.write_input(&fe.input1);
+                    self.buf.write_input(&fe.input2);
+                    match fe.operator {
+                        filter::CompositeOperator::Over => self.buf.write_u8(0),
+                        filter::CompositeOperator::In => self.buf.write_u8(1),
+                        filter::CompositeOperator::Out => self.buf.write_u8(2),
+                        filter::CompositeOperator::Atop => self.buf.write_u8(3),
+                        filter::CompositeOperator::Xor => self.buf.write_u8(4),
+                        filter::CompositeOperator::Arithmetic { k1, k2, k3, k4 } => {
+                            self.buf.write_u8(5);
+                            for n in &[k1, k2, k3, k4] {
+                                self.buf.write_f64(*n);
+                            }
+                        }
+                    }
+                }
+                Kind::ConvolveMatrix(ref fe) => {
+                    self.buf.write_u8(4);
+                    self.buf.write_input(&fe.input);
+                    self.buf.write_u32(fe.matrix.target_x());
+                    self.buf.write_u32(fe.matrix.target_y());
+                    self.buf.write_u32(fe.matrix.columns());
+                    self.buf.write_u32(fe.matrix.rows());
+                    self.buf.write_f64_list(fe.matrix.data());
+                    self.buf.write_f64(fe.divisor.value());
+                    self.buf.write_f64(fe.bias);
+                    self.buf.write_u8(fe.edge_mode.to_tag());
+                    self.buf.write_bool(fe.preserve_alpha);
+                }
+                Kind::DiffuseLighting(ref fe) => {
+                    self.buf.write_u8(5);
+                    self.buf.write_input(&fe.input);
+                    self.buf.write_f64(fe.surface_scale);
+                    self.buf.write_f64(fe.diffuse_constant);
+                    self.buf.write_color(fe.lighting_color);
+                    self.buf.write_light_source(fe.light_source);
+                }
+                Kind::DisplacementMap(ref fe) => {
+                    self.buf.write_u8(6);
+                    self.buf.write_input(&fe.input1);
+                    self.buf.write_input(&fe.input2);
+                    self.buf.write_f64(fe.scale);
+                    self.buf.write_u8(fe.x_channel_selector.to_tag());
+                    self.buf.write_u8(fe.y_channel_selector.to_tag());
+                }
+                Kind::DropShadow(ref fe) => {
+                    self.buf.write_u8(7);
+                    self.buf.write_input(&fe.input);
+                    self.buf.write_f64(fe.dx);
+                    self.buf.write_f64(fe.dy);
+                    self.buf.write_f64(fe.std_dev_x.get());
+                    self.buf.write_f64(fe.std_dev_y.get());
+                    self.buf.write_color(fe.color);
+                    self.buf.write_f64(fe.opacity.get());
+                }
+                Kind::Flood(ref fe) => {
+                    self.buf.write_u8(8);
+                    self.buf.write_color(fe.color);
+                    self.buf.write_f64(fe.opacity.get());
+                }
+                Kind::GaussianBlur(ref fe) => {
+                    self.buf.write_u8(9);
+                    self.buf.write_input(&fe.input);
+                    self.buf.write_f64(fe.std_dev_x.get());
+                    self.buf.write_f64(fe.std_dev_y.get());
+                }
+                Kind::Image(ref fe) => {
+                    self.buf.write_u8(10);
+                    self.buf.write_u8(fe.aspect.align.to_tag());
+                    self.buf.write_bool(fe.aspect.defer);
+                    self.buf.write_bool(fe.aspect.slice);
+                    self.buf.write_u8(fe.rendering_mode.to_tag());
+                    match fe.data {
+                        filter::ImageKind::Image(ref kind) => {
+                            self.buf.write_u8(0);
+                            self.write_image_kind(kind);
+                        }
+                        filter::ImageKind::Use(ref node) => {
+                            self.buf.write_u8(1);
+                            self.write_node(node);
+                        }
+                    }
+                }
+                Kind::Merge(ref fe) => {
+                    self.buf.write_u8(11);
+                    self.buf.write_len(fe.inputs.len());
+                    for input in &fe.inputs {
+                        self.buf.write_input(input);
+                    }
+                }
+                Kind::Morphology(ref fe) => {
+                    self.buf.write_u8(12);
+                    self.buf.write_input(&fe.input);
+                    self.buf.write_u8(fe.operator.to_tag());
+                    self.buf.write_f64(fe.radius_x.get());
+                    self.buf.write_f64(fe.radius_y.get());
+                }
+                Kind::Offset(ref fe) => {
+                    self.buf.write_u8(13);
+                    self.buf.write_input(&fe.input);
+                    self.buf.write_f64(fe.dx);
+                    self.buf.write_f64(fe.dy);
+                }
+                Kind::SpecularLighting(ref fe) => {
+                    self.buf.write_u8(14);
+                    self.buf.write_input(&fe.input);
+                    self.buf.write_f64(fe.surface_scale);
+                    self.buf.write_f64(fe.specular_constant);
+                    self.buf.write_f64(fe.specular_exponent);
+                    self.buf.write_color(fe.lighting_color);
+                    self.buf.write_light_source(fe.light_source);
+                }
+                Kind::Tile(ref fe) => {
+                    self.buf.write_u8(15);
+                    self.buf.write_input(&fe.input);
+                }
+                Kind::Turbulence(ref fe) => {
+                    self.buf.write_u8(16);
+                    self.buf.write_f64(fe.base_frequency.x.get());
+                    self.buf.write_f64(fe.base_frequency.y.get());
+                    self.buf.write_u32(fe.num_octaves);
+                    self.buf.write_u32(fe.seed as u32);
+                    self.buf.write_bool(fe.stitch_tiles);
+                    self.buf.write_u8(fe.kind.to_tag());
+                }
+            }
+        }
+    }
+}
+
+trait BufExt {
+    fn write_u8(&mut self, n: u8);
+    fn write_u32(&mut self, n: u32);
+    fn write_f32(&mut self, n: f32);
+    fn write_f64(&mut self, n: f64);
+    fn write_bool(&mut self, b: bool);
+    fn write_len(&mut self, len: usize);
+    fn write_bytes(&mut self, data: &[u8]);
+    fn write_str(&mut self, s: &str);
+    fn write_f64_list(&mut self, list: &[f64]);
+    fn write_transform(&mut self, ts: Transform);
+    fn write_rect(&mut self, r: Rect);
+    fn write_color(&mut self, c: Color);
+    #[cfg(feature = "filter")]
+    fn write_input(&mut self, input: &filter::Input);
+    #[cfg(feature = "filter")]
+    fn write_transfer_function(&mut self, func: &filter::TransferFunction);
+    #[cfg(feature = "filter")]
+    fn write_light_source(&mut self, light: filter::LightSource);
+}
+
+impl BufExt for Vec<u8> {
+    fn write_u8(&mut self, n: u8) {
+        self.push(n);
+    }
+
+    fn write_u32(&mut self, n: u32) {
+        self.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn write_f32(&mut self, n: f32) {
+        self.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn write_f64(&mut self, n: f64) {
+        self.extend_from_slice(&n.to_le_bytes());
+    }
+
+    fn write_bool(&mut self, b: bool) {
+        self.push(b as u8);
+    }
+
+    fn write_len(&mut self, len: usize) {
+        self.write_u32(len as u32);
+    }
+
+    fn write_bytes(&mut self, data: &[u8]) {
+        self.write_len(data.len());
+        self.extend_from_slice(data);
+    }
+
+    fn write_str(&mut self, s: &str) {
+        self.write_bytes(s.as_bytes());
+    }
+
+    fn write_f64_list(&mut self, list: &[f64]) {
+        self.write_len(list.len());
+        for n in list {
+            self.write_f64(*n);
+        }
+    }
+
+    fn write_transform(&mut self, ts: Transform) {
+        for n in &[ts.a, ts.b, ts.c, ts.d, ts.e, ts.f] {
+            self.write_f64(*n);
+        }
+    }
+
+    fn write_rect(&mut self, r: Rect) {
+        for n in &[r.x(), r.y(), r.width(), r.height()] {
+            self.write_f64(*n);
+        }
+    }
+
+    fn write_color(&mut self, c: Color) {
+        self.extend_from_slice(&[c.red, c.green, c.blue]);
+    }
+
+    #[cfg(feature = "filter")]
+    fn write_input(&mut self, input: &filter::Input) {
+        match input {
+            filter::Input::SourceGraphic => self.write_u8(0),
+            filter::Input::SourceAlpha => self.write_u8(1),
+            filter::Input::BackgroundImage => self.write_u8(2),
+            filter::Input::BackgroundAlpha => self.write_u8(3),
+            filter::Input::FillPaint => self.write_u8(4),
+            filter::Input::StrokePaint => self.write_u8(5),
+            filter::Input::Reference(ref name) => {
+                self.write_u8(6);
+                self.write_str(name);
+            }
+        }
+    }
+
+    #[cfg(feature = "filter")]
+    fn write_transfer_function(&mut self, func: &filter::TransferFunction) {
+        match func {
+            filter::TransferFunction::Identity => self.write_u8(0),
+            filter::TransferFunction::Table(ref values) => {
+                self.write_u8(1);
+                self.write_f64_list(values);
+            }
+            filter::TransferFunction::Discrete(ref values) => {
+                self.write_u8(2);
+                self.write_f64_list(values);
+            }
+            filter::TransferFunction::Linear { slope, intercept } => {
+                self.write_u8(3);
+                self.write_f64(*slope);
+                self.write_f64(*intercept);
+            }
+            filter::TransferFunction::Gamma {
+                amplitude,
+                exponent,
+                offset,
+            } => {
+                self.write_u8(4);
+                self.write_f64(*amplitude);
+                self.write_f64(*exponent);
+                self.write_f64(*offset);
+            }
+        }
+    }
+
+    #[cfg(feature = "filter")]
+    fn write_light_source(&mut self, light: filter::LightSource) {
+        match light {
+            filter::LightSource::DistantLight(light) => {
+                self.write_u8(0);
+                self.write_f64(light.azimuth);
+                self.write_f64(light.elevation);
+            }
+            filter::LightSource::PointLight(light) => {
+                self.write_u8(1);
+                self.write_f64(light.x);
+                self.write_f64(light.y);
+                self.write_f64(light.z);
+            }
+            filter::LightSource::SpotLight(light) => {
+                self.write_u8(2);
+                for n in &[
+                    light.x,
+                    light.y,
+                    light.z,
+                    light.points_at_x,
+                    light.points_at_y,
+                    light.points_at_z,
+                    light.specular_exponent.get(),
+                ] {
+                    self.write_f64(*n);
+                }
+
+                self.write_bool(light.limiting_cone_angle.is_some());
+                if let Some(n) = light.limiting_cone_angle {
+                    self.write_f64(n);
+                }
+            }
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    defs: Vec<Def>,
+    depth: u32,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let end = self.pos.checked_add(len).ok_or(Error::InvalidBinary)?;
+        let data = self.data.get(self.pos..end).ok_or(Error::InvalidBinary)?;
+        self.pos = end;
+        Ok(data)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.bytes(4)?);
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    fn f32(&mut self) -> Result<f32, Error> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.bytes(4)?);
+        let n = f32::from_le_bytes(bytes);
+        if n.is_finite() {
+            Ok(n)
+        } else {
+            Err(Error::InvalidBinary)
+        }
+    }
+
+    fn f64(&mut self) -> Result<f64, Error> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.bytes(8)?);
+        let n = f64::from_le_bytes(bytes);
+        if n.is_finite() {
+            Ok(n)
+        } else {
+            Err(Error::InvalidBinary)
+        }
+    }
+
+    fn bool(&mut self) -> Result<bool, Error> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(Error::InvalidBinary),
+        }
+    }
+
+    /// Reads a list length.
+    ///
+    /// The length is checked against the remaining data, so a malformed length
+    /// would not cause a huge allocation.
+    fn len(&mut self, item_size: usize) -> Result<usize, Error> {
+        let len = self.u32()? as usize;
+        if len.saturating_mul(item_size) > self.data.len() - self.pos {
+            return Err(Error::InvalidBinary);
+        }
+
+        Ok(len)
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let len = self.len(1)?;
+        let bytes = self.bytes(len)?;
+        let s = std::str::from_utf8(bytes).map_err(|_| Error::InvalidBinary)?;
+        Ok(s.to_string())
+    }
+
+    fn f64_list(&mut self) -> Result<Vec<f64>, Error> {
+        let len = self.len(8)?;
+        (0..len).map(|_| self.f64()).collect()
+    }
+
+    fn tag<T: Tag>(&mut self) -> Result<T, Error> {
+        T::from_tag(self.u8()?).ok_or(Error::InvalidBinary)
+    }
+
+    fn opacity(&mut self) -> Result<Opacity, Error> {
+        Opacity::new(self.f64()?).ok_or(Error::InvalidBinary)
+    }
+
+    fn positive(&mut self) -> Result<PositiveF64, Error> {
+        PositiveF64::new(self.f64()?).ok_or(Error::InvalidBinary)
+    }
+
+    fn transform(&mut self) -> Result<Transform, Error> {
+        Ok(Transform::new(
+            self.f64()?,
+            self.f64()?,
+            self.f64()?,
+            self.f64()?,
+            self.f64()?,
+            self.f64()?,
+        ))
+    }
+
+    fn rect(&mut self) -> Result<Rect, Error> {
+        Rect::new(self.f64()?, self.f64()?, self.f64()?, self.f64()?).ok_or(Error::InvalidBinary)
+    }
+
+    fn color(&mut self) -> Result<Color, Error> {
+        Ok(Color::new_rgb(self.u8()?, self.u8()?, self.u8()?))
+    }
+
+    fn aspect(&mut self) -> Result<AspectRatio, Error> {
+        Ok(AspectRatio {
+            align: self.tag()?,
+            defer: self.bool()?,
+            slice: self.bool()?,
+        })
+    }
+
+    fn view_box(&mut self) -> Result<ViewBox, Error> {
+        Ok(ViewBox {
+            rect: self.rect()?,
+            aspect: self.aspect()?,
+        })
+    }
+
+    fn def(&mut self) -> Result<Def, Error> {
+        let idx = self.u32()? as usize;
+        self.defs.get(idx).cloned().ok_or(Error::InvalidBinary)
+    }
+
+    fn paint(&mut self) -> Result<Paint, Error> {
+        match self.u8()? {
+            0 => Ok(Paint::Color(self.color()?)),
+            1 => match self.def()? {
+                Def::Paint(paint) => Ok(paint),
+                _ => Err(Error::InvalidBinary),
+            },
+            _ => Err(Error::InvalidBinary),
+        }
+    }
+
+    fn clip_path(&mut self) -> Result<Rc<ClipPath>, Error> {
+        match self.def()? {
+            Def::ClipPath(cp) => Ok(cp),
+            _ => Err(Error::InvalidBinary),
+        }
+    }
+
+    fn mask(&mut self) -> Result<Rc<Mask>, Error> {
+        match self.def()? {
+            Def::Mask(mask) => Ok(mask),
+            _ => Err(Error::InvalidBinary),
+        }
+    }
+
+    fn tree(&mut self) -> Result<Tree, Error> {
+        let size = Size::new(self.f64()?, self.f64()?).ok_or(Error::InvalidBinary)?;
+        let view_box = self.view_box()?;
+
+        // Nested trees have their own defs.
+        let parent_defs = std::mem::take(&mut self.defs);
+
+        // Each def takes at least 2 bytes.
+        let count = self.len(2)?;
+        for _ in 0..count {
+            let def = self.read_def()?;
+            self.defs.push(def);
+        }
+
+        let root = self.node()?;
+        if !matches!(*root.borrow(), NodeKind::Group(_)) {
+            return Err(Error::InvalidBinary);
+        }
+
+        self.defs = parent_defs;
+
+        Ok(Tree {
+            size,
+            view_box,
+            root,
+        })
+    }
+
+    fn read_def(&mut self) -> Result<Def, Error> {
+        match self.u8()? {
+            def_tag::LINEAR_GRADIENT => {
+                let id = self.string()?;
+                let (x1, y1, x2, y2) = (self.f64()?, self.f64()?, self.f64()?, self.f64()?);
+                let base = self.base_gradient()?;
+                Ok(Def::Paint(Paint::LinearGradient(Rc::new(LinearGradient {
+                    id,
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    base,
+                }))))
+            }
+            def_tag::RADIAL_GRADIENT => {
+                let id = self.string()?;
+                let (cx, cy) = (self.f64()?, self.f64()?);
+                let r = self.positive()?;
+                let (fx, fy) = (self.f64()?, self.f64()?);
+                let base = self.base_gradient()?;
+                Ok(Def::Paint(Paint::RadialGradient(Rc::new(RadialGradient {
+                    id,
+                    cx,
+                    cy,
+                    r,
+                    fx,
+                    fy,
+                    base,
+                }))))
+            }
+            def_tag::PATTERN => {
+                let id = self.string()?;
+                let units = self.tag()?;
+                let content_units = self.tag()?;
+                let transform = self.transform()?;
+                let rect = self.rect()?;
+                let view_box = if self.bool()? {
+                    Some(self.view_box()?)
+                } else {
+                    None
+                };
+                let root = self.node()?;
+                Ok(Def::Paint(Paint::Pattern(Rc::new(Pattern {
+                    id,
+                    units,
+                    content_units,
+                    transform,
+                    rect,
+                    view_box,
+                    root,
+                }))))
+            }
+            def_tag::CLIP_PATH => {
+                let id = self.string()?;
+                let units = self.tag()?;
+                let transform = self.transform()?;
+                let clip_path = if self.bool()? {
+                    Some(self.clip_path()?)
+                } else {
+                    None
+                };
+                let root = self.node()?;
+                Ok(Def::ClipPath(Rc::new(ClipPath {
+                    id,
+                    units,
+                    transform,
+                    clip_path,
+                    root,
+                })))
+            }
+            def_tag::MASK => {
+                let id = self.string()?;
+                let units = self.tag()?;
+                let content_units = self.tag()?;
+                let rect = self.rect()?;
+                let mask = if self.bool()? {
+                    Some(self.mask()?)
+                } else {
+                    None
+                };
+                let root = self.node()?;
+                Ok(Def::Mask(Rc::new(Mask {
+                    id,
+                    units,
+                    content_units,
+                    rect,
+                    mask,
+                    root,
+                })))
+            }
+            #[cfg(feature = "filter")]
+            def_tag::FILTER => Ok(Def::Filter(Rc::new(self.filter()?))),
+            // Filters cannot be loaded without the `filter` feature.
+            _ => Err(Error::InvalidBinary),
+        }
+    }
+
+    fn base_gradient(&mut self) -> Result<BaseGradient, Error> {
+        let units = self.tag()?;
+        let transform = self.transform()?;
+        let spread_method = self.tag()?;
+
+        // Each stop takes 19 bytes.
+        let count = self.len(19)?;
+        let mut stops = Vec::with_capacity(count);
+        for _ in 0..count {
+            stops.push(Stop {
+                offset: StopOffset::new(self.f64()?).ok_or(Error::InvalidBinary)?,
+                color: self.color()?,
+                opacity: self.opacity()?,
+            });
+        }
+
+        Ok(BaseGradient {
+            units,
+            transform,
+            spread_method,
+            stops,
+        })
+    }
+
+    fn node(&mut self) -> Result<Node, Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(Error::InvalidBinary);
+        }
+
+        self.depth += 1;
+        let node = self.node_impl();
+        self.depth -= 1;
+        node
+    }
+
+    fn node_impl(&mut self) -> Result<Node, Error> {
+        match self.u8()? {
+            node_tag::GROUP => {
+                let id = self.string()?;
+                let transform = self.transform()?;
+                let opacity = self.opacity()?;
+                let clip_path = if self.bool()? {
+                    Some(self.clip_path()?)
+                } else {
+                    None
+                };
+                let mask = if self.bool()? {
+                    Some(self.mask()?)
+                } else {
+                    None
+                };
+
+                #[cfg(feature = "filter")]
+                let (filters, filter_fill, filter_stroke) = {
+                    let count = self.len(4)?;
+                    let mut filters = Vec::with_capacity(count);
+                    for _ in 0..count {
+                        match self.def()? {
+                            Def::Filter(filter) => filters.push(filter),
+                            _ => return Err(Error::InvalidBinary),
+                        }
+                    }
+
+                    let filter_fill = if self.bool()? {
+                        Some(self.paint()?)
+                    } else {
+                        None
+                    };
+                    let filter_stroke = if self.bool()? {
+                        Some(self.paint()?)
+                    } else {
+                        None
+                    };
+
+                    (filters, filter_fill, filter_stroke)
+                };
+
+                // Filters cannot be loaded without the `filter` feature.
+                #[cfg(not(feature = "filter"))]
+                if self.u32()? != 0 || self.bool()? || self.bool()? {
+                    return Err(Error::InvalidBinary);
+                }
+
+                let enable_background = match self.u8()? {
+                    0 => None,
+                    1 => Some(EnableBackground(None)),
+                    2 => Some(EnableBackground(Some(self.rect()?))),
+                    _ => return Err(Error::InvalidBinary),
+                };
+
+                let node = Node::new(NodeKind::Group(Group {
+                    id,
+                    transform,
+                    opacity,
+                    clip_path,
+                    mask,
+                    #[cfg(feature = "filter")]
+                    filters,
+                    #[cfg(feature = "filter")]
+                    filter_fill,
+                    #[cfg(feature = "filter")]
+                    filter_stroke,
+                    enable_background,
+                }));
+
+                // Each node takes at least 1 byte.
+                let count = self.len(1)?;
+                for _ in 0..count {
+                    node.append(self.node()?);
+                }
+
+                Ok(node)
+            }
+            node_tag::PATH => {
+                let id = self.string()?;
+                let transform = self.transform()?;
+                let visibility = self.tag()?;
+
+                let fill = if self.bool()? {
+                    Some(Fill {
+                        paint: self.paint()?,
+                        opacity: self.opacity()?,
+                        rule: self.tag()?,
+                    })
+                } else {
+                    None
+                };
+
+                let stroke = if self.bool()? {
+                    let paint = self.paint()?;
+                    let dasharray = if self.bool()? {
+                        Some(self.f64_list()?)
+                    } else {
+                        None
+                    };
+                    let dashoffset = self.f32()?;
+                    let miterlimit = self.f64()?;
+                    if miterlimit < 1.0 {
+                        return Err(Error::InvalidBinary);
+                    }
+
+                    Some(Stroke {
+                        paint,
+                        dasharray,
+                        dashoffset,
+                        miterlimit: StrokeMiterlimit::new(miterlimit),
+                        opacity: self.opacity()?,
+                        width: StrokeWidth::new(self.f64()?).ok_or(Error::InvalidBinary)?,
+                        linecap: self.tag()?,
+                        linejoin: self.tag()?,
+                    })
+                } else {
+                    None
+                };
+
+                let paint_order = self.tag()?;
+                let rendering_mode = self.tag()?;
+                let text_bbox = if self.bool()? {
+                    Some(self.rect()?)
+                } else {
+                    None
+                };
+
+                // Each segment takes at least 1 byte.
+                let count = self.len(1)?;
+                let mut data = PathData::new();
+                for _ in 0..count {
+                    match self.u8()? {
+                        0 => data.push_move_to(self.f64()?, self.f64()?),
+                        1 => data.push_line_to(self.f64()?, self.f64()?),
+                        2 => data.push_curve_to(
+                            self.f64()?,
+                            self.f64()?,
+                            self.f64()?,
+                            self.f64()?,
+                            self.f64()?,
+                            self.f64()?,
+                        ),
+                        3 => data.push_close_path(),
+                        _ => return Err(Error::InvalidBinary),
+                    }
+                }
+
+                Ok(Node::new(NodeKind::Path(Path {
+                    id,
+                    transform,
+                    visibility,
+                    fill,
+                    stroke,
+                    paint_order,
+                    rendering_mode,
+                    text_bbox,
+                    data: Rc::new(data),
+                })))
+            }
+            node_tag::IMAGE => Ok(Node::new(NodeKind::Image(Image {
+                id: self.string()?,
+                transform: self.transform()?,
+                visibility: self.tag()?,
+                view_box: self.view_box()?,
+                rendering_mode: self.tag()?,
+                kind: self.image_kind()?,
+            }))),
+            _ => Err(Error::InvalidBinary),
+        }
+    }
+
+    fn image_kind(&mut self) -> Result<ImageKind, Error> {
+        let tag = self.u8()?;
+        if tag == 3 {
+            if self.depth == MAX_DEPTH {
+                return Err(Error::InvalidBinary);
+            }
+
+            self.depth += 1;
+            let tree = self.tree();
+            self.depth -= 1;
+            return Ok(ImageKind::SVG(tree?));
+        }
+
+        let len = self.len(1)?;
+        let data = Arc::new(self.bytes(len)?.to_vec());
+        match tag {
+            0 => Ok(ImageKind::JPEG(data)),
+            1 => Ok(ImageKind::PNG(data)),
+            2 => Ok(ImageKind::GIF(data)),
+            _ => Err(Error::InvalidBinary),
+        }
+    }
+
+    #[cfg(feature = "filter")]
+    fn filter(&mut self) -> Result<filter::Filter, Error> {
+        let id = self.string()?;
+        let units = self.tag()?;
+        let primitive_units = self.tag()?;
+        let rect = self.rect()?;
+
+        // Each primitive takes at least 10 bytes.
+        let count = self.len(10)?;
+        let mut primitives = Vec::with_capacity(count);
+        for _ in 0..count {
+            let mut subregion = [None; 4];
+            for n in &mut subregion {
+                if self.bool()? {
+                    *n = Some(self.f64()?);
+                }
+            }
+
+            let color_interpolation = self.tag()?;
+            let result = self.string()?;
+            let kind = self.filter_kind()?;
+
+            primitives.push(filter::Primitive {
+                x: subregion[0],
+                y: subregion[1],
+                width: subregion[2],
+                height: subregion[3],
+                color_interpolation,
+                result,
+                kind,
+            });
+        }
+
+        Ok(filter::Filter {
+            id,
+            units,
+            primitive_units,
+            rect,
+            primitives,
+        })
+    }
+
+    #[cfg(feature = "filter")]
+    fn filter_kind(&mut self) -> Result<filter::Kind, Error> {
+        use filter::Kind;
+
+        let kind = match self.u8()? {
+            0 => Kind::Blend(filter::Blend {
+                input1: self.input()?,
+                input2: self.input()?,
+                mode: self.tag()?,
+            }),
+            1 => {
+                let input = self.input()?;
+                let kind = match self.u8()? {
+                    0 => {
+                        let values = self.f64_list()?;
+                        if values.len() != 20 {
+                            return Err(Error::InvalidBinary);
+                        }
+
+                        filter::ColorMatrixKind::Matrix(values)
+                    }
+                    1 => filter::ColorMatrixKind::Saturate(self.positive()?),
+                    2 => filter::ColorMatrixKind::HueRotate(self.f64()?),
+                    3 => filter::ColorMatrixKind::LuminanceToAlpha,
+                    _ => return Err(Error::InvalidBinary),
+                };
+
+                Kind::ColorMatrix(filter::ColorMatrix { input, kind })
+            }
+            2 => Kind::ComponentTransfer(filter::ComponentTransfer {
+                input: self.input()?,
+                func_r: self.transfer_function()?,
+                func_g: self.transfer_function()?,
+                func_b: self.transfer_function()?,
+                func_a: self.transfer_function()?,
+            }),
+            3 => {
+                let input1 = self.input()?;
+                let input2 = self.input()?;
+                let operator = match self.u8()? {
+                    0 => filter::CompositeOperator::Over,
+                    1 => filter::CompositeOperator::In,
+                    2 => filter::CompositeOperator::Out,
+                    3 => filter::CompositeOperator::Atop,
+                    4 => filter::CompositeOperator::Xor,
+                    5 => filter::CompositeOperator::Arithmetic {
+                        k1: self.f64()?,
+                        k2: self.f64()?,
+                        k3: self.f64()?,
+                        k4: self.f64()?,
+                    },
+                    _ => return Err(Error::InvalidBinary),
+                };
+
+                Kind::Composite(filter::Composite {
+                    input1,
+                    input2,
+                    operator,
+                })
+            }
+            4 => {
+                let input = self.input()?;
+                let (x, y, columns, rows) = (self.u32()?, self.u32()?, self.u32()?, self.u32()?);
+                let data = self.f64_list()?;
+                if columns as u64 * rows as u64 != data.len() as u64 {
+                    return Err(Error::InvalidBinary);
+                }
+
+                let matrix = filter::ConvolveMatrixData::new(x, y, columns, rows, data)
+                    .ok_or(Error::InvalidBinary)?;
+
+                Kind::ConvolveMatrix(filter::ConvolveMatrix {
+                    input,
+                    matrix,
+                    divisor: NonZeroF64::new(self.f64()?).ok_or(Error::InvalidBinary)?,
+                    bias: self.f64()?,
+                    edge_mode: self.tag()?,
+                    preserve_alpha: self.bool()?,
+                })
+            }
+            5 => Kind::DiffuseLighting(filter::DiffuseLighting {
+                input: self.input()?,
+                surface_scale: self.f64()?,
+                diffuse_constant: self.f64()?,
+                lighting_color: self.color()?,
+                light_source: self.light_source()?,
+            }),
+            6 => Kind::DisplacementMap(filter::DisplacementMap {
+                input1: self.input()?,
+                input2: self.input()?,
+                scale: self.f64()?,
+                x_channel_selector: self.tag()?,
+                y_channel_selector: self.tag()?,
+            }),
+            7 => Kind::DropShadow(filter::DropShadow {
+                input: self.input()?,
+                dx: self.f64()?,
+                dy: self.f64()?,
+                std_dev_x: self.positive()?,
+                std_dev_y: self.positive()?,
+                color: self.color()?,
+                opacity: self.opacity()?,
+            }),
+            8 => Kind::Flood(filter::Flood {
+                color: self.color()?,
+                opacity: self.opacity()?,
+            }),
+            9 => Kind::GaussianBlur(filter::GaussianBlur {
+                input: self.input()?,
+                std_dev_x: self.positive()?,
+                std_dev_y: self.positive()?,
+            }),
+            10 => {
+                let aspect = self.aspect()?;
+                let rendering_mode = self.tag()?;
+                let data = match self.u8()? {
+                    0 => filter::ImageKind::Image(self.image_kind()?),
+                    1 => filter::ImageKind::Use(self.node()?),
+                    _ => return Err(Error::InvalidBinary),
+                };
+
+                Kind::Image(filter::Image {
+                    aspect,
+                    rendering_mode,
+                    data,
+                })
+            }
+            11 => {
+                // Each input takes at least 1 byte.
+                let count = self.len(1)?;
+                let inputs = (0..count).map(|_| self.input()).collect::<Result<_, _>>()?;
+                Kind::Merge(filter::Merge { inputs })
+            }
+            12 => Kind::Morphology(filter::Morphology {
+                input: self.input()?,
+                operator: self.tag()?,
+                radius_x: self.positive()?,
+                radius_y: self.positive()?,
+            }),
+            13 => Kind::Offset(filter::Offset {
+                input: self.input()?,
+                dx: self.f64()?,
+                dy: self.f64()?,
+            }),
+            14 => Kind::SpecularLighting(filter::SpecularLighting {
+                input: self.input()?,
+                surface_scale: self.f64()?,
+                specular_constant: self.f64()?,
+                specular_exponent: self.f64()?,
+                lighting_color: self.color()?,
+                light_source: self.light_source()?,
+            }),
+            15 => Kind::Tile(filter::Tile {
+                input: self.input()?,
+            }),
+            16 => Kind::Turbulence(filter::Turbulence {
+                base_frequency: Point::new(self.positive()?, self.positive()?),
+                num_octaves: self.u32()?,
+                seed: self.u32()? as i32,
+                stitch_tiles: self.bool()?,
+                kind: self.tag()?,
+            }),
+            _ => return Err(Error::InvalidBinary),
+        };
+
+        Ok(kind)
+    }
+
+    #[cfg(feature = "filter")]
+    fn input(&mut self) -> Result<filter::Input, Error> {
+        match self.u8()? {
+            0 => Ok(filter::Input::SourceGraphic),
+            1 => Ok(filter::Input::SourceAlpha),
+            2 => Ok(filter::Input::BackgroundImage),
+            3 => Ok(filter::Input::BackgroundAlpha),
+            4 => Ok(filter::Input::FillPaint),
+            5 => Ok(filter::Input::StrokePaint),
+            6 => Ok(filter::Input::Reference(self.string()?)),
+            _ => Err(Error::InvalidBinary),
+        }
+    }
+
+    #[cfg(feature = "filter")]
+    fn transfer_function(&mut self) -> Result<filter::TransferFunction, Error> {
+        match self.u8()? {
+            0 => Ok(filter::TransferFunction::Identity),
+            1 => Ok(filter::TransferFunction::Table(self.f64_list()?)),
+            2 => Ok(filter::TransferFunction::Discrete(self.f64_list()?)),
+            3 => Ok(filter::TransferFunction::Linear {
+                slope: self.f64()?,
+                intercept: self.f64()?,
+            }),
+            4 => Ok(filter::TransferFunction::Gamma {
+                amplitude: self.f64()?,
+                exponent: self.f64()?,
+                offset: self.f64()?,
+            }),
+            _ => Err(Error::InvalidBinary),
+        }
+    }
+
+    #[cfg(feature = "filter")]
+    fn light_source(&mut self) -> Result<filter::LightSource, Error> {
+        match self.u8()? {
+            0 => Ok(filter::LightSource::DistantLight(filter::DistantLight {
+                azimuth: self.f64()?,
+                elevation: self.f64()?,
+            })),
+            1 => Ok(filter::LightSource::PointLight(filter::PointLight {
+                x: self.f64()?,
+                y: self.f64()?,
+                z: self.f64()?,
+            })),
+            2 => Ok(filter::LightSource::SpotLight(filter::SpotLight {
+                x: self.f64()?,
+                y: self.f64()?,
+                z: self.f64()?,
+                points_at_x: self.f64()?,
+                points_at_y: self.f64()?,
+                points_at_z: self.f64()?,
+                specular_exponent: self.positive()?,
+                limiting_cone_angle: if self.bool()? {
+                    Some(self.f64()?)
+                } else {
+                    None
+                },
+            })),
+            _ => Err(Error::InvalidBinary),
+        }
+    }
+}
+
+/// A mapping between a fieldless enum and a binary tag.
+trait Tag: Sized {
+    fn to_tag(&self) -> u8;
+    fn from_tag(tag: u8) -> Option<Self>;
+}
+
+macro_rules! impl_tag {
+    ($name:ty, $($variant:path = $tag:expr),+ $(,)?) => {
+        impl Tag for $name {
+            fn to_tag(&self) -> u8 {
+                match *self {
+                    $($variant => $tag),+
+                }
+            }
+
+            fn from_tag(tag: u8) -> Option<Self> {
+                match tag {
+                    $($tag => Some($variant),)+
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+impl_tag!(
+    Units,
+    Units::UserSpaceOnUse = 0,
+    Units::ObjectBoundingBox = 1
+);
+impl_tag!(
+    Visibility,
+    Visibility::Visible = 0,
+    Visibility::Hidden = 1,
+    Visibility::Collapse = 2,
+);
+impl_tag!(
+    ShapeRendering,
+    ShapeRendering::OptimizeSpeed = 0,
+    ShapeRendering::CrispEdges = 1,
+    ShapeRendering::GeometricPrecision = 2,
+);
+impl_tag!(
+    ImageRendering,
+    ImageRendering::OptimizeQuality = 0,
+    ImageRendering::OptimizeSpeed = 1,
+);
+impl_tag!(
+    PaintOrder,
+    PaintOrder::FillAndStroke = 0,
+    PaintOrder::StrokeAndFill = 1,
+);
+impl_tag!(FillRule, FillRule::NonZero = 0, FillRule::EvenOdd = 1);
+impl_tag!(
+    LineCap,
+    LineCap::Butt = 0,
+    LineCap::Round = 1,
+    LineCap::Square = 2,
+);
+impl_tag!(
+    LineJoin,
+    LineJoin::Miter = 0,
+    LineJoin::Round = 1,
+    LineJoin::Bevel = 2,
+);
+impl_tag!(
+    SpreadMethod,
+    SpreadMethod::Pad = 0,
+    SpreadMethod::Reflect = 1,
+    SpreadMethod::Repeat = 2,
+);
+impl_tag!(
+    Align,
+    Align::None = 0,
+    Align::XMinYMin = 1,
+    Align::XMidYMin = 2,
+    Align::XMaxYMin = 3,
+    Align::XMinYMid = 4,
+    Align::XMidYMid = 5,
+    Align::XMaxYMid = 6,
+    Align::XMinYMax = 7,
+    Align::XMidYMax = 8,
+    Align::XMaxYMax = 9,
+);
+
+#[cfg(feature = "filter")]
+impl_tag!(
+    filter::ColorInterpolation,
+    filter::ColorInterpolation::SRGB = 0,
+    filter::ColorInterpolation::LinearRGB = 1,
+);
+#[cfg(feature = "filter")]
+impl_tag!(
+    filter::BlendMode,
+    filter::BlendMode::Normal = 0,
+    filter::BlendMode::Multiply = 1,
+    filter::BlendMode::Screen = 2,
+    filter::BlendMode::Overlay = 3,
+    filter::BlendMode::Darken = 4,
+    filter::BlendMode::Lighten = 5,
+    filter::BlendMode::ColorDodge = 6,
+    filter::BlendMode::ColorBurn = 7,
+    filter::BlendMode::HardLight = 8,
+    filter::BlendMode::SoftLight = 9,
+    filter::BlendMode::Difference = 10,
+    filter::BlendMode::Exclusion = 11,
+    filter::BlendMode::Hue = 12,
+    filter::BlendMode::Saturation = 13,
+    filter::BlendMode::Color = 14,
+    filter::BlendMode::Luminosity = 15,
+);
+#[cfg(feature = "filter")]
+impl_tag!(
+    filter::EdgeMode,
+    filter::EdgeMode::None = 0,
+    filter::EdgeMode::Duplicate = 1,
+    filter::EdgeMode::Wrap = 2,
+);
+#[cfg(feature = "filter")]
+impl_tag!(
+    filter::ColorChannel,
+    filter::ColorChannel::R = 0,
+    filter::ColorChannel::G = 1,
+    filter::ColorChannel::B = 2,
+    filter::ColorChannel::A = 3,
+);
+#[cfg(feature = "filter")]
+impl_tag!(
+    filter::MorphologyOperator,
+    filter::MorphologyOperator::Erode = 0,
+    filter::MorphologyOperator::Dilate = 1,
+);
+#[cfg(feature = "filter")]
+impl_tag!(
+    filter::TurbulenceKind,
+    filter::TurbulenceKind::FractalNoise = 0,
+    filter::TurbulenceKind::Turbulence = 1,
+);
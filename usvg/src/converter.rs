@@ -34,6 +34,9 @@ pub struct Cache {
     #[cfg(feature = "filter")]
     pub filters: HashMap<String, Rc<filter::Filter>>,
     pub paint: HashMap<String, Paint>,
+    /// Fallback fonts by character and the original font.
+    #[cfg(feature = "text")]
+    pub fallback_fonts: HashMap<(char, fontdb::ID), Option<fontdb::ID>>,
 
     // used for ID generation
     pub all_ids: HashSet<u64>,
@@ -99,6 +102,18 @@ pub(crate) fn convert_doc(svg_doc: &svgtree::Document, opt: &OptionsRef) -> Resu
         return Ok(tree);
     }
 
+    // Fonts from `@font-face` rules are available only to the current document.
+    #[cfg(feature = "text")]
+    let fontdb = text::web_fonts::load(svg_doc.font_faces(), opt);
+    #[cfg(feature = "text")]
+    let opt = &match fontdb {
+        Some(ref fontdb) => OptionsRef {
+            fontdb,
+            ..opt.clone()
+        },
+        None => opt.clone(),
+    };
+
     let state = State {
         parent_clip_path: None,
         parent_marker: None,
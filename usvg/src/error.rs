@@ -23,6 +23,12 @@ pub enum Error {
 
     /// Failed to parse an SVG data.
     ParsingFailed(roxmltree::Error),
+
+    /// Binary tree data is malformed.
+    InvalidBinary,
+
+    /// Binary tree data was produced by a different `usvg` version.
+    BinaryVersionMismatch,
 }
 
 impl From<roxmltree::Error> for Error {
@@ -49,6 +55,12 @@ impl std::fmt::Display for Error {
             Error::ParsingFailed(ref e) => {
                 write!(f, "SVG data parsing failed cause {}", e)
             }
+            Error::InvalidBinary => {
+                write!(f, "provided data is not a valid binary tree")
+            }
+            Error::BinaryVersionMismatch => {
+                write!(f, "binary tree was created by a different usvg version")
+            }
         }
     }
 }
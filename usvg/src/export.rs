@@ -1184,7 +1184,7 @@ fn write_light_source(light: &filter::LightSource, xml: &mut XmlWriter) {
     xml.end_element();
 }
 
-fn write_num(num: f64, buf: &mut Vec<u8>) {
+pub(crate) fn write_num(num: f64, buf: &mut Vec<u8>) {
     // If number is an integer, it's faster to write it as i32.
     if num.fract().is_fuzzy_zero() {
         write!(buf, "{}", num as i32).unwrap();
@@ -1202,3 +1202,28 @@ fn write_num(num: f64, buf: &mut Vec<u8>) {
 
     write!(buf, "{}", v).unwrap();
 }
+
+/// Adds a construct to the unsupported list, once.
+#[cfg(any(feature = "vector-drawable", feature = "xaml"))]
+pub(crate) fn report(unsupported: &mut Vec<Unsupported>, id: &str, kind: UnsupportedKind) {
+    let item = Unsupported {
+        id: id.to_string(),
+        kind,
+    };
+
+    if !unsupported.contains(&item) {
+        log::warn!("{:?} is not supported. Element: '{}'.", kind, id);
+        unsupported.push(item);
+    }
+}
+
+/// Formats a color as `#RRGGBB` or as `#AARRGGBB` when not opaque.
+#[cfg(any(feature = "vector-drawable", feature = "xaml"))]
+pub(crate) fn hex_color(c: Color, opacity: f64) -> String {
+    if opacity < 1.0 {
+        let a = (opacity.max(0.0) * 255.0).round() as u8;
+        format!("#{:02X}{:02X}{:02X}{:02X}", a, c.red, c.green, c.blue)
+    } else {
+        format!("#{:02X}{:02X}{:02X}", c.red, c.green, c.blue)
+    }
+}
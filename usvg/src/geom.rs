@@ -477,6 +477,24 @@ impl PathBbox {
         }
     }
 
+    /// Returns an intersection of two bboxes.
+    ///
+    /// Returns `None` when bboxes do not intersect.
+    #[inline]
+    pub fn intersect(&self, r: PathBbox) -> Option<Self> {
+        let x1 = self.x().max(r.x());
+        let y1 = self.y().max(r.y());
+
+        let x2 = self.right().min(r.right());
+        let y2 = self.bottom().min(r.bottom());
+
+        if x2 < x1 || y2 < y1 {
+            return None;
+        }
+
+        PathBbox::new(x1, y1, x2 - x1, y2 - y1)
+    }
+
     /// Transforms the `PathBbox` using the provided `bbox`.
     pub fn bbox_transform(&self, bbox: Rect) -> Self {
         let x = self.x() * bbox.width() + bbox.x();
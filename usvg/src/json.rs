@@ -0,0 +1,1180 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::io::Write;
+
+use xmlwriter::Indent;
+
+use crate::export::write_num;
+use crate::*;
+
+pub(crate) fn convert(tree: &Tree, opt: &XmlOptions) -> String {
+    let mut defs = Defs::default();
+    collect_defs(&tree.root, &mut defs);
+
+    let mut json = JsonWriter::new(opt);
+    json.begin_object();
+    json.key("width");
+    json.num(tree.size.width());
+    json.key("height");
+    json.num(tree.size.height());
+    json.key("view_box");
+    write_view_box(&tree.view_box, &mut json);
+    json.key("root");
+    write_node(&tree.root, &mut json);
+
+    json.key("paint_servers");
+    json.begin_array();
+    for paint in &defs.paint_servers {
+        write_paint_server(paint, &mut json);
+    }
+    json.end_array();
+
+    json.key("clip_paths");
+    json.begin_array();
+    for cp in &defs.clip_paths {
+        write_clip_path(cp, &mut json);
+    }
+    json.end_array();
+
+    json.key("masks");
+    json.begin_array();
+    for mask in &defs.masks {
+        write_mask(mask, &mut json);
+    }
+    json.end_array();
+
+    json.key("filters");
+    json.begin_array();
+    #[cfg(feature = "filter")]
+    for filter in &defs.filters {
+        write_filter(filter, &mut json);
+    }
+    json.end_array();
+
+    json.end_object();
+    json.finish()
+}
+
+/// Elements that are referenced by nodes.
+///
+/// Written once and referenced by ID, just like in SVG.
+#[derive(Default)]
+struct Defs {
+    paint_servers: Vec<Paint>,
+    clip_paths: Vec<Rc<ClipPath>>,
+    masks: Vec<Rc<Mask>>,
+    #[cfg(feature = "filter")]
+    filters: Vec<Rc<filter::Filter>>,
+}
+
+fn collect_defs(root: &Node, defs: &mut Defs) {
+    for node in root.descendants() {
+        match *node.borrow() {
+            NodeKind::Group(ref g) => {
+                if let Some(ref cp) = g.clip_path {
+                    collect_clip_path(cp, defs);
+                }
+
+                if let Some(ref mask) = g.mask {
+                    collect_mask(mask, defs);
+                }
+
+                #[cfg(feature = "filter")]
+                {
+                    for filter in &g.filters {
+                        collect_filter(filter, defs);
+                    }
+
+                    if let Some(ref paint) = g.filter_fill {
+                        collect_paint(paint, defs);
+                    }
+
+                    if let Some(ref paint) = g.filter_stroke {
+                        collect_paint(paint, defs);
+                    }
+                }
+            }
+            NodeKind::Path(ref path) => {
+                if let Some(ref fill) = path.fill {
+                    collect_paint(&fill.paint, defs);
+                }
+
+                if let Some(ref stroke) = path.stroke {
+                    collect_paint(&stroke.paint, defs);
+                }
+            }
+            NodeKind::Image(_) => {}
+        }
+    }
+}
+
+fn collect_paint(paint: &Paint, defs: &mut Defs) {
+    if matches!(paint, Paint::Color(_)) || defs.paint_servers.contains(paint) {
+        return;
+    }
+
+    defs.paint_servers.push(paint.clone());
+    if let Paint::Pattern(ref patt) = paint {
+        collect_defs(&patt.root, defs);
+    }
+}
+
+fn collect_clip_path(cp: &Rc<ClipPath>, defs: &mut Defs) {
+    if defs.clip_paths.iter().any(|other| Rc::ptr_eq(cp, other)) {
+        return;
+    }
+
+    defs.clip_paths.push(cp.clone());
+    if let Some(ref cp) = cp.clip_path {
+        collect_clip_path(cp, defs);
+    }
+
+    collect_defs(&cp.root, defs);
+}
+
+fn collect_mask(mask: &Rc<Mask>, defs: &mut Defs) {
+    if defs.masks.iter().any(|other| Rc::ptr_eq(mask, other)) {
+        return;
+    }
+
+    defs.masks.push(mask.clone());
+    if let Some(ref mask) = mask.mask {
+        collect_mask(mask, defs);
+    }
+
+    collect_defs(&mask.root, defs);
+}
+
+#[cfg(feature = "filter")]
+fn collect_filter(filter: &Rc<filter::Filter>, defs: &mut Defs) {
+    if defs.filters.iter().any(|other| Rc::ptr_eq(filter, other)) {
+        return;
+    }
+
+    defs.filters.push(filter.clone());
+    for fe in &filter.primitives {
+        if let filter::Kind::Image(ref img) = fe.kind {
+            if let filter::ImageKind::Use(ref node) = img.data {
+                collect_defs(node, defs);
+            }
+        }
+    }
+}
+
+fn write_node(node: &Node, json: &mut JsonWriter) {
+    json.begin_object();
+    match *node.borrow() {
+        NodeKind::Group(ref g) => {
+            json.key("type");
+            json.string("group");
+            json.key("id");
+            json.id(&g.id);
+            json.key("transform");
+            write_transform(g.transform, json);
+            json.key("opacity");
+            json.num(g.opacity.get());
+            json.key("clip_path");
+            json.opt_id(g.clip_path.as_ref().map(|cp| cp.id.as_str()));
+            json.key("mask");
+            json.opt_id(g.mask.as_ref().map(|mask| mask.id.as_str()));
+
+            #[cfg(feature = "filter")]
+            {
+                json.key("filters");
+                json.begin_array();
+                for filter in &g.filters {
+                    json.id(&filter.id);
+                }
+                json.end_array();
+
+                json.key("filter_fill");
+                write_opt_paint(g.filter_fill.as_ref(), json);
+                json.key("filter_stroke");
+                write_opt_paint(g.filter_stroke.as_ref(), json);
+            }
+
+            json.key("enable_background");
+            match g.enable_background {
+                Some(EnableBackground(Some(rect))) => write_rect(rect, json),
+                Some(EnableBackground(None)) => json.string("new"),
+                None => json.null(),
+            }
+        }
+        NodeKind::Path(ref path) => {
+            json.key("type");
+            json.string("path");
+            json.key("id");
+            json.id(&path.id);
+            json.key("transform");
+            write_transform(path.transform, json);
+            json.key("visibility");
+            write_visibility(path.visibility, json);
+
+            json.key("fill");
+            match path.fill {
+                Some(ref fill) => write_fill(fill, json),
+                None => json.null(),
+            }
+
+            json.key("stroke");
+            match path.stroke {
+                Some(ref stroke) => write_stroke(stroke, json),
+                None => json.null(),
+            }
+
+            json.key("paint_order");
+            json.string(match path.paint_order {
+                PaintOrder::FillAndStroke => "fill stroke",
+                PaintOrder::StrokeAndFill => "stroke fill",
+            });
+            json.key("rendering_mode");
+            json.string(match path.rendering_mode {
+                ShapeRendering::OptimizeSpeed => "optimizeSpeed",
+                ShapeRendering::CrispEdges => "crispEdges",
+                ShapeRendering::GeometricPrecision => "geometricPrecision",
+            });
+            json.key("text_bbox");
+            match path.text_bbox {
+                Some(rect) => write_rect(rect, json),
+                None => json.null(),
+            }
+            json.key("data");
+            write_path_data(&path.data, json);
+        }
+        NodeKind::Image(ref img) => {
+            json.key("type");
+            json.string("image");
+            json.key("id");
+            json.id(&img.id);
+            json.key("transform");
+            write_transform(img.transform, json);
+            json.key("visibility");
+            write_visibility(img.visibility, json);
+            json.key("view_box");
+            write_view_box(&img.view_box, json);
+            json.key("rendering_mode");
+            write_image_rendering(img.rendering_mode, json);
+            json.key("format");
+            write_image_format(&img.kind, json);
+        }
+    }
+
+    // An absolute bbox in the canvas coordinates.
+    json.key("bbox");
+    match node.calculate_bbox() {
+        Some(bbox) => json.nums(&[bbox.x(), bbox.y(), bbox.width(), bbox.height()]),
+        None => json.null(),
+    }
+
+    if let NodeKind::Group(_) = *node.borrow() {
+        json.key("children");
+        json.begin_array();
+        for child in node.children() {
+            write_node(&child, json);
+        }
+        json.end_array();
+    }
+
+    json.end_object();
+}
+
+fn write_fill(fill: &Fill, json: &mut JsonWriter) {
+    json.begin_object();
+    json.key("paint");
+    write_paint(&fill.paint, json);
+    json.key("opacity");
+    json.num(fill.opacity.get());
+    json.key("rule");
+    json.string(match fill.rule {
+        FillRule::NonZero => "nonzero",
+        FillRule::EvenOdd => "evenodd",
+    });
+    json.end_object();
+}
+
+fn write_stroke(stroke: &Stroke, json: &mut JsonWriter) {
+    json.begin_object();
+    json.key("paint");
+    write_paint(&stroke.paint, json);
+    json.key("opacity");
+    json.num(stroke.opacity.get());
+    json.key("width");
+    json.num(stroke.width.get());
+    json.key("linecap");
+    json.string(match stroke.linecap {
+        LineCap::Butt => "butt",
+        LineCap::Round => "round",
+        LineCap::Square => "square",
+    });
+    json.key("linejoin");
+    json.string(match stroke.linejoin {
+        LineJoin::Miter => "miter",
+        LineJoin::Round => "round",
+        LineJoin::Bevel => "bevel",
+    });
+    json.key("miterlimit");
+    json.num(stroke.miterlimit.get());
+    json.key("dasharray");
+    match stroke.dasharray {
+        Some(ref list) => json.nums(list),
+        None => json.null(),
+    }
+    json.key("dashoffset");
+    json.num(stroke.dashoffset as f64);
+    json.end_object();
+}
+
+/// Writes a color or a reference to a paint server.
+fn write_paint(paint: &Paint, json: &mut JsonWriter) {
+    json.begin_object();
+    json.key("type");
+    match paint {
+        Paint::Color(c) => {
+            json.string("color");
+            json.key("color");
+            write_color(*c, json);
+        }
+        Paint::LinearGradient(ref lg) => {
+            json.string("linear_gradient");
+            json.key("id");
+            json.id(&lg.id);
+        }
+        Paint::RadialGradient(ref rg) => {
+            json.string("radial_gradient");
+            json.key("id");
+            json.id(&rg.id);
+        }
+        Paint::Pattern(ref patt) => {
+            json.string("pattern");
+            json.key("id");
+            json.id(&patt.id);
+        }
+    }
+    json.end_object();
+}
+
+#[cfg(feature = "filter")]
+fn write_opt_paint(paint: Option<&Paint>, json: &mut JsonWriter) {
+    match paint {
+        Some(paint) => write_paint(paint, json),
+        None => json.null(),
+    }
+}
+
+fn write_paint_server(paint: &Paint, json: &mut JsonWriter) {
+    json.begin_object();
+    json.key("type");
+    match paint {
+        Paint::Color(_) => unreachable!(),
+        Paint::LinearGradient(ref lg) => {
+            json.string("linear_gradient");
+            json.key("id");
+            json.id(&lg.id);
+            json.key("x1");
+            json.num(lg.x1);
+            json.key("y1");
+            json.num(lg.y1);
+            json.key("x2");
+            json.num(lg.x2);
+            json.key("y2");
+            json.num(lg.y2);
+            write_base_gradient(&lg.base, json);
+        }
+        Paint::RadialGradient(ref rg) => {
+            json.string("radial_gradient");
+            json.key("id");
+            json.id(&rg.id);
+            json.key("cx");
+            json.num(rg.cx);
+            json.key("cy");
+            json.num(rg.cy);
+            json.key("r");
+            json.num(rg.r.get());
+            json.key("fx");
+            json.num(rg.fx);
+            json.key("fy");
+            json.num(rg.fy);
+            write_base_gradient(&rg.base, json);
+        }
+        Paint::Pattern(ref patt) => {
+            json.string("pattern");
+            json.key("id");
+            json.id(&patt.id);
+            json.key("units");
+            write_units(patt.units, json);
+            json.key("content_units");
+            write_units(patt.content_units, json);
+            json.key("transform");
+            write_transform(patt.transform, json);
+            json.key("rect");
+            write_rect(patt.rect, json);
+            json.key("view_box");
+            match patt.view_box {
+                Some(ref vbox) => write_view_box(vbox, json),
+                None => json.null(),
+            }
+            json.key("root");
+            write_node(&patt.root, json);
+        }
+    }
+    json.end_object();
+}
+
+fn write_base_gradient(g: &BaseGradient, json: &mut JsonWriter) {
+    json.key("units");
+    write_units(g.units, json);
+    json.key("transform");
+    write_transform(g.transform, json);
+    json.key("spread_method");
+    json.string(match g.spread_method {
+        SpreadMethod::Pad => "pad",
+        SpreadMethod::Reflect => "reflect",
+        SpreadMethod::Repeat => "repeat",
+    });
+
+    json.key("stops");
+    json.begin_array();
+    for stop in &g.stops {
+        json.begin_object();
+        json.key("offset");
+        json.num(stop.offset.get());
+        json.key("color");
+        write_color(stop.color, json);
+        json.key("opacity");
+        json.num(stop.opacity.get());
+        json.end_object();
+    }
+    json.end_array();
+}
+
+fn write_clip_path(cp: &ClipPath, json: &mut JsonWriter) {
+    json.begin_object();
+    json.key("id");
+    json.id(&cp.id);
+    json.key("units");
+    write_units(cp.units, json);
+    json.key("transform");
+    write_transform(cp.transform, json);
+    json.key("clip_path");
+    json.opt_id(cp.clip_path.as_ref().map(|cp| cp.id.as_str()));
+    json.key("root");
+    write_node(&cp.root, json);
+    json.end_object();
+}
+
+fn write_mask(mask: &Mask, json: &mut JsonWriter) {
+    json.begin_object();
+    json.key("id");
+    json.id(&mask.id);
+    json.key("units");
+    write_units(mask.units, json);
+    json.key("content_units");
+    write_units(mask.content_units, json);
+    json.key("rect");
+    write_rect(mask.rect, json);
+    json.key("mask");
+    json.opt_id(mask.mask.as_ref().map(|mask| mask.id.as_str()));
+    json.key("root");
+    write_node(&mask.root, json);
+    json.end_object();
+}
+
+#[cfg(feature = "filter")]
+fn write_filter(filter: &filter::Filter, json: &mut JsonWriter) {
+    json.begin_object();
+    json.key("id");
+    json.id(&filter.id);
+    json.key("units");
+    write_units(filter.units, json);
+    json.key("primitive_units");
+    write_units(filter.primitive_units, json);
+    json.key("rect");
+    write_rect(filter.rect, json);
+
+    json.key("primitives");
+    json.begin_array();
+    for fe in &filter.primitives {
+        write_filter_primitive(fe, json);
+    }
+    json.end_array();
+
+    json.end_object();
+}
+
+#[cfg(feature = "filter")]
+fn write_filter_primitive(fe: &filter::Primitive, json: &mut JsonWriter) {
+    use filter::Kind;
+
+    json.begin_object();
+    json.key("type");
+    json.string(match fe.kind {
+        Kind::Blend(_) => "blend",
+        Kind::ColorMatrix(_) => "color_matrix",
+        Kind::ComponentTransfer(_) => "component_transfer",
+        Kind::Composite(_) => "composite",
+        Kind::ConvolveMatrix(_) => "convolve_matrix",
+        Kind::DiffuseLighting(_) => "diffuse_lighting",
+        Kind::DisplacementMap(_) => "displacement_map",
+        Kind::DropShadow(_) => "drop_shadow",
+        Kind::Flood(_) => "flood",
+        Kind::GaussianBlur(_) => "gaussian_blur",
+        Kind::Image(_) => "image",
+        Kind::Merge(_) => "merge",
+        Kind::Morphology(_) => "morphology",
+        Kind::Offset(_) => "offset",
+        Kind::SpecularLighting(_) => "specular_lighting",
+        Kind::Tile(_) => "tile",
+        Kind::Turbulence(_) => "turbulence",
+    });
+
+    for &(name, value) in &[
+        ("x", fe.x),
+        ("y", fe.y),
+        ("width", fe.width),
+        ("height", fe.height),
+    ] {
+        json.key(name);
+        match value {
+            Some(n) => json.num(n),
+            None => json.null(),
+        }
+    }
+
+    json.key("color_interpolation");
+    json.string(match fe.color_interpolation {
+        filter::ColorInterpolation::SRGB => "sRGB",
+        filter::ColorInterpolation::LinearRGB => "linearRGB",
+    });
+    json.key("result");
+    json.string(&fe.result);
+
+    match fe.kind {
+        Kind::Blend(ref fe) => {
+            write_filter_input("in", &fe.input1, json);
+            write_filter_input("in2", &fe.input2, json);
+            json.key("mode");
+            json.string(match fe.mode {
+                filter::BlendMode::Normal => "normal",
+                filter::BlendMode::Multiply => "multiply",
+                filter::BlendMode::Screen => "screen",
+                filter::BlendMode::Overlay => "overlay",
+                filter::BlendMode::Darken => "darken",
+                filter::BlendMode::Lighten => "lighten",
+                filter::BlendMode::ColorDodge => "color-dodge",
+                filter::BlendMode::ColorBurn => "color-burn",
+                filter::BlendMode::HardLight => "hard-light",
+                filter::BlendMode::SoftLight => "soft-light",
+                filter::BlendMode::Difference => "difference",
+                filter::BlendMode::Exclusion => "exclusion",
+                filter::BlendMode::Hue => "hue",
+                filter::BlendMode::Saturation => "saturation",
+                filter::BlendMode::Color => "color",
+                filter::BlendMode::Luminosity => "luminosity",
+            });
+        }
+        Kind::ColorMatrix(ref fe) => {
+            write_filter_input("in", &fe.input, json);
+            json.key("kind");
+            match fe.kind {
+                filter::ColorMatrixKind::Matrix(ref values) => {
+                    json.string("matrix");
+                    json.key("values");
+                    json.nums(values);
+                }
+                filter::ColorMatrixKind::Saturate(value) => {
+                    json.string("saturate");
+                    json.key("values");
+                    json.nums(&[value.get()]);
+                }
+                filter::ColorMatrixKind::HueRotate(angle) => {
+                    json.string("hueRotate");
+                    json.key("values");
+                    json.nums(&[angle]);
+                }
+                filter::ColorMatrixKind::LuminanceToAlpha => {
+                    json.string("luminanceToAlpha");
+                }
+            }
+        }
+        Kind::ComponentTransfer(ref fe) => {
+            write_filter_input("in", &fe.input, json);
+            write_transfer_function("func_r", &fe.func_r, json);
+            write_transfer_function("func_g", &fe.func_g, json);
+            write_transfer_function("func_b", &fe.func_b, json);
+            write_transfer_function("func_a", &fe.func_a, json);
+        }
+        Kind::Composite(ref fe) => {
+            write_filter_input("in", &fe.input1, json);
+            write_filter_input("in2", &fe.input2, json);
+            json.key("operator");
+            match fe.operator {
+                filter::CompositeOperator::Over => json.string("over"),
+                filter::CompositeOperator::In => json.string("in"),
+                filter::CompositeOperator::Out => json.string("out"),
+                filter::CompositeOperator::Atop => json.string("atop"),
+                filter::CompositeOperator::Xor => json.string("xor"),
+                filter::CompositeOperator::Arithmetic { k1, k2, k3, k4 } => {
+                    json.string("arithmetic");
+                    json.key("k");
+                    json.nums(&[k1, k2, k3, k4]);
+                }
+            }
+        }
+        Kind::ConvolveMatrix(ref fe) => {
+            write_filter_input("in", &fe.input, json);
+            json.key("order");
+            json.nums(&[fe.matrix.columns() as f64, fe.matrix.rows() as f64]);
+            json.key("kernel_matrix");
+            json.nums(fe.matrix.data());
+            json.key("target");
+            json.nums(&[fe.matrix.target_x() as f64, fe.matrix.target_y() as f64]);
+            json.key("divisor");
+            json.num(fe.divisor.value());
+            json.key("bias");
+            json.num(fe.bias);
+            json.key("edge_mode");
+            json.string(match fe.edge_mode {
+                filter::EdgeMode::None => "none",
+                filter::EdgeMode::Duplicate => "duplicate",
+                filter::EdgeMode::Wrap => "wrap",
+            });
+            json.key("preserve_alpha");
+            json.bool(fe.preserve_alpha);
+        }
+        Kind::DiffuseLighting(ref fe) => {
+            write_filter_input("in", &fe.input, json);
+            json.key("surface_scale");
+            json.num(fe.surface_scale);
+            json.key("diffuse_constant");
+            json.num(fe.diffuse_constant);
+            json.key("lighting_color");
+            write_color(fe.lighting_color, json);
+            json.key("light_source");
+            write_light_source(fe.light_source, json);
+        }
+        Kind::DisplacementMap(ref fe) => {
+            write_filter_input("in", &fe.input1, json);
+            write_filter_input("in2", &fe.input2, json);
+            json.key("scale");
+            json.num(fe.scale);
+
+            let channel = |c| match c {
+                filter::ColorChannel::R => "R",
+                filter::ColorChannel::G => "G",
+                filter::ColorChannel::B => "B",
+                filter::ColorChannel::A => "A",
+            };
+            json.key("x_channel_selector");
+            json.string(channel(fe.x_channel_selector));
+            json.key("y_channel_selector");
+            json.string(channel(fe.y_channel_selector));
+        }
+        Kind::DropShadow(ref fe) => {
+            write_filter_input("in", &fe.input, json);
+            json.key("dx");
+            json.num(fe.dx);
+            json.key("dy");
+            json.num(fe.dy);
+            json.key("std_dev");
+            json.nums(&[fe.std_dev_x.get(), fe.std_dev_y.get()]);
+            json.key("color");
+            write_color(fe.color, json);
+            json.key("opacity");
+            json.num(fe.opacity.get());
+        }
+        Kind::Flood(ref fe) => {
+            json.key("color");
+            write_color(fe.color, json);
+            json.key("opacity");
+            json.num(fe.opacity.get());
+        }
+        Kind::GaussianBlur(ref fe) => {
+            write_filter_input("in", &fe.input, json);
+            json.key("std_dev");
+            json.nums(&[fe.std_dev_x.get(), fe.std_dev_y.get()]);
+        }
+        Kind::Image(ref fe) => {
+            json.key("aspect");
+            write_aspect(fe.aspect, json);
+            json.key("rendering_mode");
+            write_image_rendering(fe.rendering_mode, json);
+            match fe.data {
+                filter::ImageKind::Image(ref kind) => {
+                    json.key("format");
+                    write_image_format(kind, json);
+                }
+                filter::ImageKind::Use(ref node) => {
+                    json.key("node");
+                    write_node(node, json);
+                }
+            }
+        }
+        Kind::Merge(ref fe) => {
+            json.key("inputs");
+            json.begin_array();
+            for input in &fe.inputs {
+                write_filter_input_value(input, json);
+            }
+            json.end_array();
+        }
+        Kind::Morphology(ref fe) => {
+            write_filter_input("in", &fe.input, json);
+            json.key("operator");
+            json.string(match fe.operator {
+                filter::MorphologyOperator::Erode => "erode",
+                filter::MorphologyOperator::Dilate => "dilate",
+            });
+            json.key("radius");
+            json.nums(&[fe.radius_x.get(), fe.radius_y.get()]);
+        }
+        Kind::Offset(ref fe) => {
+            write_filter_input("in", &fe.input, json);
+            json.key("dx");
+            json.num(fe.dx);
+            json.key("dy");
+            json.num(fe.dy);
+        }
+        Kind::SpecularLighting(ref fe) => {
+            write_filter_input("in", &fe.input, json);
+            json.key("surface_scale");
+            json.num(fe.surface_scale);
+            json.key("specular_constant");
+            json.num(fe.specular_constant);
+            json.key("specular_exponent");
+            json.num(fe.specular_exponent);
+            json.key("lighting_color");
+            write_color(fe.lighting_color, json);
+            json.key("light_source");
+            write_light_source(fe.light_source, json);
+        }
+        Kind::Tile(ref fe) => {
+            write_filter_input("in", &fe.input, json);
+        }
+        Kind::Turbulence(ref fe) => {
+            json.key("base_frequency");
+            json.nums(&[fe.base_frequency.x.get(), fe.base_frequency.y.get()]);
+            json.key("num_octaves");
+            json.num(fe.num_octaves as f64);
+            json.key("seed");
+            json.num(fe.seed as f64);
+            json.key("stitch_tiles");
+            json.bool(fe.stitch_tiles);
+            json.key("kind");
+            json.string(match fe.kind {
+                filter::TurbulenceKind::FractalNoise => "fractalNoise",
+                filter::TurbulenceKind::Turbulence => "turbulence",
+            });
+        }
+    }
+
+    json.end_object();
+}
+
+#[cfg(feature = "filter")]
+fn write_filter_input(name: &str, input: &filter::Input, json: &mut JsonWriter) {
+    json.key(name);
+    write_filter_input_value(input, json);
+}
+
+#[cfg(feature = "filter")]
+fn write_filter_input_value(input: &filter::Input, json: &mut JsonWriter) {
+    json.string(match input {
+        filter::Input::SourceGraphic => "SourceGraphic",
+        filter::Input::SourceAlpha => "SourceAlpha",
+        filter::Input::BackgroundImage => "BackgroundImage",
+        filter::Input::BackgroundAlpha => "BackgroundAlpha",
+        filter::Input::FillPaint => "FillPaint",
+        filter::Input::StrokePaint => "StrokePaint",
+        filter::Input::Reference(ref name) => name,
+    });
+}
+
+#[cfg(feature = "filter")]
+fn write_transfer_function(name: &str, func: &filter::TransferFunction, json: &mut JsonWriter) {
+    json.key(name);
+    json.begin_object();
+    json.key("type");
+    match func {
+        filter::TransferFunction::Identity => json.string("identity"),
+        filter::TransferFunction::Table(ref values) => {
+            json.string("table");
+            json.key("values");
+            json.nums(values);
+        }
+        filter::TransferFunction::Discrete(ref values) => {
+            json.string("discrete");
+            json.key("values");
+            json.nums(values);
+        }
+        filter::TransferFunction::Linear { slope, intercept } => {
+            json.string("linear");
+            json.key("slope");
+            json.num(*slope);
+            json.key("intercept");
+            json.num(*intercept);
+        }
+        filter::TransferFunction::Gamma {
+            amplitude,
+            exponent,
+            offset,
+        } => {
+            json.string("gamma");
+            json.key("amplitude");
+            json.num(*amplitude);
+            json.key("exponent");
+            json.num(*exponent);
+            json.key("offset");
+            json.num(*offset);
+        }
+    }
+    json.end_object();
+}
+
+#[cfg(feature = "filter")]
+fn write_light_source(light: filter::LightSource, json: &mut JsonWriter) {
+    json.begin_object();
+    json.key("type");
+    match light {
+        filter::LightSource::DistantLight(light) => {
+            json.string("distant");
+            json.key("azimuth");
+            json.num(light.azimuth);
+            json.key("elevation");
+            json.num(light.elevation);
+        }
+        filter::LightSource::PointLight(light) => {
+            json.string("point");
+            json.key("position");
+            json.nums(&[light.x, light.y, light.z]);
+        }
+        filter::LightSource::SpotLight(light) => {
+            json.string("spot");
+            json.key("position");
+            json.nums(&[light.x, light.y, light.z]);
+            json.key("points_at");
+            json.nums(&[light.points_at_x, light.points_at_y, light.points_at_z]);
+            json.key("specular_exponent");
+            json.num(light.specular_exponent.get());
+            json.key("limiting_cone_angle");
+            match light.limiting_cone_angle {
+                Some(n) => json.num(n),
+                None => json.null(),
+            }
+        }
+    }
+    json.end_object();
+}
+
+fn write_transform(ts: Transform, json: &mut JsonWriter) {
+    json.nums(&[ts.a, ts.b, ts.c, ts.d, ts.e, ts.f]);
+}
+
+fn write_rect(r: Rect, json: &mut JsonWriter) {
+    json.nums(&[r.x(), r.y(), r.width(), r.height()]);
+}
+
+fn write_view_box(vb: &ViewBox, json: &mut JsonWriter) {
+    json.begin_object();
+    json.key("rect");
+    write_rect(vb.rect, json);
+    json.key("aspect");
+    write_aspect(vb.aspect, json);
+    json.end_object();
+}
+
+/// Writes an aspect ratio the same way as `preserveAspectRatio`.
+fn write_aspect(aspect: AspectRatio, json: &mut JsonWriter) {
+    let align = match aspect.align {
+        Align::None => "none",
+        Align::XMinYMin => "xMinYMin",
+        Align::XMidYMin => "xMidYMin",
+        Align::XMaxYMin => "xMaxYMin",
+        Align::XMinYMid => "xMinYMid",
+        Align::XMidYMid => "xMidYMid",
+        Align::XMaxYMid => "xMaxYMid",
+        Align::XMinYMax => "xMinYMax",
+        Align::XMidYMax => "xMidYMax",
+        Align::XMaxYMax => "xMaxYMax",
+    };
+
+    let defer = if aspect.defer { "defer " } else { "" };
+    let slice = if aspect.slice { " slice" } else { "" };
+    json.string(&format!("{}{}{}", defer, align, slice));
+}
+
+fn write_units(units: Units, json: &mut JsonWriter) {
+    json.string(match units {
+        Units::UserSpaceOnUse => "userSpaceOnUse",
+        Units::ObjectBoundingBox => "objectBoundingBox",
+    });
+}
+
+fn write_visibility(visibility: Visibility, json: &mut JsonWriter) {
+    json.string(match visibility {
+        Visibility::Visible => "visible",
+        Visibility::Hidden => "hidden",
+        Visibility::Collapse => "collapse",
+    });
+}
+
+fn write_image_rendering(mode: ImageRendering, json: &mut JsonWriter) {
+    json.string(match mode {
+        ImageRendering::OptimizeQuality => "optimizeQuality",
+        ImageRendering::OptimizeSpeed => "optimizeSpeed",
+    });
+}
+
+/// Writes only the image format, since the image data can be large.
+fn write_image_format(kind: &ImageKind, json: &mut JsonWriter) {
+    json.string(match kind {
+        ImageKind::JPEG(_) => "jpeg",
+        ImageKind::PNG(_) => "png",
+        ImageKind::GIF(_) => "gif",
+        ImageKind::SVG(_) => "svg",
+    });
+}
+
+fn write_color(c: Color, json: &mut JsonWriter) {
+    json.string(&format!("#{:02x}{:02x}{:02x}", c.red, c.green, c.blue));
+}
+
+/// Writes path data as an SVG path string with absolute commands.
+fn write_path_data(data: &PathData, json: &mut JsonWriter) {
+    json.value_start();
+    json.buf.push(b'"');
+    for seg in data.segments() {
+        match seg {
+            PathSegment::MoveTo { x, y } => {
+                json.buf.extend_from_slice(b"M ");
+                write_coords(&[x, y], &mut json.buf);
+            }
+            PathSegment::LineTo { x, y } => {
+                json.buf.extend_from_slice(b"L ");
+                write_coords(&[x, y], &mut json.buf);
+            }
+            PathSegment::CurveTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            } => {
+                json.buf.extend_from_slice(b"C ");
+                write_coords(&[x1, y1, x2, y2, x, y], &mut json.buf);
+            }
+            PathSegment::ClosePath => {
+                json.buf.extend_from_slice(b"Z ");
+            }
+        }
+    }
+
+    if !data.is_empty() {
+        json.buf.pop();
+    }
+
+    json.buf.push(b'"');
+}
+
+fn write_coords(coords: &[f64], buf: &mut Vec<u8>) {
+    for n in coords {
+        write_num(*n, buf);
+        buf.push(b' ');
+    }
+}
+
+/// A minimal streaming JSON writer.
+struct JsonWriter {
+    buf: Vec<u8>,
+    indent: Indent,
+    id_prefix: String,
+    depth: usize,
+    /// Set when no values were written to the current object or array yet.
+    is_empty: bool,
+    /// Set after a key, so the next value doesn't need a separator.
+    after_key: bool,
+}
+
+impl JsonWriter {
+    fn new(opt: &XmlOptions) -> Self {
+        JsonWriter {
+            buf: Vec::new(),
+            indent: opt.writer_opts.indent,
+            id_prefix: opt.id_prefix.clone().unwrap_or_default(),
+            depth: 0,
+            is_empty: true,
+            after_key: false,
+        }
+    }
+
+    fn finish(mut self) -> String {
+        if self.indent != Indent::None {
+            self.buf.push(b'\n');
+        }
+
+        // Unwrap is safe, because only valid UTF-8 strings are written.
+        String::from_utf8(self.buf).unwrap()
+    }
+
+    fn new_line(&mut self) {
+        let (c, count) = match self.indent {
+            Indent::None => return,
+            Indent::Spaces(n) => (b' ', n as usize * self.depth),
+            Indent::Tabs => (b'\t', self.depth),
+        };
+
+        self.buf.push(b'\n');
+        for _ in 0..count {
+            self.buf.push(c);
+        }
+    }
+
+    fn value_start(&mut self) {
+        if self.after_key {
+            self.after_key = false;
+            return;
+        }
+
+        if self.depth == 0 {
+            return;
+        }
+
+        if !self.is_empty {
+            self.buf.push(b',');
+        }
+
+        self.is_empty = false;
+        self.new_line();
+    }
+
+    fn begin_object(&mut self) {
+        self.value_start();
+        self.buf.push(b'{');
+        self.depth += 1;
+        self.is_empty = true;
+    }
+
+    fn end_object(&mut self) {
+        self.end(b'}');
+    }
+
+    fn begin_array(&mut self) {
+        self.value_start();
+        self.buf.push(b'[');
+        self.depth += 1;
+        self.is_empty = true;
+    }
+
+    fn end_array(&mut self) {
+        self.end(b']');
+    }
+
+    fn end(&mut self, c: u8) {
+        self.depth -= 1;
+        if !self.is_empty {
+            self.new_line();
+        }
+
+        self.buf.push(c);
+        self.is_empty = false;
+    }
+
+    fn key(&mut self, name: &str) {
+        self.value_start();
+        self.write_escaped(name);
+        self.buf.push(b':');
+        if self.indent != Indent::None {
+            self.buf.push(b' ');
+        }
+
+        self.after_key = true;
+    }
+
+    fn string(&mut self, s: &str) {
+        self.value_start();
+        self.write_escaped(s);
+    }
+
+    /// Writes an element ID with the prefix.
+    ///
+    /// Empty IDs are not prefixed.
+    fn id(&mut self, id: &str) {
+        if id.is_empty() {
+            self.string(id);
+        } else {
+            let id = format!("{}{}", self.id_prefix, id);
+            self.string(&id);
+        }
+    }
+
+    fn opt_id(&mut self, id: Option<&str>) {
+        match id {
+            Some(id) => self.id(id),
+            None => self.null(),
+        }
+    }
+
+    fn num(&mut self, n: f64) {
+        self.value_start();
+        self.write_num(n);
+    }
+
+    /// Writes a list of numbers on a single line.
+    fn nums(&mut self, list: &[f64]) {
+        self.value_start();
+        self.buf.push(b'[');
+        for (i, n) in list.iter().enumerate() {
+            if i != 0 {
+                self.buf.push(b',');
+                if self.indent != Indent::None {
+                    self.buf.push(b' ');
+                }
+            }
+
+            self.write_num(*n);
+        }
+        self.buf.push(b']');
+    }
+
+    fn bool(&mut self, b: bool) {
+        self.value_start();
+        self.buf
+            .extend_from_slice(if b { b"true" as &[u8] } else { b"false" });
+    }
+
+    fn null(&mut self) {
+        self.value_start();
+        self.buf.extend_from_slice(b"null");
+    }
+
+    fn write_num(&mut self, n: f64) {
+        // JSON doesn't support NaN and infinity.
+        if n.is_finite() {
+            write_num(n, &mut self.buf);
+        } else {
+            self.buf.extend_from_slice(b"null");
+        }
+    }
+
+    fn write_escaped(&mut self, s: &str) {
+        self.buf.push(b'"');
+        for c in s.chars() {
+            match c {
+                '"' => self.buf.extend_from_slice(b"\\\""),
+                '\\' => self.buf.extend_from_slice(b"\\\\"),
+                '\n' => self.buf.extend_from_slice(b"\\n"),
+                '\r' => self.buf.extend_from_slice(b"\\r"),
+                '\t' => self.buf.extend_from_slice(b"\\t"),
+                c if (c as u32) < 0x20 => write!(self.buf, "\\u{:04x}", c as u32).unwrap(),
+                c => {
+                    let mut tmp = [0; 4];
+                    self.buf
+                        .extend_from_slice(c.encode_utf8(&mut tmp).as_bytes());
+                }
+            }
+        }
+        self.buf.push(b'"');
+    }
+}
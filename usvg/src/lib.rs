@@ -105,6 +105,8 @@ macro_rules! impl_from_str {
     };
 }
 
+#[cfg(feature = "binary")]
+mod binary;
 mod clippath;
 mod converter;
 mod error;
@@ -114,6 +116,8 @@ mod export;
 pub mod filter;
 mod geom;
 mod image;
+#[cfg(feature = "json")]
+mod json;
 mod marker;
 mod mask;
 mod options;
@@ -128,6 +132,10 @@ mod text;
 mod units;
 mod use_node;
 pub mod utils;
+#[cfg(feature = "vector-drawable")]
+mod vector_drawable;
+#[cfg(feature = "xaml")]
+mod xaml;
 
 pub use image::ImageHrefResolver;
 pub use strict_num::{ApproxEq, ApproxEqUlps, NonZeroPositiveF64, NormalizedF64, PositiveF64};
@@ -140,6 +148,12 @@ pub use roxmltree;
 #[cfg(feature = "text")]
 pub use fontdb;
 
+#[cfg(feature = "text")]
+pub use crate::text::{
+    decode_web_font, measure_text, FontFallback, FontFallbackSelectFn, FontProperties, TextCluster,
+    TextMetrics, WebFontsExt,
+};
+
 pub use crate::clippath::*;
 pub use crate::error::*;
 pub use crate::geom::*;
@@ -175,6 +189,56 @@ pub struct XmlOptions {
     pub writer_opts: xmlwriter::Options,
 }
 
+/// A result of a conversion into a format that supports only a subset of SVG.
+#[cfg(any(feature = "vector-drawable", feature = "xaml"))]
+#[derive(Clone, Debug)]
+pub struct Conversion {
+    /// A converted document.
+    pub data: String,
+
+    /// A list of constructs that were ignored or approximated.
+    pub unsupported: Vec<Unsupported>,
+}
+
+/// A construct that cannot be represented by a target format.
+#[cfg(any(feature = "vector-drawable", feature = "xaml"))]
+#[derive(Clone, PartialEq, Debug)]
+pub struct Unsupported {
+    /// An ID of the affected element.
+    ///
+    /// Filters, masks, patterns, clip paths and gradients are referenced by their own ID.
+    /// Can be empty for groups, paths and images.
+    pub id: String,
+
+    /// A construct kind.
+    pub kind: UnsupportedKind,
+}
+
+/// A kind of an unsupported construct.
+#[cfg(any(feature = "vector-drawable", feature = "xaml"))]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum UnsupportedKind {
+    /// A filter. Ignored.
+    Filter,
+    /// A mask. Ignored.
+    Mask,
+    /// A pattern fill or stroke. Ignored.
+    Pattern,
+    /// A clip path that is not a single path. Ignored.
+    ClipPath,
+    /// A raster or SVG image. Ignored.
+    Image,
+    /// An opacity of a group with multiple shapes.
+    /// Applied to each shape separately.
+    GroupOpacity,
+    /// A stroke dash array. Ignored.
+    StrokeDasharray,
+    /// A gradient transform that cannot be represented. Approximated.
+    GradientTransform,
+    /// A radial gradient focal point. Ignored.
+    FocalPoint,
+}
+
 /// Checks that type has a default value.
 pub trait IsDefault: Default {
     /// Checks that type has a default value.
@@ -594,12 +658,100 @@ impl Tree {
         self.root.descendants().find(|node| &*node.id() == id)
     }
 
+    /// Calculates the drawing bounding box.
+    ///
+    /// The visual bounding box of all elements in the `view_box` coordinates,
+    /// which includes strokes, filter regions, clipping and masking.
+    /// See [`NodeExt::calculate_visual_bbox`] for details.
+    ///
+    /// Can be expensive on large trees.
+    pub fn drawing_bbox(&self) -> Option<PathBbox> {
+        self.root.calculate_visual_bbox()
+    }
+
+    /// Rewrites `size` and `view_box` to fit the drawing.
+    ///
+    /// The `view_box` is set to the [drawing bbox](Self::drawing_bbox),
+    /// while `size` is adjusted to keep the current scale.
+    /// Content outside the original `view_box` is included as well.
+    ///
+    /// Returns `None` and keeps the tree unchanged when the drawing is empty.
+    pub fn fit_to_drawing(&mut self) -> Option<()> {
+        let rect = self.drawing_bbox()?.to_rect()?;
+
+        let ts = utils::view_box_to_transform(self.view_box.rect, self.view_box.aspect, self.size);
+        let (sx, sy) = ts.get_scale();
+
+        self.size = Size::new(rect.width() * sx, rect.height() * sy)?;
+        self.view_box = ViewBox {
+            rect,
+            aspect: AspectRatio::default(),
+        };
+
+        Some(())
+    }
+
     /// Converts an SVG.
     #[inline]
     #[cfg(feature = "export")]
     pub fn to_string(&self, opt: &XmlOptions) -> String {
         crate::export::convert(self, opt)
     }
+
+    /// Converts the tree into JSON.
+    ///
+    /// Contains all nodes with their bounding boxes, as well as paint servers,
+    /// clip paths, masks and filters, which are referenced by ID.
+    /// Image data is omitted, only the format is written.
+    ///
+    /// Only `XmlOptions::id_prefix` and `XmlOptions::writer_opts.indent` are used.
+    #[inline]
+    #[cfg(feature = "json")]
+    pub fn to_json(&self, opt: &XmlOptions) -> String {
+        crate::json::convert(self, opt)
+    }
+
+    /// Converts the tree into an Android `VectorDrawable`.
+    ///
+    /// Paths, fills, strokes, gradients, transforms and single-path clip paths are supported.
+    /// Group opacity is applied to each shape.
+    /// Everything else is ignored and listed in [`Conversion::unsupported`].
+    #[inline]
+    #[cfg(feature = "vector-drawable")]
+    pub fn to_vector_drawable(&self, opt: &XmlOptions) -> Conversion {
+        crate::vector_drawable::convert(self, opt)
+    }
+
+    /// Converts the tree into a WPF XAML `Canvas`.
+    ///
+    /// Paths, fills, strokes, gradients, transforms, opacity
+    /// and single-path clip paths are supported.
+    /// Everything else is ignored and listed in [`Conversion::unsupported`].
+    #[inline]
+    #[cfg(feature = "xaml")]
+    pub fn to_xaml(&self, opt: &XmlOptions) -> Conversion {
+        crate::xaml::convert(self, opt)
+    }
+
+    /// Serializes the tree into a compact binary representation.
+    ///
+    /// Can be loaded back using [`Tree::from_binary`] without fonts or the original SVG.
+    /// The data is tied to the current `usvg` version.
+    #[inline]
+    #[cfg(feature = "binary")]
+    pub fn to_binary(&self) -> Vec<u8> {
+        crate::binary::write(self)
+    }
+
+    /// Loads a tree previously serialized by [`Tree::to_binary`].
+    ///
+    /// Returns [`Error::BinaryVersionMismatch`] when the data was produced
+    /// by a different `usvg` version.
+    #[inline]
+    #[cfg(feature = "binary")]
+    pub fn from_binary(data: &[u8]) -> Result<Self, Error> {
+        crate::binary::read(data)
+    }
 }
 
 /// Additional `Node` methods.
@@ -632,6 +784,34 @@ pub trait NodeExt {
     /// Can be expensive on large paths and groups.
     fn calculate_bbox(&self) -> Option<PathBbox>;
 
+    /// Calculates node's absolute object bounding box.
+    ///
+    /// Contains only the fill geometry, i.e. strokes, filters, clipping
+    /// and masking are ignored. Matches the `objectBoundingBox` units definition.
+    ///
+    /// Can be expensive on large paths and groups.
+    fn calculate_object_bbox(&self) -> Option<PathBbox>;
+
+    /// Calculates node's absolute stroke bounding box.
+    ///
+    /// The same as [`calculate_object_bbox`](Self::calculate_object_bbox),
+    /// but includes strokes. Unlike [`calculate_bbox`](Self::calculate_bbox),
+    /// miter joins and square caps are taken into account.
+    ///
+    /// Can be expensive on large paths and groups.
+    fn calculate_stroke_bbox(&self) -> Option<PathBbox>;
+
+    /// Calculates node's absolute visual bounding box.
+    ///
+    /// A region that can be affected by the node rendering.
+    /// The same as [`calculate_stroke_bbox`](Self::calculate_stroke_bbox),
+    /// but groups with filters are expanded to the filter region,
+    /// while clip paths and masks reduce the region.
+    /// Invisible elements are ignored.
+    ///
+    /// Can be expensive on large paths and groups.
+    fn calculate_visual_bbox(&self) -> Option<PathBbox>;
+
     /// Returns the node starting from which the filter background should be rendered.
     #[cfg(feature = "filter")]
     fn filter_background_start_node(&self, filter: &filter::Filter) -> Option<Node>;
@@ -674,6 +854,21 @@ impl NodeExt for Node {
         calc_node_bbox(self, self.abs_transform())
     }
 
+    #[inline]
+    fn calculate_object_bbox(&self) -> Option<PathBbox> {
+        calc_node_bbox_of_kind(self, self.abs_transform(), BboxKind::Object)
+    }
+
+    #[inline]
+    fn calculate_stroke_bbox(&self) -> Option<PathBbox> {
+        calc_node_bbox_of_kind(self, self.abs_transform(), BboxKind::Stroke)
+    }
+
+    #[inline]
+    fn calculate_visual_bbox(&self) -> Option<PathBbox> {
+        calc_node_bbox_of_kind(self, self.abs_transform(), BboxKind::Visual)
+    }
+
     #[cfg(feature = "filter")]
     fn filter_background_start_node(&self, filter: &filter::Filter) -> Option<Node> {
         fn has_enable_background(node: &Node) -> bool {
@@ -743,3 +938,146 @@ fn calc_node_bbox(node: &Node, ts: Transform) -> Option<PathBbox> {
         }
     }
 }
+
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum BboxKind {
+    Object,
+    Stroke,
+    Visual,
+}
+
+pub(crate) fn calc_node_bbox_of_kind(
+    node: &Node,
+    ts: Transform,
+    kind: BboxKind,
+) -> Option<PathBbox> {
+    match *node.borrow() {
+        NodeKind::Path(ref path) => {
+            if kind == BboxKind::Visual
+                && (path.visibility != Visibility::Visible
+                    || (path.fill.is_none() && path.stroke.is_none()))
+            {
+                return None;
+            }
+
+            match path.stroke {
+                Some(ref stroke) if kind != BboxKind::Object => {
+                    path.data.stroke_bbox_with_transform(ts, stroke)
+                }
+                _ => path.data.bbox_with_transform(ts, None),
+            }
+        }
+        NodeKind::Image(ref img) => {
+            if kind == BboxKind::Visual && img.visibility != Visibility::Visible {
+                return None;
+            }
+
+            let path = PathData::from_rect(img.view_box.rect);
+            path.bbox_with_transform(ts, None)
+        }
+        NodeKind::Group(ref g) => {
+            let mut bbox = PathBbox::new_bbox();
+
+            for child in node.children() {
+                let mut child_transform = ts;
+                child_transform.append(&child.transform());
+                if let Some(c_bbox) = calc_node_bbox_of_kind(&child, child_transform, kind) {
+                    bbox = bbox.expand(c_bbox);
+                }
+            }
+
+            // Make sure bbox was changed.
+            let bbox = if bbox.fuzzy_eq(&PathBbox::new_bbox()) {
+                None
+            } else {
+                Some(bbox)
+            };
+
+            if kind == BboxKind::Visual {
+                calc_group_visual_bbox(node, g, ts, bbox)
+            } else {
+                bbox
+            }
+        }
+    }
+}
+
+fn calc_group_visual_bbox(
+    node: &Node,
+    g: &Group,
+    ts: Transform,
+    bbox: Option<PathBbox>,
+) -> Option<PathBbox> {
+    // `objectBoundingBox` units are resolved in the group's own coordinate system.
+    let object_bbox = || {
+        calc_node_bbox_of_kind(node, Transform::default(), BboxKind::Object)
+            .and_then(|r| r.to_rect())
+    };
+
+    // Filters can draw outside of the content, but never outside of the filter region.
+    #[cfg(feature = "filter")]
+    let bbox = match g.filters.last() {
+        Some(filter) => {
+            let region = if filter.units == Units::ObjectBoundingBox {
+                filter.rect.bbox_transform(object_bbox()?)
+            } else {
+                filter.rect
+            };
+
+            region.transform(&ts).map(|r| r.to_path_bbox())
+        }
+        None => bbox,
+    };
+
+    let mut bbox = bbox?;
+
+    if let Some(ref cp) = g.clip_path {
+        bbox = bbox.intersect(calc_clip_path_bbox(cp, ts, &object_bbox)?)?;
+    }
+
+    if let Some(ref mask) = g.mask {
+        bbox = bbox.intersect(calc_mask_bbox(mask, ts, &object_bbox)?)?;
+    }
+
+    Some(bbox)
+}
+
+fn calc_clip_path_bbox(
+    cp: &ClipPath,
+    ts: Transform,
+    object_bbox: &dyn Fn() -> Option<Rect>,
+) -> Option<PathBbox> {
+    let mut clip_ts = ts;
+    clip_ts.append(&cp.transform);
+    if cp.units == Units::ObjectBoundingBox {
+        clip_ts.append(&Transform::from_bbox(object_bbox()?));
+    }
+
+    let mut bbox = calc_node_bbox_of_kind(&cp.root, clip_ts, BboxKind::Visual)?;
+
+    if let Some(ref cp) = cp.clip_path {
+        bbox = bbox.intersect(calc_clip_path_bbox(cp, ts, object_bbox)?)?;
+    }
+
+    Some(bbox)
+}
+
+fn calc_mask_bbox(
+    mask: &Mask,
+    ts: Transform,
+    object_bbox: &dyn Fn() -> Option<Rect>,
+) -> Option<PathBbox> {
+    let rect = if mask.units == Units::ObjectBoundingBox {
+        mask.rect.bbox_transform(object_bbox()?)
+    } else {
+        mask.rect
+    };
+
+    let mut bbox = rect.transform(&ts)?.to_path_bbox();
+
+    if let Some(ref mask) = mask.mask {
+        bbox = bbox.intersect(calc_mask_bbox(mask, ts, object_bbox)?)?;
+    }
+
+    Some(bbox)
+}
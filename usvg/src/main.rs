@@ -9,6 +9,8 @@ use std::process;
 
 use pico_args::Arguments;
 
+use usvg::WebFontsExt;
+
 const HELP: &str = "\
 usvg (micro SVG) is an SVG simplification tool.
 
@@ -23,6 +25,12 @@ OPTIONS:
   -V, --version                 Prints version information
   -c                            Prints the output SVG to the stdout
 
+  --format FORMAT               Sets the output format.
+                                Formats other than SVG require the build feature
+                                with the same name
+                                [default: svg]
+                                [possible values: svg, vector-drawable, xaml, json]
+
   --dpi DPI                     Sets the resolution
                                 [default: 96] [possible values: 10..4000]
   --languages LANG              Sets a comma-separated list of languages that
@@ -65,6 +73,7 @@ OPTIONS:
   --monospace-family FAMILY     Sets the 'monospace' font family
                                 [default: Courier New]
   --use-font-file PATH          Load a specified font file into the fonts database.
+                                WOFF and WOFF2 files are supported as well.
                                 Will be used during text to path conversion.
                                 This option can be set multiple times
   --use-fonts-dir PATH          Loads all fonts from the specified directory
@@ -87,6 +96,8 @@ OPTIONS:
                                 Refer to the explanation of the '--default-width'
                                 option. [values: 1..2^32] [default: 100]
 
+  --export-area-drawing         Sets the output size and viewBox to the drawing bounds,
+                                which include strokes, filter regions and clipping
   --keep-named-groups           Disables removing of groups with non-empty ID
   --id-prefix                   Adds a prefix to each ID attribute
   --indent INDENT               Sets the XML nodes indent
@@ -101,8 +112,43 @@ ARGS:
   <out-svg>                     Output file
 ";
 
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum OutputFormat {
+    Svg,
+    #[cfg(feature = "vector-drawable")]
+    VectorDrawable,
+    #[cfg(feature = "xaml")]
+    Xaml,
+    #[cfg(feature = "json")]
+    Json,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "svg" => Ok(OutputFormat::Svg),
+            #[cfg(feature = "vector-drawable")]
+            "vector-drawable" => Ok(OutputFormat::VectorDrawable),
+            #[cfg(feature = "xaml")]
+            "xaml" => Ok(OutputFormat::Xaml),
+            #[cfg(not(feature = "vector-drawable"))]
+            "vector-drawable" => Err("the vector-drawable feature is not enabled".to_string()),
+            #[cfg(not(feature = "xaml"))]
+            "xaml" => Err("the xaml feature is not enabled".to_string()),
+            #[cfg(feature = "json")]
+            "json" => Ok(OutputFormat::Json),
+            #[cfg(not(feature = "json"))]
+            "json" => Err("the json feature is not enabled".to_string()),
+            _ => Err("unsupported format".to_string()),
+        }
+    }
+}
+
 #[derive(Debug)]
 struct Args {
+    format: OutputFormat,
     dpi: u32,
     languages: Vec<String>,
     shape_rendering: usvg::ShapeRendering,
@@ -124,6 +170,7 @@ struct Args {
     default_width: u32,
     default_height: u32,
 
+    export_area_drawing: bool,
     keep_named_groups: bool,
     id_prefix: Option<String>,
     indent: xmlwriter::Indent,
@@ -149,6 +196,9 @@ fn collect_args() -> Result<Args, pico_args::Error> {
     }
 
     Ok(Args {
+        format: input
+            .opt_value_from_str("--format")?
+            .unwrap_or(OutputFormat::Svg),
         dpi: input.opt_value_from_fn("--dpi", parse_dpi)?.unwrap_or(96),
         languages: input
             .opt_value_from_fn("--languages", parse_languages)?
@@ -186,6 +236,7 @@ fn collect_args() -> Result<Args, pico_args::Error> {
             .opt_value_from_fn("--default-height", parse_length)?
             .unwrap_or(100),
 
+        export_area_drawing: input.contains("--export-area-drawing"),
         keep_named_groups: input.contains("--keep-named-groups"),
         id_prefix: input.opt_value_from_str("--id-prefix")?,
         indent: input
@@ -321,7 +372,7 @@ fn process(args: Args) -> Result<(), String> {
     }
 
     for path in &args.font_files {
-        if let Err(e) = fontdb.load_font_file(path) {
+        if let Err(e) = fontdb.load_web_font_file(path) {
             log::warn!("Failed to load '{}' cause {}.", path.display(), e);
         }
     }
@@ -384,6 +435,9 @@ fn process(args: Args) -> Result<(), String> {
         default_size: usvg::Size::new(args.default_width as f64, args.default_height as f64)
             .unwrap(),
         fontdb,
+        font_fallback: usvg::FontFallback::default(),
+        synthetic_bold: true,
+        synthetic_oblique: true,
         image_href_resolver: usvg::ImageHrefResolver::default(),
     };
 
@@ -392,7 +446,12 @@ fn process(args: Args) -> Result<(), String> {
         InputFrom::File(ref path) => std::fs::read(path).map_err(|e| e.to_string()),
     }?;
 
-    let tree = usvg::Tree::from_data(&input_svg, &re_opt.to_ref()).map_err(|e| format!("{}", e))?;
+    let mut tree =
+        usvg::Tree::from_data(&input_svg, &re_opt.to_ref()).map_err(|e| format!("{}", e))?;
+
+    if args.export_area_drawing && tree.fit_to_drawing().is_none() {
+        log::warn!("The drawing is empty. The original size is used.");
+    }
 
     let xml_opt = usvg::XmlOptions {
         id_prefix: args.id_prefix,
@@ -403,7 +462,15 @@ fn process(args: Args) -> Result<(), String> {
         },
     };
 
-    let s = tree.to_string(&xml_opt);
+    let s = match args.format {
+        OutputFormat::Svg => tree.to_string(&xml_opt),
+        #[cfg(feature = "vector-drawable")]
+        OutputFormat::VectorDrawable => tree.to_vector_drawable(&xml_opt).data,
+        #[cfg(feature = "xaml")]
+        OutputFormat::Xaml => tree.to_xaml(&xml_opt).data,
+        #[cfg(feature = "json")]
+        OutputFormat::Json => tree.to_json(&xml_opt),
+    };
     match out_svg {
         OutputTo::Stdout => {
             io::stdout()
@@ -4,6 +4,9 @@
 
 use crate::{ImageHrefResolver, ImageRendering, ScreenSize, ShapeRendering, Size, TextRendering};
 
+#[cfg(feature = "text")]
+use crate::FontFallback;
+
 /// Image fit options.
 ///
 /// All variants will preserve the original aspect.
@@ -129,6 +132,30 @@ pub struct Options {
     #[cfg(feature = "text")]
     pub fontdb: fontdb::Database,
 
+    /// Specifies how fonts are selected for characters missing in the resolved font.
+    ///
+    /// Default: see type's documentation for details
+    #[cfg(feature = "text")]
+    pub font_fallback: FontFallback,
+
+    /// Synthesize bold text when the matched font face is not bold enough.
+    ///
+    /// Glyph outlines will be emboldened, like browsers do.
+    /// Fonts with a `wght` variation axis are never emboldened.
+    ///
+    /// Default: true
+    #[cfg(feature = "text")]
+    pub synthetic_bold: bool,
+
+    /// Synthesize italic and oblique text when the matched font face is upright.
+    ///
+    /// Glyph outlines will be slanted, like browsers do.
+    /// Fonts with a `slnt` or `ital` variation axis are never slanted.
+    ///
+    /// Default: true
+    #[cfg(feature = "text")]
+    pub synthetic_oblique: bool,
+
     /// Specifies the way `xlink:href` in `<image>` elements should be handled.
     ///
     /// Default: see type's documentation for details
@@ -151,6 +178,12 @@ impl Default for Options {
             default_size: Size::new(100.0, 100.0).unwrap(),
             #[cfg(feature = "text")]
             fontdb: fontdb::Database::new(),
+            #[cfg(feature = "text")]
+            font_fallback: FontFallback::default(),
+            #[cfg(feature = "text")]
+            synthetic_bold: true,
+            #[cfg(feature = "text")]
+            synthetic_oblique: true,
             image_href_resolver: ImageHrefResolver::default(),
         }
     }
@@ -173,6 +206,12 @@ impl Options {
             default_size: self.default_size,
             #[cfg(feature = "text")]
             fontdb: &self.fontdb,
+            #[cfg(feature = "text")]
+            font_fallback: &self.font_fallback,
+            #[cfg(feature = "text")]
+            synthetic_bold: self.synthetic_bold,
+            #[cfg(feature = "text")]
+            synthetic_oblique: self.synthetic_oblique,
             image_href_resolver: &self.image_href_resolver,
         }
     }
@@ -196,6 +235,12 @@ pub struct OptionsRef<'a> {
     pub default_size: Size,
     #[cfg(feature = "text")]
     pub fontdb: &'a fontdb::Database,
+    #[cfg(feature = "text")]
+    pub font_fallback: &'a FontFallback,
+    #[cfg(feature = "text")]
+    pub synthetic_bold: bool,
+    #[cfg(feature = "text")]
+    pub synthetic_oblique: bool,
     pub image_href_resolver: &'a ImageHrefResolver,
 }
 
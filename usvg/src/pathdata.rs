@@ -245,6 +245,23 @@ impl PathData {
         calc_bbox_with_transform(self, ts, stroke)
     }
 
+    /// Calculates path's stroke bounding box with a specified transform.
+    ///
+    /// Unlike [`bbox_with_transform`](Self::bbox_with_transform), takes the stroke outline
+    /// into account, including miter joins and square caps.
+    /// Dashes are not resolved, therefore a dashed stroke is treated as a solid one,
+    /// but with caps at every segment end.
+    ///
+    /// This operation is expensive.
+    #[inline]
+    pub fn stroke_bbox_with_transform(
+        &self,
+        ts: Transform,
+        stroke: &super::Stroke,
+    ) -> Option<PathBbox> {
+        calc_stroke_bbox_with_transform(self, ts, stroke)
+    }
+
     /// Checks that path has a bounding box.
     ///
     /// This operation is expensive.
@@ -509,6 +526,292 @@ fn calc_bbox_with_transform(
     PathBbox::new(minx, miny, width, height)
 }
 
+/// The number of samples used to offset a curve.
+const CURVE_STROKE_SAMPLES: usize = 16;
+
+fn calc_stroke_bbox_with_transform(
+    path: &PathData,
+    ts: Transform,
+    stroke: &super::Stroke,
+) -> Option<PathBbox> {
+    use super::LineCap;
+
+    let hw = stroke.width.get() / 2.0;
+    // Caps are drawn at each dash end, which we do not resolve.
+    let is_dashed = stroke.dasharray.is_some() && stroke.linecap != LineCap::Butt;
+
+    let mut bbox = StrokeBbox::new(ts, hw);
+
+    // Tangents of the first segment of the current subpath and of the previous segment.
+    let mut start = (0.0, 0.0);
+    let mut start_tangent = None;
+    let mut prev = (0.0, 0.0);
+    let mut prev_tangent = None;
+    let mut has_segments = false;
+
+    let finish_subpath = |bbox: &mut StrokeBbox,
+                          start: (f64, f64),
+                          start_tangent: Option<(f64, f64)>,
+                          end: (f64, f64),
+                          end_tangent: Option<(f64, f64)>,
+                          has_segments: bool| {
+        match (start_tangent, end_tangent) {
+            (Some(st), Some(et)) => {
+                bbox.add_cap(start, (-st.0, -st.1), stroke.linecap);
+                bbox.add_cap(end, et, stroke.linecap);
+            }
+            _ if has_segments && stroke.linecap != LineCap::Butt => {
+                // A zero-length subpath is rendered as a dot, unless caps are butt.
+                bbox.add_cap(start, (1.0, 0.0), stroke.linecap);
+                bbox.add_cap(start, (-1.0, 0.0), stroke.linecap);
+            }
+            _ => {}
+        }
+    };
+
+    for seg in path.segments() {
+        let (p, in_tangent, out_tangent) = match seg {
+            PathSegment::MoveTo { x, y } => {
+                finish_subpath(
+                    &mut bbox,
+                    start,
+                    start_tangent,
+                    prev,
+                    prev_tangent,
+                    has_segments,
+                );
+
+                start = (x, y);
+                start_tangent = None;
+                prev = (x, y);
+                prev_tangent = None;
+                has_segments = false;
+                continue;
+            }
+            PathSegment::LineTo { x, y } => {
+                let t = normalize(x - prev.0, y - prev.1);
+                if let Some(t) = t {
+                    bbox.add_offset(prev, t);
+                    bbox.add_offset((x, y), t);
+                }
+
+                ((x, y), t, t)
+            }
+            PathSegment::CurveTo {
+                x1,
+                y1,
+                x2,
+                y2,
+                x,
+                y,
+            } => {
+                use kurbo::ParamCurveDeriv;
+
+                let curve = kurbo::CubicBez::from_points(prev.0, prev.1, x1, y1, x2, y2, x, y);
+                let deriv = curve.deriv();
+                for i in 0..=CURVE_STROKE_SAMPLES {
+                    let t = i as f64 / CURVE_STROKE_SAMPLES as f64;
+                    let p = curve.eval(t);
+                    let d = deriv.eval(t);
+                    if let Some(n) = normalize(d.x, d.y) {
+                        bbox.add_offset((p.x, p.y), n);
+                    }
+                }
+
+                let in_tangent = [(x1, y1), (x2, y2), (x, y)]
+                    .iter()
+                    .find_map(|p| normalize(p.0 - prev.0, p.1 - prev.1));
+                let out_tangent = [(x2, y2), (x1, y1), prev]
+                    .iter()
+                    .find_map(|p| normalize(x - p.0, y - p.1));
+
+                ((x, y), in_tangent, out_tangent)
+            }
+            PathSegment::ClosePath => {
+                let t = normalize(start.0 - prev.0, start.1 - prev.1);
+                if let Some(t) = t {
+                    bbox.add_offset(prev, t);
+                    bbox.add_join(prev, prev_tangent, Some(t), stroke);
+                    bbox.add_join(start, Some(t), start_tangent, stroke);
+                } else {
+                    bbox.add_join(start, prev_tangent, start_tangent, stroke);
+                }
+
+                if start_tangent.is_none() && prev_tangent.is_none() {
+                    // A zero-length closed subpath.
+                    finish_subpath(&mut bbox, start, None, start, None, true);
+                }
+
+                prev = start;
+                prev_tangent = None;
+                start_tangent = None;
+                has_segments = false;
+                continue;
+            }
+        };
+
+        has_segments = true;
+
+        if let Some(t) = in_tangent {
+            if is_dashed {
+                bbox.add_cap(prev, (-t.0, -t.1), stroke.linecap);
+            }
+
+            if prev_tangent.is_some() {
+                bbox.add_join(prev, prev_tangent, Some(t), stroke);
+            } else if start_tangent.is_none() {
+                start_tangent = Some(t);
+            }
+        }
+
+        if let Some(t) = out_tangent {
+            if is_dashed {
+                bbox.add_cap(p, t, stroke.linecap);
+            }
+
+            prev_tangent = Some(t);
+        }
+
+        prev = p;
+    }
+
+    finish_subpath(
+        &mut bbox,
+        start,
+        start_tangent,
+        prev,
+        prev_tangent,
+        has_segments,
+    );
+
+    bbox.to_path_bbox()
+}
+
+fn normalize(x: f64, y: f64) -> Option<(f64, f64)> {
+    let len = (x * x + y * y).sqrt();
+    if len.is_fuzzy_zero() {
+        None
+    } else {
+        Some((x / len, y / len))
+    }
+}
+
+/// A stroke outline bbox builder.
+///
+/// Points are accumulated in user space and transformed afterwards.
+struct StrokeBbox {
+    ts: Transform,
+    hw: f64,
+    minx: f64,
+    miny: f64,
+    maxx: f64,
+    maxy: f64,
+}
+
+impl StrokeBbox {
+    fn new(ts: Transform, hw: f64) -> Self {
+        StrokeBbox {
+            ts,
+            hw,
+            minx: f64::MAX,
+            miny: f64::MAX,
+            maxx: f64::MIN,
+            maxy: f64::MIN,
+        }
+    }
+
+    fn add_point(&mut self, x: f64, y: f64) {
+        let (x, y) = self.ts.apply(x, y);
+        self.minx = self.minx.min(x);
+        self.miny = self.miny.min(y);
+        self.maxx = self.maxx.max(x);
+        self.maxy = self.maxy.max(y);
+    }
+
+    /// Adds stroke edges at the point with the provided tangent.
+    fn add_offset(&mut self, p: (f64, f64), t: (f64, f64)) {
+        let (nx, ny) = (-t.1 * self.hw, t.0 * self.hw);
+        self.add_point(p.0 + nx, p.1 + ny);
+        self.add_point(p.0 - nx, p.1 - ny);
+    }
+
+    /// Adds a circle with a stroke width diameter.
+    fn add_circle(&mut self, p: (f64, f64)) {
+        // A transformed circle is an ellipse with the following half extents.
+        let ts = &self.ts;
+        let rx = self.hw * (ts.a * ts.a + ts.c * ts.c).sqrt();
+        let ry = self.hw * (ts.b * ts.b + ts.d * ts.d).sqrt();
+        let (x, y) = ts.apply(p.0, p.1);
+        self.minx = self.minx.min(x - rx);
+        self.miny = self.miny.min(y - ry);
+        self.maxx = self.maxx.max(x + rx);
+        self.maxy = self.maxy.max(y + ry);
+    }
+
+    /// Adds a cap at the point. `t` points outside of the path.
+    fn add_cap(&mut self, p: (f64, f64), t: (f64, f64), cap: super::LineCap) {
+        match cap {
+            super::LineCap::Butt => self.add_offset(p, t),
+            super::LineCap::Round => self.add_circle(p),
+            super::LineCap::Square => {
+                self.add_offset(p, t);
+                self.add_offset((p.0 + t.0 * self.hw, p.1 + t.1 * self.hw), t);
+            }
+        }
+    }
+
+    fn add_join(
+        &mut self,
+        p: (f64, f64),
+        t1: Option<(f64, f64)>,
+        t2: Option<(f64, f64)>,
+        stroke: &super::Stroke,
+    ) {
+        let (t1, t2) = match (t1, t2) {
+            (Some(t1), Some(t2)) => (t1, t2),
+            _ => return,
+        };
+
+        self.add_offset(p, t1);
+        self.add_offset(p, t2);
+
+        match stroke.linejoin {
+            super::LineJoin::Miter => {
+                // The miter tip lies on the bisector of the outer angle.
+                let cos = t1.0 * t2.0 + t1.1 * t2.1;
+                // `1 / sin(theta / 2)`, where `theta` is the angle between segments.
+                let sin_half = ((1.0 + cos) / 2.0).sqrt();
+                if sin_half.is_fuzzy_zero() {
+                    return;
+                }
+
+                let ratio = 1.0 / sin_half;
+                if ratio <= stroke.miterlimit.get() {
+                    if let Some(dir) = normalize(t1.0 - t2.0, t1.1 - t2.1) {
+                        let len = self.hw * ratio;
+                        self.add_point(p.0 + dir.0 * len, p.1 + dir.1 * len);
+                    }
+                }
+            }
+            super::LineJoin::Round => self.add_circle(p),
+            super::LineJoin::Bevel => {}
+        }
+    }
+
+    fn to_path_bbox(&self) -> Option<PathBbox> {
+        if self.minx > self.maxx || self.miny > self.maxy {
+            return None;
+        }
+
+        PathBbox::new(
+            self.minx,
+            self.miny,
+            self.maxx - self.minx,
+            self.maxy - self.miny,
+        )
+    }
+}
+
 fn has_bbox(path: &PathData) -> bool {
     if path.is_empty() {
         return false;
@@ -25,6 +25,18 @@ pub struct Document {
     nodes: Vec<NodeData>,
     attrs: Vec<Attribute>,
     links: HashMap<String, NodeId>,
+    #[cfg(feature = "text")]
+    font_faces: Vec<FontFace>,
+}
+
+/// An unresolved `@font-face` rule.
+#[cfg(feature = "text")]
+#[derive(Clone, Default, Debug)]
+pub struct FontFace {
+    pub family: String,
+    pub weight: Option<String>,
+    pub style: Option<String>,
+    pub src: String,
 }
 
 impl Document {
@@ -52,6 +64,13 @@ impl Document {
         Some(self.get(*node_id))
     }
 
+    /// Returns `@font-face` rules from all `style` elements.
+    #[cfg(feature = "text")]
+    #[inline]
+    pub fn font_faces(&self) -> &[FontFace] {
+        &self.font_faces
+    }
+
     #[inline]
     pub fn get(&self, id: NodeId) -> Node {
         Node {
@@ -813,7 +832,9 @@ impl AId {
                 | AId::FontStretch
                 | AId::FontStyle
                 | AId::FontVariant
+                | AId::FontVariationSettings
                 | AId::FontWeight
+                | AId::GlyphOrientationVertical
                 | AId::ImageRendering
                 | AId::LetterSpacing
                 | AId::MarkerEnd
@@ -836,7 +857,9 @@ impl AId {
                 | AId::StrokeWidth
                 | AId::TextAnchor
                 | AId::TextDecoration
+                | AId::TextOrientation
                 | AId::TextRendering
+                | AId::UnicodeBidi
                 | AId::Visibility
                 | AId::WordSpacing
                 | AId::WritingMode
@@ -872,7 +895,9 @@ impl AId {
                 | AId::FontStretch
                 | AId::FontStyle
                 | AId::FontVariant
+                | AId::FontVariationSettings
                 | AId::FontWeight
+                | AId::GlyphOrientationVertical
                 | AId::ImageRendering
                 | AId::LetterSpacing
                 | AId::MarkerEnd
@@ -894,7 +919,9 @@ impl AId {
                 | AId::StrokeWidth
                 | AId::TextAnchor
                 | AId::TextDecoration
+                | AId::TextOrientation
                 | AId::TextRendering
+                | AId::UnicodeBidi
                 | AId::Visibility
                 | AId::WordSpacing
                 | AId::WritingMode
@@ -917,5 +944,6 @@ fn is_non_inheritable(id: AId) -> bool {
             | AId::StopColor
             | AId::StopOpacity
             | AId::TextDecoration
+            | AId::UnicodeBidi
     )
 }
@@ -209,6 +209,7 @@ pub enum AId {
     FontVariantLigatures,
     FontVariantNumeric,
     FontVariantPosition,
+    FontVariationSettings,
     FontWeight,
     Fx,
     Fy,
@@ -352,241 +353,242 @@ pub enum AId {
 }
 
 static ATTRIBUTES: Map<AId> = Map {
-    key: 732231254413039614,
+    key: 3213172566270843353,
     disps: &[
-        (0, 103),
-        (1, 31),
-        (0, 1),
+        (0, 158),
+        (0, 13),
+        (0, 56),
+        (0, 14),
+        (0, 0),
+        (0, 2),
+        (4, 136),
         (0, 2),
+        (0, 3),
+        (0, 3),
+        (0, 0),
+        (4, 99),
+        (6, 98),
         (0, 31),
-        (3, 20),
-        (0, 164),
-        (0, 97),
+        (3, 55),
+        (0, 3),
+        (0, 103),
+        (1, 58),
         (0, 2),
-        (0, 4),
-        (0, 12),
-        (0, 166),
-        (0, 1),
-        (4, 174),
-        (2, 158),
-        (0, 13),
-        (0, 18),
+        (2, 103),
+        (5, 52),
         (0, 0),
-        (0, 138),
-        (7, 16),
-        (1, 100),
-        (5, 19),
-        (10, 59),
-        (0, 5),
-        (0, 146),
-        (0, 44),
-        (0, 72),
-        (0, 26),
+        (6, 88),
+        (0, 110),
+        (0, 141),
+        (2, 87),
+        (5, 151),
         (0, 1),
-        (3, 147),
-        (0, 7),
-        (0, 11),
-        (0, 10),
-        (5, 91),
-        (0, 74),
-        (4, 37),
-        (0, 138),
-        (0, 3),
-        (6, 164),
+        (0, 1),
+        (0, 88),
+        (0, 0),
+        (4, 99),
+        (54, 4),
+        (0, 20),
+        (8, 117),
+        (2, 19),
+        (0, 164),
+        (0, 46),
+        (1, 33),
     ],
     entries: &[
         ("color-interpolation", AId::ColorInterpolation),
-        ("id", AId::Id),
-        ("inline-size", AId::InlineSize),
-        ("flood-opacity", AId::FloodOpacity),
-        ("kernelMatrix", AId::KernelMatrix),
-        ("font-stretch", AId::FontStretch),
-        ("mask", AId::Mask),
-        ("order", AId::Order),
-        ("kerning", AId::Kerning),
-        ("font-size-adjust", AId::FontSizeAdjust),
-        ("limitingConeAngle", AId::LimitingConeAngle),
-        ("azimuth", AId::Azimuth),
-        ("k4", AId::K4),
-        ("fill", AId::Fill),
-        ("intercept", AId::Intercept),
+        ("elevation", AId::Elevation),
+        ("stroke-linecap", AId::StrokeLinecap),
+        ("font-feature-settings", AId::FontFeatureSettings),
+        ("x2", AId::X2),
+        ("r", AId::R),
+        ("writing-mode", AId::WritingMode),
+        ("shape-subtract", AId::ShapeSubtract),
         ("text-align-last", AId::TextAlignLast),
-        ("text-decoration-fill", AId::TextDecorationFill),
-        ("textLength", AId::TextLength),
-        ("patternUnits", AId::PatternUnits),
-        ("space", AId::Space),
-        ("side", AId::Side),
-        ("line-height", AId::LineHeight),
-        ("stroke-linejoin", AId::StrokeLinejoin),
+        ("markerHeight", AId::MarkerHeight),
         ("xChannelSelector", AId::XChannelSelector),
-        ("kernelUnitLength", AId::KernelUnitLength),
-        ("k3", AId::K3),
-        ("font-feature-settings", AId::FontFeatureSettings),
-        ("fill-opacity", AId::FillOpacity),
-        ("transform-box", AId::TransformBox),
-        ("lengthAdjust", AId::LengthAdjust),
-        ("marker-end", AId::MarkerEnd),
-        ("y1", AId::Y1),
-        ("specularExponent", AId::SpecularExponent),
-        ("direction", AId::Direction),
-        ("stroke-linecap", AId::StrokeLinecap),
-        ("display", AId::Display),
-        ("text-overflow", AId::TextOverflow),
-        ("text-decoration-color", AId::TextDecorationColor),
-        ("transform-origin", AId::TransformOrigin),
-        ("lighting-color", AId::LightingColor),
-        ("systemLanguage", AId::SystemLanguage),
-        ("pointsAtZ", AId::PointsAtZ),
-        ("cy", AId::Cy),
         ("k2", AId::K2),
-        ("specularConstant", AId::SpecularConstant),
-        ("tableValues", AId::TableValues),
-        ("dominant-baseline", AId::DominantBaseline),
-        ("stroke-width", AId::StrokeWidth),
-        ("dx", AId::Dx),
-        ("font-variant-caps", AId::FontVariantCaps),
-        ("shape-subtract", AId::ShapeSubtract),
-        ("shape-image-threshold", AId::ShapeImageThreshold),
-        ("gradientTransform", AId::GradientTransform),
-        ("pointsAtX", AId::PointsAtX),
-        ("cx", AId::Cx),
-        ("filterUnits", AId::FilterUnits),
-        ("image-rendering", AId::ImageRendering),
-        ("fx", AId::Fx),
-        ("gradientUnits", AId::GradientUnits),
-        ("text-orientation", AId::TextOrientation),
-        ("exponent", AId::Exponent),
-        ("rotate", AId::Rotate),
-        ("d", AId::D),
-        ("mix-blend-mode", AId::MixBlendMode),
-        ("rx", AId::Rx),
         ("text-rendering", AId::TextRendering),
-        ("glyph-orientation-horizontal", AId::GlyphOrientationHorizontal),
-        ("enable-background", AId::EnableBackground),
-        ("scale", AId::Scale),
-        ("in", AId::In),
-        ("font-variant-numeric", AId::FontVariantNumeric),
-        ("font-variant-east-asian", AId::FontVariantEastAsian),
-        ("text-anchor", AId::TextAnchor),
-        ("glyph-orientation-vertical", AId::GlyphOrientationVertical),
-        ("dy", AId::Dy),
-        ("color-interpolation-filters", AId::ColorInterpolationFilters),
-        ("clip-rule", AId::ClipRule),
-        ("stroke-miterlimit", AId::StrokeMiterlimit),
-        ("isolation", AId::Isolation),
-        ("transform", AId::Transform),
-        ("stroke-dashoffset", AId::StrokeDashoffset),
-        ("z", AId::Z),
-        ("refX", AId::RefX),
+        ("y", AId::Y),
+        ("font-kerning", AId::FontKerning),
         ("width", AId::Width),
-        ("requiredExtensions", AId::RequiredExtensions),
-        ("font-synthesis", AId::FontSynthesis),
-        ("radius", AId::Radius),
-        ("white-space", AId::WhiteSpace),
-        ("patternTransform", AId::PatternTransform),
-        ("maskContentUnits", AId::MaskContentUnits),
-        ("stop-color", AId::StopColor),
-        ("amplitude", AId::Amplitude),
-        ("visibility", AId::Visibility),
-        ("yChannelSelector", AId::YChannelSelector),
-        ("color-rendering", AId::ColorRendering),
-        ("diffuseConstant", AId::DiffuseConstant),
-        ("stroke", AId::Stroke),
-        ("text-decoration-style", AId::TextDecorationStyle),
-        ("y2", AId::Y2),
+        ("x", AId::X),
+        ("fill", AId::Fill),
         ("operator", AId::Operator),
-        ("font-variant", AId::FontVariant),
+        ("bias", AId::Bias),
+        ("gradientTransform", AId::GradientTransform),
+        ("markerUnits", AId::MarkerUnits),
+        ("text-decoration-color", AId::TextDecorationColor),
         ("clip-path", AId::ClipPath),
-        ("edgeMode", AId::EdgeMode),
-        ("text-indent", AId::TextIndent),
-        ("numOctaves", AId::NumOctaves),
-        ("font-size", AId::FontSize),
+        ("preserveAspectRatio", AId::PreserveAspectRatio),
+        ("patternUnits", AId::PatternUnits),
+        ("ry", AId::Ry),
+        ("unicode-range", AId::UnicodeRange),
+        ("viewBox", AId::ViewBox),
+        ("scale", AId::Scale),
+        ("id", AId::Id),
+        ("font-size-adjust", AId::FontSizeAdjust),
+        ("stop-opacity", AId::StopOpacity),
+        ("shape-image-threshold", AId::ShapeImageThreshold),
+        ("dominant-baseline", AId::DominantBaseline),
+        ("shape-rendering", AId::ShapeRendering),
+        ("diffuseConstant", AId::DiffuseConstant),
+        ("clipPathUnits", AId::ClipPathUnits),
+        ("marker-mid", AId::MarkerMid),
         ("alignment-baseline", AId::AlignmentBaseline),
-        ("stroke-opacity", AId::StrokeOpacity),
-        ("class", AId::Class),
-        ("refY", AId::RefY),
-        ("stitchTiles", AId::StitchTiles),
-        ("x2", AId::X2),
-        ("unicode-bidi", AId::UnicodeBidi),
-        ("targetY", AId::TargetY),
-        ("vector-effect", AId::VectorEffect),
+        ("marker-end", AId::MarkerEnd),
+        ("text-decoration", AId::TextDecoration),
+        ("text-indent", AId::TextIndent),
+        ("fx", AId::Fx),
+        ("side", AId::Side),
+        ("pointsAtZ", AId::PointsAtZ),
+        ("orient", AId::Orient),
         ("fy", AId::Fy),
-        ("clipPathUnits", AId::ClipPathUnits),
-        ("href", AId::Href),
         ("x1", AId::X1),
-        ("result", AId::Result),
-        ("fill-rule", AId::FillRule),
-        ("color-profile", AId::ColorProfile),
+        ("font-family", AId::FontFamily),
+        ("intercept", AId::Intercept),
+        ("mode", AId::Mode),
+        ("filter", AId::Filter),
+        ("text-decoration-fill", AId::TextDecorationFill),
+        ("cx", AId::Cx),
+        ("filterUnits", AId::FilterUnits),
+        ("kerning", AId::Kerning),
+        ("word-spacing", AId::WordSpacing),
+        ("gradientUnits", AId::GradientUnits),
+        ("pointsAtX", AId::PointsAtX),
+        ("font-style", AId::FontStyle),
+        ("stroke-linejoin", AId::StrokeLinejoin),
+        ("limitingConeAngle", AId::LimitingConeAngle),
+        ("text-underline-position", AId::TextUnderlinePosition),
+        ("values", AId::Values),
+        ("stop-color", AId::StopColor),
+        ("color-rendering", AId::ColorRendering),
+        ("font-variation-settings", AId::FontVariationSettings),
+        ("enable-background", AId::EnableBackground),
+        ("mask", AId::Mask),
         ("font", AId::Font),
-        ("font-weight", AId::FontWeight),
-        ("shape-padding", AId::ShapePadding),
+        ("points", AId::Points),
+        ("rx", AId::Rx),
+        ("systemLanguage", AId::SystemLanguage),
+        ("stroke-width", AId::StrokeWidth),
+        ("seed", AId::Seed),
+        ("image-rendering", AId::ImageRendering),
+        ("font-size", AId::FontSize),
+        ("markerWidth", AId::MarkerWidth),
+        ("offset", AId::Offset),
+        ("k1", AId::K1),
         ("primitiveUnits", AId::PrimitiveUnits),
+        ("font-variant-ligatures", AId::FontVariantLigatures),
+        ("clip-rule", AId::ClipRule),
+        ("stroke-miterlimit", AId::StrokeMiterlimit),
+        ("type", AId::Type),
+        ("font-weight", AId::FontWeight),
         ("height", AId::Height),
-        ("bias", AId::Bias),
+        ("shape-margin", AId::ShapeMargin),
+        ("unicode-bidi", AId::UnicodeBidi),
+        ("refY", AId::RefY),
+        ("in2", AId::In2),
+        ("text-align", AId::TextAlign),
+        ("flood-opacity", AId::FloodOpacity),
+        ("targetX", AId::TargetX),
+        ("divisor", AId::Divisor),
+        ("font-variant-numeric", AId::FontVariantNumeric),
+        ("shape-inside", AId::ShapeInside),
+        ("text-orientation", AId::TextOrientation),
+        ("dx", AId::Dx),
+        ("color-profile", AId::ColorProfile),
+        ("text-anchor", AId::TextAnchor),
+        ("azimuth", AId::Azimuth),
+        ("surfaceScale", AId::SurfaceScale),
+        ("text-overflow", AId::TextOverflow),
+        ("path", AId::Path),
         ("maskUnits", AId::MaskUnits),
+        ("kernelUnitLength", AId::KernelUnitLength),
+        ("yChannelSelector", AId::YChannelSelector),
+        ("mix-blend-mode", AId::MixBlendMode),
+        ("text-decoration-style", AId::TextDecorationStyle),
         ("pointsAtY", AId::PointsAtY),
-        ("paint-order", AId::PaintOrder),
-        ("text-decoration-line", AId::TextDecorationLine),
+        ("y2", AId::Y2),
+        ("font-variant-caps", AId::FontVariantCaps),
         ("preserveAlpha", AId::PreserveAlpha),
-        ("shape-rendering", AId::ShapeRendering),
-        ("text-underline-position", AId::TextUnderlinePosition),
+        ("maskContentUnits", AId::MaskContentUnits),
+        ("fill-opacity", AId::FillOpacity),
         ("stroke-dasharray", AId::StrokeDasharray),
-        ("ry", AId::Ry),
-        ("preserveAspectRatio", AId::PreserveAspectRatio),
-        ("markerWidth", AId::MarkerWidth),
-        ("letter-spacing", AId::LetterSpacing),
+        ("font-variant", AId::FontVariant),
+        ("lighting-color", AId::LightingColor),
+        ("stroke-opacity", AId::StrokeOpacity),
+        ("shape-padding", AId::ShapePadding),
+        ("stitchTiles", AId::StitchTiles),
+        ("patternContentUnits", AId::PatternContentUnits),
+        ("requiredFeatures", AId::RequiredFeatures),
+        ("numOctaves", AId::NumOctaves),
+        ("cy", AId::Cy),
         ("font-variant-position", AId::FontVariantPosition),
-        ("y", AId::Y),
+        ("stroke-dashoffset", AId::StrokeDashoffset),
+        ("spreadMethod", AId::SpreadMethod),
+        ("stroke", AId::Stroke),
+        ("clip", AId::Clip),
+        ("text-decoration-stroke", AId::TextDecorationStroke),
+        ("exponent", AId::Exponent),
+        ("visibility", AId::Visibility),
+        ("edgeMode", AId::EdgeMode),
+        ("order", AId::Order),
+        ("direction", AId::Direction),
+        ("transform-box", AId::TransformBox),
+        ("text-decoration-line", AId::TextDecorationLine),
+        ("patternTransform", AId::PatternTransform),
+        ("requiredExtensions", AId::RequiredExtensions),
+        ("refX", AId::RefX),
+        ("d", AId::D),
+        ("baseline-shift", AId::BaselineShift),
+        ("textLength", AId::TextLength),
+        ("radius", AId::Radius),
+        ("specularConstant", AId::SpecularConstant),
+        ("color-interpolation-filters", AId::ColorInterpolationFilters),
+        ("font-synthesis", AId::FontSynthesis),
+        ("transform-origin", AId::TransformOrigin),
+        ("amplitude", AId::Amplitude),
+        ("letter-spacing", AId::LetterSpacing),
+        ("pathLength", AId::PathLength),
+        ("slope", AId::Slope),
+        ("fill-rule", AId::FillRule),
+        ("specularExponent", AId::SpecularExponent),
+        ("baseFrequency", AId::BaseFrequency),
+        ("paint-order", AId::PaintOrder),
+        ("display", AId::Display),
+        ("z", AId::Z),
+        ("in", AId::In),
+        ("dy", AId::Dy),
+        ("transform", AId::Transform),
+        ("kernelMatrix", AId::KernelMatrix),
+        ("space", AId::Space),
+        ("lengthAdjust", AId::LengthAdjust),
+        ("glyph-orientation-vertical", AId::GlyphOrientationVertical),
+        ("class", AId::Class),
+        ("y1", AId::Y1),
+        ("white-space", AId::WhiteSpace),
+        ("inline-size", AId::InlineSize),
         ("style", AId::Style),
-        ("requiredFeatures", AId::RequiredFeatures),
-        ("text-align", AId::TextAlign),
-        ("divisor", AId::Divisor),
-        ("offset", AId::Offset),
+        ("line-height", AId::LineHeight),
+        ("font-variant-east-asian", AId::FontVariantEastAsian),
+        ("tableValues", AId::TableValues),
+        ("isolation", AId::Isolation),
+        ("k3", AId::K3),
         ("overflow", AId::Overflow),
-        ("font-kerning", AId::FontKerning),
-        ("slope", AId::Slope),
-        ("marker-mid", AId::MarkerMid),
-        ("mode", AId::Mode),
-        ("x", AId::X),
-        ("elevation", AId::Elevation),
-        ("k1", AId::K1),
+        ("targetY", AId::TargetY),
+        ("font-stretch", AId::FontStretch),
         ("startOffset", AId::StartOffset),
-        ("clip", AId::Clip),
-        ("surfaceScale", AId::SurfaceScale),
-        ("points", AId::Points),
-        ("targetX", AId::TargetX),
-        ("text-decoration", AId::TextDecoration),
-        ("font-style", AId::FontStyle),
-        ("seed", AId::Seed),
-        ("stop-opacity", AId::StopOpacity),
-        ("values", AId::Values),
-        ("stdDeviation", AId::StdDeviation),
-        ("spreadMethod", AId::SpreadMethod),
-        ("flood-color", AId::FloodColor),
         ("color", AId::Color),
-        ("shape-inside", AId::ShapeInside),
-        ("marker-start", AId::MarkerStart),
-        ("text-decoration-stroke", AId::TextDecorationStroke),
-        ("patternContentUnits", AId::PatternContentUnits),
-        ("word-spacing", AId::WordSpacing),
-        ("orient", AId::Orient),
-        ("in2", AId::In2),
-        ("unicode-range", AId::UnicodeRange),
+        ("href", AId::Href),
+        ("flood-color", AId::FloodColor),
+        ("result", AId::Result),
+        ("stdDeviation", AId::StdDeviation),
         ("opacity", AId::Opacity),
-        ("baseFrequency", AId::BaseFrequency),
-        ("path", AId::Path),
-        ("markerHeight", AId::MarkerHeight),
-        ("pathLength", AId::PathLength),
-        ("r", AId::R),
-        ("writing-mode", AId::WritingMode),
-        ("shape-margin", AId::ShapeMargin),
-        ("viewBox", AId::ViewBox),
-        ("font-family", AId::FontFamily),
-        ("filter", AId::Filter),
-        ("markerUnits", AId::MarkerUnits),
-        ("type", AId::Type),
-        ("baseline-shift", AId::BaselineShift),
-        ("font-variant-ligatures", AId::FontVariantLigatures),
+        ("k4", AId::K4),
+        ("vector-effect", AId::VectorEffect),
+        ("glyph-orientation-horizontal", AId::GlyphOrientationHorizontal),
+        ("rotate", AId::Rotate),
+        ("marker-start", AId::MarkerStart),
     ],
 };
 
@@ -2,10 +2,13 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::rc::Rc;
 use std::str::FromStr;
 
+#[cfg(feature = "text")]
+use super::Range;
 use super::{AId, Attribute, AttributeValue, Document, EId, Node, NodeData, NodeId, NodeKind};
 use crate::{EnableBackground, Error, Opacity, Rect};
 
@@ -64,6 +67,8 @@ fn parse(xml: &roxmltree::Document) -> Result<Document, Error> {
         nodes: Vec::new(),
         attrs: Vec::new(),
         links: HashMap::new(),
+        #[cfg(feature = "text")]
+        font_faces: Vec::new(),
     };
 
     // Add a root node.
@@ -74,7 +79,14 @@ fn parse(xml: &roxmltree::Document) -> Result<Document, Error> {
         kind: NodeKind::Root,
     });
 
-    let style_sheet = resolve_css(xml);
+    let styles = collect_styles(xml);
+    #[cfg(feature = "text")]
+    let styles = {
+        let (styles, font_faces) = extract_font_faces(styles);
+        doc.font_faces = font_faces;
+        styles
+    };
+    let style_sheet = resolve_css(&styles);
 
     parse_xml_node_children(
         xml.root(),
@@ -468,7 +480,7 @@ fn parse_svg_attribute(tag_name: EId, aid: AId, value: &str) -> Option<Attribute
             _ => AttributeValue::Color(svgtypes::Color::from_str(value).ok()?),
         },
 
-        AId::D => {
+        AId::D | AId::Path => {
             let segments = parse_path(value);
             if segments.len() >= 2 {
                 AttributeValue::Path(Rc::new(segments))
@@ -849,7 +861,10 @@ fn resolve_inherit(parent_id: NodeId, tag_name: EId, aid: AId, doc: &mut Documen
 
     // Fallback to a default value if possible.
     let value = match aid {
-        AId::ImageRendering | AId::ShapeRendering | AId::TextRendering => "auto",
+        AId::GlyphOrientationVertical
+        | AId::ImageRendering
+        | AId::ShapeRendering
+        | AId::TextRendering => "auto",
 
         AId::ClipPath
         | AId::Filter
@@ -864,8 +879,10 @@ fn resolve_inherit(parent_id: NodeId, tag_name: EId, aid: AId, doc: &mut Documen
         AId::FontStretch
         | AId::FontStyle
         | AId::FontVariant
+        | AId::FontVariationSettings
         | AId::FontWeight
         | AId::LetterSpacing
+        | AId::UnicodeBidi
         | AId::WordSpacing => "normal",
 
         AId::Fill | AId::FloodColor | AId::StopColor => "black",
@@ -890,6 +907,7 @@ fn resolve_inherit(parent_id: NodeId, tag_name: EId, aid: AId, doc: &mut Documen
         AId::StrokeMiterlimit => "4",
         AId::StrokeWidth => "1",
         AId::TextAnchor => "start",
+        AId::TextOrientation => "mixed",
         AId::Visibility => "visible",
         AId::WritingMode => "lr-tb",
         _ => return false,
@@ -986,8 +1004,8 @@ fn parse_svg_use_element(
     parse_xml_node(link, node, parent_id, style_sheet, true, depth + 1, doc)
 }
 
-fn resolve_css<'a>(xml: &'a roxmltree::Document<'a>) -> simplecss::StyleSheet<'a> {
-    let mut sheet = simplecss::StyleSheet::new();
+fn collect_styles<'a>(xml: &'a roxmltree::Document) -> Vec<Cow<'a, str>> {
+    let mut styles = Vec::new();
 
     for node in xml.descendants().filter(|n| n.has_tag_name("style")) {
         match node.attribute("type") {
@@ -996,17 +1014,173 @@ fn resolve_css<'a>(xml: &'a roxmltree::Document<'a>) -> simplecss::StyleSheet<'a
             None => {}
         }
 
-        let text = match node.text() {
-            Some(v) => v,
-            None => continue,
-        };
+        if let Some(text) = node.text() {
+            styles.push(Cow::Borrowed(text));
+        }
+    }
 
+    styles
+}
+
+fn resolve_css<'a>(styles: &'a [Cow<str>]) -> simplecss::StyleSheet<'a> {
+    let mut sheet = simplecss::StyleSheet::new();
+
+    for text in styles {
         sheet.parse_more(text);
     }
 
     sheet
 }
 
+/// Removes `@font-face` rules from style sheets and returns them.
+#[cfg(feature = "text")]
+fn extract_font_faces(mut styles: Vec<Cow<str>>) -> (Vec<Cow<str>>, Vec<super::FontFace>) {
+    let mut faces = Vec::new();
+
+    for text in &mut styles {
+        let rules = parse_font_faces(text, &mut faces);
+        if rules.is_empty() {
+            continue;
+        }
+
+        // `simplecss` doesn't support at-rules and would warn about them.
+        let mut stripped = String::with_capacity(text.len());
+        let mut pos = 0;
+        for rule in rules {
+            stripped.push_str(&text[pos..rule.start]);
+            pos = rule.end;
+        }
+        stripped.push_str(&text[pos..]);
+
+        *text = Cow::Owned(stripped);
+    }
+
+    (styles, faces)
+}
+
+/// Collects `@font-face` rules from a style sheet.
+///
+/// `simplecss` skips at-rules, so we have to look for them manually.
+///
+/// Returns rules' ranges.
+#[cfg(feature = "text")]
+fn parse_font_faces(text: &str, faces: &mut Vec<super::FontFace>) -> Vec<Range> {
+    let mut rules = Vec::new();
+    let bytes = text.as_bytes();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'/' if bytes.get(pos + 1) == Some(&b'*') => pos = skip_css_comment(text, pos),
+            b'"' | b'\'' => pos = skip_css_string(bytes, pos),
+            b'@' => {
+                let name_start = pos + 1;
+                let name_len = bytes[name_start..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_alphanumeric() || **c == b'-')
+                    .count();
+                pos = name_start + name_len;
+
+                if !text[name_start..pos].eq_ignore_ascii_case("font-face") {
+                    continue;
+                }
+
+                let block_start = match text[pos..].find('{') {
+                    Some(idx) => pos + idx + 1,
+                    None => break,
+                };
+                let block_end = find_css_block_end(text, block_start);
+
+                if let Some(face) = parse_font_face(&text[block_start..block_end]) {
+                    faces.push(face);
+                }
+
+                pos = (block_end + 1).min(text.len());
+                rules.push(name_start - 1..pos);
+            }
+            _ => pos += 1,
+        }
+    }
+
+    rules
+}
+
+#[cfg(feature = "text")]
+fn parse_font_face(text: &str) -> Option<super::FontFace> {
+    let mut face = super::FontFace::default();
+    for declaration in simplecss::DeclarationTokenizer::from(text) {
+        match declaration.name {
+            "font-family" => {
+                face.family = declaration
+                    .value
+                    .trim_matches(|c| c == '\'' || c == '"')
+                    .to_string();
+            }
+            "font-weight" => face.weight = Some(declaration.value.to_string()),
+            "font-style" => face.style = Some(declaration.value.to_string()),
+            "src" => face.src = declaration.value.to_string(),
+            _ => {}
+        }
+    }
+
+    if face.family.is_empty() || face.src.is_empty() {
+        log::warn!("@font-face rule must have 'font-family' and 'src'. Skipped.");
+        return None;
+    }
+
+    Some(face)
+}
+
+/// Returns a position of the `}` that closes a block, or the text length.
+#[cfg(feature = "text")]
+fn find_css_block_end(text: &str, mut pos: usize) -> usize {
+    let bytes = text.as_bytes();
+    let mut depth = 1;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'/' if bytes.get(pos + 1) == Some(&b'*') => pos = skip_css_comment(text, pos),
+            b'"' | b'\'' => pos = skip_css_string(bytes, pos),
+            b'{' => {
+                depth += 1;
+                pos += 1;
+            }
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return pos;
+                }
+
+                pos += 1;
+            }
+            _ => pos += 1,
+        }
+    }
+
+    bytes.len()
+}
+
+#[cfg(feature = "text")]
+fn skip_css_comment(text: &str, pos: usize) -> usize {
+    match text[pos + 2..].find("*/") {
+        Some(idx) => pos + 2 + idx + 2,
+        None => text.len(),
+    }
+}
+
+#[cfg(feature = "text")]
+fn skip_css_string(bytes: &[u8], pos: usize) -> usize {
+    let quote = bytes[pos];
+    let mut pos = pos + 1;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\\' => pos += 2,
+            c if c == quote => return pos + 1,
+            _ => pos += 1,
+        }
+    }
+
+    bytes.len()
+}
+
 struct XmlNode<'a, 'input: 'a>(roxmltree::Node<'a, 'input>);
 
 impl simplecss::Element for XmlNode<'_, '_> {
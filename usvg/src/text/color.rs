@@ -0,0 +1,834 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Color glyphs loading.
+//!
+//! Supports `COLR` (v0 and v1) with `CPAL`, `CBDT` and `sbix` PNG bitmaps
+//! and `SVG` glyph documents.
+
+use std::rc::Rc;
+use std::sync::Arc;
+
+use rustybuzz::{ttf_parser, Variation};
+use ttf_parser::{GlyphId, Tag};
+
+use super::fontdb_ext;
+use super::stream::Stream;
+use crate::{
+    Align, AspectRatio, BaseGradient, Color, Fill, FillRule, Group, Image, ImageKind,
+    LinearGradient, Node, NodeExt, NodeKind, Opacity, OptionsRef, Paint, Path, PathData,
+    PositiveF64, RadialGradient, Rect, SpreadMethod, Stop, StopOffset, Transform, Tree, Units,
+    ViewBox, Visibility,
+};
+
+/// A nesting limit for `COLR` v1 paints, which prevents infinite recursion on malformed fonts.
+const MAX_PAINT_DEPTH: u8 = 64;
+
+/// A limit of `COLR` v1 paints per glyph.
+///
+/// Paints can be shared, so a small font can describe an exponential number of layers.
+const MAX_PAINTS: u32 = 4096;
+
+/// A palette index that refers to the text foreground color.
+const FOREGROUND_INDEX: u16 = 0xFFFF;
+
+/// A color glyph, positioned in a cluster.
+#[derive(Clone)]
+pub struct ColorGlyph {
+    /// Maps the glyph coordinates into the cluster ones.
+    pub transform: Transform,
+    pub kind: ColorGlyphKind,
+}
+
+#[derive(Clone)]
+pub enum ColorGlyphKind {
+    /// `COLR` layers in font units.
+    ///
+    /// Unlike other kinds, uses the font coordinate system, where the Y axis points up.
+    Layers(Vec<ColorLayer>),
+    /// A bitmap glyph or an SVG glyph document in font units.
+    Image(Image),
+}
+
+#[derive(Clone)]
+pub struct ColorLayer {
+    pub path: PathData,
+    pub paint: LayerPaint,
+}
+
+#[derive(Clone)]
+pub enum LayerPaint {
+    /// The text fill with an additional opacity.
+    Foreground(Opacity),
+    Paint(Paint, Opacity),
+}
+
+/// Loads a color glyph.
+///
+/// The order matches the one used by browsers: SVG, then `COLR` and then bitmaps.
+pub fn load(
+    opt: &OptionsRef,
+    font_id: fontdb::ID,
+    glyph_id: GlyphId,
+    variations: &[Variation],
+) -> Option<ColorGlyphKind> {
+    opt.fontdb
+        .with_face_data(font_id, |data, face_index| -> Option<ColorGlyphKind> {
+            let mut face = ttf_parser::Face::parse(data, face_index).ok()?;
+            fontdb_ext::set_variations(&mut face, variations);
+            let units_per_em = f64::from(face.units_per_em());
+
+            if let Some(doc) = face.glyph_svg_image(glyph_id) {
+                if let Some(image) = load_svg(doc, glyph_id, units_per_em, opt) {
+                    return Some(ColorGlyphKind::Image(image));
+                }
+            }
+
+            if let Some(layers) = load_colr(&face, glyph_id) {
+                return Some(ColorGlyphKind::Layers(layers));
+            }
+
+            load_bitmap(&face, glyph_id, units_per_em, opt).map(ColorGlyphKind::Image)
+        })?
+}
+
+/// Converts a color glyph into a group.
+///
+/// `transform` maps the glyph coordinates into the text ones.
+/// `fill` is used for layers that reference the foreground color.
+pub fn convert(
+    glyph: &ColorGlyph,
+    transform: Transform,
+    fill: Option<&Fill>,
+    visibility: Visibility,
+) -> Node {
+    let node = Node::new(NodeKind::Group(Group::default()));
+
+    match glyph.kind {
+        ColorGlyphKind::Layers(ref layers) => {
+            // Layers are transformed directly, so the text fill would use the text coordinates.
+            for layer in layers {
+                let fill = match layer.paint {
+                    LayerPaint::Foreground(opacity) => match fill {
+                        Some(fill) => Fill {
+                            paint: fill.paint.clone(),
+                            opacity: fill.opacity * opacity,
+                            rule: FillRule::NonZero,
+                        },
+                        None => continue,
+                    },
+                    LayerPaint::Paint(ref paint, opacity) => Fill {
+                        paint: transform_paint(paint, transform),
+                        opacity,
+                        rule: FillRule::NonZero,
+                    },
+                };
+
+                let mut path = layer.path.clone();
+                path.transform(transform);
+
+                node.append(Node::new(NodeKind::Path(Path {
+                    visibility,
+                    fill: Some(fill),
+                    data: Rc::new(path),
+                    ..Path::default()
+                })));
+            }
+        }
+        ColorGlyphKind::Image(ref image) => {
+            node.append(Node::new(NodeKind::Image(Image {
+                transform,
+                visibility,
+                ..image.clone()
+            })));
+        }
+    }
+
+    node
+}
+
+fn transform_paint(paint: &Paint, ts: Transform) -> Paint {
+    match paint {
+        Paint::LinearGradient(ref lg) => {
+            let mut lg = LinearGradient::clone(lg);
+            lg.base.transform.prepend(&ts);
+            Paint::LinearGradient(Rc::new(lg))
+        }
+        Paint::RadialGradient(ref rg) => {
+            let mut rg = RadialGradient::clone(rg);
+            rg.base.transform.prepend(&ts);
+            Paint::RadialGradient(Rc::new(rg))
+        }
+        _ => paint.clone(),
+    }
+}
+
+fn load_svg(data: &[u8], glyph_id: GlyphId, units_per_em: f64, opt: &OptionsRef) -> Option<Image> {
+    // Text inside glyph documents is not supported, which also prevents infinite recursion.
+    let fontdb = fontdb::Database::new();
+
+    let mut sub_opt = opt.clone();
+    sub_opt.resources_dir = None;
+    sub_opt.keep_named_groups = true;
+    sub_opt.fontdb = &fontdb;
+
+    let mut tree = match Tree::from_data(data, &sub_opt) {
+        Ok(tree) => tree,
+        Err(_) => {
+            log::warn!("Failed to load an SVG glyph.");
+            return None;
+        }
+    };
+
+    // A single document can contain multiple glyphs.
+    if let Some(node) = tree.node_by_id(&format!("glyph{}", glyph_id.0)) {
+        let transform = node
+            .parent()
+            .map(|parent| parent.abs_transform())
+            .unwrap_or_default();
+
+        node.detach();
+        let children: Vec<_> = tree.root.children().collect();
+        for child in children {
+            child.detach();
+        }
+
+        let group = tree.root.append_kind(NodeKind::Group(Group {
+            transform,
+            ..Group::default()
+        }));
+        group.append(node);
+    }
+
+    // Glyph documents use font units and have an origin at the baseline,
+    // so the document size and viewBox are ignored.
+    // Instead, we are using a region large enough to fit any glyph.
+    let rect = Rect::new(
+        -units_per_em,
+        -units_per_em * 2.0,
+        units_per_em * 3.0,
+        units_per_em * 3.0,
+    )?;
+    let view_box = ViewBox {
+        rect,
+        aspect: AspectRatio::default(),
+    };
+    tree.size = rect.size();
+    tree.view_box = view_box;
+
+    Some(Image {
+        id: String::new(),
+        transform: Transform::default(),
+        visibility: Visibility::Visible,
+        view_box,
+        rendering_mode: opt.image_rendering,
+        kind: ImageKind::SVG(tree),
+    })
+}
+
+fn load_bitmap(
+    face: &ttf_parser::Face,
+    glyph_id: GlyphId,
+    units_per_em: f64,
+    opt: &OptionsRef,
+) -> Option<Image> {
+    // Use the largest strike, since we don't know the final scale.
+    let image = face.glyph_raster_image(glyph_id, u16::MAX)?;
+    if image.pixels_per_em == 0 {
+        return None;
+    }
+
+    let kind = match image.format {
+        ttf_parser::RasterImageFormat::PNG => ImageKind::PNG(Arc::new(image.data.to_vec())),
+    };
+
+    // Bitmap metrics are in pixels and `y` is the bottom edge.
+    let scale = units_per_em / f64::from(image.pixels_per_em);
+    let rect = Rect::new(
+        f64::from(image.x) * scale,
+        -(f64::from(image.y) + f64::from(image.height)) * scale,
+        f64::from(image.width) * scale,
+        f64::from(image.height) * scale,
+    )?;
+
+    Some(Image {
+        id: String::new(),
+        transform: Transform::default(),
+        visibility: Visibility::Visible,
+        view_box: ViewBox {
+            rect,
+            aspect: AspectRatio {
+                align: Align::None,
+                ..AspectRatio::default()
+            },
+        },
+        rendering_mode: opt.image_rendering,
+        kind,
+    })
+}
+
+fn load_colr(face: &ttf_parser::Face, glyph_id: GlyphId) -> Option<Vec<ColorLayer>> {
+    let colr = Colr::parse(face.raw_face().table(Tag::from_bytes(b"COLR"))?)?;
+    let palette = face
+        .raw_face()
+        .table(Tag::from_bytes(b"CPAL"))
+        .and_then(Palette::parse);
+
+    let mut builder = LayersBuilder {
+        face,
+        colr: &colr,
+        palette,
+        layers: Vec::new(),
+        paints: 0,
+        active_paints: Vec::new(),
+        active_glyphs: vec![glyph_id],
+        is_aborted: false,
+    };
+
+    if let Some(offset) = colr.base_glyph_paint(glyph_id) {
+        builder.paint(offset, Transform::default(), None, 0);
+        if builder.is_aborted {
+            // Fallback to the glyph outline.
+            return None;
+        }
+    } else {
+        let (first, count) = colr.base_glyph_layers(glyph_id)?;
+        for i in first..first.checked_add(count)? {
+            let (layer_glyph_id, palette_index) = colr.layer_record(i)?;
+            if let Some(path) = fontdb_ext::outline_glyph(face, layer_glyph_id) {
+                let paint = builder.solid(palette_index, 1.0);
+                builder.layers.push(ColorLayer { path, paint });
+            }
+        }
+    }
+
+    if builder.layers.is_empty() {
+        None
+    } else {
+        Some(builder.layers)
+    }
+}
+
+/// A `COLR` table.
+struct Colr<'a> {
+    data: &'a [u8],
+    base_glyph_records: usize,
+    num_base_glyph_records: u16,
+    layer_records: usize,
+    num_layer_records: u16,
+    /// `COLR` v1 base glyph paints.
+    base_glyph_list: Option<usize>,
+    /// `COLR` v1 layers.
+    layer_list: Option<usize>,
+}
+
+impl<'a> Colr<'a> {
+    fn parse(data: &'a [u8]) -> Option<Self> {
+        let mut s = Stream::new(data);
+        let version = s.read_u16()?;
+        let num_base_glyph_records = s.read_u16()?;
+        let base_glyph_records = s.read_u32()? as usize;
+        let layer_records = s.read_u32()? as usize;
+        let num_layer_records = s.read_u16()?;
+
+        let mut base_glyph_list = None;
+        let mut layer_list = None;
+        if version >= 1 {
+            let offset = |n: u32| if n != 0 { Some(n as usize) } else { None };
+            base_glyph_list = offset(s.read_u32()?);
+            layer_list = offset(s.read_u32()?);
+        }
+
+        Some(Colr {
+            data,
+            base_glyph_records,
+            num_base_glyph_records,
+            layer_records,
+            num_layer_records,
+            base_glyph_list,
+            layer_list,
+        })
+    }
+
+    /// Returns the first layer index and the number of layers.
+    fn base_glyph_layers(&self, glyph_id: GlyphId) -> Option<(u16, u16)> {
+        // Records are sorted by a glyph ID.
+        let record = self.binary_search(
+            self.base_glyph_records,
+            u32::from(self.num_base_glyph_records),
+            6,
+            glyph_id,
+        )?;
+        let mut s = Stream::new_at(self.data, record + 2)?;
+        Some((s.read_u16()?, s.read_u16()?))
+    }
+
+    /// Returns a glyph ID and a palette index.
+    fn layer_record(&self, index: u16) -> Option<(GlyphId, u16)> {
+        if index >= self.num_layer_records {
+            return None;
+        }
+
+        let mut s = Stream::new_at(self.data, self.layer_records + usize::from(index) * 4)?;
+        Some((GlyphId(s.read_u16()?), s.read_u16()?))
+    }
+
+    /// Returns a `COLR` v1 paint offset.
+    fn base_glyph_paint(&self, glyph_id: GlyphId) -> Option<usize> {
+        let list = self.base_glyph_list?;
+        let count = Stream::new_at(self.data, list)?.read_u32()?;
+        let record = self.binary_search(list + 4, count, 6, glyph_id)?;
+        let offset = Stream::new_at(self.data, record + 2)?.read_u32()?;
+        list.checked_add(offset as usize)
+    }
+
+    /// Returns a `COLR` v1 layer paint offset.
+    fn layer_paint(&self, index: u32) -> Option<usize> {
+        let list = self.layer_list?;
+        let mut s = Stream::new_at(self.data, list)?;
+        if index >= s.read_u32()? {
+            return None;
+        }
+
+        let offset = Stream::new_at(self.data, list + 4 + index as usize * 4)?.read_u32()?;
+        list.checked_add(offset as usize)
+    }
+
+    /// Finds a record that starts with a glyph ID.
+    fn binary_search(
+        &self,
+        start: usize,
+        count: u32,
+        record_size: usize,
+        glyph_id: GlyphId,
+    ) -> Option<usize> {
+        let (mut lo, mut hi) = (0usize, count as usize);
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            let offset = start.checked_add(mid * record_size)?;
+            let id = Stream::new_at(self.data, offset)?.read_u16()?;
+            match id.cmp(&glyph_id.0) {
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+                std::cmp::Ordering::Equal => return Some(offset),
+            }
+        }
+
+        None
+    }
+}
+
+/// The first `CPAL` palette.
+struct Palette<'a> {
+    colors: &'a [u8],
+}
+
+impl<'a> Palette<'a> {
+    fn parse(data: &'a [u8]) -> Option<Self> {
+        let mut s = Stream::new(data);
+        s.skip(2)?; // version
+        let num_entries = s.read_u16()?;
+        s.skip(4)?; // number of palettes and color records
+        let records = s.read_u32()? as usize;
+        let first_index = s.read_u16()?;
+
+        let start = records.checked_add(usize::from(first_index) * 4)?;
+        let colors = data.get(start..start.checked_add(usize::from(num_entries) * 4)?)?;
+        Some(Palette { colors })
+    }
+
+    /// Returns a color and its alpha.
+    fn get(&self, index: u16) -> Option<(Color, f64)> {
+        let start = usize::from(index) * 4;
+        let bgra = self.colors.get(start..start + 4)?;
+        Some((
+            Color::new_rgb(bgra[2], bgra[1], bgra[0]),
+            f64::from(bgra[3]) / 255.0,
+        ))
+    }
+}
+
+/// Flattens `COLR` v1 paints into a list of filled layers.
+///
+/// Some features are approximated: nested glyph clips use the innermost glyph,
+/// composite modes use the source-over mode, sweep gradients use the first stop color
+/// and radial gradients ignore the start circle radius.
+struct LayersBuilder<'a, 'b> {
+    face: &'b ttf_parser::Face<'a>,
+    colr: &'b Colr<'a>,
+    palette: Option<Palette<'a>>,
+    layers: Vec<ColorLayer>,
+    /// The number of processed paints.
+    paints: u32,
+    /// Paints and base glyphs that are being processed. Used to detect cycles.
+    active_paints: Vec<usize>,
+    active_glyphs: Vec<GlyphId>,
+    /// Set when the glyph has a cycle or too many paints.
+    is_aborted: bool,
+}
+
+impl LayersBuilder<'_, '_> {
+    fn solid(&self, palette_index: u16, alpha: f64) -> LayerPaint {
+        let alpha = Opacity::new_clamped(alpha);
+        if palette_index == FOREGROUND_INDEX {
+            return LayerPaint::Foreground(alpha);
+        }
+
+        match self.palette.as_ref().and_then(|p| p.get(palette_index)) {
+            Some((color, color_alpha)) => LayerPaint::Paint(
+                Paint::Color(color),
+                Opacity::new_clamped(color_alpha) * alpha,
+            ),
+            None => LayerPaint::Foreground(alpha),
+        }
+    }
+
+    fn paint(&mut self, offset: usize, ts: Transform, clip: Option<&PathData>, depth: u8) {
+        if depth == MAX_PAINT_DEPTH || self.is_aborted {
+            return;
+        }
+
+        self.paints += 1;
+        if self.paints > MAX_PAINTS {
+            log::warn!("A COLR glyph has too many paints. Fallback to an outline.");
+            self.is_aborted = true;
+            return;
+        }
+
+        if self.active_paints.contains(&offset) {
+            log::warn!("A COLR glyph has a paints cycle. Fallback to an outline.");
+            self.is_aborted = true;
+            return;
+        }
+
+        self.active_paints.push(offset);
+        if self.paint_impl(offset, ts, clip, depth).is_none() {
+            log::warn!("Failed to parse a COLR paint.");
+        }
+        self.active_paints.pop();
+    }
+
+    fn paint_impl(
+        &mut self,
+        offset: usize,
+        ts: Transform,
+        clip: Option<&PathData>,
+        depth: u8,
+    ) -> Option<()> {
+        let data = self.colr.data;
+        let mut s = Stream::new_at(data, offset)?;
+        let format = s.read_u8()?;
+        // Variable paints have the same layout as the regular ones, followed by
+        // a variation index, which we ignore.
+        let is_var = matches!(format, 3..=9 | 13..=31) && format % 2 == 1;
+        let format = if is_var { format - 1 } else { format };
+
+        let child = |s: &mut Stream| -> Option<usize> { Some(offset + s.read_u24()? as usize) };
+        let depth = depth + 1;
+
+        match format {
+            1 => {
+                // PaintColrLayers
+                let count = u32::from(s.read_u8()?);
+                let first = s.read_u32()?;
+                for i in first..first.checked_add(count)? {
+                    let layer = self.colr.layer_paint(i)?;
+                    self.paint(layer, ts, clip, depth);
+                }
+            }
+            2 => {
+                // PaintSolid
+                let palette_index = s.read_u16()?;
+                let alpha = read_f2dot14(&mut s)?;
+                let paint = self.solid(palette_index, alpha);
+                self.push(clip, paint);
+            }
+            4 | 6 | 8 => {
+                let color_line = child(&mut s)?;
+                let paint = if format == 4 {
+                    // PaintLinearGradient
+                    let mut p = [0.0; 6];
+                    for n in p.iter_mut() {
+                        *n = f64::from(s.read_i16()?);
+                    }
+                    self.linear_gradient(color_line, is_var, &p, ts)?
+                } else if format == 6 {
+                    // PaintRadialGradient
+                    let x0 = f64::from(s.read_i16()?);
+                    let y0 = f64::from(s.read_i16()?);
+                    s.skip(2)?; // r0
+                    let x1 = f64::from(s.read_i16()?);
+                    let y1 = f64::from(s.read_i16()?);
+                    let r1 = f64::from(s.read_u16()?);
+                    self.radial_gradient(color_line, is_var, (x0, y0), (x1, y1, r1), ts)?
+                } else {
+                    // PaintSweepGradient
+                    let stops = self.color_line(color_line, is_var)?.1;
+                    let stop = stops.first()?;
+                    LayerPaint::Paint(Paint::Color(stop.color), stop.opacity)
+                };
+                self.push(clip, paint);
+            }
+            10 => {
+                // PaintGlyph
+                let paint = child(&mut s)?;
+                let glyph_id = GlyphId(s.read_u16()?);
+                let mut path = fontdb_ext::outline_glyph(self.face, glyph_id)?;
+                path.transform(ts);
+                self.paint(paint, ts, Some(&path), depth);
+            }
+            11 => {
+                // PaintColrGlyph
+                let glyph_id = GlyphId(s.read_u16()?);
+                if self.active_glyphs.contains(&glyph_id) {
+                    log::warn!("A COLR glyph references itself. Fallback to an outline.");
+                    self.is_aborted = true;
+                    return Some(());
+                }
+
+                let paint = self.colr.base_glyph_paint(glyph_id)?;
+                self.active_glyphs.push(glyph_id);
+                self.paint(paint, ts, clip, depth);
+                self.active_glyphs.pop();
+            }
+            12..=31 => {
+                let paint = child(&mut s)?;
+                let mut child_ts = ts;
+                child_ts.append(&read_transform(format, &mut s, offset, data)?);
+                self.paint(paint, child_ts, clip, depth);
+            }
+            32 => {
+                // PaintComposite
+                let source = child(&mut s)?;
+                s.skip(1)?; // composite mode
+                let backdrop = child(&mut s)?;
+                self.paint(backdrop, ts, clip, depth);
+                self.paint(source, ts, clip, depth);
+            }
+            _ => return None,
+        }
+
+        Some(())
+    }
+
+    fn push(&mut self, clip: Option<&PathData>, paint: LayerPaint) {
+        // A paint outside of a glyph has no geometry.
+        if let Some(path) = clip {
+            self.layers.push(ColorLayer {
+                path: path.clone(),
+                paint,
+            });
+        }
+    }
+
+    fn linear_gradient(
+        &self,
+        color_line: usize,
+        is_var: bool,
+        p: &[f64; 6],
+        ts: Transform,
+    ) -> Option<LayerPaint> {
+        let (spread_method, stops) = self.color_line(color_line, is_var)?;
+        if stops.len() == 1 {
+            return Some(LayerPaint::Paint(
+                Paint::Color(stops[0].color),
+                stops[0].opacity,
+            ));
+        }
+
+        // The gradient is perpendicular to the p0-p2 line, so project p1 on it.
+        let (x0, y0, x1, y1, x2, y2) = (p[0], p[1], p[2], p[3], p[4], p[5]);
+        let (dx, dy) = (x2 - x0, y2 - y0);
+        let len = dx * dx + dy * dy;
+        let (x3, y3) = if len > 0.0 {
+            let k = ((x1 - x0) * dx + (y1 - y0) * dy) / len;
+            (x1 - k * dx, y1 - k * dy)
+        } else {
+            (x1, y1)
+        };
+
+        Some(LayerPaint::Paint(
+            Paint::LinearGradient(Rc::new(LinearGradient {
+                id: String::new(),
+                x1: x0,
+                y1: y0,
+                x2: x3,
+                y2: y3,
+                base: BaseGradient {
+                    units: Units::UserSpaceOnUse,
+                    transform: ts,
+                    spread_method,
+                    stops,
+                },
+            })),
+            Opacity::ONE,
+        ))
+    }
+
+    fn radial_gradient(
+        &self,
+        color_line: usize,
+        is_var: bool,
+        start: (f64, f64),
+        end: (f64, f64, f64),
+        ts: Transform,
+    ) -> Option<LayerPaint> {
+        let (spread_method, stops) = self.color_line(color_line, is_var)?;
+        let r = match PositiveF64::new(end.2) {
+            Some(r) if stops.len() > 1 => r,
+            _ => {
+                let stop = stops.last()?;
+                return Some(LayerPaint::Paint(Paint::Color(stop.color), stop.opacity));
+            }
+        };
+
+        Some(LayerPaint::Paint(
+            Paint::RadialGradient(Rc::new(RadialGradient {
+                id: String::new(),
+                cx: end.0,
+                cy: end.1,
+                r,
+                fx: start.0,
+                fy: start.1,
+                base: BaseGradient {
+                    units: Units::UserSpaceOnUse,
+                    transform: ts,
+                    spread_method,
+                    stops,
+                },
+            })),
+            Opacity::ONE,
+        ))
+    }
+
+    /// Parses a color line.
+    ///
+    /// Stops outside of the 0..1 range are clamped.
+    fn color_line(&self, offset: usize, is_var: bool) -> Option<(SpreadMethod, Vec<Stop>)> {
+        let data = self.colr.data;
+        let mut s = Stream::new_at(data, offset)?;
+        let spread_method = match s.read_u8()? {
+            1 => SpreadMethod::Repeat,
+            2 => SpreadMethod::Reflect,
+            _ => SpreadMethod::Pad,
+        };
+
+        // Variable stops have an additional variation index.
+        let count = s.read_u16()?;
+        let stride = if is_var { 10 } else { 6 };
+
+        let mut stops = Vec::with_capacity(usize::from(count));
+        for i in 0..usize::from(count) {
+            let mut s = Stream::new_at(data, offset + 3 + i * stride)?;
+            let stop_offset = read_f2dot14(&mut s)?;
+            let palette_index = s.read_u16()?;
+            let alpha = read_f2dot14(&mut s)?;
+            let (color, opacity) = match self.solid(palette_index, alpha) {
+                LayerPaint::Paint(Paint::Color(color), opacity) => (color, opacity),
+                LayerPaint::Foreground(opacity) => (Color::black(), opacity),
+                LayerPaint::Paint(..) => unreachable!(),
+            };
+
+            stops.push(Stop {
+                offset: StopOffset::new_clamped(stop_offset),
+                color,
+                opacity,
+            });
+        }
+
+        stops.sort_by(|a, b| {
+            a.offset
+                .get()
+                .partial_cmp(&b.offset.get())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        if stops.is_empty() {
+            None
+        } else {
+            Some((spread_method, stops))
+        }
+    }
+}
+
+/// Reads a `COLR` v1 transform paint into a matrix.
+fn read_transform(format: u8, s: &mut Stream, offset: usize, data: &[u8]) -> Option<Transform> {
+    let around_center = |s: &mut Stream, ts: Transform| -> Option<Transform> {
+        let cx = f64::from(s.read_i16()?);
+        let cy = f64::from(s.read_i16()?);
+        let mut center_ts = Transform::new_translate(cx, cy);
+        center_ts.append(&ts);
+        center_ts.translate(-cx, -cy);
+        Some(center_ts)
+    };
+
+    let ts = match format {
+        12 => {
+            // PaintTransform
+            let affine = offset + s.read_u24()? as usize;
+            let mut s = Stream::new_at(data, affine)?;
+            let mut m = [0.0; 6];
+            for n in m.iter_mut() {
+                *n = f64::from(s.read_u32()? as i32) / 65536.0;
+            }
+            Transform::new(m[0], m[1], m[2], m[3], m[4], m[5])
+        }
+        14 => {
+            // PaintTranslate
+            let dx = f64::from(s.read_i16()?);
+            let dy = f64::from(s.read_i16()?);
+            Transform::new_translate(dx, dy)
+        }
+        16 | 18 => {
+            // PaintScale, PaintScaleAroundCenter
+            let sx = read_f2dot14(s)?;
+            let sy = read_f2dot14(s)?;
+            let ts = Transform::new_scale(sx, sy);
+            if format == 18 {
+                around_center(s, ts)?
+            } else {
+                ts
+            }
+        }
+        20 | 22 => {
+            // PaintScaleUniform, PaintScaleUniformAroundCenter
+            let scale = read_f2dot14(s)?;
+            let ts = Transform::new_scale(scale, scale);
+            if format == 22 {
+                around_center(s, ts)?
+            } else {
+                ts
+            }
+        }
+        24 | 26 => {
+            // PaintRotate, PaintRotateAroundCenter
+            // Angles are in 180 degree units, counter-clockwise.
+            let angle = read_f2dot14(s)? * 180.0;
+            let ts = Transform::new_rotate(angle);
+            if format == 26 {
+                around_center(s, ts)?
+            } else {
+                ts
+            }
+        }
+        28 | 30 => {
+            // PaintSkew, PaintSkewAroundCenter
+            let x_angle = (read_f2dot14(s)? * 180.0).to_radians();
+            let y_angle = (read_f2dot14(s)? * 180.0).to_radians();
+            let ts = Transform::new(1.0, y_angle.tan(), -x_angle.tan(), 1.0, 0.0, 0.0);
+            if format == 30 {
+                around_center(s, ts)?
+            } else {
+                ts
+            }
+        }
+        _ => return None,
+    };
+
+    Some(ts)
+}
+
+fn read_f2dot14(s: &mut Stream) -> Option<f64> {
+    s.read_i16().map(|n| f64::from(n) / 16384.0)
+}
@@ -4,6 +4,8 @@
 
 use std::rc::Rc;
 
+use rustybuzz::ttf_parser::Tag;
+use rustybuzz::Variation;
 use strict_num::NonZeroPositiveF64;
 use svgtypes::{Length, LengthUnit};
 
@@ -13,7 +15,7 @@ use crate::svgtree::{self, AId, EId};
 use crate::{
     converter, style, units, OptionLog, PaintOrder, ShapeRendering, TextRendering, Visibility,
 };
-use crate::{SharedPathData, Transform, Units};
+use crate::{FuzzyEq, PathData, PathSegment, SharedPathData, Transform, Units};
 
 /// A read-only text index in bytes.
 ///
@@ -58,12 +60,76 @@ impl_enum_from_str!(TextAnchor,
     "end"       => TextAnchor::End
 );
 
+#[derive(Clone, Copy, PartialEq)]
+pub enum TextDirection {
+    LeftToRight,
+    RightToLeft,
+}
+
+impl_enum_default!(TextDirection, LeftToRight);
+
+impl_enum_from_str!(TextDirection,
+    "ltr"   => TextDirection::LeftToRight,
+    "rtl"   => TextDirection::RightToLeft
+);
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum UnicodeBidi {
+    Normal,
+    Embed,
+    Isolate,
+    BidiOverride,
+    IsolateOverride,
+    Plaintext,
+}
+
+impl_enum_default!(UnicodeBidi, Normal);
+
+impl_enum_from_str!(UnicodeBidi,
+    "normal"            => UnicodeBidi::Normal,
+    "embed"             => UnicodeBidi::Embed,
+    "isolate"           => UnicodeBidi::Isolate,
+    "bidi-override"     => UnicodeBidi::BidiOverride,
+    "isolate-override"  => UnicodeBidi::IsolateOverride,
+    "plaintext"         => UnicodeBidi::Plaintext
+);
+
+/// A glyph orientation in the vertical writing mode.
+#[derive(Clone, Copy, PartialEq)]
+pub enum TextOrientation {
+    /// Upright for CJK characters and sideways for everything else.
+    Mixed,
+    Upright,
+    Sideways,
+}
+
+impl_enum_default!(TextOrientation, Mixed);
+
+impl_enum_from_str!(TextOrientation,
+    "mixed"     => TextOrientation::Mixed,
+    "upright"   => TextOrientation::Upright,
+    "sideways"  => TextOrientation::Sideways
+);
+
+/// An explicit BIDI embedding, override or isolate.
+///
+/// Created by a `unicode-bidi` property and uses the `direction` of the same element.
+#[derive(Clone, Copy, PartialEq)]
+pub struct BidiEmbedding {
+    /// An element that created the embedding.
+    pub node: svgtree::NodeId,
+    pub kind: UnicodeBidi,
+    pub direction: TextDirection,
+}
+
 impl crate::svgtree::EnumFromStr for fontdb::Style {
     fn enum_from_str(s: &str) -> Option<Self> {
         match s {
             "normal" => Some(fontdb::Style::Normal),
             "italic" => Some(fontdb::Style::Italic),
             "oblique" => Some(fontdb::Style::Oblique),
+            // `oblique <angle>`
+            _ if s.starts_with("oblique ") => Some(fontdb::Style::Oblique),
             _ => None,
         }
     }
@@ -90,7 +156,10 @@ pub enum TextFlow {
 pub struct TextChunk {
     pub x: Option<f64>,
     pub y: Option<f64>,
+    /// Already resolved according to the `direction`.
     pub anchor: TextAnchor,
+    /// A BIDI paragraph direction.
+    pub direction: TextDirection,
     pub spans: Vec<TextSpan>,
     pub text_flow: TextFlow,
     pub text: String,
@@ -119,6 +188,12 @@ pub struct TextSpan {
     pub font: super::fontdb_ext::Font,
     pub font_size: NonZeroPositiveF64,
     pub small_caps: bool,
+    /// Explicit BIDI embeddings, from the outermost one.
+    pub bidi_embeddings: Vec<BidiEmbedding>,
+    /// Used only in the vertical writing mode.
+    pub text_orientation: TextOrientation,
+    pub variations: Vec<Variation>,
+    pub synthesis: FontSynthesis,
     pub decoration: TextDecoration,
     pub baseline_shift: f64,
     pub visibility: Visibility,
@@ -132,6 +207,16 @@ impl TextSpan {
     }
 }
 
+/// Requested font properties that can be synthesized.
+///
+/// Applied only to fonts without a matching face.
+#[derive(Clone, Copy, Default)]
+pub struct FontSynthesis {
+    pub bold: bool,
+    /// A slant angle in degrees.
+    pub oblique: Option<f64>,
+}
+
 #[derive(Clone, Copy, PartialEq)]
 pub enum WritingMode {
     LeftToRight,
@@ -139,6 +224,7 @@ pub enum WritingMode {
 }
 
 struct IterState {
+    writing_mode: WritingMode,
     chars_count: usize,
     chunk_bytes_count: usize,
     split_chunk: bool,
@@ -153,6 +239,7 @@ pub fn collect_text_chunks(
     cache: &mut converter::Cache,
 ) -> Vec<TextChunk> {
     let mut iter_state = IterState {
+        writing_mode: convert_writing_mode(text_node),
         chars_count: 0,
         chunk_bytes_count: 0,
         split_chunk: false,
@@ -223,6 +310,15 @@ fn collect_text_chunks_impl(
         }
 
         let anchor = parent.find_attribute(AId::TextAnchor).unwrap_or_default();
+        let anchor = match resolve_direction(parent, iter_state.writing_mode) {
+            TextDirection::LeftToRight => anchor,
+            // `start` and `end` are the right and left sides for right-to-left text.
+            TextDirection::RightToLeft => match anchor {
+                TextAnchor::Start => TextAnchor::End,
+                TextAnchor::Middle => TextAnchor::Middle,
+                TextAnchor::End => TextAnchor::Start,
+            },
+        };
 
         // TODO: what to do when <= 0? UB?
         let font_size = units::resolve_font_size(parent, state);
@@ -258,6 +354,10 @@ fn collect_text_chunks_impl(
             font,
             font_size,
             small_caps: parent.find_attribute(AId::FontVariant) == Some("small-caps"),
+            bidi_embeddings: resolve_bidi_embeddings(text_node, parent, iter_state.writing_mode),
+            text_orientation: resolve_text_orientation(parent),
+            variations: resolve_font_variations(parent),
+            synthesis: resolve_font_synthesis(parent, state),
             decoration: resolve_decoration(text_node, parent, state, cache),
             visibility: parent.find_attribute(AId::Visibility).unwrap_or_default(),
             baseline_shift: resolve_baseline_shift(parent, state),
@@ -292,6 +392,7 @@ fn collect_text_chunks_impl(
                     x: pos_list[iter_state.chars_count].x,
                     y: pos_list[iter_state.chars_count].y,
                     anchor,
+                    direction: resolve_direction(*text_node, iter_state.writing_mode),
                     spans: vec![span2],
                     text_flow: iter_state.text_flow.clone(),
                     text: c.to_string(),
@@ -324,7 +425,96 @@ fn collect_text_chunks_impl(
     }
 }
 
+fn resolve_bidi_embeddings(
+    text_node: TextNode,
+    node: svgtree::Node,
+    writing_mode: WritingMode,
+) -> Vec<BidiEmbedding> {
+    let mut embeddings = Vec::new();
+    for n in node.ancestors() {
+        let kind = n.attribute(AId::UnicodeBidi).unwrap_or_default();
+        if kind != UnicodeBidi::Normal {
+            embeddings.push(BidiEmbedding {
+                node: n.id(),
+                kind,
+                direction: resolve_direction(n, writing_mode),
+            });
+        }
+
+        if n.id() == text_node.id() {
+            break;
+        }
+    }
+
+    embeddings.reverse();
+    embeddings
+}
+
+/// Resolves `direction`.
+///
+/// `text-orientation: upright` forces the left-to-right direction in the vertical writing mode.
+fn resolve_direction(node: svgtree::Node, writing_mode: WritingMode) -> TextDirection {
+    if writing_mode == WritingMode::TopToBottom
+        && resolve_text_orientation(node) == TextOrientation::Upright
+    {
+        return TextDirection::LeftToRight;
+    }
+
+    node.find_attribute(AId::Direction).unwrap_or_default()
+}
+
+/// Resolves `text-orientation`.
+///
+/// The deprecated `glyph-orientation-vertical` is treated as an alias, as defined by
+/// [CSS Writing Modes Level 3](https://www.w3.org/TR/css-writing-modes-3/#glyph-orientation).
+/// Angles other than `0deg` and `90deg` are ignored.
+fn resolve_text_orientation(node: svgtree::Node) -> TextOrientation {
+    for n in node.ancestors() {
+        if let Some(orientation) = n.attribute(AId::TextOrientation) {
+            return orientation;
+        }
+
+        let orientation = match n.attribute(AId::GlyphOrientationVertical) {
+            Some("auto") => TextOrientation::Mixed,
+            Some("0") | Some("0deg") => TextOrientation::Upright,
+            Some("90") | Some("90deg") => TextOrientation::Sideways,
+            _ => continue,
+        };
+
+        return orientation;
+    }
+
+    TextOrientation::default()
+}
+
 fn resolve_text_flow(node: svgtree::Node, state: &converter::State) -> Option<TextFlow> {
+    // `path` takes precedence over `href`, unless it's invalid.
+    let path = match node.attribute::<SharedPathData>(AId::Path) {
+        Some(path) => path,
+        None => resolve_text_path_link(node, state)?,
+    };
+
+    // Placing text on the right side of a path is the same as reversing the path.
+    let path = if node.attribute(AId::Side) == Some("right") {
+        Rc::new(reverse_path(&path))
+    } else {
+        path
+    };
+
+    let start_offset: Length = node.attribute(AId::StartOffset).unwrap_or_default();
+    let start_offset = if start_offset.unit == LengthUnit::Percent {
+        // 'If a percentage is given, then the `startOffset` represents
+        // a percentage distance along the entire path.'
+        let path_len = path.length();
+        path_len * (start_offset.number / 100.0)
+    } else {
+        node.resolve_length(AId::StartOffset, state, 0.0)
+    };
+
+    Some(TextFlow::Path(Rc::new(TextPath { start_offset, path })))
+}
+
+fn resolve_text_path_link(node: svgtree::Node, state: &converter::State) -> Option<SharedPathData> {
     let linked_node = node.attribute::<svgtree::Node>(AId::Href)?;
 
     let path = match linked_node.tag_name()? {
@@ -344,17 +534,83 @@ fn resolve_text_flow(node: svgtree::Node, state: &converter::State) -> Option<Te
         path
     };
 
-    let start_offset: Length = node.attribute(AId::StartOffset).unwrap_or_default();
-    let start_offset = if start_offset.unit == LengthUnit::Percent {
-        // 'If a percentage is given, then the `startOffset` represents
-        // a percentage distance along the entire path.'
-        let path_len = path.length();
-        path_len * (start_offset.number / 100.0)
-    } else {
-        node.resolve_length(AId::StartOffset, state, 0.0)
-    };
+    Some(path)
+}
 
-    Some(TextFlow::Path(Rc::new(TextPath { start_offset, path })))
+/// Reverses the direction of each subpath.
+///
+/// Closed subpaths still start at the same point.
+fn reverse_path(path: &PathData) -> PathData {
+    fn push_subpath(subpath: &[PathSegment], is_closed: bool, new_path: &mut PathData) {
+        let (start_x, start_y) = match subpath.first() {
+            Some(PathSegment::MoveTo { x, y }) => (*x, *y),
+            _ => return,
+        };
+
+        // Segment end points, from the start point to the last one.
+        let mut points = vec![(start_x, start_y)];
+        for seg in &subpath[1..] {
+            match *seg {
+                PathSegment::LineTo { x, y } | PathSegment::CurveTo { x, y, .. } => {
+                    points.push((x, y))
+                }
+                _ => {}
+            }
+        }
+
+        let (last_x, last_y) = points[points.len() - 1];
+        if is_closed {
+            new_path.push_move_to(start_x, start_y);
+            if !(last_x.fuzzy_eq(&start_x) && last_y.fuzzy_eq(&start_y)) {
+                new_path.push_line_to(last_x, last_y);
+            }
+        } else {
+            new_path.push_move_to(last_x, last_y);
+        }
+
+        for (seg, (prev_x, prev_y)) in subpath[1..].iter().zip(&points).rev() {
+            match *seg {
+                PathSegment::LineTo { .. } => new_path.push_line_to(*prev_x, *prev_y),
+                PathSegment::CurveTo { x1, y1, x2, y2, .. } => {
+                    new_path.push_curve_to(x2, y2, x1, y1, *prev_x, *prev_y)
+                }
+                _ => {}
+            }
+        }
+
+        if is_closed {
+            new_path.push_close_path();
+        }
+    }
+
+    let mut new_path = PathData::new();
+    let mut subpath = Vec::new();
+    let mut move_to = None;
+    for seg in path.segments() {
+        match seg {
+            PathSegment::MoveTo { .. } => {
+                push_subpath(&subpath, false, &mut new_path);
+                subpath.clear();
+                subpath.push(seg);
+                move_to = Some(seg);
+            }
+            PathSegment::ClosePath => {
+                push_subpath(&subpath, true, &mut new_path);
+                subpath.clear();
+            }
+            _ => {
+                // A segment after ClosePath starts at the previous MoveTo.
+                if subpath.is_empty() {
+                    subpath.extend(move_to);
+                }
+
+                subpath.push(seg);
+            }
+        }
+    }
+    push_subpath(&subpath, false, &mut new_path);
+
+    new_path
 }
 
 pub fn resolve_rendering_mode(text_node: TextNode, state: &converter::State) -> ShapeRendering {
@@ -369,7 +625,7 @@ pub fn resolve_rendering_mode(text_node: TextNode, state: &converter::State) ->
     }
 }
 
-fn resolve_font(node: svgtree::Node, state: &converter::State) -> Option<fontdb_ext::Font> {
+pub fn resolve_font(node: svgtree::Node, state: &converter::State) -> Option<fontdb_ext::Font> {
     let style = node.find_attribute(AId::FontStyle).unwrap_or_default();
     let stretch = conv_font_stretch(node);
     let weight = resolve_font_weight(node);
@@ -425,23 +681,172 @@ fn resolve_font(node: svgtree::Node, state: &converter::State) -> Option<fontdb_
     state.opt.fontdb.load_font(id)
 }
 
-// TODO: properly resolve narrower/wider
 fn conv_font_stretch(node: svgtree::Node) -> fontdb::Stretch {
-    if let Some(n) = node.find_node_with_attribute(AId::FontStretch) {
-        match n.attribute(AId::FontStretch).unwrap_or("") {
-            "narrower" | "condensed" => fontdb::Stretch::Condensed,
-            "ultra-condensed" => fontdb::Stretch::UltraCondensed,
-            "extra-condensed" => fontdb::Stretch::ExtraCondensed,
-            "semi-condensed" => fontdb::Stretch::SemiCondensed,
-            "semi-expanded" => fontdb::Stretch::SemiExpanded,
-            "wider" | "expanded" => fontdb::Stretch::Expanded,
-            "extra-expanded" => fontdb::Stretch::ExtraExpanded,
-            "ultra-expanded" => fontdb::Stretch::UltraExpanded,
-            _ => fontdb::Stretch::Normal,
+    // Percentages are matched to the closest keyword.
+    let stretch = resolve_font_stretch(node);
+    if stretch <= 56.25 {
+        fontdb::Stretch::UltraCondensed
+    } else if stretch <= 68.75 {
+        fontdb::Stretch::ExtraCondensed
+    } else if stretch <= 81.25 {
+        fontdb::Stretch::Condensed
+    } else if stretch < 100.0 {
+        fontdb::Stretch::SemiCondensed
+    } else if stretch == 100.0 {
+        fontdb::Stretch::Normal
+    } else if stretch <= 118.75 {
+        fontdb::Stretch::SemiExpanded
+    } else if stretch <= 137.5 {
+        fontdb::Stretch::Expanded
+    } else if stretch <= 175.0 {
+        fontdb::Stretch::ExtraExpanded
+    } else {
+        fontdb::Stretch::UltraExpanded
+    }
+}
+
+/// Resolves `font-stretch` as a percentage of the normal font width.
+// TODO: properly resolve narrower/wider
+fn resolve_font_stretch(node: svgtree::Node) -> f32 {
+    let n = match node.find_node_with_attribute(AId::FontStretch) {
+        Some(n) => n,
+        None => return 100.0,
+    };
+
+    match n.attribute(AId::FontStretch).unwrap_or("") {
+        "narrower" | "condensed" => 75.0,
+        "ultra-condensed" => 50.0,
+        "extra-condensed" => 62.5,
+        "semi-condensed" => 87.5,
+        "semi-expanded" => 112.5,
+        "wider" | "expanded" => 125.0,
+        "extra-expanded" => 150.0,
+        "ultra-expanded" => 200.0,
+        value => value
+            .strip_suffix('%')
+            .and_then(|n| n.trim().parse::<f32>().ok())
+            .filter(|n| n.is_finite() && *n >= 0.0)
+            .unwrap_or(100.0),
+    }
+}
+
+/// The default `oblique` angle from the CSS Fonts spec.
+const DEFAULT_OBLIQUE_ANGLE: f32 = 14.0;
+
+/// Resolves an oblique `font-style` angle in degrees.
+///
+/// Returns `None` for `normal` and `italic`.
+fn resolve_oblique_angle(node: svgtree::Node) -> Option<f32> {
+    let n = node.find_node_with_attribute(AId::FontStyle)?;
+    let value = n
+        .attribute::<&str>(AId::FontStyle)?
+        .strip_prefix("oblique")?;
+    if value.is_empty() {
+        return Some(DEFAULT_OBLIQUE_ANGLE);
+    }
+
+    let angle: svgtypes::Angle = value.trim().parse().ok()?;
+    let angle = angle.to_degrees() as f32;
+    if (-90.0..=90.0).contains(&angle) {
+        Some(angle)
+    } else {
+        None
+    }
+}
+
+fn resolve_font_synthesis(node: svgtree::Node, state: &converter::State) -> FontSynthesis {
+    let bold = state.opt.synthetic_bold && resolve_font_weight(node).0 >= 600;
+
+    let oblique = if state.opt.synthetic_oblique {
+        match node.find_attribute(AId::FontStyle).unwrap_or_default() {
+            fontdb::Style::Normal => None,
+            fontdb::Style::Italic => Some(DEFAULT_OBLIQUE_ANGLE),
+            fontdb::Style::Oblique => {
+                Some(resolve_oblique_angle(node).unwrap_or(DEFAULT_OBLIQUE_ANGLE))
+            }
         }
     } else {
-        fontdb::Stretch::Normal
+        None
+    };
+
+    FontSynthesis {
+        bold,
+        oblique: oblique.map(f64::from),
+    }
+}
+
+/// Resolves font variation axes values.
+///
+/// `font-weight`, `font-stretch` and an oblique `font-style` are mapped onto
+/// the `wght`, `wdth` and `slnt` axes, which can be overridden by `font-variation-settings`.
+/// Axes that are not present in a font are ignored during shaping and outlining.
+fn resolve_font_variations(node: svgtree::Node) -> Vec<Variation> {
+    let mut variations = vec![
+        Variation {
+            tag: Tag::from_bytes(b"wght"),
+            value: f32::from(resolve_font_weight(node).0),
+        },
+        Variation {
+            tag: Tag::from_bytes(b"wdth"),
+            value: resolve_font_stretch(node),
+        },
+    ];
+
+    if let Some(angle) = resolve_oblique_angle(node) {
+        // `slnt` uses counter-clockwise angles.
+        variations.push(Variation {
+            tag: Tag::from_bytes(b"slnt"),
+            value: -angle,
+        });
     }
+
+    if let Some(n) = node.find_node_with_attribute(AId::FontVariationSettings) {
+        let value = n.attribute(AId::FontVariationSettings).unwrap_or("");
+        match parse_font_variation_settings(value) {
+            Some(settings) => {
+                for variation in settings {
+                    variations.retain(|v| v.tag != variation.tag);
+                    variations.push(variation);
+                }
+            }
+            None => log::warn!("Invalid font-variation-settings value: '{}'.", value),
+        }
+    }
+
+    variations
+}
+
+/// Parses a `font-variation-settings` value, like `"wght" 650, "wdth" 80`.
+fn parse_font_variation_settings(text: &str) -> Option<Vec<Variation>> {
+    let text = text.trim();
+    if text == "normal" {
+        return Some(Vec::new());
+    }
+
+    let mut variations = Vec::new();
+    for setting in text.split(',') {
+        let setting = setting.trim();
+        let quote = setting.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let setting = &setting[1..];
+        let end = setting.find(quote)?;
+
+        let tag = &setting.as_bytes()[..end];
+        if tag.len() != 4 || !tag.iter().all(|c| (0x20..=0x7E).contains(c)) {
+            return None;
+        }
+
+        let value: f32 = setting[end + 1..].trim().parse().ok()?;
+        if !value.is_finite() {
+            return None;
+        }
+
+        variations.push(Variation {
+            tag: Tag::from_bytes(&[tag[0], tag[1], tag[2], tag[3]]),
+            value,
+        });
+    }
+
+    Some(variations)
 }
 
 #[derive(Clone, Copy)]
@@ -760,7 +1165,11 @@ fn resolve_font_weight(node: svgtree::Node) -> fontdb::Weight {
 
                 bound(100, weight - step, 900)
             }
-            _ => weight,
+            // CSS Fonts 4 allows any number in the 1..1000 range.
+            value => match value.parse::<f64>() {
+                Ok(n) if (1.0..=1000.0).contains(&n) => n.round() as usize,
+                _ => weight,
+            },
         };
     }
 
@@ -0,0 +1,76 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::collections::HashMap;
+
+use crate::OptionsRef;
+
+/// A shorthand for [FontFallback]'s select function.
+pub type FontFallbackSelectFn =
+    Box<dyn Fn(char, &fontdb::FaceInfo, &OptionsRef) -> Option<fontdb::ID> + Send + Sync>;
+
+/// A font fallback strategy.
+///
+/// Used when a font resolved via `font-family` doesn't have a character.
+/// Candidates are checked in the following order:
+///
+/// 1. [`FontFallback::select`]
+/// 2. [`FontFallback::emoji_families`], for emoji characters
+/// 3. [`FontFallback::language_families`], for each language in
+///    [Options::languages](crate::Options::languages)
+/// 4. [`FontFallback::script_families`], for the character script
+/// 5. [`FontFallback::families`]
+/// 6. Any font in the database
+///
+/// A font is used only when it has the character and wasn't used for the current text already.
+/// Fonts are selected using the original font style, weight and stretch.
+///
+/// Decisions are cached per character and original font during a single parsing.
+#[derive(Default)]
+pub struct FontFallback {
+    /// A function that selects a fallback font for a character.
+    ///
+    /// Called with a character, the original font and parsing options.
+    /// When `None` is returned, the rest of the fallback chain is used.
+    ///
+    /// Default: `None`
+    pub select: Option<FontFallbackSelectFn>,
+
+    /// Font families for emoji characters.
+    ///
+    /// Default: empty
+    pub emoji_families: Vec<String>,
+
+    /// Font families for languages.
+    ///
+    /// Keys are language tags, like `ja` or `zh-TW`.
+    /// `zh-TW` would be looked up as `zh-TW` first and then as `zh`.
+    ///
+    /// Default: empty
+    pub language_families: HashMap<String, Vec<String>>,
+
+    /// Font families for scripts.
+    ///
+    /// Keys are ISO 15924 script codes, like `Hani`, `Arab` or `Cyrl`.
+    ///
+    /// Default: empty
+    pub script_families: HashMap<String, Vec<String>>,
+
+    /// Font families that should be used for any character.
+    ///
+    /// Default: empty
+    pub families: Vec<String>,
+}
+
+impl std::fmt::Debug for FontFallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("FontFallback")
+            .field("select", &self.select.as_ref().map(|_| ".."))
+            .field("emoji_families", &self.emoji_families)
+            .field("language_families", &self.language_families)
+            .field("script_families", &self.script_families)
+            .field("families", &self.families)
+            .finish()
+    }
+}
@@ -5,16 +5,16 @@
 use std::convert::TryFrom;
 use std::num::NonZeroU16;
 
-use rustybuzz::ttf_parser;
+use rustybuzz::{ttf_parser, Variation};
 
 use fontdb::{Database, ID};
 use ttf_parser::GlyphId;
 
-use crate::PathData;
+use crate::{PathCommand, PathData};
 
 pub trait DatabaseExt {
     fn load_font(&self, id: ID) -> Option<Font>;
-    fn outline(&self, id: ID, glyph_id: GlyphId) -> Option<PathData>;
+    fn outline(&self, id: ID, glyph_id: GlyphId, variations: &[Variation]) -> Option<PathData>;
     fn has_char(&self, id: ID, c: char) -> bool;
 }
 
@@ -77,6 +77,18 @@ impl DatabaseExt for Database {
                 superscript_offset = metrics.y_offset;
             }
 
+            let has_axis = |tag: &[u8; 4]| {
+                let tag = ttf_parser::Tag::from_bytes(tag);
+                font.variation_axes()
+                    .into_iter()
+                    .any(|axis| axis.tag == tag)
+            };
+
+            let info = self.face(id)?;
+            let is_bold = info.weight.0 >= 600 || has_axis(b"wght");
+            let is_oblique =
+                info.style != fontdb::Style::Normal || has_axis(b"slnt") || has_axis(b"ital");
+
             Some(Font {
                 id,
                 units_per_em,
@@ -88,20 +100,18 @@ impl DatabaseExt for Database {
                 line_through_position,
                 subscript_offset,
                 superscript_offset,
+                is_bold,
+                is_oblique,
             })
         })?
     }
 
     #[inline(never)]
-    fn outline(&self, id: ID, glyph_id: GlyphId) -> Option<PathData> {
+    fn outline(&self, id: ID, glyph_id: GlyphId, variations: &[Variation]) -> Option<PathData> {
         self.with_face_data(id, |data, face_index| -> Option<PathData> {
-            let font = ttf_parser::Face::parse(data, face_index).ok()?;
-
-            let mut builder = PathBuilder {
-                path: PathData::new(),
-            };
-            font.outline_glyph(glyph_id, &mut builder)?;
-            Some(builder.path)
+            let mut font = ttf_parser::Face::parse(data, face_index).ok()?;
+            set_variations(&mut font, variations);
+            outline_glyph(&font, glyph_id)
         })?
     }
 
@@ -138,6 +148,11 @@ pub struct Font {
 
     subscript_offset: i16,
     superscript_offset: i16,
+
+    // Whether the font is bold or oblique by itself or via variation axes.
+    // Otherwise, bold and oblique text has to be synthesized.
+    is_bold: bool,
+    is_oblique: bool,
 }
 
 impl Font {
@@ -190,6 +205,173 @@ impl Font {
     pub fn superscript_offset(&self, font_size: f64) -> f64 {
         self.superscript_offset as f64 * self.scale(font_size)
     }
+
+    #[inline]
+    pub fn is_bold(&self) -> bool {
+        self.is_bold
+    }
+
+    #[inline]
+    pub fn is_oblique(&self) -> bool {
+        self.is_oblique
+    }
+
+    /// Returns a synthetic bold outline offset in font units.
+    ///
+    /// Makes glyphs 1/24 em wider, like FreeType does.
+    #[inline]
+    pub fn synthetic_bold_offset(&self) -> f64 {
+        self.units_per_em.get() as f64 / 48.0
+    }
+}
+
+/// Sets variation axes values.
+///
+/// Axes that are not present in the font are ignored.
+pub fn set_variations(font: &mut ttf_parser::Face, variations: &[Variation]) {
+    for variation in variations {
+        font.set_variation(variation.tag, variation.value);
+    }
+}
+
+/// Outlines a glyph in font units.
+pub fn outline_glyph(font: &ttf_parser::Face, glyph_id: GlyphId) -> Option<PathData> {
+    let mut builder = PathBuilder {
+        path: PathData::new(),
+    };
+    font.outline_glyph(glyph_id, &mut builder)?;
+    Some(builder.path)
+}
+
+/// Emboldens a glyph outline.
+///
+/// Each contour point, including control ones, is moved outwards by `offset`
+/// along the bisector of the adjacent edges. Based on FreeType's `FT_Outline_EmboldenXY`.
+///
+/// Expects an outline in font units, where the Y axis points up.
+pub fn embolden(path: &PathData, offset: f64) -> PathData {
+    let points: Vec<_> = path.points().chunks(2).map(|p| (p[0], p[1])).collect();
+
+    // Split points into contours.
+    let mut contours = Vec::new();
+    let mut start = 0;
+    let mut idx = 0;
+    for cmd in path.commands() {
+        match cmd {
+            PathCommand::MoveTo => {
+                if idx > start {
+                    contours.push(start..idx);
+                }
+
+                start = idx;
+                idx += 1;
+            }
+            PathCommand::LineTo => idx += 1,
+            PathCommand::CurveTo => idx += 3,
+            PathCommand::ClosePath => {}
+        }
+    }
+
+    if idx > start {
+        contours.push(start..idx);
+    }
+
+    // Closed contours may end with the start point, which has to be moved with it.
+    let mut end_points = Vec::new();
+    for contour in &mut contours {
+        if contour.len() > 1 && points[contour.start] == points[contour.end - 1] {
+            contour.end -= 1;
+            end_points.push((contour.start, contour.end));
+        }
+    }
+
+    // Outer contours are clockwise in TrueType fonts and counter-clockwise in CFF ones.
+    let mut area = 0.0;
+    for contour in &contours {
+        let ring = &points[contour.clone()];
+        for (i, p) in ring.iter().enumerate() {
+            let next = ring[(i + 1) % ring.len()];
+            area += p.0 * next.1 - next.0 * p.1;
+        }
+    }
+    let is_clockwise = area < 0.0;
+
+    let mut new_points = points.clone();
+    for contour in &contours {
+        let ring = &points[contour.clone()];
+        let len = ring.len();
+        for (i, p) in ring.iter().enumerate() {
+            // Find the closest distinct points around the current one.
+            let prev = (1..len).map(|n| ring[(i + len - n) % len]).find(|v| v != p);
+            let next = (1..len).map(|n| ring[(i + n) % len]).find(|v| v != p);
+            let (prev, next) = match (prev, next) {
+                (Some(prev), Some(next)) => (prev, next),
+                _ => continue,
+            };
+
+            let l_in = (p.0 - prev.0).hypot(p.1 - prev.1);
+            let l_out = (next.0 - p.0).hypot(next.1 - p.1);
+            let v_in = ((p.0 - prev.0) / l_in, (p.1 - prev.1) / l_in);
+            let v_out = ((next.0 - p.0) / l_out, (next.1 - p.1) / l_out);
+
+            // Do not shift points with a turn sharper than ~160 degrees.
+            let d = v_in.0 * v_out.0 + v_in.1 * v_out.1;
+            if d <= -0.9375 {
+                continue;
+            }
+            let d = d + 1.0;
+
+            // Shift along the lateral bisector in a proper direction.
+            let mut shift = (v_in.1 + v_out.1, v_in.0 + v_out.0);
+            let mut q = v_out.0 * v_in.1 - v_out.1 * v_in.0;
+            if is_clockwise {
+                shift.0 = -shift.0;
+                q = -q;
+            } else {
+                shift.1 = -shift.1;
+            }
+
+            // Restrict the shift to better handle collapsing segments.
+            let l = l_in.min(l_out);
+            let scale = if offset * q <= l * d {
+                offset / d
+            } else {
+                l / q
+            };
+
+            let new_point = &mut new_points[contour.start + i];
+            new_point.0 += shift.0 * scale;
+            new_point.1 += shift.1 * scale;
+        }
+    }
+
+    for (start, end) in end_points {
+        new_points[end] = new_points[start];
+    }
+
+    let mut new_path = PathData::new();
+    let mut points = new_points.into_iter();
+    for cmd in path.commands() {
+        match cmd {
+            PathCommand::MoveTo => {
+                let p = points.next().unwrap();
+                new_path.push_move_to(p.0, p.1);
+            }
+            PathCommand::LineTo => {
+                let p = points.next().unwrap();
+                new_path.push_line_to(p.0, p.1);
+            }
+            PathCommand::CurveTo => {
+                let p1 = points.next().unwrap();
+                let p2 = points.next().unwrap();
+                let p = points.next().unwrap();
+                new_path.push_curve_to(p1.0, p1.1, p2.0, p2.1, p.0, p.1);
+            }
+            PathCommand::ClosePath => new_path.push_close_path(),
+        }
+    }
+
+    new_path
 }
 
 struct PathBuilder {
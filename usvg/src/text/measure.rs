@@ -0,0 +1,335 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use super::{convert, layout_chunk, TextNode};
+use crate::svgtree::{self, EId};
+use crate::{converter, IsValidLength, OptionsRef, PathBbox, PathData, Rect, Size, Transform};
+
+/// Font properties used by [`measure_text`].
+///
+/// Correspond to the `text` element attributes with the same names.
+#[derive(Clone, Debug)]
+pub struct FontProperties {
+    /// A `font-family` value.
+    ///
+    /// Can contain a list of families, including generic ones, like `"'Noto Sans', serif"`.
+    /// `Options::font_family` will be used when empty.
+    ///
+    /// Default: empty
+    pub family: String,
+
+    /// A font size in user units.
+    ///
+    /// Default: 12
+    pub size: f64,
+
+    /// A font weight in the 1..1000 range.
+    ///
+    /// Default: 400
+    pub weight: u16,
+
+    /// A font style.
+    ///
+    /// Default: `Normal`
+    pub style: fontdb::Style,
+
+    /// A font stretch.
+    ///
+    /// Default: `Normal`
+    pub stretch: fontdb::Stretch,
+
+    /// Enables `font-variant: small-caps`.
+    ///
+    /// Default: false
+    pub small_caps: bool,
+
+    /// A letter spacing in user units.
+    ///
+    /// Default: 0
+    pub letter_spacing: f64,
+
+    /// A word spacing in user units.
+    ///
+    /// Default: 0
+    pub word_spacing: f64,
+}
+
+impl Default for FontProperties {
+    fn default() -> Self {
+        FontProperties {
+            family: String::new(),
+            size: 12.0,
+            weight: 400,
+            style: fontdb::Style::Normal,
+            stretch: fontdb::Stretch::Normal,
+            small_caps: false,
+            letter_spacing: 0.0,
+            word_spacing: 0.0,
+        }
+    }
+}
+
+/// Text measurement results.
+///
+/// All values are in user units. Positions are relative to the text start on the baseline
+/// and the Y axis points down.
+#[derive(Clone, Debug)]
+pub struct TextMetrics {
+    /// A total text advance.
+    ///
+    /// Includes letter and word spacing.
+    pub advance: f64,
+
+    /// A bounding box of the rendered glyphs.
+    ///
+    /// Unlike the line metrics, it's based on the glyph shapes.
+    /// `None` when the text has no visible glyphs.
+    pub bbox: Option<PathBbox>,
+
+    /// The largest ascent of all used fonts. Positive.
+    pub ascent: f64,
+
+    /// The smallest descent of all used fonts. Usually negative.
+    pub descent: f64,
+
+    /// A x-height of the primary font.
+    pub x_height: f64,
+
+    /// An underline position of the primary font, relative to the baseline.
+    pub underline_position: f64,
+
+    /// An underline thickness of the primary font.
+    pub underline_thickness: f64,
+
+    /// Glyph clusters in the visual order.
+    pub clusters: Vec<TextCluster>,
+}
+
+impl TextMetrics {
+    /// Returns a line height, that is `ascent - descent`.
+    pub fn height(&self) -> f64 {
+        self.ascent - self.descent
+    }
+}
+
+/// A measured glyph cluster.
+///
+/// A cluster is one or more glyphs that represent a single, unbroken, renderable character.
+#[derive(Clone, Debug)]
+pub struct TextCluster {
+    /// A position of the first cluster character in bytes in the original string.
+    pub byte_idx: usize,
+
+    /// A cluster position along the X axis.
+    pub x: f64,
+
+    /// A cluster advance.
+    ///
+    /// Includes letter and word spacing.
+    pub advance: f64,
+
+    /// Cluster outlines, already positioned.
+    ///
+    /// Present only when requested. Color glyphs are not included.
+    pub outline: Option<PathData>,
+}
+
+/// Measures a single line of text.
+///
+/// Fonts are taken from `Options::fontdb`. Font fallback and font synthesis
+/// are controlled by `Options` as well.
+///
+/// The text goes through exactly the same steps as during the `text` element conversion:
+/// font matching and fallback, BIDI reordering, shaping, spacing and positioning.
+/// So the results will match the rendered text with the same properties.
+///
+/// Since this is a single line, new lines and tabs are treated as spaces.
+/// Spaces are preserved, just like with `xml:space="preserve"`.
+///
+/// Characters that are not allowed in XML, like control characters, are treated as spaces
+/// as well. U+FFFE and U+FFFF are replaced with U+FFFD.
+/// Byte indices are not affected by those replacements.
+///
+/// Returns `None` when a font size is invalid or when no matching font was found.
+pub fn measure_text(
+    text: &str,
+    font: &FontProperties,
+    opt: &OptionsRef,
+    with_outlines: bool,
+) -> Option<TextMetrics> {
+    if !font.size.is_valid_length() {
+        return None;
+    }
+
+    // New lines and tabs are converted into spaces during parsing anyway,
+    // but doing it beforehand keeps the byte indices intact, since XML normalizes `\r\n`.
+    // Characters that are not allowed in XML at all would make the parsing fail,
+    // so they are replaced with characters of the same UTF-8 length.
+    let text: String = text
+        .chars()
+        .map(|c| match c {
+            '\u{0}'..='\u{1F}' => ' ',
+            '\u{FFFE}' | '\u{FFFF}' => '\u{FFFD}',
+            _ => c,
+        })
+        .collect();
+
+    // Instead of duplicating the style resolving, we create a `text` element
+    // and process it as usual.
+    let svg = text_element(&text, font);
+    let xml = roxmltree::Document::parse(&svg).ok()?;
+    let doc = svgtree::Document::parse(&xml).ok()?;
+    let node = doc.descendants().find(|n| n.has_tag_name(EId::Text))?;
+    let text_node = TextNode::new(node);
+
+    let state = converter::State {
+        parent_clip_path: None,
+        parent_marker: None,
+        fe_image_link: false,
+        size: Size::new(100.0, 100.0).unwrap(),
+        view_box: Rect::new(0.0, 0.0, 100.0, 100.0).unwrap(),
+        use_size: (None, None),
+        opt,
+    };
+    let mut cache = converter::Cache::default();
+
+    let primary_font = convert::resolve_font(node, &state)?;
+
+    let mut metrics = TextMetrics {
+        advance: 0.0,
+        bbox: None,
+        ascent: primary_font.ascent(font.size),
+        descent: primary_font.descent(font.size),
+        x_height: primary_font.x_height(font.size),
+        underline_position: primary_font.underline_position(font.size),
+        underline_thickness: primary_font.underline_thickness(font.size),
+        clusters: Vec::new(),
+    };
+
+    let pos_list = convert::resolve_positions_list(text_node, &state);
+    let rotate_list = convert::resolve_rotate_list(text_node);
+    let chunks = convert::collect_text_chunks(text_node, &pos_list, &state, &mut cache);
+
+    let mut bboxes_data = PathData::new();
+    let mut char_offset = 0;
+    let mut byte_offset = 0;
+    for chunk in &chunks {
+        let x = chunk.x.unwrap_or(metrics.advance);
+        if let Some((clusters, curr_pos)) = layout_chunk(
+            chunk,
+            char_offset,
+            &pos_list,
+            &rotate_list,
+            convert::WritingMode::LeftToRight,
+            Transform::default(),
+            &state,
+            &mut cache,
+        ) {
+            let chunk_ts = Transform::new_translate(x, 0.0);
+            for mut cluster in clusters {
+                metrics.ascent = metrics.ascent.max(cluster.ascent);
+                metrics.descent = metrics.descent.min(cluster.descent);
+
+                let mut ts = chunk_ts;
+                ts.append(&cluster.transform);
+
+                cluster.path.transform(ts);
+                bboxes_data.push_path(&cluster.path);
+
+                if !cluster.color_glyphs.is_empty() {
+                    // Just like during rendering, use font metrics and not glyph shape.
+                    if let Some(r) =
+                        Rect::new(0.0, -cluster.ascent, cluster.advance, cluster.height())
+                    {
+                        if let Some(r) = r.transform(&ts) {
+                            bboxes_data.push_rect(r);
+                        }
+                    }
+                }
+
+                metrics.clusters.push(TextCluster {
+                    byte_idx: byte_offset + cluster.byte_idx.value(),
+                    x: ts.e,
+                    advance: cluster.advance,
+                    outline: if with_outlines {
+                        Some(cluster.path)
+                    } else {
+                        None
+                    },
+                });
+            }
+
+            metrics.advance = x + curr_pos.0;
+        }
+
+        char_offset += chunk.text.chars().count();
+        byte_offset += chunk.text.len();
+    }
+
+    metrics.bbox = bboxes_data.bbox();
+
+    Some(metrics)
+}
+
+fn text_element(text: &str, font: &FontProperties) -> String {
+    let mut attrs = String::new();
+    if !font.family.is_empty() {
+        attrs.push_str(&format!(" font-family=\"{}\"", escape_xml(&font.family)));
+    }
+
+    let style = match font.style {
+        fontdb::Style::Normal => "normal",
+        fontdb::Style::Italic => "italic",
+        fontdb::Style::Oblique => "oblique",
+    };
+
+    let stretch = match font.stretch {
+        fontdb::Stretch::UltraCondensed => "ultra-condensed",
+        fontdb::Stretch::ExtraCondensed => "extra-condensed",
+        fontdb::Stretch::Condensed => "condensed",
+        fontdb::Stretch::SemiCondensed => "semi-condensed",
+        fontdb::Stretch::Normal => "normal",
+        fontdb::Stretch::SemiExpanded => "semi-expanded",
+        fontdb::Stretch::Expanded => "expanded",
+        fontdb::Stretch::ExtraExpanded => "extra-expanded",
+        fontdb::Stretch::UltraExpanded => "ultra-expanded",
+    };
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\">\
+            <text xml:space=\"preserve\"{} font-size=\"{}\" font-weight=\"{}\" font-style=\"{}\" \
+                  font-stretch=\"{}\" font-variant=\"{}\" letter-spacing=\"{}\" word-spacing=\"{}\">\
+                {}\
+            </text>\
+        </svg>",
+        attrs,
+        font.size,
+        font.weight,
+        style,
+        stretch,
+        if font.small_caps {
+            "small-caps"
+        } else {
+            "normal"
+        },
+        font.letter_spacing,
+        font.word_spacing,
+        escape_xml(text),
+    )
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut s = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => s.push_str("&amp;"),
+            '<' => s.push_str("&lt;"),
+            '>' => s.push_str("&gt;"),
+            '"' => s.push_str("&quot;"),
+            _ => s.push(c),
+        }
+    }
+
+    s
+}
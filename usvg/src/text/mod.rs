@@ -4,9 +4,19 @@
 
 use std::rc::Rc;
 
+mod color;
 mod convert;
+mod fallback;
 mod fontdb_ext;
+mod measure;
 mod shaper;
+mod stream;
+pub(crate) mod web_fonts;
+mod woff;
+
+pub use fallback::{FontFallback, FontFallbackSelectFn};
+pub use measure::{measure_text, FontProperties, TextCluster, TextMetrics};
+pub use woff::{decode_web_font, WebFontsExt};
 
 use crate::{converter, svgtree};
 use crate::{BaseGradient, Color, LinearGradient, Pattern, RadialGradient};
@@ -59,14 +69,18 @@ pub(crate) fn convert(
     parent: &mut Node,
 ) {
     let text_node = TextNode::new(node);
-    let (mut new_paths, bbox) = text_to_paths(text_node, state, cache, parent);
-
-    if new_paths.len() == 1 {
-        // Copy `text` id to the first path.
-        new_paths[0].id = node.element_id().to_string();
+    let (new_nodes, bbox) = text_to_paths(text_node, state, cache, parent);
+
+    if new_nodes.len() == 1 {
+        // Copy `text` id to the first node.
+        match *new_nodes[0].borrow_mut() {
+            NodeKind::Path(ref mut path) => path.id = node.element_id().to_string(),
+            NodeKind::Group(ref mut g) => g.id = node.element_id().to_string(),
+            _ => {}
+        }
     }
 
-    let mut parent = if state.opt.keep_named_groups && new_paths.len() > 1 {
+    let parent = if state.opt.keep_named_groups && new_nodes.len() > 1 {
         // Create a group will all paths that was created during text-to-path conversion.
         parent.append_kind(NodeKind::Group(Group {
             id: node.element_id().to_string(),
@@ -77,10 +91,15 @@ pub(crate) fn convert(
     };
 
     let rendering_mode = convert::resolve_rendering_mode(text_node, state);
-    for mut path in new_paths {
-        fix_obj_bounding_box(&mut path, bbox);
-        path.rendering_mode = rendering_mode;
-        parent.append_kind(NodeKind::Path(path));
+    for node in new_nodes {
+        for child in node.descendants() {
+            if let NodeKind::Path(ref mut path) = *child.borrow_mut() {
+                fix_obj_bounding_box(path, bbox);
+                path.rendering_mode = rendering_mode;
+            }
+        }
+
+        parent.append(node);
     }
 }
 
@@ -89,7 +108,7 @@ fn text_to_paths(
     state: &converter::State,
     cache: &mut converter::Cache,
     parent: &mut Node,
-) -> (Vec<Path>, PathBbox) {
+) -> (Vec<Node>, PathBbox) {
     let abs_ts = {
         let mut ts = parent.abs_transform();
         ts.append(
@@ -109,31 +128,29 @@ fn text_to_paths(
     let mut char_offset = 0;
     let mut last_x = 0.0;
     let mut last_y = 0.0;
-    let mut new_paths = Vec::new();
+    let mut new_nodes = Vec::new();
     for chunk in &mut chunks {
         let (x, y) = match chunk.text_flow {
             TextFlow::Horizontal => (chunk.x.unwrap_or(last_x), chunk.y.unwrap_or(last_y)),
             TextFlow::Path(_) => (0.0, 0.0),
         };
 
-        let mut clusters = shaper::outline_chunk(chunk, state);
-        if clusters.is_empty() {
-            char_offset += chunk.text.chars().count();
-            continue;
-        }
-
-        shaper::apply_writing_mode(writing_mode, &mut clusters);
-        shaper::apply_letter_spacing(chunk, &mut clusters);
-        shaper::apply_word_spacing(chunk, &mut clusters);
-        let mut curr_pos = shaper::resolve_clusters_positions(
+        let (mut clusters, mut curr_pos) = match layout_chunk(
             chunk,
             char_offset,
             &pos_list,
             &rotate_list,
             writing_mode,
             abs_ts,
-            &mut clusters,
-        );
+            state,
+            cache,
+        ) {
+            Some(v) => v,
+            None => {
+                char_offset += chunk.text.chars().count();
+                continue;
+            }
+        };
 
         let mut text_ts = Transform::default();
         if writing_mode == WritingMode::TopToBottom {
@@ -170,7 +187,7 @@ fn text_to_paths(
                     bbox = bbox.expand(r);
                 }
 
-                new_paths.push(path);
+                new_nodes.push(Node::new(NodeKind::Path(path)));
             }
 
             if let Some(decoration) = span.decoration.overline.take() {
@@ -185,16 +202,24 @@ fn text_to_paths(
                     bbox = bbox.expand(r);
                 }
 
-                new_paths.push(path);
+                new_nodes.push(Node::new(NodeKind::Path(path)));
             }
 
+            // Must be called before `convert_span`, which takes the span fill.
+            let color_glyphs = convert_color_glyphs(span, &clusters, &span_ts);
+
             if let Some(path) = convert_span(span, &mut clusters, &span_ts, parent, false) {
                 // Use `text_bbox` here and not `path.data.bbox()`.
                 if let Some(r) = path.text_bbox {
                     bbox = bbox.expand(r.to_path_bbox());
                 }
 
-                new_paths.push(path);
+                new_nodes.push(Node::new(NodeKind::Path(path)));
+            }
+
+            if let Some((node, r)) = color_glyphs {
+                bbox = bbox.expand(r);
+                new_nodes.push(node);
             }
 
             if let Some(decoration) = span.decoration.line_through.take() {
@@ -211,7 +236,7 @@ fn text_to_paths(
                     bbox = bbox.expand(r);
                 }
 
-                new_paths.push(path);
+                new_nodes.push(Node::new(NodeKind::Path(path)));
             }
         }
 
@@ -227,7 +252,41 @@ fn text_to_paths(
         last_y = y + curr_pos.1;
     }
 
-    (new_paths, bbox)
+    (new_nodes, bbox)
+}
+
+/// Shapes and positions a text chunk.
+///
+/// Returns positioned clusters and the last text position, relative to the chunk start.
+fn layout_chunk(
+    chunk: &convert::TextChunk,
+    char_offset: usize,
+    pos_list: &[convert::CharacterPosition],
+    rotate_list: &[f64],
+    writing_mode: WritingMode,
+    abs_ts: Transform,
+    state: &converter::State,
+    cache: &mut converter::Cache,
+) -> Option<(Vec<OutlinedCluster>, (f64, f64))> {
+    let mut clusters = shaper::outline_chunk(chunk, writing_mode, state, cache);
+    if clusters.is_empty() {
+        return None;
+    }
+
+    shaper::apply_writing_mode(writing_mode, &mut clusters);
+    shaper::apply_letter_spacing(chunk, &mut clusters);
+    shaper::apply_word_spacing(chunk, &mut clusters);
+    let curr_pos = shaper::resolve_clusters_positions(
+        chunk,
+        char_offset,
+        pos_list,
+        rotate_list,
+        writing_mode,
+        abs_ts,
+        &mut clusters,
+    );
+
+    Some((clusters, curr_pos))
 }
 
 fn convert_span(
@@ -298,6 +357,46 @@ fn convert_span(
     Some(path)
 }
 
+/// Converts span's color glyphs into a group.
+///
+/// Returns the group and its text bbox.
+fn convert_color_glyphs(
+    span: &TextSpan,
+    clusters: &[OutlinedCluster],
+    text_ts: &Transform,
+) -> Option<(Node, PathBbox)> {
+    let mut group = None;
+    let mut bboxes_data = PathData::new();
+    for cluster in clusters {
+        if !cluster.visible || !span.contains(cluster.byte_idx) {
+            continue;
+        }
+
+        for glyph in &cluster.color_glyphs {
+            let mut ts = *text_ts;
+            ts.append(&cluster.transform);
+            ts.append(&glyph.transform);
+
+            let node = color::convert(glyph, ts, span.fill.as_ref(), span.visibility);
+            group
+                .get_or_insert_with(|| Node::new(NodeKind::Group(Group::default())))
+                .append(node);
+        }
+
+        if !cluster.color_glyphs.is_empty() {
+            // Just like in `convert_span`, use font metrics and not glyph shape.
+            if let Some(r) = Rect::new(0.0, -cluster.ascent, cluster.advance, cluster.height()) {
+                if let Some(r) = r.transform(&cluster.transform) {
+                    bboxes_data.push_rect(r);
+                }
+            }
+        }
+    }
+
+    bboxes_data.transform(*text_ts);
+    Some((group?, bboxes_data.bbox()?))
+}
+
 // Only for debug purposes.
 fn dump_cluster(cluster: &OutlinedCluster, text_ts: Transform, parent: &mut Node) {
     fn new_stroke(color: Color) -> Option<Stroke> {
@@ -2,16 +2,23 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::ops::Range;
+
 use kurbo::{ParamCurve, ParamCurveArclen, ParamCurveDeriv};
 use rustybuzz::ttf_parser::GlyphId;
+use rustybuzz::Variation;
 use unicode_script::UnicodeScript;
 use unicode_vo::Orientation as CharOrientation;
 
+use super::color::{self, ColorGlyph, ColorGlyphKind};
 use super::convert::{
-    ByteIndex, CharacterPosition, TextAnchor, TextChunk, TextFlow, TextPath, WritingMode,
+    BidiEmbedding, ByteIndex, CharacterPosition, TextAnchor, TextChunk, TextDirection, TextFlow,
+    TextOrientation, TextPath, TextSpan, UnicodeBidi, WritingMode,
 };
 use super::fontdb_ext::{self, DatabaseExt};
-use crate::{converter, CubicBezExt, FuzzyZero, IsValidLength, PathData, PathSegment, Transform};
+use crate::{
+    converter, CubicBezExt, FuzzyZero, IsValidLength, OptionsRef, PathData, PathSegment, Transform,
+};
 
 /// A glyph.
 ///
@@ -33,8 +40,13 @@ struct Glyph {
     dy: i32,
 
     /// The glyph width / X-advance in font units.
+    ///
+    /// A Y-advance for upright glyphs.
     width: i32,
 
+    /// Indicates that the glyph was shaped vertically.
+    upright: bool,
+
     /// Reference to the source font.
     ///
     /// Each glyph can have it's own source font.
@@ -87,6 +99,11 @@ pub struct OutlinedCluster {
     /// A x-height in SVG coordinates.
     pub x_height: f64,
 
+    /// Indicates that this cluster is upright in the vertical writing mode.
+    ///
+    /// Upright clusters are already rotated, so the text line can be rotated as a whole.
+    pub upright: bool,
+
     /// Indicates that this cluster was affected by the relative shift (via dx/dy attributes)
     /// during the text layouting. Which breaks the `text-decoration` line.
     ///
@@ -96,6 +113,9 @@ pub struct OutlinedCluster {
     /// An actual outline.
     pub path: PathData,
 
+    /// Color glyphs, which are not a part of the `path`.
+    pub color_glyphs: Vec<ColorGlyph>,
+
     /// A cluster's transform that contains it's position, rotation, etc.
     pub transform: Transform,
 
@@ -152,10 +172,28 @@ impl<'a> Iterator for GlyphClusters<'a> {
 ///
 /// This function will do the BIDI reordering, text shaping and glyphs outlining,
 /// but not the text layouting. So all clusters are in the 0x0 position.
-pub fn outline_chunk(chunk: &TextChunk, state: &converter::State) -> Vec<OutlinedCluster> {
+pub fn outline_chunk(
+    chunk: &TextChunk,
+    writing_mode: WritingMode,
+    state: &converter::State,
+    cache: &mut converter::Cache,
+) -> Vec<OutlinedCluster> {
+    let mut runs = bidi_runs(chunk, writing_mode);
+    if writing_mode == WritingMode::TopToBottom {
+        runs = split_runs_by_orientation(chunk, runs);
+    }
+
     let mut glyphs = Vec::new();
     for span in &chunk.spans {
-        let tmp_glyphs = shape_text(&chunk.text, span.font, span.small_caps, state);
+        let tmp_glyphs = shape_text(
+            &chunk.text,
+            &runs,
+            span.font,
+            span.small_caps,
+            &span.variations,
+            state,
+            cache,
+        );
 
         // Do nothing with the first run.
         if glyphs.is_empty() {
@@ -182,12 +220,11 @@ pub fn outline_chunk(chunk: &TextChunk, state: &converter::State) -> Vec<Outline
     let mut clusters = Vec::new();
     for (range, byte_idx) in GlyphClusters::new(&glyphs) {
         if let Some(span) = chunk.span_at(byte_idx) {
-            let db = &state.opt.fontdb;
             clusters.push(outline_cluster(
                 &glyphs[range],
                 &chunk.text,
-                span.font_size.get(),
-                db,
+                span,
+                state.opt,
             ));
         }
     }
@@ -195,14 +232,195 @@ pub fn outline_chunk(chunk: &TextChunk, state: &converter::State) -> Vec<Outline
     clusters
 }
 
+/// A text range with a single direction and glyph orientation.
+struct TextRun {
+    range: Range<usize>,
+    is_rtl: bool,
+    /// Upright runs are shaped vertically.
+    upright: bool,
+}
+
+const LRE: char = '\u{202A}';
+const RLE: char = '\u{202B}';
+const PDF: char = '\u{202C}';
+const LRO: char = '\u{202D}';
+const RLO: char = '\u{202E}';
+const LRI: char = '\u{2066}';
+const RLI: char = '\u{2067}';
+const FSI: char = '\u{2068}';
+const PDI: char = '\u{2069}';
+
+/// Splits a text chunk into BIDI runs in visual order.
+///
+/// Explicit embeddings, overrides and isolates are emulated by inserting BIDI control
+/// characters around spans. Control characters are not present in the resulting runs.
+///
+/// Upright text in the vertical writing mode is always left-to-right.
+fn bidi_runs(chunk: &TextChunk, writing_mode: WritingMode) -> Vec<TextRun> {
+    fn push_controls(controls: &[char], text: &mut String, indices: &mut Vec<Option<usize>>) {
+        for c in controls {
+            text.push(*c);
+            indices.resize(indices.len() + c.len_utf8(), None);
+        }
+    }
+
+    let mut text = String::with_capacity(chunk.text.len());
+    // Maps `text` bytes to `chunk.text` ones.
+    let mut indices = Vec::with_capacity(chunk.text.len());
+
+    let mut embeddings: &[BidiEmbedding] = &[];
+    for span in &chunk.spans {
+        let common = embeddings
+            .iter()
+            .zip(&span.bidi_embeddings)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        for embedding in embeddings[common..].iter().rev() {
+            push_controls(bidi_controls(embedding).1, &mut text, &mut indices);
+        }
+
+        for embedding in &span.bidi_embeddings[common..] {
+            push_controls(bidi_controls(embedding).0, &mut text, &mut indices);
+        }
+
+        embeddings = &span.bidi_embeddings;
+
+        let is_upright = writing_mode == WritingMode::TopToBottom
+            && span.text_orientation == TextOrientation::Upright;
+
+        if is_upright {
+            push_controls(&[LRO], &mut text, &mut indices);
+        }
+
+        text.push_str(&chunk.text[span.start..span.end]);
+        indices.extend((span.start..span.end).map(Some));
+
+        if is_upright {
+            push_controls(&[PDF], &mut text, &mut indices);
+        }
+    }
+
+    for embedding in embeddings.iter().rev() {
+        push_controls(bidi_controls(embedding).1, &mut text, &mut indices);
+    }
+
+    let level = match chunk.direction {
+        TextDirection::LeftToRight => unicode_bidi::Level::ltr(),
+        TextDirection::RightToLeft => unicode_bidi::Level::rtl(),
+    };
+
+    let bidi_info = unicode_bidi::BidiInfo::new(&text, Some(level));
+
+    let mut runs = Vec::new();
+    for paragraph in &bidi_info.paragraphs {
+        let (levels, visual_runs) = bidi_info.visual_runs(paragraph, paragraph.range.clone());
+        for run in visual_runs {
+            let mut run_indices = indices[run.clone()].iter().flatten();
+            let start = match run_indices.next() {
+                Some(idx) => *idx,
+                // A run with control characters only.
+                None => continue,
+            };
+            let end = run_indices.last().unwrap_or(&start) + 1;
+
+            runs.push(TextRun {
+                range: start..end,
+                is_rtl: levels[run.start].is_rtl(),
+                upright: false,
+            });
+        }
+    }
+
+    runs
+}
+
+/// Returns opening and closing BIDI control characters.
+fn bidi_controls(embedding: &BidiEmbedding) -> (&'static [char], &'static [char]) {
+    let is_rtl = embedding.direction == TextDirection::RightToLeft;
+    match (embedding.kind, is_rtl) {
+        (UnicodeBidi::Normal, _) => (&[], &[]),
+        (UnicodeBidi::Embed, false) => (&[LRE], &[PDF]),
+        (UnicodeBidi::Embed, true) => (&[RLE], &[PDF]),
+        (UnicodeBidi::BidiOverride, false) => (&[LRO], &[PDF]),
+        (UnicodeBidi::BidiOverride, true) => (&[RLO], &[PDF]),
+        (UnicodeBidi::Isolate, false) => (&[LRI], &[PDI]),
+        (UnicodeBidi::Isolate, true) => (&[RLI], &[PDI]),
+        (UnicodeBidi::IsolateOverride, false) => (&[LRI, LRO], &[PDF, PDI]),
+        (UnicodeBidi::IsolateOverride, true) => (&[RLI, RLO], &[PDF, PDI]),
+        (UnicodeBidi::Plaintext, _) => (&[FSI], &[PDI]),
+    }
+}
+
+/// Splits BIDI runs into runs with a single glyph orientation.
+///
+/// Combining marks use the orientation of the base character.
+fn split_runs_by_orientation(chunk: &TextChunk, runs: Vec<TextRun>) -> Vec<TextRun> {
+    let mut new_runs = Vec::with_capacity(runs.len());
+    for run in runs {
+        let mut sub_runs: Vec<TextRun> = Vec::new();
+        for (idx, c) in chunk.text[run.range.clone()].char_indices() {
+            let start = run.range.start + idx;
+            let end = start + c.len_utf8();
+
+            let upright = match sub_runs.last() {
+                Some(prev) if c.script() == unicode_script::Script::Inherited => prev.upright,
+                _ => chunk
+                    .span_at(ByteIndex::new(start))
+                    .map(|span| is_upright(c, span.text_orientation))
+                    .unwrap_or(false),
+            };
+
+            match sub_runs.last_mut() {
+                Some(prev) if prev.upright == upright => prev.range.end = end,
+                _ => sub_runs.push(TextRun {
+                    range: start..end,
+                    is_rtl: run.is_rtl,
+                    upright,
+                }),
+            }
+        }
+
+        // Runs must stay in the visual order.
+        if run.is_rtl {
+            sub_runs.reverse();
+        }
+
+        new_runs.extend(sub_runs);
+    }
+
+    new_runs
+}
+
+/// Checks that a character should be upright in the vertical writing mode.
+///
+/// In the `mixed` mode, uses the
+/// [Unicode Vertical_Orientation Property](https://www.unicode.org/reports/tr50/tr50-19.html).
+/// Characters that should be transformed or rotated (`Tr`) are rotated,
+/// since we cannot tell whether a font has a vertical alternate for them.
+fn is_upright(c: char, orientation: TextOrientation) -> bool {
+    match orientation {
+        TextOrientation::Mixed => matches!(
+            unicode_vo::char_orientation(c),
+            CharOrientation::Upright | CharOrientation::TransformedOrUpright
+        ),
+        TextOrientation::Upright => true,
+        TextOrientation::Sideways => false,
+    }
+}
+
 /// Text shaping with font fallback.
 fn shape_text(
     text: &str,
+    runs: &[TextRun],
     font: fontdb_ext::Font,
     small_caps: bool,
+    variations: &[Variation],
     state: &converter::State,
+    cache: &mut converter::Cache,
 ) -> Vec<Glyph> {
-    let mut glyphs = shape_text_with_font(text, font, small_caps, state).unwrap_or_default();
+    let mut glyphs =
+        shape_text_with_font(text, runs, font, small_caps, variations, state).unwrap_or_default();
 
     // Remember all fonts used for shaping.
     let mut used_fonts = vec![font.id];
@@ -218,14 +436,15 @@ fn shape_text(
         }
 
         if let Some(c) = missing {
-            let fallback_font = match find_font_for_char(c, &used_fonts, state) {
+            let fallback_font = match find_font_for_char(c, &used_fonts, state, cache) {
                 Some(v) => v,
                 None => break 'outer,
             };
 
             // Shape again, using a new font.
             let fallback_glyphs =
-                shape_text_with_font(text, fallback_font, small_caps, state).unwrap_or_default();
+                shape_text_with_font(text, runs, fallback_font, small_caps, variations, state)
+                    .unwrap_or_default();
 
             let all_matched = fallback_glyphs.iter().all(|g| !g.is_missing());
             if all_matched {
@@ -274,33 +493,37 @@ fn shape_text(
 
 /// Converts a text into a list of glyph IDs.
 ///
-/// This function will do the BIDI reordering and text shaping.
+/// This function will do the text shaping. Glyphs are in the `runs` order.
 fn shape_text_with_font(
     text: &str,
+    runs: &[TextRun],
     font: fontdb_ext::Font,
     small_caps: bool,
+    variations: &[Variation],
     state: &converter::State,
 ) -> Option<Vec<Glyph>> {
     state
         .opt
         .fontdb
         .with_face_data(font.id, |font_data, face_index| -> Option<Vec<Glyph>> {
-            let rb_font = rustybuzz::Face::from_slice(font_data, face_index)?;
+            let mut rb_font = rustybuzz::Face::from_slice(font_data, face_index)?;
+            rb_font.set_variations(variations);
 
-            let bidi_info = unicode_bidi::BidiInfo::new(text, Some(unicode_bidi::Level::ltr()));
-            let paragraph = &bidi_info.paragraphs[0];
-            let line = paragraph.range.clone();
+            // Unlike HarfBuzz, rustybuzz places the glyph top at the vertical origin
+            // when a font doesn't have vertical metrics. Use the ascender instead.
+            let fix_vertical_origin = rb_font.tables().vmtx.is_none();
 
             let mut glyphs = Vec::new();
-
-            let (levels, runs) = bidi_info.visual_runs(paragraph, line);
-            for run in runs.iter() {
-                let sub_text = &text[run.clone()];
+            for run in runs {
+                let sub_text = &text[run.range.clone()];
                 if sub_text.is_empty() {
                     continue;
                 }
 
-                let hb_direction = if levels[run.start].is_rtl() {
+                // The vertical direction enables the `vert` feature and vertical metrics.
+                let hb_direction = if run.upright {
+                    rustybuzz::Direction::TopToBottom
+                } else if run.is_rtl {
                     rustybuzz::Direction::RightToLeft
                 } else {
                     rustybuzz::Direction::LeftToRight
@@ -325,15 +548,28 @@ fn shape_text_with_font(
                 let infos = output.glyph_infos();
 
                 for (pos, info) in positions.iter().zip(infos) {
-                    let idx = run.start + info.cluster as usize;
+                    let idx = run.range.start + info.cluster as usize;
                     debug_assert!(text.get(idx..).is_some());
 
+                    let id = GlyphId(info.glyph_id as u16);
+
+                    let mut dy = pos.y_offset;
+                    if run.upright && fix_vertical_origin {
+                        let y_max = rb_font.glyph_bounding_box(id).map(|r| r.y_max).unwrap_or(0);
+                        dy += i32::from(y_max) - i32::from(rb_font.ascender());
+                    }
+
                     glyphs.push(Glyph {
                         byte_idx: ByteIndex::new(idx),
-                        id: GlyphId(info.glyph_id as u16),
+                        id,
                         dx: pos.x_offset,
-                        dy: pos.y_offset,
-                        width: pos.x_advance,
+                        dy,
+                        width: if run.upright {
+                            -pos.y_advance
+                        } else {
+                            pos.x_advance
+                        },
+                        upright: run.upright,
                         font,
                     });
                 }
@@ -349,38 +585,80 @@ fn shape_text_with_font(
 fn outline_cluster(
     glyphs: &[Glyph],
     text: &str,
-    font_size: f64,
-    db: &fontdb::Database,
+    span: &TextSpan,
+    opt: &OptionsRef,
 ) -> OutlinedCluster {
+    let font_size = span.font_size.get();
+
     debug_assert!(!glyphs.is_empty());
 
     let mut path = PathData::new();
+    let mut color_glyphs = Vec::new();
     let mut width = 0.0;
     let mut x = 0.0;
 
     for glyph in glyphs {
-        let mut outline = db.outline(glyph.font.id, glyph.id).unwrap_or_default();
-
         let sx = glyph.font.scale(font_size);
 
-        if !outline.is_empty() {
+        let mut ts = if glyph.upright {
+            // Upright glyphs are rotated counterclockwise and mirrored,
+            // since the whole text line will be rotated clockwise later.
+            Transform::new(0.0, -1.0, -1.0, 0.0, 0.0, 0.0)
+        } else {
             // By default, glyphs are upside-down, so we have to mirror them.
-            let mut ts = Transform::new_scale(1.0, -1.0);
-
-            // Scale to font-size.
-            ts.scale(sx, sx);
+            Transform::new_scale(1.0, -1.0)
+        };
 
-            // Apply offset.
-            //
-            // The first glyph in the cluster will have an offset from 0x0,
-            // but the later one will have an offset from the "current position".
-            // So we have to keep an advance.
-            // TODO: should be done only inside a single text span
+        // Scale to font-size.
+        ts.scale(sx, sx);
+
+        // Apply offset.
+        //
+        // The first glyph in the cluster will have an offset from 0x0,
+        // but the later one will have an offset from the "current position".
+        // So we have to keep an advance.
+        // TODO: should be done only inside a single text span
+        if glyph.upright {
+            ts.translate(glyph.dx as f64, glyph.dy as f64 - x);
+        } else {
             ts.translate(x + glyph.dx as f64, glyph.dy as f64);
+        }
+
+        if let Some(angle) = span.synthesis.oblique {
+            if !glyph.font.is_oblique() {
+                // Slant to the right. The Y axis points up in font units.
+                ts.append(&Transform::new(
+                    1.0,
+                    0.0,
+                    angle.to_radians().tan(),
+                    1.0,
+                    0.0,
+                    0.0,
+                ));
+            }
+        }
 
-            outline.transform(ts);
+        if let Some(kind) = color::load(opt, glyph.font.id, glyph.id, &span.variations) {
+            // Images are already in the SVG coordinate system.
+            let mut transform = ts;
+            if let ColorGlyphKind::Image(_) = kind {
+                transform.scale(1.0, -1.0);
+            }
+
+            color_glyphs.push(ColorGlyph { transform, kind });
+        } else {
+            let mut outline = opt
+                .fontdb
+                .outline(glyph.font.id, glyph.id, &span.variations)
+                .unwrap_or_default();
+            if !outline.is_empty() {
+                if span.synthesis.bold && !glyph.font.is_bold() {
+                    outline = fontdb_ext::embolden(&outline, glyph.font.synthetic_bold_offset());
+                }
 
-            path.push_path(&outline);
+                outline.transform(ts);
+                path.push_path(&outline);
+            }
         }
 
         x += glyph.width as f64;
@@ -393,16 +671,28 @@ fn outline_cluster(
 
     let byte_idx = glyphs[0].byte_idx;
     let font = glyphs[0].font;
+    let upright = glyphs[0].upright;
     OutlinedCluster {
         byte_idx,
         codepoint: byte_idx.char_from(text),
         width,
         advance: width,
-        ascent: font.ascent(font_size),
-        descent: font.descent(font_size),
+        // Upright clusters are centered on the baseline and use the em box.
+        ascent: if upright {
+            font_size / 2.0
+        } else {
+            font.ascent(font_size)
+        },
+        descent: if upright {
+            -font_size / 2.0
+        } else {
+            font.descent(font_size)
+        },
         x_height: font.x_height(font_size),
+        upright,
         has_relative_shift: false,
         path,
+        color_glyphs,
         transform: Transform::default(),
         visible: true,
     }
@@ -410,23 +700,103 @@ fn outline_cluster(
 
 /// Finds a font with a specified char.
 ///
-/// This is a rudimentary font fallback algorithm.
+/// Uses `Options::font_fallback` first and then any font in the database.
+/// Decisions are cached per parsing.
 fn find_font_for_char(
     c: char,
     exclude_fonts: &[fontdb::ID],
     state: &converter::State,
+    cache: &mut converter::Cache,
 ) -> Option<fontdb_ext::Font> {
     let base_font_id = exclude_fonts[0];
+    let key = (c, base_font_id);
 
-    // Iterate over fonts and check if any of them support the specified char.
-    for face in state.opt.fontdb.faces() {
-        // Ignore fonts, that were used for shaping already.
-        if exclude_fonts.contains(&face.id) {
-            continue;
+    let id = match cache.fallback_fonts.get(&key) {
+        // A cached font could be already used for this text. Search again in this case.
+        Some(Some(id)) if exclude_fonts.contains(id) => {
+            find_fallback_face(c, exclude_fonts, state)?
+        }
+        Some(id) => (*id)?,
+        None => {
+            let id = find_fallback_face(c, exclude_fonts, state);
+            cache.fallback_fonts.insert(key, id);
+            id?
         }
+    };
+
+    let db = &state.opt.fontdb;
+    log::warn!(
+        "Fallback from {} to {}.",
+        db.face(base_font_id)?.family,
+        db.face(id)?.family
+    );
+    db.load_font(id)
+}
+
+fn find_fallback_face(
+    c: char,
+    exclude_fonts: &[fontdb::ID],
+    state: &converter::State,
+) -> Option<fontdb::ID> {
+    let db = &state.opt.fontdb;
+    let fallback = state.opt.font_fallback;
+    let base_face = db.face(exclude_fonts[0])?;
 
+    let is_suitable = |id: fontdb::ID| !exclude_fonts.contains(&id) && db.has_char(id, c);
+
+    if let Some(ref select) = fallback.select {
+        if let Some(id) = select(c, base_face, state.opt) {
+            if is_suitable(id) {
+                return Some(id);
+            }
+        }
+    }
+
+    let mut families: Vec<&str> = Vec::new();
+    if is_emoji(c) {
+        families.extend(fallback.emoji_families.iter().map(|s| s.as_str()));
+    }
+
+    for lang in state.opt.languages {
+        // Try `zh-TW` and then `zh`.
+        let primary = lang.split('-').next().unwrap_or(lang);
+        for tag in &[lang.as_str(), primary] {
+            if let Some(list) = fallback.language_families.get(*tag) {
+                families.extend(list.iter().map(|s| s.as_str()));
+            }
+        }
+    }
+
+    let script = c.script();
+    if !matches!(
+        script,
+        unicode_script::Script::Common | unicode_script::Script::Inherited
+    ) {
+        if let Some(list) = fallback.script_families.get(script.short_name()) {
+            families.extend(list.iter().map(|s| s.as_str()));
+        }
+    }
+
+    families.extend(fallback.families.iter().map(|s| s.as_str()));
+
+    for family in families {
+        let query = fontdb::Query {
+            families: &[fontdb::Family::Name(family)],
+            weight: base_face.weight,
+            stretch: base_face.stretch,
+            style: base_face.style,
+        };
+
+        if let Some(id) = db.query(&query) {
+            if is_suitable(id) {
+                return Some(id);
+            }
+        }
+    }
+
+    // Iterate over fonts and check if any of them support the specified char.
+    for face in db.faces() {
         // Check that the new face has the same style.
-        let base_face = state.opt.fontdb.face(base_font_id)?;
         if base_face.style != face.style
             && base_face.weight != face.weight
             && base_face.stretch != face.stretch
@@ -434,17 +804,25 @@ fn find_font_for_char(
             continue;
         }
 
-        if !state.opt.fontdb.has_char(face.id, c) {
-            continue;
+        if is_suitable(face.id) {
+            return Some(face.id);
         }
-
-        log::warn!("Fallback from {} to {}.", base_face.family, face.family);
-        return state.opt.fontdb.load_font(face.id);
     }
 
     None
 }
 
+/// Checks that a character is an emoji.
+///
+/// Covers only the main emoji blocks and not the whole Unicode `Emoji` property,
+/// since characters like digits are usually rendered using text fonts.
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2B00..=0x2BFF | 0xFE0F | 0x200D
+    )
+}
+
 /// Resolves clusters positions.
 ///
 /// Mainly sets the `transform` property.
@@ -773,6 +1151,7 @@ pub fn apply_letter_spacing(chunk: &TextChunk, clusters: &mut [OutlinedCluster])
                     cluster.width = 0.0;
                     cluster.advance = 0.0;
                     cluster.path.clear();
+                    cluster.color_glyphs.clear();
                 }
             }
         }
@@ -845,30 +1224,16 @@ fn is_word_separator_characters(c: char) -> bool {
     )
 }
 
-/// Rotates clusters according to
-/// [Unicode Vertical_Orientation Property](https://www.unicode.org/reports/tr50/tr50-19.html).
+/// Shifts sideways clusters in the top-to-bottom mode.
+///
+/// Upright clusters are already rotated during outlining.
 pub fn apply_writing_mode(writing_mode: WritingMode, clusters: &mut [OutlinedCluster]) {
     if writing_mode != WritingMode::TopToBottom {
         return;
     }
 
     for cluster in clusters {
-        let orientation = unicode_vo::char_orientation(cluster.codepoint);
-        if orientation == CharOrientation::Upright {
-            // Additional offset. Not sure why.
-            let dy = cluster.width - cluster.height();
-
-            // Rotate a cluster 90deg counter clockwise by the center.
-            let mut ts = Transform::default();
-            ts.translate(cluster.width / 2.0, 0.0);
-            ts.rotate(-90.0);
-            ts.translate(-cluster.width / 2.0, -dy);
-            cluster.path.transform(ts);
-
-            // Move "baseline" to the middle and make height equal to width.
-            cluster.ascent = cluster.width / 2.0;
-            cluster.descent = -cluster.width / 2.0;
-        } else {
+        if !cluster.upright {
             // Could not find a spec that explains this,
             // but this is how other applications are shifting the "rotated" characters
             // in the top-to-bottom mode.
@@ -0,0 +1,97 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+/// A big-endian data reader.
+pub struct Stream<'a> {
+    pub data: &'a [u8],
+    pub offset: usize,
+}
+
+impl<'a> Stream<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Stream { data, offset: 0 }
+    }
+
+    /// Creates a stream at the specified offset.
+    ///
+    /// Returns `None` when the offset is out of bounds.
+    pub fn new_at(data: &'a [u8], offset: usize) -> Option<Self> {
+        if offset <= data.len() {
+            Some(Stream { data, offset })
+        } else {
+            None
+        }
+    }
+
+    pub fn skip(&mut self, len: usize) -> Option<()> {
+        self.read_bytes(len).map(|_| ())
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.offset..self.offset.checked_add(len)?)?;
+        self.offset += len;
+        Some(bytes)
+    }
+
+    pub fn read_u8(&mut self) -> Option<u8> {
+        self.read_bytes(1).map(|b| b[0])
+    }
+
+    pub fn read_u16(&mut self) -> Option<u16> {
+        self.read_bytes(2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    pub fn read_i16(&mut self) -> Option<i16> {
+        self.read_u16().map(|n| n as i16)
+    }
+
+    pub fn read_u24(&mut self) -> Option<u32> {
+        self.read_bytes(3)
+            .map(|b| u32::from_be_bytes([0, b[0], b[1], b[2]]))
+    }
+
+    pub fn read_u32(&mut self) -> Option<u32> {
+        self.read_bytes(4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// Reads a `UIntBase128` number.
+    pub fn read_base128(&mut self) -> Option<u32> {
+        let mut n = 0u32;
+        for i in 0..5 {
+            let b = self.read_u8()?;
+            // Leading zeros are not allowed.
+            if i == 0 && b == 0x80 {
+                return None;
+            }
+
+            // Overflow check.
+            if n & 0xFE000000 != 0 {
+                return None;
+            }
+
+            n = (n << 7) | u32::from(b & 0x7F);
+            if b & 0x80 == 0 {
+                return Some(n);
+            }
+        }
+
+        None
+    }
+
+    /// Reads a `255UInt16` number.
+    pub fn read_255_u16(&mut self) -> Option<u16> {
+        const ONE_MORE_BYTE_CODE_1: u8 = 255;
+        const ONE_MORE_BYTE_CODE_2: u8 = 254;
+        const WORD_CODE: u8 = 253;
+        const LOWEST_U_CODE: u16 = 253;
+
+        match self.read_u8()? {
+            WORD_CODE => self.read_u16(),
+            ONE_MORE_BYTE_CODE_1 => Some(u16::from(self.read_u8()?) + LOWEST_U_CODE),
+            ONE_MORE_BYTE_CODE_2 => Some(u16::from(self.read_u8()?) + LOWEST_U_CODE * 2),
+            n => Some(u16::from(n)),
+        }
+    }
+}
@@ -0,0 +1,147 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use crate::svgtree::FontFace;
+use crate::{OptionLog, OptionsRef};
+
+/// Loads `@font-face` rules into a copy of `OptionsRef::fontdb`.
+///
+/// Returns `None` when no fonts were loaded.
+pub(crate) fn load(faces: &[FontFace], opt: &OptionsRef) -> Option<fontdb::Database> {
+    let mut fontdb = None;
+    for face in faces {
+        let info = match face_info(face, opt) {
+            Some(v) => v,
+            None => {
+                log::warn!("Failed to load the '{}' @font-face.", face.family);
+                continue;
+            }
+        };
+
+        fontdb
+            .get_or_insert_with(|| opt.fontdb.clone())
+            .push_face_info(info);
+    }
+
+    fontdb
+}
+
+fn face_info(face: &FontFace, opt: &OptionsRef) -> Option<fontdb::FaceInfo> {
+    // Use the first source that can be loaded.
+    let mut info = split_list(&face.src)
+        .into_iter()
+        .find_map(|src| load_source(src, opt))?;
+
+    info.family = face.family.clone();
+    info.weight = face
+        .weight
+        .as_deref()
+        .map(parse_weight)
+        .unwrap_or(fontdb::Weight::NORMAL);
+    info.style = face
+        .style
+        .as_deref()
+        .map(parse_style)
+        .unwrap_or(fontdb::Style::Normal);
+
+    Some(info)
+}
+
+fn load_source(src: &str, opt: &OptionsRef) -> Option<fontdb::FaceInfo> {
+    if let Some(name) = function_arg(src, "local") {
+        return opt
+            .fontdb
+            .faces()
+            .iter()
+            .find(|face| face.post_script_name == name || face.family == name)
+            .cloned();
+    }
+
+    let url = function_arg(src, "url")?;
+    let data = if let Ok(url) = data_url::DataUrl::process(url) {
+        url.decode_to_vec().ok()?.0
+    } else if url.contains("://") {
+        log::warn!(
+            "Only local fonts can be loaded via @font-face. '{}' skipped.",
+            url
+        );
+        return None;
+    } else {
+        let path = opt.get_abs_path(std::path::Path::new(url));
+        std::fs::read(&path)
+            .ok()
+            .log_none(|| log::warn!("Failed to load '{}'.", path.display()))?
+    };
+
+    let data =
+        super::decode_web_font(data).log_none(|| log::warn!("Failed to decode a web font."))?;
+
+    let mut fontdb = fontdb::Database::new();
+    fontdb.load_font_data(data);
+    fontdb.faces().first().cloned()
+}
+
+/// Returns an unquoted argument of a CSS function like `url(...)`.
+///
+/// Anything after the function, like `format(...)`, is ignored.
+fn function_arg<'a>(text: &'a str, name: &str) -> Option<&'a str> {
+    let prefix = text.get(..name.len() + 1)?;
+    if !prefix[..name.len()].eq_ignore_ascii_case(name) || !prefix.ends_with('(') {
+        return None;
+    }
+
+    let arg = text[name.len() + 1..].trim_start();
+    match arg.chars().next()? {
+        quote @ '\'' | quote @ '"' => {
+            let arg = &arg[1..];
+            arg.find(quote).map(|end| &arg[..end])
+        }
+        _ => arg.find(')').map(|end| arg[..end].trim()),
+    }
+}
+
+/// Splits a comma-separated list, ignoring commas inside functions and strings.
+fn split_list(text: &str) -> Vec<&str> {
+    let mut list = Vec::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if q == c => quote = None,
+            (Some(_), _) => {}
+            (None, '\'') | (None, '"') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                list.push(text[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+
+    list.push(text[start..].trim());
+    list
+}
+
+fn parse_weight(text: &str) -> fontdb::Weight {
+    // Variable fonts can specify a range. Use the lower bound.
+    let text = text.split_whitespace().next().unwrap_or_default();
+    match text {
+        "bold" => fontdb::Weight::BOLD,
+        _ => match text.parse::<f64>() {
+            Ok(n) if (1.0..=1000.0).contains(&n) => fontdb::Weight(n.round() as u16),
+            _ => fontdb::Weight::NORMAL,
+        },
+    }
+}
+
+fn parse_style(text: &str) -> fontdb::Style {
+    match text.split_whitespace().next() {
+        Some("italic") => fontdb::Style::Italic,
+        Some("oblique") => fontdb::Style::Oblique,
+        _ => fontdb::Style::Normal,
+    }
+}
@@ -0,0 +1,947 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! WOFF and WOFF2 to sfnt conversion.
+//!
+//! WOFF tables are optionally compressed with zlib one by one.
+//! WOFF2 tables are compressed with brotli as a single stream,
+//! while `glyf`, `loca` and `hmtx` can be additionally transformed.
+//!
+//! See <https://www.w3.org/TR/WOFF/> and <https://www.w3.org/TR/WOFF2/>.
+
+use std::convert::TryFrom;
+use std::io::Read;
+use std::path::Path;
+
+use super::stream::Stream;
+
+const WOFF_SIGNATURE: &[u8; 4] = b"wOFF";
+const WOFF2_SIGNATURE: &[u8; 4] = b"wOF2";
+const TTC_FLAVOR: u32 = 0x74746366; // ttcf
+
+const GLYF: u32 = 0x676C7966;
+const LOCA: u32 = 0x6C6F6361;
+const HMTX: u32 = 0x686D7478;
+const HHEA: u32 = 0x68686561;
+const HEAD: u32 = 0x68656164;
+
+/// Prevents huge allocations on malformed data.
+const MAX_SFNT_SIZE: usize = 256 * 1024 * 1024;
+
+/// Known WOFF2 table tags, indexed by a table directory flag.
+const KNOWN_TAGS: &[&[u8; 4]; 63] = &[
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm",
+    b"glyf", b"loca", b"prep", b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern",
+    b"LTSH", b"PCLT", b"VDMX", b"vhea", b"vmtx", b"BASE", b"GDEF", b"GPOS", b"GSUB", b"EBSC",
+    b"JSTF", b"MATH", b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt", b"avar",
+    b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar", b"gvar", b"hsty",
+    b"just", b"lcar", b"mort", b"morx", b"opbd", b"prop", b"trak", b"Zapf", b"Silf", b"Glat",
+    b"Gloc", b"Feat", b"Sill",
+];
+
+/// Converts WOFF and WOFF2 font data into TrueType/OpenType data.
+///
+/// Any other data is returned as is.
+/// Returns `None` when a web font is malformed.
+pub fn decode_web_font(data: Vec<u8>) -> Option<Vec<u8>> {
+    match data.get(0..4) {
+        Some(s) if s == WOFF_SIGNATURE => decode_woff(&data),
+        Some(s) if s == WOFF2_SIGNATURE => decode_woff2(&data),
+        _ => Some(data),
+    }
+}
+
+/// Web fonts loading for [`fontdb::Database`].
+pub trait WebFontsExt {
+    /// Loads a font data into the database.
+    ///
+    /// Unlike [`fontdb::Database::load_font_data`], accepts WOFF and WOFF2 data as well.
+    /// Malformed web fonts are ignored.
+    fn load_web_font_data(&mut self, data: Vec<u8>);
+
+    /// Loads a font file into the database.
+    ///
+    /// Unlike [`fontdb::Database::load_font_file`], accepts WOFF and WOFF2 files as well.
+    fn load_web_font_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), std::io::Error>;
+}
+
+impl WebFontsExt for fontdb::Database {
+    fn load_web_font_data(&mut self, data: Vec<u8>) {
+        match decode_web_font(data) {
+            Some(data) => self.load_font_data(data),
+            None => log::warn!("Failed to decode a web font."),
+        }
+    }
+
+    fn load_web_font_file<P: AsRef<Path>>(&mut self, path: P) -> Result<(), std::io::Error> {
+        let path = path.as_ref();
+
+        let mut signature = [0; 4];
+        let is_web_font = std::fs::File::open(path)?
+            .read_exact(&mut signature)
+            .map(|_| &signature == WOFF_SIGNATURE || &signature == WOFF2_SIGNATURE)
+            .unwrap_or(false);
+
+        if !is_web_font {
+            // Let `fontdb` handle it, so memmaping would be used when enabled.
+            #[cfg(feature = "system-fonts")]
+            {
+                return self.load_font_file(path);
+            }
+
+            #[cfg(not(feature = "system-fonts"))]
+            {
+                self.load_font_data(std::fs::read(path)?);
+                return Ok(());
+            }
+        }
+
+        let data = decode_web_font(std::fs::read(path)?).ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidData, "malformed web font")
+        })?;
+        self.load_font_data(data);
+        Ok(())
+    }
+}
+
+struct TableEntry {
+    tag: u32,
+    data: Vec<u8>,
+}
+
+fn decode_woff(data: &[u8]) -> Option<Vec<u8>> {
+    let mut s = Stream::new(data);
+    s.skip(4)?; // signature
+    let flavor = s.read_u32()?;
+    let length = s.read_u32()? as usize;
+    let num_tables = s.read_u16()?;
+    s.skip(2)?; // reserved
+    let total_sfnt_size = s.read_u32()? as usize;
+    if length != data.len() || num_tables == 0 || total_sfnt_size > MAX_SFNT_SIZE {
+        return None;
+    }
+
+    s.skip(24)?; // version, metadata and private data
+    let mut tables = Vec::with_capacity(usize::from(num_tables));
+    for _ in 0..num_tables {
+        let tag = s.read_u32()?;
+        let offset = s.read_u32()? as usize;
+        let comp_length = s.read_u32()? as usize;
+        let orig_length = s.read_u32()? as usize;
+        s.skip(4)?; // checksum
+
+        if orig_length > total_sfnt_size {
+            return None;
+        }
+
+        let comp_data = data.get(offset..offset.checked_add(comp_length)?)?;
+        let table_data = if comp_length < orig_length {
+            let mut buf = Vec::with_capacity(orig_length);
+            flate2::read::ZlibDecoder::new(comp_data)
+                .take(orig_length as u64)
+                .read_to_end(&mut buf)
+                .ok()?;
+            buf
+        } else if comp_length == orig_length {
+            comp_data.to_vec()
+        } else {
+            return None;
+        };
+
+        if table_data.len() != orig_length {
+            return None;
+        }
+
+        tables.push(TableEntry {
+            tag,
+            data: table_data,
+        });
+    }
+
+    Some(write_sfnt(flavor, tables))
+}
+
+struct Woff2Table {
+    tag: u32,
+    orig_length: usize,
+    /// Transformed tables have a custom layout and have to be reconstructed.
+    transformed: bool,
+    /// A range in the decompressed stream.
+    range: std::ops::Range<usize>,
+}
+
+struct Woff2Font {
+    flavor: u32,
+    /// Indices in the tables list.
+    tables: Vec<usize>,
+}
+
+fn decode_woff2(data: &[u8]) -> Option<Vec<u8>> {
+    let mut s = Stream::new(data);
+    s.skip(4)?; // signature
+    let flavor = s.read_u32()?;
+    let length = s.read_u32()? as usize;
+    let num_tables = s.read_u16()?;
+    s.skip(2)?; // reserved
+    let total_sfnt_size = s.read_u32()? as usize;
+    let total_compressed_size = s.read_u32()? as usize;
+    if length != data.len() || num_tables == 0 || total_sfnt_size > MAX_SFNT_SIZE {
+        return None;
+    }
+
+    s.skip(24)?; // version, metadata and private data
+
+    let mut tables = Vec::with_capacity(usize::from(num_tables));
+    let mut offset = 0usize;
+    for _ in 0..num_tables {
+        let flags = s.read_u8()?;
+        let tag = match flags & 0x3F {
+            0x3F => s.read_u32()?,
+            n => u32::from_be_bytes(*KNOWN_TAGS[usize::from(n)]),
+        };
+
+        // For `glyf` and `loca` the version 0 means a transformed table.
+        let version = flags >> 6;
+        let transformed = if tag == GLYF || tag == LOCA {
+            version == 0
+        } else {
+            version != 0
+        };
+
+        let orig_length = s.read_base128()? as usize;
+        let length = if transformed {
+            s.read_base128()? as usize
+        } else {
+            orig_length
+        };
+
+        // A transformed `loca` table must be empty, since it will be rebuilt from `glyf`.
+        if orig_length > total_sfnt_size || (tag == LOCA && transformed && length != 0) {
+            return None;
+        }
+
+        let end = offset.checked_add(length)?;
+        tables.push(Woff2Table {
+            tag,
+            orig_length,
+            transformed,
+            range: offset..end,
+        });
+        offset = end;
+    }
+
+    let fonts = if flavor == TTC_FLAVOR {
+        let version = s.read_u32()?;
+        let num_fonts = s.read_255_u16()?;
+        let mut fonts = Vec::with_capacity(usize::from(num_fonts));
+        for _ in 0..num_fonts {
+            let num_tables = s.read_255_u16()?;
+            let flavor = s.read_u32()?;
+            let mut indices = Vec::with_capacity(usize::from(num_tables));
+            for _ in 0..num_tables {
+                let idx = usize::from(s.read_255_u16()?);
+                if idx >= tables.len() {
+                    return None;
+                }
+
+                indices.push(idx);
+            }
+
+            fonts.push(Woff2Font {
+                flavor,
+                tables: indices,
+            });
+        }
+
+        Some((version, fonts))
+    } else {
+        None
+    };
+
+    let compressed = s.read_bytes(total_compressed_size)?;
+    let mut stream = Vec::with_capacity(offset);
+    brotli_decompressor::Decompressor::new(compressed, 4096)
+        .take(offset as u64)
+        .read_to_end(&mut stream)
+        .ok()?;
+    if stream.len() != offset {
+        return None;
+    }
+
+    match fonts {
+        Some((version, fonts)) => {
+            let mut table_data = vec![None; tables.len()];
+            let mut faces = Vec::with_capacity(fonts.len());
+            for font in &fonts {
+                reconstruct_font(&font.tables, &tables, &stream, &mut table_data)?;
+                let entries = font
+                    .tables
+                    .iter()
+                    .map(|&idx| (tables[idx].tag, idx))
+                    .collect();
+                faces.push((font.flavor, entries));
+            }
+
+            let table_data: Option<Vec<Vec<u8>>> = table_data.into_iter().collect();
+            Some(write_collection(version, &faces, table_data?))
+        }
+        None => {
+            let indices: Vec<usize> = (0..tables.len()).collect();
+            let mut table_data = vec![None; tables.len()];
+            reconstruct_font(&indices, &tables, &stream, &mut table_data)?;
+
+            let mut entries = Vec::with_capacity(tables.len());
+            for (table, data) in tables.iter().zip(table_data) {
+                entries.push(TableEntry {
+                    tag: table.tag,
+                    data: data?,
+                });
+            }
+
+            Some(write_sfnt(flavor, entries))
+        }
+    }
+}
+
+/// Reconstructs font tables that are not yet reconstructed.
+///
+/// Tables can be shared between collection fonts, so they are reconstructed only once.
+fn reconstruct_font(
+    indices: &[usize],
+    tables: &[Woff2Table],
+    stream: &[u8],
+    table_data: &mut [Option<Vec<u8>>],
+) -> Option<()> {
+    let find = |tag: u32| indices.iter().copied().find(|&idx| tables[idx].tag == tag);
+
+    // Glyph xMin values, which are required by the `hmtx` transform.
+    let mut x_mins = None;
+    if let Some(glyf_idx) = find(GLYF) {
+        let glyf = &tables[glyf_idx];
+        if glyf.transformed {
+            // A transformed `glyf` must be paired with a transformed `loca`.
+            let loca_idx = find(LOCA)?;
+            let loca = &tables[loca_idx];
+            if !loca.transformed {
+                return None;
+            }
+
+            let glyphs = reconstruct_glyf(&stream[glyf.range.clone()])?;
+            if glyphs.loca.len() != loca.orig_length {
+                return None;
+            }
+
+            x_mins = Some(glyphs.x_mins);
+            table_data[glyf_idx] = Some(glyphs.glyf);
+            table_data[loca_idx] = Some(glyphs.loca);
+        }
+    }
+
+    for &idx in indices {
+        let table = &tables[idx];
+        if table_data[idx].is_some() {
+            continue;
+        }
+
+        let data = &stream[table.range.clone()];
+        let data = if !table.transformed {
+            data.to_vec()
+        } else if table.tag == HMTX {
+            let hhea = &stream[tables[find(HHEA)?].range.clone()];
+            let num_h_metrics = Stream::new(hhea.get(34..36)?).read_u16()?;
+            reconstruct_hmtx(data, num_h_metrics, x_mins.as_deref()?)?
+        } else {
+            // Unknown transform or a `glyf` without `loca`.
+            return None;
+        };
+
+        if data.len() != table.orig_length {
+            return None;
+        }
+
+        table_data[idx] = Some(data);
+    }
+
+    Some(())
+}
+
+struct Glyphs {
+    glyf: Vec<u8>,
+    loca: Vec<u8>,
+    x_mins: Vec<i16>,
+}
+
+fn reconstruct_glyf(data: &[u8]) -> Option<Glyphs> {
+    let mut s = Stream::new(data);
+    s.skip(2)?; // reserved
+
+    // The overlap bitmap is not needed, since it doesn't affect the rendering.
+    s.skip(2)?; // option flags
+    let num_glyphs = s.read_u16()?;
+    let index_format = s.read_u16()?;
+
+    let mut streams = [&[][..]; 7];
+    let mut sizes = [0usize; 7];
+    for size in sizes.iter_mut() {
+        *size = s.read_u32()? as usize;
+    }
+    for (stream, size) in streams.iter_mut().zip(sizes.iter()) {
+        *stream = s.read_bytes(*size)?;
+    }
+
+    let mut n_contour_stream = Stream::new(streams[0]);
+    let mut n_points_stream = Stream::new(streams[1]);
+    let mut flag_stream = Stream::new(streams[2]);
+    let mut glyph_stream = Stream::new(streams[3]);
+    let mut composite_stream = Stream::new(streams[4]);
+    let mut bbox_stream = Stream::new(streams[5]);
+    let mut instruction_stream = Stream::new(streams[6]);
+
+    let bbox_bitmap_len = ((usize::from(num_glyphs) + 31) >> 5) << 2;
+    let bbox_bitmap = bbox_stream.read_bytes(bbox_bitmap_len)?;
+
+    let mut glyf = Vec::new();
+    let mut offsets = Vec::with_capacity(usize::from(num_glyphs) + 1);
+    let mut x_mins = Vec::with_capacity(usize::from(num_glyphs));
+    let mut points = Vec::new();
+    let mut end_points = Vec::new();
+    for glyph_id in 0..usize::from(num_glyphs) {
+        offsets.push(glyf.len());
+
+        let has_bbox = bbox_bitmap[glyph_id >> 3] & (0x80 >> (glyph_id & 7)) != 0;
+        let n_contours = n_contour_stream.read_i16()?;
+        if n_contours == 0 {
+            if has_bbox {
+                return None;
+            }
+
+            x_mins.push(0);
+            continue;
+        }
+
+        if n_contours == -1 {
+            // Composite glyphs must have an explicit bbox.
+            if !has_bbox {
+                return None;
+            }
+
+            let bbox = bbox_stream.read_bytes(8)?;
+            let (components, have_instructions) = composite_glyph(&mut composite_stream)?;
+
+            glyf.write_i16(-1);
+            glyf.extend_from_slice(bbox);
+            glyf.extend_from_slice(components);
+            if have_instructions {
+                let len = glyph_stream.read_255_u16()?;
+                glyf.write_u16(len);
+                glyf.extend_from_slice(instruction_stream.read_bytes(usize::from(len))?);
+            }
+
+            x_mins.push(Stream::new(bbox).read_i16()?);
+        } else if n_contours > 0 {
+            end_points.clear();
+            let mut total_points = 0u16;
+            for _ in 0..n_contours {
+                let n = n_points_stream.read_255_u16()?;
+                total_points = total_points.checked_add(n)?;
+                end_points.push(total_points.checked_sub(1)?);
+            }
+
+            points.clear();
+            let (mut x, mut y) = (0i16, 0i16);
+            for _ in 0..total_points {
+                let flag = flag_stream.read_u8()?;
+                let (dx, dy) = read_triplet(flag, &mut glyph_stream)?;
+                x = x.wrapping_add(dx);
+                y = y.wrapping_add(dy);
+                points.push((x, y, flag & 0x80 == 0));
+            }
+
+            let instructions_len = glyph_stream.read_255_u16()?;
+            let instructions = instruction_stream.read_bytes(usize::from(instructions_len))?;
+
+            let (x_min, y_min, x_max, y_max) = if has_bbox {
+                let mut bbox = Stream::new(bbox_stream.read_bytes(8)?);
+                (
+                    bbox.read_i16()?,
+                    bbox.read_i16()?,
+                    bbox.read_i16()?,
+                    bbox.read_i16()?,
+                )
+            } else {
+                points.iter().fold(
+                    (i16::MAX, i16::MAX, i16::MIN, i16::MIN),
+                    |(x_min, y_min, x_max, y_max), &(x, y, _)| {
+                        (x_min.min(x), y_min.min(y), x_max.max(x), y_max.max(y))
+                    },
+                )
+            };
+
+            glyf.write_i16(n_contours);
+            glyf.write_i16(x_min);
+            glyf.write_i16(y_min);
+            glyf.write_i16(x_max);
+            glyf.write_i16(y_max);
+            for end in &end_points {
+                glyf.write_u16(*end);
+            }
+            glyf.write_u16(instructions_len);
+            glyf.extend_from_slice(instructions);
+            write_simple_glyph_points(&points, &mut glyf);
+
+            x_mins.push(x_min);
+        } else {
+            return None;
+        }
+
+        // Glyphs are 4-byte aligned, which is also enough for the short `loca` format.
+        while glyf.len() % 4 != 0 {
+            glyf.push(0);
+        }
+    }
+    offsets.push(glyf.len());
+
+    let mut loca = Vec::new();
+    if index_format == 0 {
+        for offset in offsets {
+            let offset = u16::try_from(offset / 2).ok()?;
+            loca.write_u16(offset);
+        }
+    } else {
+        for offset in offsets {
+            let offset = u32::try_from(offset).ok()?;
+            loca.extend_from_slice(&offset.to_be_bytes());
+        }
+    }
+
+    Some(Glyphs { glyf, loca, x_mins })
+}
+
+/// Returns composite glyph components and whether the glyph has instructions.
+fn composite_glyph<'a>(s: &mut Stream<'a>) -> Option<(&'a [u8], bool)> {
+    const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+    const WE_HAVE_A_SCALE: u16 = 0x0008;
+    const MORE_COMPONENTS: u16 = 0x0020;
+    const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+    const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+    const WE_HAVE_INSTRUCTIONS: u16 = 0x0100;
+
+    let start = s.offset;
+    let mut have_instructions = false;
+    loop {
+        let flags = s.read_u16()?;
+        have_instructions |= flags & WE_HAVE_INSTRUCTIONS != 0;
+
+        let mut size = 2; // glyph index
+        size += if flags & ARG_1_AND_2_ARE_WORDS != 0 {
+            4
+        } else {
+            2
+        };
+        if flags & WE_HAVE_A_SCALE != 0 {
+            size += 2;
+        } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+            size += 4;
+        } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+            size += 8;
+        }
+        s.skip(size)?;
+
+        if flags & MORE_COMPONENTS == 0 {
+            break;
+        }
+    }
+
+    Some((&s.data[start..s.offset], have_instructions))
+}
+
+/// Decodes a point coordinates delta.
+///
+/// See <https://www.w3.org/TR/WOFF2/#triplet_decoding>.
+fn read_triplet(flag: u8, s: &mut Stream) -> Option<(i16, i16)> {
+    fn with_sign(flag: u8, value: i32) -> i32 {
+        if flag & 1 != 0 {
+            value
+        } else {
+            -value
+        }
+    }
+
+    let flag = flag & 0x7F;
+    let (dx, dy) = if flag < 10 {
+        let b0 = i32::from(s.read_u8()?);
+        (0, with_sign(flag, (i32::from(flag & 14) << 7) + b0))
+    } else if flag < 20 {
+        let b0 = i32::from(s.read_u8()?);
+        (with_sign(flag, (i32::from((flag - 10) & 14) << 7) + b0), 0)
+    } else if flag < 84 {
+        let b0 = i32::from(flag - 20);
+        let b1 = i32::from(s.read_u8()?);
+        (
+            with_sign(flag, 1 + (b0 & 0x30) + (b1 >> 4)),
+            with_sign(flag >> 1, 1 + ((b0 & 0x0C) << 2) + (b1 & 0x0F)),
+        )
+    } else if flag < 120 {
+        let b0 = i32::from(flag - 84);
+        let b1 = i32::from(s.read_u8()?);
+        let b2 = i32::from(s.read_u8()?);
+        (
+            with_sign(flag, 1 + ((b0 / 12) << 8) + b1),
+            with_sign(flag >> 1, 1 + (((b0 % 12) >> 2) << 8) + b2),
+        )
+    } else if flag < 124 {
+        let b1 = i32::from(s.read_u8()?);
+        let b2 = i32::from(s.read_u8()?);
+        let b3 = i32::from(s.read_u8()?);
+        (
+            with_sign(flag, (b1 << 4) + (b2 >> 4)),
+            with_sign(flag >> 1, ((b2 & 0x0F) << 8) + b3),
+        )
+    } else {
+        let b1 = i32::from(s.read_u8()?);
+        let b2 = i32::from(s.read_u8()?);
+        let b3 = i32::from(s.read_u8()?);
+        let b4 = i32::from(s.read_u8()?);
+        (
+            with_sign(flag, (b1 << 8) + b2),
+            with_sign(flag >> 1, (b3 << 8) + b4),
+        )
+    };
+
+    Some((dx as i16, dy as i16))
+}
+
+/// Writes simple glyph flags and coordinates.
+///
+/// Uses short vectors when possible, but doesn't bother with flags repeating.
+fn write_simple_glyph_points(points: &[(i16, i16, bool)], buf: &mut Vec<u8>) {
+    const ON_CURVE_POINT: u8 = 0x01;
+    const X_SHORT_VECTOR: u8 = 0x02;
+    const Y_SHORT_VECTOR: u8 = 0x04;
+    const X_IS_SAME_OR_POSITIVE: u8 = 0x10;
+    const Y_IS_SAME_OR_POSITIVE: u8 = 0x20;
+
+    fn flag(delta: i32, short: u8, same_or_positive: u8) -> u8 {
+        if delta == 0 {
+            same_or_positive
+        } else if delta.abs() < 256 {
+            short | if delta > 0 { same_or_positive } else { 0 }
+        } else {
+            0
+        }
+    }
+
+    let mut deltas = Vec::with_capacity(points.len());
+    let (mut prev_x, mut prev_y) = (0i32, 0i32);
+    for &(x, y, on_curve) in points {
+        let dx = i32::from(x) - prev_x;
+        let dy = i32::from(y) - prev_y;
+        prev_x = i32::from(x);
+        prev_y = i32::from(y);
+
+        let mut f = if on_curve { ON_CURVE_POINT } else { 0 };
+        f |= flag(dx, X_SHORT_VECTOR, X_IS_SAME_OR_POSITIVE);
+        f |= flag(dy, Y_SHORT_VECTOR, Y_IS_SAME_OR_POSITIVE);
+        buf.push(f);
+        deltas.push((dx, dy, f));
+    }
+
+    for &(dx, _, f) in &deltas {
+        write_coordinate(
+            dx,
+            f & X_SHORT_VECTOR != 0,
+            f & X_IS_SAME_OR_POSITIVE != 0,
+            buf,
+        );
+    }
+
+    for &(_, dy, f) in &deltas {
+        write_coordinate(
+            dy,
+            f & Y_SHORT_VECTOR != 0,
+            f & Y_IS_SAME_OR_POSITIVE != 0,
+            buf,
+        );
+    }
+}
+
+fn write_coordinate(delta: i32, is_short: bool, is_same_or_positive: bool, buf: &mut Vec<u8>) {
+    if is_short {
+        buf.push(delta.unsigned_abs() as u8);
+    } else if !is_same_or_positive {
+        // Deltas are wrapped the same way as coordinates.
+        buf.write_i16(delta as i16);
+    }
+}
+
+fn reconstruct_hmtx(data: &[u8], num_h_metrics: u16, x_mins: &[i16]) -> Option<Vec<u8>> {
+    const PROPORTIONAL_LSB_ABSENT: u8 = 0x01;
+    const MONOSPACED_LSB_ABSENT: u8 = 0x02;
+
+    let num_glyphs = x_mins.len();
+    let num_h_metrics = usize::from(num_h_metrics);
+    if num_h_metrics == 0 || num_h_metrics > num_glyphs {
+        return None;
+    }
+
+    let mut s = Stream::new(data);
+    let flags = s.read_u8()?;
+    let mut advances = Vec::with_capacity(num_h_metrics);
+    for _ in 0..num_h_metrics {
+        advances.push(s.read_u16()?);
+    }
+
+    let mut lsbs = Vec::with_capacity(num_glyphs);
+    for (i, x_min) in x_mins.iter().enumerate() {
+        let absent = if i < num_h_metrics {
+            flags & PROPORTIONAL_LSB_ABSENT != 0
+        } else {
+            flags & MONOSPACED_LSB_ABSENT != 0
+        };
+
+        lsbs.push(if absent { *x_min } else { s.read_i16()? });
+    }
+
+    let mut hmtx = Vec::with_capacity(num_h_metrics * 2 + num_glyphs * 2);
+    for (i, lsb) in lsbs.into_iter().enumerate() {
+        if let Some(advance) = advances.get(i) {
+            hmtx.write_u16(*advance);
+        }
+        hmtx.write_i16(lsb);
+    }
+
+    Some(hmtx)
+}
+
+/// Writes an sfnt table directory header.
+fn write_offset_table(flavor: u32, num_tables: u16, buf: &mut Vec<u8>) {
+    let mut entry_selector = 0u16;
+    while (2u32 << entry_selector) <= u32::from(num_tables) {
+        entry_selector += 1;
+    }
+    let search_range = (1u32 << entry_selector) * 16;
+    let range_shift = (u32::from(num_tables) * 16).saturating_sub(search_range);
+
+    buf.extend_from_slice(&flavor.to_be_bytes());
+    buf.write_u16(num_tables);
+    buf.write_u16(search_range as u16);
+    buf.write_u16(entry_selector);
+    buf.write_u16(range_shift as u16);
+}
+
+fn write_sfnt(flavor: u32, mut tables: Vec<TableEntry>) -> Vec<u8> {
+    // Table records must be sorted by tag.
+    tables.sort_by_key(|t| t.tag);
+
+    let num_tables = tables.len() as u16;
+    let mut buf = Vec::new();
+    write_offset_table(flavor, num_tables, &mut buf);
+
+    let mut offset = 12 + 16 * tables.len();
+    for table in &tables {
+        buf.extend_from_slice(&table.tag.to_be_bytes());
+        buf.extend_from_slice(&checksum(&table.data).to_be_bytes());
+        buf.extend_from_slice(&(offset as u32).to_be_bytes());
+        buf.extend_from_slice(&(table.data.len() as u32).to_be_bytes());
+        offset += padded_len(table.data.len());
+    }
+
+    let mut head_offset = None;
+    for table in &tables {
+        if table.tag == HEAD {
+            head_offset = Some(buf.len());
+        }
+
+        buf.extend_from_slice(&table.data);
+        pad(&mut buf);
+    }
+
+    if let Some(offset) = head_offset {
+        update_checksum_adjustment(&mut buf, offset);
+    }
+
+    buf
+}
+
+fn write_collection(
+    version: u32,
+    faces: &[(u32, Vec<(u32, usize)>)],
+    tables: Vec<Vec<u8>>,
+) -> Vec<u8> {
+    let mut header_len = 12 + 4 * faces.len();
+    if version == 0x00020000 {
+        // DSIG tag, length and offset.
+        header_len += 12;
+    }
+
+    let mut directories_len = 0;
+    for (_, entries) in faces {
+        directories_len += 12 + 16 * entries.len();
+    }
+
+    let mut table_offsets = Vec::with_capacity(tables.len());
+    let mut offset = header_len + directories_len;
+    for data in &tables {
+        table_offsets.push(offset);
+        offset += padded_len(data.len());
+    }
+
+    let mut buf = Vec::with_capacity(offset);
+    buf.extend_from_slice(&TTC_FLAVOR.to_be_bytes());
+    buf.extend_from_slice(&version.to_be_bytes());
+    buf.extend_from_slice(&(faces.len() as u32).to_be_bytes());
+    let mut directory_offset = header_len;
+    for (_, entries) in faces {
+        buf.extend_from_slice(&(directory_offset as u32).to_be_bytes());
+        directory_offset += 12 + 16 * entries.len();
+    }
+    if version == 0x00020000 {
+        buf.extend_from_slice(&[0; 12]);
+    }
+
+    for (flavor, entries) in faces {
+        let mut entries = entries.clone();
+        entries.sort_by_key(|(tag, _)| *tag);
+
+        write_offset_table(*flavor, entries.len() as u16, &mut buf);
+        for (tag, idx) in entries {
+            buf.extend_from_slice(&tag.to_be_bytes());
+            buf.extend_from_slice(&checksum(&tables[idx]).to_be_bytes());
+            buf.extend_from_slice(&(table_offsets[idx] as u32).to_be_bytes());
+            buf.extend_from_slice(&(tables[idx].len() as u32).to_be_bytes());
+        }
+    }
+
+    for data in &tables {
+        buf.extend_from_slice(data);
+        pad(&mut buf);
+    }
+
+    buf
+}
+
+fn update_checksum_adjustment(font: &mut [u8], head_offset: usize) {
+    let adjustment = match font.get_mut(head_offset + 8..head_offset + 12) {
+        Some(v) => v,
+        None => return,
+    };
+    adjustment.copy_from_slice(&[0; 4]);
+
+    let value = 0xB1B0AFBAu32.wrapping_sub(checksum(font));
+    font[head_offset + 8..head_offset + 12].copy_from_slice(&value.to_be_bytes());
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    let mut sum = 0u32;
+    for chunk in data.chunks(4) {
+        let mut bytes = [0; 4];
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        sum = sum.wrapping_add(u32::from_be_bytes(bytes));
+    }
+
+    sum
+}
+
+fn padded_len(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+fn pad(buf: &mut Vec<u8>) {
+    buf.resize(padded_len(buf.len()), 0);
+}
+
+trait WriteBe {
+    fn write_u16(&mut self, n: u16);
+    fn write_i16(&mut self, n: i16);
+}
+
+impl WriteBe for Vec<u8> {
+    fn write_u16(&mut self, n: u16) {
+        self.extend_from_slice(&n.to_be_bytes());
+    }
+
+    fn write_i16(&mut self, n: i16) {
+        self.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base128() {
+        assert_eq!(Stream::new(&[0x3F]).read_base128(), Some(63));
+        assert_eq!(Stream::new(&[0x81, 0x00]).read_base128(), Some(128));
+        assert_eq!(Stream::new(&[0x80, 0x01]).read_base128(), None);
+        assert_eq!(Stream::new(&[0xFF; 5]).read_base128(), None);
+    }
+
+    #[test]
+    fn uint_255() {
+        assert_eq!(Stream::new(&[252]).read_255_u16(), Some(252));
+        assert_eq!(Stream::new(&[255, 253]).read_255_u16(), Some(506));
+        assert_eq!(Stream::new(&[254, 0]).read_255_u16(), Some(506));
+        assert_eq!(Stream::new(&[253, 1, 250]).read_255_u16(), Some(506));
+    }
+
+    #[test]
+    fn triplet() {
+        // Flag 0: dx = 0, dy = -b0.
+        assert_eq!(read_triplet(0, &mut Stream::new(&[10])), Some((0, -10)));
+        // Flag 11: dx = +b0, dy = 0.
+        assert_eq!(read_triplet(11, &mut Stream::new(&[10])), Some((10, 0)));
+        // Flag 127: two signed 16-bit values.
+        assert_eq!(
+            read_triplet(127, &mut Stream::new(&[0x01, 0x00, 0x02, 0x00])),
+            Some((256, 512))
+        );
+        // The on-curve bit is ignored.
+        assert_eq!(read_triplet(0x80, &mut Stream::new(&[1])), Some((0, -1)));
+    }
+
+    #[test]
+    fn transformed_glyf() {
+        let mut data = vec![
+            0, 0, // reserved
+            0, 0, // option flags
+            0, 1, // number of glyphs
+            0, 0, // index format
+        ];
+        let streams: [&[u8]; 7] = [
+            &[0, 1], // number of contours
+            &[3],    // number of points
+            &[127, 127, 126],
+            &[
+                0, 0, 0, 0, // (0, 0)
+                0, 100, 0, 0, // (+100, 0)
+                0, 50, 0, 100, // (-50, +100)
+                0,   // instructions length
+            ],
+            &[],           // composite
+            &[0, 0, 0, 0], // bbox bitmap
+            &[],           // instructions
+        ];
+        for stream in &streams {
+            data.extend_from_slice(&(stream.len() as u32).to_be_bytes());
+        }
+        for stream in &streams {
+            data.extend_from_slice(stream);
+        }
+
+        let glyphs = reconstruct_glyf(&data).unwrap();
+        assert_eq!(
+            &glyphs.glyf[..14],
+            &[
+                0, 1, // number of contours
+                0, 0, 0, 0, 0, 100, 0, 100, // bbox
+                0, 2, // end points
+                0, 0, // instructions length
+            ]
+        );
+        assert_eq!(glyphs.glyf.len() % 4, 0);
+        assert_eq!(glyphs.loca, &[0, 0, 0, glyphs.glyf.len() as u8 / 2]);
+        assert_eq!(glyphs.x_mins, &[0]);
+    }
+}
@@ -4,7 +4,10 @@
 
 //! Some useful utilities.
 
-use crate::{Align, AspectRatio, Rect, ScreenSize, Size, Transform, ViewBox};
+use crate::{
+    Align, AspectRatio, BaseGradient, ClipPath, Node, NodeKind, OptionLog, Path, PathBbox, Rect,
+    ScreenSize, Size, Transform, TransformFromBBox, Units, ViewBox, Visibility,
+};
 
 // TODO: https://github.com/rust-lang/rust/issues/44095
 /// Bounds `f64` number.
@@ -116,6 +119,77 @@ pub fn aligned_pos(align: Align, x: f64, y: f64, w: f64, h: f64) -> (f64, f64) {
     }
 }
 
+/// Calculates a node's object bounding box in its own user space.
+///
+/// Unlike [`NodeExt::calculate_object_bbox`](crate::NodeExt::calculate_object_bbox), the node's own transform
+/// and the transforms of its ancestors are not applied.
+/// This is the bbox `objectBoundingBox` units are resolved against.
+pub fn object_bbox(node: &Node) -> Option<PathBbox> {
+    crate::calc_node_bbox_of_kind(node, Transform::default(), crate::BboxKind::Object)
+}
+
+/// Returns a transform from the clip path space to the user space of the clipped `node`.
+///
+/// Returns `None` for `objectBoundingBox` units and a zero-sized `node`.
+pub fn clip_path_transform(cp: &ClipPath, node: &Node) -> Option<Transform> {
+    let mut ts = cp.transform;
+    if cp.units == Units::ObjectBoundingBox {
+        let bbox = object_bbox(node)
+            .and_then(|r| r.to_rect())
+            .log_none(|| log::warn!("Clipping of zero-sized shapes is not allowed."))?;
+
+        ts.append(&Transform::from_bbox(bbox));
+    }
+
+    Some(ts)
+}
+
+/// Returns the only path of a clip path that consists of a single filled path.
+///
+/// The returned path `transform` maps it into the user space of the clipped `node`.
+///
+/// Returns `None` for all other clip paths. The clip path's own `clip-path`
+/// is ignored and has to be handled by the caller.
+pub fn simple_clip_path(cp: &ClipPath, node: &Node) -> Option<Path> {
+    let mut children = cp.root.children();
+    let child = children.next()?;
+    if children.next().is_some() {
+        return None;
+    }
+
+    let mut path = match *child.borrow() {
+        NodeKind::Path(ref path) => path.clone(),
+        _ => return None,
+    };
+
+    if path.fill.is_none() || path.visibility != Visibility::Visible || path.data.is_empty() {
+        return None;
+    }
+
+    let mut ts = clip_path_transform(cp, node)?;
+    ts.append(&path.transform);
+    path.transform = ts;
+    Some(path)
+}
+
+/// Returns a transform from the gradient space to the user space of a shape
+/// with the `bbox` object bounding box.
+///
+/// Returns `None` for `objectBoundingBox` units and a zero-sized shape.
+pub fn gradient_transform(g: &BaseGradient, bbox: Option<PathBbox>) -> Option<Transform> {
+    if g.units == Units::ObjectBoundingBox {
+        let bbox = bbox
+            .and_then(|r| r.to_rect())
+            .log_none(|| log::warn!("Gradient on zero-sized shapes is not allowed."))?;
+
+        let mut ts = Transform::from_bbox(bbox);
+        ts.append(&g.transform);
+        Some(ts)
+    } else {
+        Some(g.transform)
+    }
+}
+
 pub(crate) fn file_extension(path: &std::path::Path) -> Option<&str> {
     path.extension().and_then(|e| e.to_str())
 }
@@ -0,0 +1,529 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use xmlwriter::XmlWriter;
+
+use crate::export::{hex_color, report, write_num};
+use crate::utils::{gradient_transform, simple_clip_path};
+use crate::*;
+
+struct Context<'a> {
+    opt: &'a XmlOptions,
+    unsupported: Vec<Unsupported>,
+}
+
+pub(crate) fn convert(tree: &Tree, opt: &XmlOptions) -> Conversion {
+    let mut xml = XmlWriter::new(opt.writer_opts);
+    let mut ctx = Context {
+        opt,
+        unsupported: Vec::new(),
+    };
+
+    xml.start_element("vector");
+    xml.write_attribute(
+        "xmlns:android",
+        "http://schemas.android.com/apk/res/android",
+    );
+    if has_gradients(tree) {
+        xml.write_attribute("xmlns:aapt", "http://schemas.android.com/aapt");
+    }
+    xml.write_attribute_raw("android:width", |buf| {
+        write_num(tree.size.width(), buf);
+        buf.extend_from_slice(b"dp");
+    });
+    xml.write_attribute_raw("android:height", |buf| {
+        write_num(tree.size.height(), buf);
+        buf.extend_from_slice(b"dp");
+    });
+    write_num_attribute("android:viewportWidth", tree.size.width(), &mut xml);
+    write_num_attribute("android:viewportHeight", tree.size.height(), &mut xml);
+
+    // The viewport matches the size, so the `viewBox` becomes a group.
+    // It's always a scale and a translate, therefore can be decomposed.
+    let ts = utils::view_box_to_transform(tree.view_box.rect, tree.view_box.aspect, tree.size);
+    if ts.is_default() {
+        conv_children(&tree.root, ts, 1.0, &mut ctx, &mut xml);
+    } else {
+        xml.start_element("group");
+        let ts = write_transform(ts, &mut xml);
+        conv_children(&tree.root, ts, 1.0, &mut ctx, &mut xml);
+        xml.end_element();
+    }
+
+    Conversion {
+        data: xml.end_document(),
+        unsupported: ctx.unsupported,
+    }
+}
+
+fn has_gradients(tree: &Tree) -> bool {
+    let is_gradient =
+        |paint: &Paint| matches!(paint, Paint::LinearGradient(_) | Paint::RadialGradient(_));
+
+    tree.root.descendants().any(|node| {
+        if let NodeKind::Path(ref path) = *node.borrow() {
+            matches!(path.fill, Some(ref f) if is_gradient(&f.paint))
+                || matches!(path.stroke, Some(ref s) if is_gradient(&s.paint))
+        } else {
+            false
+        }
+    })
+}
+
+/// Converts children of a node.
+///
+/// `ts` is a transform that cannot be represented by a group
+/// and has to be applied to the path data directly.
+/// `opacity` is an accumulated group opacity.
+fn conv_children(
+    parent: &Node,
+    ts: Transform,
+    opacity: f64,
+    ctx: &mut Context,
+    xml: &mut XmlWriter,
+) {
+    for node in parent.children() {
+        match *node.borrow() {
+            NodeKind::Group(ref g) => conv_group(&node, g, ts, opacity, ctx, xml),
+            NodeKind::Path(ref path) => conv_path(path, ts, opacity, ctx, xml),
+            NodeKind::Image(ref img) => {
+                report(&mut ctx.unsupported, &img.id, UnsupportedKind::Image)
+            }
+        }
+    }
+}
+
+fn conv_group(
+    node: &Node,
+    g: &Group,
+    ts: Transform,
+    opacity: f64,
+    ctx: &mut Context,
+    xml: &mut XmlWriter,
+) {
+    #[cfg(feature = "filter")]
+    for filter in &g.filters {
+        report(&mut ctx.unsupported, &filter.id, UnsupportedKind::Filter);
+    }
+
+    if let Some(ref mask) = g.mask {
+        report(&mut ctx.unsupported, &mask.id, UnsupportedKind::Mask);
+    }
+
+    // VectorDrawable doesn't have a group opacity, but it's fine to apply it
+    // to each shape separately as long as shapes do not overlap.
+    if g.opacity != Opacity::ONE {
+        let shapes = node
+            .descendants()
+            .filter(|n| matches!(*n.borrow(), NodeKind::Path(_)))
+            .count();
+        if shapes > 1 {
+            report(&mut ctx.unsupported, &g.id, UnsupportedKind::GroupOpacity);
+        }
+    }
+    let opacity = opacity * g.opacity.get();
+
+    let clip_path = g.clip_path.as_ref().and_then(|cp| {
+        let path = simple_clip_path(cp, node).filter(|path| {
+            let rule = path.fill.as_ref().map(|f| f.rule).unwrap_or_default();
+            cp.clip_path.is_none() && rule == FillRule::NonZero
+        });
+        if path.is_none() {
+            report(&mut ctx.unsupported, &cp.id, UnsupportedKind::ClipPath);
+        }
+
+        path
+    });
+
+    let mut ts = ts;
+    ts.append(&g.transform);
+
+    if g.id.is_empty() && clip_path.is_none() && ts.is_default() {
+        conv_children(node, ts, opacity, ctx, xml);
+        return;
+    }
+
+    xml.start_element("group");
+    write_name(&g.id, ctx.opt, xml);
+    let ts = write_transform(ts, xml);
+
+    if let Some(ref path) = clip_path {
+        let mut clip_ts = ts;
+        clip_ts.append(&path.transform);
+
+        xml.start_element("clip-path");
+        write_path_data("android:pathData", &path.data, clip_ts, xml);
+        xml.end_element();
+    }
+
+    conv_children(node, ts, opacity, ctx, xml);
+    xml.end_element();
+}
+
+fn conv_path(path: &Path, ts: Transform, opacity: f64, ctx: &mut Context, xml: &mut XmlWriter) {
+    if path.visibility != Visibility::Visible {
+        return;
+    }
+
+    let mut ts = ts;
+    ts.append(&path.transform);
+
+    let has_group = !ts.is_default() && decompose(ts).is_some();
+    if has_group {
+        xml.start_element("group");
+        ts = write_transform(ts, xml);
+    }
+
+    // VectorDrawable always draws a stroke above a fill.
+    if path.paint_order == PaintOrder::StrokeAndFill && path.fill.is_some() {
+        write_path(path, None, path.stroke.as_ref(), ts, opacity, ctx, xml);
+        write_path(path, path.fill.as_ref(), None, ts, opacity, ctx, xml);
+    } else {
+        write_path(
+            path,
+            path.fill.as_ref(),
+            path.stroke.as_ref(),
+            ts,
+            opacity,
+            ctx,
+            xml,
+        );
+    }
+
+    if has_group {
+        xml.end_element();
+    }
+}
+
+fn write_path(
+    path: &Path,
+    fill: Option<&Fill>,
+    stroke: Option<&Stroke>,
+    ts: Transform,
+    opacity: f64,
+    ctx: &mut Context,
+    xml: &mut XmlWriter,
+) {
+    let fill_paint = fill.and_then(|fill| conv_paint(&fill.paint, path, ts, ctx));
+    let stroke_paint = stroke.and_then(|stroke| conv_paint(&stroke.paint, path, ts, ctx));
+
+    xml.start_element("path");
+    write_name(&path.id, ctx.opt, xml);
+    write_path_data("android:pathData", &path.data, ts, xml);
+
+    if let (Some(fill), Some(paint)) = (fill, &fill_paint) {
+        if let VdPaint::Color(c) = paint {
+            xml.write_attribute("android:fillColor", &hex_color(*c, 1.0));
+        }
+
+        let alpha = fill.opacity.get() * opacity;
+        if alpha != 1.0 {
+            write_num_attribute("android:fillAlpha", alpha, xml);
+        }
+
+        if fill.rule == FillRule::EvenOdd {
+            xml.write_attribute("android:fillType", "evenOdd");
+        }
+    }
+
+    if let (Some(stroke), Some(paint)) = (stroke, &stroke_paint) {
+        if let VdPaint::Color(c) = paint {
+            xml.write_attribute("android:strokeColor", &hex_color(*c, 1.0));
+        }
+
+        let alpha = stroke.opacity.get() * opacity;
+        if alpha != 1.0 {
+            write_num_attribute("android:strokeAlpha", alpha, xml);
+        }
+
+        // A baked-in transform scales the stroke as well.
+        let scale = (ts.a * ts.d - ts.b * ts.c).abs().sqrt();
+        write_num_attribute("android:strokeWidth", stroke.width.get() * scale, xml);
+
+        match stroke.linecap {
+            LineCap::Butt => {}
+            LineCap::Round => xml.write_attribute("android:strokeLineCap", "round"),
+            LineCap::Square => xml.write_attribute("android:strokeLineCap", "square"),
+        }
+
+        match stroke.linejoin {
+            LineJoin::Miter => {}
+            LineJoin::Round => xml.write_attribute("android:strokeLineJoin", "round"),
+            LineJoin::Bevel => xml.write_attribute("android:strokeLineJoin", "bevel"),
+        }
+
+        if !stroke.miterlimit.is_default() {
+            write_num_attribute("android:strokeMiterLimit", stroke.miterlimit.get(), xml);
+        }
+
+        if stroke.dasharray.is_some() {
+            report(
+                &mut ctx.unsupported,
+                &path.id,
+                UnsupportedKind::StrokeDasharray,
+            );
+        }
+    }
+
+    if let Some(VdPaint::Gradient(ref g)) = fill_paint {
+        write_gradient("android:fillColor", g, xml);
+    }
+
+    if let Some(VdPaint::Gradient(ref g)) = stroke_paint {
+        write_gradient("android:strokeColor", g, xml);
+    }
+
+    xml.end_element();
+}
+
+enum VdPaint<'a> {
+    Color(Color),
+    Gradient(Gradient<'a>),
+}
+
+struct Gradient<'a> {
+    kind: GradientKind,
+    spread_method: SpreadMethod,
+    stops: &'a [Stop],
+}
+
+enum GradientKind {
+    Linear { start: (f64, f64), end: (f64, f64) },
+    Radial { center: (f64, f64), radius: f64 },
+}
+
+fn conv_paint<'a>(
+    paint: &'a Paint,
+    path: &Path,
+    ts: Transform,
+    ctx: &mut Context,
+) -> Option<VdPaint<'a>> {
+    // VectorDrawable gradients are defined in the path coordinates
+    // and do not support transforms. So we have to map them manually.
+    let base_ts = |g: &BaseGradient| {
+        let mut gts = ts;
+        gts.append(&gradient_transform(g, path.data.bbox())?);
+        Some(gts)
+    };
+
+    let kind = match paint {
+        Paint::Color(c) => return Some(VdPaint::Color(*c)),
+        Paint::LinearGradient(ref lg) => {
+            let gts = base_ts(lg)?;
+            let det = gts.a * gts.d - gts.b * gts.c;
+            if det.is_fuzzy_zero() {
+                return None;
+            }
+
+            // A transformed linear gradient is still a linear gradient,
+            // but the gradient vector has to stay perpendicular to isolines.
+            let start = gts.apply(lg.x1, lg.y1);
+            let (dx, dy) = (lg.x2 - lg.x1, lg.y2 - lg.y1);
+            let len2 = dx * dx + dy * dy;
+            let end = if len2.is_fuzzy_zero() {
+                gts.apply(lg.x2, lg.y2)
+            } else {
+                let gx = (gts.d * dx - gts.b * dy) / det / len2;
+                let gy = (-gts.c * dx + gts.a * dy) / det / len2;
+                let glen2 = gx * gx + gy * gy;
+                (start.0 + gx / glen2, start.1 + gy / glen2)
+            };
+
+            GradientKind::Linear { start, end }
+        }
+        Paint::RadialGradient(ref rg) => {
+            let gts = base_ts(rg)?;
+            let scale = (gts.a * gts.d - gts.b * gts.c).abs().sqrt();
+            if scale.is_fuzzy_zero() {
+                return None;
+            }
+
+            // Only uniform scaling and rotation keep a circle a circle.
+            let (sx, sy) = (gts.a.hypot(gts.b), gts.c.hypot(gts.d));
+            let skew = gts.a * gts.c + gts.b * gts.d;
+            if !sx.fuzzy_eq(&sy) || !(skew / (sx * sy)).is_fuzzy_zero() {
+                report(
+                    &mut ctx.unsupported,
+                    &rg.id,
+                    UnsupportedKind::GradientTransform,
+                );
+            }
+
+            if !rg.fx.fuzzy_eq(&rg.cx) || !rg.fy.fuzzy_eq(&rg.cy) {
+                report(&mut ctx.unsupported, &rg.id, UnsupportedKind::FocalPoint);
+            }
+
+            GradientKind::Radial {
+                center: gts.apply(rg.cx, rg.cy),
+                radius: rg.r.get() * scale,
+            }
+        }
+        Paint::Pattern(ref patt) => {
+            report(&mut ctx.unsupported, &patt.id, UnsupportedKind::Pattern);
+            return None;
+        }
+    };
+
+    let base = match paint {
+        Paint::LinearGradient(ref lg) => &lg.base,
+        Paint::RadialGradient(ref rg) => &rg.base,
+        _ => unreachable!(),
+    };
+
+    Some(VdPaint::Gradient(Gradient {
+        kind,
+        spread_method: base.spread_method,
+        stops: &base.stops,
+    }))
+}
+
+fn write_gradient(aid: &str, g: &Gradient, xml: &mut XmlWriter) {
+    xml.start_element("aapt:attr");
+    xml.write_attribute("name", aid);
+    xml.start_element("gradient");
+
+    match g.kind {
+        GradientKind::Linear { start, end } => {
+            xml.write_attribute("android:type", "linear");
+            write_num_attribute("android:startX", start.0, xml);
+            write_num_attribute("android:startY", start.1, xml);
+            write_num_attribute("android:endX", end.0, xml);
+            write_num_attribute("android:endY", end.1, xml);
+        }
+        GradientKind::Radial { center, radius } => {
+            xml.write_attribute("android:type", "radial");
+            write_num_attribute("android:centerX", center.0, xml);
+            write_num_attribute("android:centerY", center.1, xml);
+            write_num_attribute("android:gradientRadius", radius, xml);
+        }
+    }
+
+    match g.spread_method {
+        SpreadMethod::Pad => {}
+        SpreadMethod::Reflect => xml.write_attribute("android:tileMode", "mirror"),
+        SpreadMethod::Repeat => xml.write_attribute("android:tileMode", "repeat"),
+    }
+
+    for stop in g.stops {
+        xml.start_element("item");
+        write_num_attribute("android:offset", stop.offset.get(), xml);
+        xml.write_attribute("android:color", &hex_color(stop.color, stop.opacity.get()));
+        xml.end_element();
+    }
+
+    xml.end_element();
+    xml.end_element();
+}
+
+/// Splits a transform into `translate * rotate * scale`, which is the only order
+/// supported by VectorDrawable groups.
+///
+/// Returns `None` when a transform contains a skew or a zero scale.
+fn decompose(ts: Transform) -> Option<(f64, f64, f64, f64, f64)> {
+    let sx = ts.a.hypot(ts.b);
+    if sx.is_fuzzy_zero() {
+        return None;
+    }
+
+    let angle = ts.b.atan2(ts.a);
+    let (sin, cos) = angle.sin_cos();
+    let skew = cos * ts.c + sin * ts.d;
+    let sy = -sin * ts.c + cos * ts.d;
+    if !(skew / sx).is_fuzzy_zero() || sy.is_fuzzy_zero() {
+        return None;
+    }
+
+    Some((ts.e, ts.f, angle.to_degrees(), sx, sy))
+}
+
+/// Writes a transform as group attributes.
+///
+/// Returns a transform that has to be applied to the path data instead,
+/// which is a default one when the transform can be decomposed.
+fn write_transform(ts: Transform, xml: &mut XmlWriter) -> Transform {
+    let (tx, ty, angle, sx, sy) = match decompose(ts) {
+        Some(v) => v,
+        None => return ts,
+    };
+
+    if !angle.is_fuzzy_zero() {
+        write_num_attribute("android:rotation", angle, xml);
+    }
+
+    if !sx.fuzzy_eq(&1.0) {
+        write_num_attribute("android:scaleX", sx, xml);
+    }
+
+    if !sy.fuzzy_eq(&1.0) {
+        write_num_attribute("android:scaleY", sy, xml);
+    }
+
+    if !tx.is_fuzzy_zero() {
+        write_num_attribute("android:translateX", tx, xml);
+    }
+
+    if !ty.is_fuzzy_zero() {
+        write_num_attribute("android:translateY", ty, xml);
+    }
+
+    Transform::default()
+}
+
+fn write_name(id: &str, opt: &XmlOptions, xml: &mut XmlWriter) {
+    if id.is_empty() {
+        return;
+    }
+
+    if let Some(ref prefix) = opt.id_prefix {
+        xml.write_attribute_fmt("android:name", format_args!("{}{}", prefix, id));
+    } else {
+        xml.write_attribute("android:name", id);
+    }
+}
+
+fn write_num_attribute(name: &str, num: f64, xml: &mut XmlWriter) {
+    xml.write_attribute_raw(name, |buf| write_num(num, buf));
+}
+
+fn write_path_data(name: &str, data: &PathData, ts: Transform, xml: &mut XmlWriter) {
+    xml.write_attribute_raw(name, |buf| {
+        let write_coords = |coords: &[f64], buf: &mut Vec<u8>| {
+            for n in coords {
+                write_num(*n, buf);
+                buf.push(b' ');
+            }
+        };
+
+        for seg in TransformedPath::new(data, ts) {
+            match seg {
+                PathSegment::MoveTo { x, y } => {
+                    buf.extend_from_slice(b"M ");
+                    write_coords(&[x, y], buf);
+                }
+                PathSegment::LineTo { x, y } => {
+                    buf.extend_from_slice(b"L ");
+                    write_coords(&[x, y], buf);
+                }
+                PathSegment::CurveTo {
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    x,
+                    y,
+                } => {
+                    buf.extend_from_slice(b"C ");
+                    write_coords(&[x1, y1, x2, y2, x, y], buf);
+                }
+                PathSegment::ClosePath => {
+                    buf.extend_from_slice(b"Z ");
+                }
+            }
+        }
+
+        if !data.is_empty() {
+            buf.pop();
+        }
+    });
+}
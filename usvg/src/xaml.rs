@@ -0,0 +1,356 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use xmlwriter::XmlWriter;
+
+use crate::export::{hex_color, report, write_num};
+use crate::utils::{gradient_transform, simple_clip_path};
+use crate::*;
+
+struct Context<'a> {
+    opt: &'a XmlOptions,
+    unsupported: Vec<Unsupported>,
+}
+
+pub(crate) fn convert(tree: &Tree, opt: &XmlOptions) -> Conversion {
+    let mut xml = XmlWriter::new(opt.writer_opts);
+    let mut ctx = Context {
+        opt,
+        unsupported: Vec::new(),
+    };
+
+    xml.start_element("Canvas");
+    xml.write_attribute(
+        "xmlns",
+        "http://schemas.microsoft.com/winfx/2006/xaml/presentation",
+    );
+    xml.write_attribute("xmlns:x", "http://schemas.microsoft.com/winfx/2006/xaml");
+    write_num_attribute("Width", tree.size.width(), &mut xml);
+    write_num_attribute("Height", tree.size.height(), &mut xml);
+    xml.write_attribute("ClipToBounds", "True");
+
+    let ts = utils::view_box_to_transform(tree.view_box.rect, tree.view_box.aspect, tree.size);
+    if ts.is_default() {
+        conv_children(&tree.root, &mut ctx, &mut xml);
+    } else {
+        xml.start_element("Canvas");
+        write_transform("RenderTransform", ts, &mut xml);
+        conv_children(&tree.root, &mut ctx, &mut xml);
+        xml.end_element();
+    }
+
+    Conversion {
+        data: xml.end_document(),
+        unsupported: ctx.unsupported,
+    }
+}
+
+fn conv_children(parent: &Node, ctx: &mut Context, xml: &mut XmlWriter) {
+    for node in parent.children() {
+        match *node.borrow() {
+            NodeKind::Group(ref g) => conv_group(&node, g, ctx, xml),
+            NodeKind::Path(ref path) => conv_path(path, ctx, xml),
+            NodeKind::Image(ref img) => {
+                report(&mut ctx.unsupported, &img.id, UnsupportedKind::Image)
+            }
+        }
+    }
+}
+
+fn conv_group(node: &Node, g: &Group, ctx: &mut Context, xml: &mut XmlWriter) {
+    #[cfg(feature = "filter")]
+    for filter in &g.filters {
+        report(&mut ctx.unsupported, &filter.id, UnsupportedKind::Filter);
+    }
+
+    if let Some(ref mask) = g.mask {
+        report(&mut ctx.unsupported, &mask.id, UnsupportedKind::Mask);
+    }
+
+    xml.start_element("Canvas");
+    write_name(&g.id, ctx.opt, xml);
+    write_transform("RenderTransform", g.transform, xml);
+
+    if g.opacity != Opacity::ONE {
+        write_num_attribute("Opacity", g.opacity.get(), xml);
+    }
+
+    // `Clip` is defined in the element's local coordinates,
+    // which are the same as the clip path user space.
+    if let Some(ref cp) = g.clip_path {
+        match simple_clip_path(cp, node).filter(|_| cp.clip_path.is_none()) {
+            Some(path) => {
+                let mut data = (*path.data).clone();
+                data.transform(path.transform);
+                let rule = path.fill.as_ref().map(|f| f.rule).unwrap_or_default();
+                write_path_data("Clip", &data, rule, xml);
+            }
+            None => report(&mut ctx.unsupported, &cp.id, UnsupportedKind::ClipPath),
+        }
+    }
+
+    conv_children(node, ctx, xml);
+    xml.end_element();
+}
+
+fn conv_path(path: &Path, ctx: &mut Context, xml: &mut XmlWriter) {
+    if path.visibility != Visibility::Visible {
+        return;
+    }
+
+    // XAML always draws a stroke above a fill.
+    if path.paint_order == PaintOrder::StrokeAndFill && path.fill.is_some() {
+        write_path(path, None, path.stroke.as_ref(), ctx, xml);
+        write_path(path, path.fill.as_ref(), None, ctx, xml);
+    } else {
+        write_path(path, path.fill.as_ref(), path.stroke.as_ref(), ctx, xml);
+    }
+}
+
+fn write_path(
+    path: &Path,
+    fill: Option<&Fill>,
+    stroke: Option<&Stroke>,
+    ctx: &mut Context,
+    xml: &mut XmlWriter,
+) {
+    let fill = fill.filter(|fill| is_supported_paint(&fill.paint, ctx));
+    let stroke = stroke.filter(|stroke| is_supported_paint(&stroke.paint, ctx));
+
+    xml.start_element("Path");
+    write_name(&path.id, ctx.opt, xml);
+    write_transform("RenderTransform", path.transform, xml);
+
+    let rule = fill.map(|fill| fill.rule).unwrap_or_default();
+    write_path_data("Data", &path.data, rule, xml);
+
+    if let Some(fill) = fill {
+        if let Paint::Color(c) = fill.paint {
+            xml.write_attribute("Fill", &hex_color(c, fill.opacity.get()));
+        }
+    }
+
+    if let Some(stroke) = stroke {
+        if let Paint::Color(c) = stroke.paint {
+            xml.write_attribute("Stroke", &hex_color(c, stroke.opacity.get()));
+        }
+
+        let width = stroke.width.get();
+        write_num_attribute("StrokeThickness", width, xml);
+
+        let cap = match stroke.linecap {
+            LineCap::Butt => "Flat",
+            LineCap::Round => "Round",
+            LineCap::Square => "Square",
+        };
+
+        if stroke.linecap != LineCap::Butt {
+            xml.write_attribute("StrokeStartLineCap", cap);
+            xml.write_attribute("StrokeEndLineCap", cap);
+        }
+
+        match stroke.linejoin {
+            LineJoin::Miter => {}
+            LineJoin::Round => xml.write_attribute("StrokeLineJoin", "Round"),
+            LineJoin::Bevel => xml.write_attribute("StrokeLineJoin", "Bevel"),
+        }
+
+        // XAML miter limit is relative to a half of the stroke width.
+        write_num_attribute("StrokeMiterLimit", stroke.miterlimit.get() * 2.0, xml);
+
+        // XAML dashes are relative to the stroke width.
+        if let Some(ref list) = stroke.dasharray {
+            if stroke.linecap != LineCap::Butt {
+                xml.write_attribute("StrokeDashCap", cap);
+            }
+
+            // An odd list has to be repeated, like in SVG.
+            let count = if list.len() % 2 == 1 { 2 } else { 1 };
+            xml.write_attribute_raw("StrokeDashArray", |buf| {
+                for n in list.iter().cycle().take(list.len() * count) {
+                    write_num(*n / width, buf);
+                    buf.push(b' ');
+                }
+                buf.pop();
+            });
+
+            if stroke.dashoffset != 0.0 {
+                write_num_attribute("StrokeDashOffset", stroke.dashoffset as f64 / width, xml);
+            }
+        }
+    }
+
+    if let Some(fill) = fill {
+        write_brush("Path.Fill", &fill.paint, fill.opacity, path, xml);
+    }
+
+    if let Some(stroke) = stroke {
+        write_brush("Path.Stroke", &stroke.paint, stroke.opacity, path, xml);
+    }
+
+    xml.end_element();
+}
+
+fn is_supported_paint(paint: &Paint, ctx: &mut Context) -> bool {
+    if let Paint::Pattern(ref patt) = paint {
+        report(&mut ctx.unsupported, &patt.id, UnsupportedKind::Pattern);
+        false
+    } else {
+        true
+    }
+}
+
+/// Writes a gradient brush as a property element.
+///
+/// Gradients always use absolute coordinates with a transform,
+/// so the `objectBoundingBox` units are resolved manually.
+fn write_brush(name: &str, paint: &Paint, opacity: Opacity, path: &Path, xml: &mut XmlWriter) {
+    let base = match paint {
+        Paint::LinearGradient(ref lg) => &lg.base,
+        Paint::RadialGradient(ref rg) => &rg.base,
+        Paint::Color(_) | Paint::Pattern(_) => return,
+    };
+
+    let ts = match gradient_transform(base, path.data.bbox()) {
+        Some(v) => v,
+        None => return,
+    };
+
+    xml.start_element(name);
+    match paint {
+        Paint::LinearGradient(ref lg) => {
+            xml.start_element("LinearGradientBrush");
+            write_point_attribute("StartPoint", lg.x1, lg.y1, xml);
+            write_point_attribute("EndPoint", lg.x2, lg.y2, xml);
+        }
+        Paint::RadialGradient(ref rg) => {
+            xml.start_element("RadialGradientBrush");
+            write_point_attribute("Center", rg.cx, rg.cy, xml);
+            write_point_attribute("GradientOrigin", rg.fx, rg.fy, xml);
+            write_num_attribute("RadiusX", rg.r.get(), xml);
+            write_num_attribute("RadiusY", rg.r.get(), xml);
+        }
+        Paint::Color(_) | Paint::Pattern(_) => unreachable!(),
+    }
+
+    xml.write_attribute("MappingMode", "Absolute");
+
+    match base.spread_method {
+        SpreadMethod::Pad => {}
+        SpreadMethod::Reflect => xml.write_attribute("SpreadMethod", "Reflect"),
+        SpreadMethod::Repeat => xml.write_attribute("SpreadMethod", "Repeat"),
+    }
+
+    write_transform("Transform", ts, xml);
+
+    if opacity != Opacity::ONE {
+        write_num_attribute("Opacity", opacity.get(), xml);
+    }
+
+    for stop in &base.stops {
+        xml.start_element("GradientStop");
+        write_num_attribute("Offset", stop.offset.get(), xml);
+        xml.write_attribute("Color", &hex_color(stop.color, stop.opacity.get()));
+        xml.end_element();
+    }
+
+    xml.end_element();
+    xml.end_element();
+}
+
+/// Writes an `x:Name`.
+///
+/// Unlike SVG IDs, XAML names must be valid identifiers,
+/// so all other characters are replaced with underscores.
+fn write_name(id: &str, opt: &XmlOptions, xml: &mut XmlWriter) {
+    if id.is_empty() {
+        return;
+    }
+
+    let prefix = opt.id_prefix.as_deref().unwrap_or("");
+    let mut name: String = prefix
+        .chars()
+        .chain(id.chars())
+        .map(|c| if c.is_alphanumeric() { c } else { '_' })
+        .collect();
+
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+
+    xml.write_attribute("x:Name", &name);
+}
+
+fn write_transform(name: &str, ts: Transform, xml: &mut XmlWriter) {
+    if ts.is_default() {
+        return;
+    }
+
+    xml.write_attribute_raw(name, |buf| {
+        for n in &[ts.a, ts.b, ts.c, ts.d, ts.e, ts.f] {
+            write_num(*n, buf);
+            buf.push(b',');
+        }
+        buf.pop();
+    });
+}
+
+fn write_num_attribute(name: &str, num: f64, xml: &mut XmlWriter) {
+    xml.write_attribute_raw(name, |buf| write_num(num, buf));
+}
+
+fn write_point_attribute(name: &str, x: f64, y: f64, xml: &mut XmlWriter) {
+    xml.write_attribute_raw(name, |buf| {
+        write_num(x, buf);
+        buf.push(b',');
+        write_num(y, buf);
+    });
+}
+
+/// Writes the path markup syntax, which is almost the same as the SVG one,
+/// but the fill rule is a part of the data.
+fn write_path_data(name: &str, data: &PathData, rule: FillRule, xml: &mut XmlWriter) {
+    xml.write_attribute_raw(name, |buf| {
+        match rule {
+            FillRule::EvenOdd => buf.extend_from_slice(b"F0"),
+            FillRule::NonZero => buf.extend_from_slice(b"F1"),
+        }
+
+        let write_point = |x: f64, y: f64, buf: &mut Vec<u8>| {
+            buf.push(b' ');
+            write_num(x, buf);
+            buf.push(b',');
+            write_num(y, buf);
+        };
+
+        for seg in data.segments() {
+            match seg {
+                PathSegment::MoveTo { x, y } => {
+                    buf.extend_from_slice(b" M");
+                    write_point(x, y, buf);
+                }
+                PathSegment::LineTo { x, y } => {
+                    buf.extend_from_slice(b" L");
+                    write_point(x, y, buf);
+                }
+                PathSegment::CurveTo {
+                    x1,
+                    y1,
+                    x2,
+                    y2,
+                    x,
+                    y,
+                } => {
+                    buf.extend_from_slice(b" C");
+                    write_point(x1, y1, buf);
+                    write_point(x2, y2, buf);
+                    write_point(x, y, buf);
+                }
+                PathSegment::ClosePath => {
+                    buf.extend_from_slice(b" Z");
+                }
+            }
+        }
+    });
+}
@@ -162,3 +162,1138 @@ test_size_err!(
     size_detection_err,
     "<svg width='0' height='0' viewBox='0 0 10 20' xmlns='http://www.w3.org/2000/svg'>"
 );
+
+macro_rules! test_bbox {
+    ($name:ident, $method:ident, $input:expr, $expected:expr) => {
+        #[test]
+        fn $name() {
+            use usvg::{FuzzyEq, NodeExt};
+            let opt = usvg::Options::default();
+            let tree = usvg::Tree::from_str($input, &opt.to_ref()).unwrap();
+            let bbox = tree.root.$method().unwrap();
+            let (x, y, w, h) = $expected;
+            assert!(
+                bbox.fuzzy_eq(&usvg::PathBbox::new(x, y, w, h).unwrap()),
+                "{:?}",
+                bbox
+            );
+        }
+    };
+}
+
+test_bbox!(
+    object_bbox_ignores_stroke,
+    calculate_object_bbox,
+    "<svg viewBox='0 0 100 100' xmlns='http://www.w3.org/2000/svg'>
+        <rect x='10' y='20' width='30' height='40' stroke='black' stroke-width='10'/>
+    </svg>",
+    (10.0, 20.0, 30.0, 40.0)
+);
+
+test_bbox!(
+    stroke_bbox_rect,
+    calculate_stroke_bbox,
+    "<svg viewBox='0 0 100 100' xmlns='http://www.w3.org/2000/svg'>
+        <rect x='10' y='20' width='30' height='40' stroke='black' stroke-width='10'/>
+    </svg>",
+    (5.0, 15.0, 40.0, 50.0)
+);
+
+test_bbox!(
+    stroke_bbox_miter_join,
+    calculate_stroke_bbox,
+    "<svg viewBox='0 0 100 100' xmlns='http://www.w3.org/2000/svg'>
+        <path d='M 10 50 L 50 10 L 90 50' fill='none' stroke='black' stroke-width='10'/>
+    </svg>",
+    (
+        10.0 - 5.0 * std::f64::consts::FRAC_1_SQRT_2,
+        10.0 - 5.0 * std::f64::consts::SQRT_2,
+        80.0 + 10.0 * std::f64::consts::FRAC_1_SQRT_2,
+        40.0 + 5.0 * std::f64::consts::SQRT_2 + 5.0 * std::f64::consts::FRAC_1_SQRT_2
+    )
+);
+
+test_bbox!(
+    stroke_bbox_square_cap,
+    calculate_stroke_bbox,
+    "<svg viewBox='0 0 100 100' xmlns='http://www.w3.org/2000/svg'>
+        <path d='M 10 50 L 90 50' stroke='black' stroke-width='10' stroke-linecap='square'/>
+    </svg>",
+    (5.0, 45.0, 90.0, 10.0)
+);
+
+test_bbox!(
+    visual_bbox_filter_region,
+    calculate_visual_bbox,
+    "<svg viewBox='0 0 100 100' xmlns='http://www.w3.org/2000/svg'>
+        <filter id='filter1'>
+            <feGaussianBlur stdDeviation='5'/>
+        </filter>
+        <rect x='20' y='20' width='50' height='50' filter='url(#filter1)'/>
+    </svg>",
+    (15.0, 15.0, 60.0, 60.0)
+);
+
+test_bbox!(
+    visual_bbox_clip_path,
+    calculate_visual_bbox,
+    "<svg viewBox='0 0 100 100' xmlns='http://www.w3.org/2000/svg'>
+        <clipPath id='clip1'>
+            <rect x='0' y='0' width='40' height='30'/>
+        </clipPath>
+        <rect x='20' y='20' width='50' height='50' stroke='black' clip-path='url(#clip1)'/>
+    </svg>",
+    (19.5, 19.5, 20.5, 10.5)
+);
+
+#[test]
+fn fit_to_drawing() {
+    use usvg::FuzzyEq;
+    let opt = usvg::Options::default();
+    let mut tree = usvg::Tree::from_str(
+        "<svg width='400' height='400' viewBox='0 0 200 200' xmlns='http://www.w3.org/2000/svg'>
+            <rect x='50' y='60' width='40' height='30' stroke='black' stroke-width='10'/>
+        </svg>",
+        &opt.to_ref(),
+    )
+    .unwrap();
+    tree.fit_to_drawing().unwrap();
+    assert!(tree
+        .view_box
+        .rect
+        .fuzzy_eq(&usvg::Rect::new(45.0, 55.0, 50.0, 40.0).unwrap()));
+    assert!(tree.size.fuzzy_eq(&usvg::Size::new(100.0, 80.0).unwrap()));
+}
+
+#[cfg(feature = "vector-drawable")]
+#[test]
+fn vector_drawable() {
+    let opt = usvg::Options::default();
+    let tree = usvg::Tree::from_str(
+        "<svg width='48' height='48' viewBox='0 0 24 24' xmlns='http://www.w3.org/2000/svg'>
+            <linearGradient id='lg1'>
+                <stop offset='0' stop-color='red'/>
+                <stop offset='1' stop-color='blue' stop-opacity='0.5'/>
+            </linearGradient>
+            <rect x='2' y='2' width='10' height='10' fill='url(#lg1)' transform='rotate(90)'/>
+            <path d='M 0 0 L 10 10' stroke='#FF8000' stroke-width='2' stroke-linecap='round'/>
+        </svg>",
+        &opt.to_ref(),
+    )
+    .unwrap();
+
+    let xml_opt = usvg::XmlOptions {
+        id_prefix: None,
+        writer_opts: xmlwriter::Options {
+            indent: xmlwriter::Indent::None,
+            ..xmlwriter::Options::default()
+        },
+    };
+    let result = tree.to_vector_drawable(&xml_opt);
+    assert_eq!(
+        result.data,
+        "<vector xmlns:android=\"http://schemas.android.com/apk/res/android\" \
+            xmlns:aapt=\"http://schemas.android.com/aapt\" \
+            android:width=\"48dp\" android:height=\"48dp\" \
+            android:viewportWidth=\"48\" android:viewportHeight=\"48\">\
+        <group android:scaleX=\"2\" android:scaleY=\"2\">\
+        <group android:rotation=\"90\">\
+        <path android:pathData=\"M 2 2 L 12 2 L 12 12 L 2 12 Z\">\
+        <aapt:attr name=\"android:fillColor\">\
+        <gradient android:type=\"linear\" android:startX=\"2\" android:startY=\"2\" \
+            android:endX=\"12\" android:endY=\"2\">\
+        <item android:offset=\"0\" android:color=\"#FF0000\"/>\
+        <item android:offset=\"1\" android:color=\"#800000FF\"/>\
+        </gradient>\
+        </aapt:attr>\
+        </path>\
+        </group>\
+        <path android:pathData=\"M 0 0 L 10 10\" android:fillColor=\"#000000\" \
+            android:strokeColor=\"#FF8000\" \
+            android:strokeWidth=\"2\" android:strokeLineCap=\"round\"/>\
+        </group>\
+        </vector>"
+    );
+    assert!(result.unsupported.is_empty());
+}
+
+#[cfg(feature = "vector-drawable")]
+#[test]
+fn vector_drawable_unsupported() {
+    let opt = usvg::Options::default();
+    let tree = usvg::Tree::from_str(
+        "<svg viewBox='0 0 100 100' xmlns='http://www.w3.org/2000/svg'>
+            <filter id='filter1'><feGaussianBlur stdDeviation='2'/></filter>
+            <mask id='mask1'><rect width='50' height='50' fill='white'/></mask>
+            <pattern id='patt1' width='10' height='10' patternUnits='userSpaceOnUse'>
+                <rect width='5' height='5'/>
+            </pattern>
+            <rect width='10' height='10' filter='url(#filter1)'/>
+            <rect width='10' height='10' mask='url(#mask1)'/>
+            <rect width='10' height='10' fill='url(#patt1)'/>
+        </svg>",
+        &opt.to_ref(),
+    )
+    .unwrap();
+
+    let xml_opt = usvg::XmlOptions {
+        id_prefix: None,
+        writer_opts: xmlwriter::Options {
+            indent: xmlwriter::Indent::None,
+            ..xmlwriter::Options::default()
+        },
+    };
+    let result = tree.to_vector_drawable(&xml_opt);
+    let kinds: Vec<_> = result
+        .unsupported
+        .iter()
+        .map(|u| (u.id.as_str(), u.kind))
+        .collect();
+    assert_eq!(
+        kinds,
+        [
+            ("filter1", usvg::UnsupportedKind::Filter),
+            ("mask1", usvg::UnsupportedKind::Mask),
+            ("patt1", usvg::UnsupportedKind::Pattern),
+        ]
+    );
+}
+
+#[cfg(feature = "xaml")]
+#[test]
+fn xaml() {
+    let opt = usvg::Options::default();
+    let tree = usvg::Tree::from_str(
+        "<svg viewBox='0 0 100 100' xmlns='http://www.w3.org/2000/svg'>
+            <clipPath id='clip1'><rect width='50' height='50'/></clipPath>
+            <g id='g-1' opacity='0.5' clip-path='url(#clip1)'>
+                <rect width='60' height='60' fill='#00FF00' fill-opacity='0.5' fill-rule='evenodd'
+                      stroke='black' stroke-width='2' stroke-dasharray='4 2'
+                      transform='skewX(10)'/>
+            </g>
+        </svg>",
+        &opt.to_ref(),
+    )
+    .unwrap();
+
+    let xml_opt = usvg::XmlOptions {
+        id_prefix: None,
+        writer_opts: xmlwriter::Options {
+            indent: xmlwriter::Indent::None,
+            ..xmlwriter::Options::default()
+        },
+    };
+    let result = tree.to_xaml(&xml_opt);
+    assert_eq!(
+        result.data,
+        "<Canvas xmlns=\"http://schemas.microsoft.com/winfx/2006/xaml/presentation\" \
+            xmlns:x=\"http://schemas.microsoft.com/winfx/2006/xaml\" \
+            Width=\"100\" Height=\"100\" ClipToBounds=\"True\">\
+        <Canvas x:Name=\"g_1\" Opacity=\"0.5\" Clip=\"F1 M 0,0 L 50,0 L 50,50 L 0,50 Z\">\
+        <Path RenderTransform=\"1,0,0.17632698,1,0,0\" \
+            Data=\"F0 M 0,0 L 60,0 L 60,60 L 0,60 Z\" Fill=\"#8000FF00\" Stroke=\"#000000\" \
+            StrokeThickness=\"2\" StrokeMiterLimit=\"8\" StrokeDashArray=\"2 1\"/>\
+        </Canvas>\
+        </Canvas>"
+    );
+    assert!(result.unsupported.is_empty());
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json() {
+    let opt = usvg::Options::default();
+    let tree = usvg::Tree::from_str(
+        "<svg width='100' height='50' xmlns='http://www.w3.org/2000/svg'>
+            <linearGradient id='lg1'>
+                <stop offset='0' stop-color='red'/>
+                <stop offset='1' stop-color='blue'/>
+            </linearGradient>
+            <clipPath id='clip1'>
+                <rect width='40' height='40'/>
+            </clipPath>
+            <g clip-path='url(#clip1)'>
+                <rect id='r\"1' width='50' height='50' fill='url(#lg1)' stroke='blue'/>
+            </g>
+        </svg>",
+        &opt.to_ref(),
+    )
+    .unwrap();
+
+    let xml_opt = usvg::XmlOptions {
+        id_prefix: None,
+        writer_opts: xmlwriter::Options {
+            indent: xmlwriter::Indent::None,
+            ..xmlwriter::Options::default()
+        },
+    };
+    assert_eq!(
+        tree.to_json(&xml_opt),
+        "{\"width\":100,\"height\":50,\
+        \"view_box\":{\"rect\":[0,0,100,50],\"aspect\":\"xMidYMid\"},\
+        \"root\":{\"type\":\"group\",\"id\":\"\",\"transform\":[1,0,0,1,0,0],\"opacity\":1,\
+            \"clip_path\":null,\"mask\":null,\"filters\":[],\"filter_fill\":null,\
+            \"filter_stroke\":null,\"enable_background\":null,\"bbox\":[-0.5,-0.5,51,51],\
+            \"children\":[\
+            {\"type\":\"group\",\"id\":\"\",\"transform\":[1,0,0,1,0,0],\"opacity\":1,\
+                \"clip_path\":\"clip1\",\"mask\":null,\"filters\":[],\"filter_fill\":null,\
+                \"filter_stroke\":null,\"enable_background\":null,\"bbox\":[-0.5,-0.5,51,51],\
+                \"children\":[\
+                {\"type\":\"path\",\"id\":\"r\\\"1\",\"transform\":[1,0,0,1,0,0],\
+                    \"visibility\":\"visible\",\
+                    \"fill\":{\"paint\":{\"type\":\"linear_gradient\",\"id\":\"lg1\"},\
+                        \"opacity\":1,\"rule\":\"nonzero\"},\
+                    \"stroke\":{\"paint\":{\"type\":\"color\",\"color\":\"#0000ff\"},\
+                        \"opacity\":1,\"width\":1,\"linecap\":\"butt\",\"linejoin\":\"miter\",\
+                        \"miterlimit\":4,\"dasharray\":null,\"dashoffset\":0},\
+                    \"paint_order\":\"fill stroke\",\"rendering_mode\":\"geometricPrecision\",\
+                    \"text_bbox\":null,\"data\":\"M 0 0 L 50 0 L 50 50 L 0 50 Z\",\
+                    \"bbox\":[-0.5,-0.5,51,51]}]}]},\
+        \"paint_servers\":[{\"type\":\"linear_gradient\",\"id\":\"lg1\",\
+            \"x1\":0,\"y1\":0,\"x2\":1,\"y2\":0,\"units\":\"objectBoundingBox\",\
+            \"transform\":[1,0,0,1,0,0],\"spread_method\":\"pad\",\
+            \"stops\":[{\"offset\":0,\"color\":\"#ff0000\",\"opacity\":1},\
+                {\"offset\":1,\"color\":\"#0000ff\",\"opacity\":1}]}],\
+        \"clip_paths\":[{\"id\":\"clip1\",\"units\":\"userSpaceOnUse\",\
+            \"transform\":[1,0,0,1,0,0],\"clip_path\":null,\
+            \"root\":{\"type\":\"group\",\"id\":\"\",\"transform\":[1,0,0,1,0,0],\"opacity\":1,\
+                \"clip_path\":null,\"mask\":null,\"filters\":[],\"filter_fill\":null,\
+                \"filter_stroke\":null,\"enable_background\":null,\"bbox\":[0,0,40,40],\
+                \"children\":[\
+                {\"type\":\"path\",\"id\":\"\",\"transform\":[1,0,0,1,0,0],\
+                    \"visibility\":\"visible\",\
+                    \"fill\":{\"paint\":{\"type\":\"color\",\"color\":\"#000000\"},\
+                        \"opacity\":1,\"rule\":\"nonzero\"},\
+                    \"stroke\":null,\"paint_order\":\"fill stroke\",\
+                    \"rendering_mode\":\"geometricPrecision\",\"text_bbox\":null,\
+                    \"data\":\"M 0 0 L 40 0 L 40 40 L 0 40 Z\",\"bbox\":[0,0,40,40]}]}}],\
+        \"masks\":[],\"filters\":[]}"
+    );
+}
+
+#[cfg(feature = "binary")]
+#[test]
+fn binary() {
+    let opt = usvg::Options::default();
+    let tree = usvg::Tree::from_str(
+        "<svg width='100' height='50' xmlns='http://www.w3.org/2000/svg'>
+            <linearGradient id='lg1'>
+                <stop offset='0' stop-color='red'/>
+                <stop offset='1' stop-color='blue'/>
+            </linearGradient>
+            <clipPath id='clip1'>
+                <rect width='40' height='40'/>
+            </clipPath>
+            <filter id='filter1'>
+                <feGaussianBlur stdDeviation='2'/>
+            </filter>
+            <g clip-path='url(#clip1)' filter='url(#filter1)'>
+                <rect width='50' height='50' fill='url(#lg1)' stroke='url(#lg1)'/>
+            </g>
+        </svg>",
+        &opt.to_ref(),
+    )
+    .unwrap();
+
+    let data = tree.to_binary();
+    let tree2 = usvg::Tree::from_binary(&data).unwrap();
+    assert_eq!(
+        tree.to_string(&usvg::XmlOptions::default()),
+        tree2.to_string(&usvg::XmlOptions::default())
+    );
+
+    // Make sure that shared defs are still shared.
+    assert_eq!(data, tree2.to_binary());
+
+    // Truncated data must be rejected.
+    for len in 0..data.len() {
+        assert!(usvg::Tree::from_binary(&data[..len]).is_err());
+    }
+
+    // Format version.
+    let mut stale = data.clone();
+    stale[4] = stale[4].wrapping_add(1);
+    assert!(matches!(
+        usvg::Tree::from_binary(&stale),
+        Err(usvg::Error::BinaryVersionMismatch)
+    ));
+}
+
+fn first_path_data(tree: &usvg::Tree) -> String {
+    for node in tree.root.descendants() {
+        if let usvg::NodeKind::Path(ref path) = *node.borrow() {
+            return path
+                .data
+                .segments()
+                .map(|seg| format!("{:?}", seg))
+                .collect();
+        }
+    }
+
+    String::new()
+}
+
+fn text_svg(family: &str, text: &str) -> String {
+    format!(
+        "<svg viewBox='0 0 100 100' xmlns='http://www.w3.org/2000/svg'>
+            <text x='10' y='50' font-family='{}' font-size='20'>{}</text>
+        </svg>",
+        family, text
+    )
+}
+
+#[test]
+fn font_fallback_script_families() {
+    let mut opt = usvg::Options::default();
+    opt.fontdb.load_fonts_dir("../tests/fonts");
+    let expected = usvg::Tree::from_str(&text_svg("Noto Mono", "Ж"), &opt.to_ref()).unwrap();
+
+    opt.font_fallback
+        .script_families
+        .insert("Cyrl".to_string(), vec!["Noto Mono".to_string()]);
+    let tree = usvg::Tree::from_str(&text_svg("Yellowtail", "Ж"), &opt.to_ref()).unwrap();
+
+    assert!(!first_path_data(&tree).is_empty());
+    assert_eq!(first_path_data(&tree), first_path_data(&expected));
+}
+
+#[test]
+fn font_fallback_select() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let mut opt = usvg::Options::default();
+    opt.fontdb.load_fonts_dir("../tests/fonts");
+    let expected = usvg::Tree::from_str(&text_svg("Noto Serif", "Ж"), &opt.to_ref()).unwrap();
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let calls2 = calls.clone();
+    opt.font_fallback.select = Some(Box::new(move |c, base_face, opt| {
+        assert_eq!(c, 'Ж');
+        assert_eq!(base_face.family, "Yellowtail");
+        calls2.fetch_add(1, Ordering::SeqCst);
+        opt.fontdb
+            .faces()
+            .iter()
+            .find(|face| face.family == "Noto Serif")
+            .map(|face| face.id)
+    }));
+
+    // A decision must be made only once per character.
+    let tree = usvg::Tree::from_str(
+        "<svg viewBox='0 0 100 100' xmlns='http://www.w3.org/2000/svg'>
+            <text x='10' y='50' font-family='Yellowtail' font-size='20'>Ж</text>
+            <text x='10' y='80' font-family='Yellowtail' font-size='20'>Ж</text>
+        </svg>",
+        &opt.to_ref(),
+    )
+    .unwrap();
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert_eq!(first_path_data(&tree), first_path_data(&expected));
+}
+
+#[test]
+fn font_face_rule() {
+    let mut opt = usvg::Options::default();
+    opt.fontdb
+        .load_font_file("../tests/fonts/NotoSerif-Regular.ttf")
+        .unwrap();
+    let expected = usvg::Tree::from_str(&text_svg("Noto Serif", "Text"), &opt.to_ref()).unwrap();
+
+    let mut opt = usvg::Options::default();
+    opt.resources_dir = Some(std::path::PathBuf::from("../tests/fonts"));
+    opt.fontdb
+        .load_font_file("../tests/fonts/NotoSans-Regular.ttf")
+        .unwrap();
+    let tree = usvg::Tree::from_str(
+        "<svg viewBox='0 0 100 100' xmlns='http://www.w3.org/2000/svg'>
+            <style>
+                /* @font-face { font-family: 'Web Font'; src: local('Noto Sans') } */
+                @FONT-FACE {
+                    font-family: 'Web Font';
+                    src: url('missing.ttf') format('truetype'), url(NotoSerif-Regular.ttf);
+                }
+                text { font-size: 20px }
+            </style>
+            <text x='10' y='50' font-family='Web Font'>Text</text>
+        </svg>",
+        &opt.to_ref(),
+    )
+    .unwrap();
+
+    assert!(!first_path_data(&tree).is_empty());
+    assert_eq!(first_path_data(&tree), first_path_data(&expected));
+
+    // Fonts must not leak into the database.
+    assert_eq!(opt.fontdb.len(), 1);
+}
+
+/// Returns sfnt flavor and tables.
+fn sfnt_tables(data: &[u8]) -> (&[u8], Vec<(&[u8], &[u8])>) {
+    let be_u32 = |offset: usize| {
+        u32::from_be_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ]) as usize
+    };
+
+    let num_tables = u16::from_be_bytes([data[4], data[5]]) as usize;
+    let tables = (0..num_tables)
+        .map(|i| {
+            let record = 12 + i * 16;
+            let offset = be_u32(record + 8);
+            let len = be_u32(record + 12);
+            (&data[record..record + 4], &data[offset..offset + len])
+        })
+        .collect();
+
+    (&data[..4], tables)
+}
+
+fn to_woff(data: &[u8]) -> Vec<u8> {
+    use std::io::Write;
+
+    let (flavor, tables) = sfnt_tables(data);
+
+    let mut directory = Vec::new();
+    let mut table_data = Vec::new();
+    let data_offset = 44 + 20 * tables.len();
+    for (tag, table) in &tables {
+        let mut encoder =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(table).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let stored: &[u8] = if compressed.len() < table.len() {
+            &compressed
+        } else {
+            table
+        };
+
+        directory.extend_from_slice(tag);
+        directory.extend_from_slice(&((data_offset + table_data.len()) as u32).to_be_bytes());
+        directory.extend_from_slice(&(stored.len() as u32).to_be_bytes());
+        directory.extend_from_slice(&(table.len() as u32).to_be_bytes());
+        directory.extend_from_slice(&[0; 4]); // checksum
+        table_data.extend_from_slice(stored);
+        while table_data.len() % 4 != 0 {
+            table_data.push(0);
+        }
+    }
+
+    let mut woff = Vec::new();
+    woff.extend_from_slice(b"wOFF");
+    woff.extend_from_slice(flavor);
+    woff.extend_from_slice(&((data_offset + table_data.len()) as u32).to_be_bytes());
+    woff.extend_from_slice(&(tables.len() as u16).to_be_bytes());
+    woff.extend_from_slice(&[0; 2]); // reserved
+    woff.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    woff.extend_from_slice(&[0; 24]); // version, metadata and private data
+    woff.extend_from_slice(&directory);
+    woff.extend_from_slice(&table_data);
+    woff
+}
+
+/// Creates a WOFF2 font without table transforms.
+fn to_woff2(data: &[u8]) -> Vec<u8> {
+    let (flavor, tables) = sfnt_tables(data);
+
+    let mut directory = Vec::new();
+    let mut stream = Vec::new();
+    for (tag, table) in &tables {
+        // An arbitrary tag with a null transform.
+        let version = if *tag == b"glyf" || *tag == b"loca" {
+            3
+        } else {
+            0
+        };
+        directory.push(version << 6 | 0x3F);
+        directory.extend_from_slice(tag);
+
+        // UIntBase128
+        let mut len = table.len() as u32;
+        let mut bytes = vec![(len & 0x7F) as u8];
+        len >>= 7;
+        while len != 0 {
+            bytes.push((len & 0x7F) as u8 | 0x80);
+            len >>= 7;
+        }
+        bytes.reverse();
+        directory.extend_from_slice(&bytes);
+
+        stream.extend_from_slice(table);
+    }
+
+    // A brotli stream with a single uncompressed meta-block.
+    let mlen = stream.len() as u32 - 1;
+    let nibbles = if mlen < 1 << 16 {
+        4
+    } else if mlen < 1 << 20 {
+        5
+    } else {
+        6
+    };
+    let header = ((nibbles - 4) << 2) | (mlen << 4) | (1 << (4 + nibbles * 4));
+    let header_len = (4 + nibbles * 4 + 1 + 7) / 8;
+    let mut compressed = header.to_le_bytes()[..header_len as usize].to_vec();
+    compressed.extend_from_slice(&stream);
+    compressed.push(0x03); // the last empty meta-block
+
+    let mut woff = Vec::new();
+    woff.extend_from_slice(b"wOF2");
+    woff.extend_from_slice(flavor);
+    woff.extend_from_slice(&((48 + directory.len() + compressed.len()) as u32).to_be_bytes());
+    woff.extend_from_slice(&(tables.len() as u16).to_be_bytes());
+    woff.extend_from_slice(&[0; 2]); // reserved
+    woff.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    woff.extend_from_slice(&(compressed.len() as u32).to_be_bytes());
+    woff.extend_from_slice(&[0; 24]); // version, metadata and private data
+    woff.extend_from_slice(&directory);
+    woff.extend_from_slice(&compressed);
+    woff
+}
+
+#[test]
+fn web_fonts_loading() {
+    use usvg::WebFontsExt;
+
+    let data = std::fs::read("../tests/fonts/NotoSerif-Regular.ttf").unwrap();
+
+    let mut opt = usvg::Options::default();
+    opt.fontdb.load_font_data(data.clone());
+    let expected = usvg::Tree::from_str(&text_svg("Noto Serif", "Text"), &opt.to_ref()).unwrap();
+
+    for web_font in &[to_woff(&data), to_woff2(&data)] {
+        let mut opt = usvg::Options::default();
+        opt.fontdb.load_web_font_data(web_font.clone());
+        assert_eq!(opt.fontdb.len(), 1);
+
+        let tree = usvg::Tree::from_str(&text_svg("Noto Serif", "Text"), &opt.to_ref()).unwrap();
+        assert!(!first_path_data(&tree).is_empty());
+        assert_eq!(first_path_data(&tree), first_path_data(&expected));
+    }
+
+    // Non-web fonts are passed as is.
+    assert_eq!(usvg::decode_web_font(data.clone()), Some(data.clone()));
+
+    // Malformed web fonts are rejected.
+    let mut woff = to_woff(&data);
+    woff.truncate(woff.len() - 1);
+    assert_eq!(usvg::decode_web_font(woff), None);
+}
+
+#[test]
+fn web_fonts_data_url() {
+    let data = std::fs::read("../tests/fonts/Yellowtail-Regular.ttf").unwrap();
+
+    let mut opt = usvg::Options::default();
+    opt.fontdb.load_font_data(data.clone());
+    let expected = usvg::Tree::from_str(&text_svg("Yellowtail", "Text"), &opt.to_ref()).unwrap();
+
+    let mut opt = usvg::Options::default();
+    opt.fontdb
+        .load_font_file("../tests/fonts/NotoSans-Regular.ttf")
+        .unwrap();
+    let svg = format!(
+        "<svg viewBox='0 0 100 100' xmlns='http://www.w3.org/2000/svg'>
+            <style>
+                @font-face {{
+                    font-family: 'Web Font';
+                    src: url(data:font/woff2;base64,{}) format('woff2');
+                }}
+                text {{ font-size: 20px }}
+            </style>
+            <text x='10' y='50' font-family='Web Font'>Text</text>
+        </svg>",
+        base64::encode(to_woff2(&data))
+    );
+    let tree = usvg::Tree::from_str(&svg, &opt.to_ref()).unwrap();
+
+    assert!(!first_path_data(&tree).is_empty());
+    assert_eq!(first_path_data(&tree), first_path_data(&expected));
+    assert_eq!(opt.fontdb.len(), 1);
+}
+
+#[test]
+fn web_fonts_without_tables() {
+    use usvg::WebFontsExt;
+
+    let mut woff = Vec::new();
+    woff.extend_from_slice(b"wOFF");
+    woff.extend_from_slice(&0x00010000u32.to_be_bytes()); // flavor
+    woff.extend_from_slice(&44u32.to_be_bytes()); // length
+    woff.extend_from_slice(&0u16.to_be_bytes()); // numTables
+    woff.extend_from_slice(&0u16.to_be_bytes()); // reserved
+    woff.extend_from_slice(&12u32.to_be_bytes()); // totalSfntSize
+    woff.resize(44, 0); // version, metadata and private data
+    assert_eq!(usvg::decode_web_font(woff.clone()), None);
+
+    let mut opt = usvg::Options::default();
+    opt.fontdb.load_web_font_data(woff);
+    assert_eq!(opt.fontdb.len(), 0);
+}
+
+/// Adds tables to an sfnt font.
+fn add_font_tables(data: &[u8], new_tables: &[(&[u8], Vec<u8>)]) -> Vec<u8> {
+    let (flavor, mut tables) = sfnt_tables(data);
+    for (tag, table) in new_tables {
+        tables.push((tag, table));
+    }
+    tables.sort_by_key(|(tag, _)| tag.to_vec());
+
+    let mut font = flavor.to_vec();
+    font.extend_from_slice(&(tables.len() as u16).to_be_bytes());
+    font.extend_from_slice(&[0; 6]); // search params aren't used by the parser
+
+    let mut offset = 12 + 16 * tables.len();
+    for (tag, table) in &tables {
+        font.extend_from_slice(tag);
+        font.extend_from_slice(&[0; 4]); // checksum
+        font.extend_from_slice(&(offset as u32).to_be_bytes());
+        font.extend_from_slice(&(table.len() as u32).to_be_bytes());
+        offset += (table.len() + 3) & !3;
+    }
+
+    for (_, table) in &tables {
+        font.extend_from_slice(table);
+        while font.len() % 4 != 0 {
+            font.push(0);
+        }
+    }
+
+    font
+}
+
+#[test]
+fn color_glyphs() {
+    use rustybuzz::ttf_parser;
+
+    let data = std::fs::read("../tests/fonts/NotoSans-Regular.ttf").unwrap();
+    let face = ttf_parser::Face::parse(&data, 0).unwrap();
+    let glyph = |c| face.glyph_index(c).unwrap().0.to_be_bytes();
+    let be16 = |n: u16| n.to_be_bytes();
+    let be32 = |n: u32| n.to_be_bytes();
+
+    // 'A' uses COLR v0 layers, 'D' uses a COLR v1 gradient.
+    let mut colr = Vec::new();
+    colr.extend_from_slice(&be16(1)); // version
+    colr.extend_from_slice(&be16(1)); // base glyph records
+    colr.extend_from_slice(&be32(34));
+    colr.extend_from_slice(&be32(40)); // layer records
+    colr.extend_from_slice(&be16(2));
+    colr.extend_from_slice(&be32(48)); // base glyph list
+    colr.extend_from_slice(&[0; 16]); // layer list, clip list and variations
+    colr.extend_from_slice(&glyph('A'));
+    colr.extend_from_slice(&be16(0));
+    colr.extend_from_slice(&be16(2));
+    colr.extend_from_slice(&glyph('A'));
+    colr.extend_from_slice(&be16(0)); // red
+    colr.extend_from_slice(&glyph('B'));
+    colr.extend_from_slice(&be16(0xFFFF)); // foreground
+    colr.extend_from_slice(&be32(1));
+    colr.extend_from_slice(&glyph('D'));
+    colr.extend_from_slice(&be32(10));
+    colr.extend_from_slice(&[10, 0, 0, 6]); // PaintGlyph
+    colr.extend_from_slice(&glyph('D'));
+    colr.extend_from_slice(&[4, 0, 0, 16]); // PaintLinearGradient
+    for n in &[0i16, 0, 500, 0, 0, 500] {
+        colr.extend_from_slice(&n.to_be_bytes());
+    }
+    colr.extend_from_slice(&[0, 0, 2]); // color line
+    colr.extend_from_slice(&[0, 0, 0, 0, 0x40, 0]);
+    colr.extend_from_slice(&[0x40, 0, 0, 1, 0x40, 0]);
+
+    let mut cpal = Vec::new();
+    cpal.extend_from_slice(&be16(0)); // version
+    cpal.extend_from_slice(&be16(2)); // palette entries
+    cpal.extend_from_slice(&be16(1)); // palettes
+    cpal.extend_from_slice(&be16(2)); // color records
+    cpal.extend_from_slice(&be32(14));
+    cpal.extend_from_slice(&be16(0));
+    cpal.extend_from_slice(&[0, 0, 255, 255]); // red
+    cpal.extend_from_slice(&[0, 255, 0, 255]); // green
+
+    // 'B' uses an sbix bitmap.
+    let png = std::fs::read("../tests/images/image.png").unwrap();
+    let num_glyphs = u32::from(face.number_of_glyphs());
+    let glyph_b = u32::from(face.glyph_index('B').unwrap().0);
+    let data_start = 4 + 4 * (num_glyphs + 1);
+    let mut sbix = Vec::new();
+    sbix.extend_from_slice(&be16(1)); // version
+    sbix.extend_from_slice(&be16(1)); // flags
+    sbix.extend_from_slice(&be32(1)); // strikes
+    sbix.extend_from_slice(&be32(12));
+    sbix.extend_from_slice(&be16(100)); // ppem
+    sbix.extend_from_slice(&be16(72)); // ppi
+    for i in 0..=num_glyphs {
+        let offset = if i <= glyph_b {
+            data_start
+        } else {
+            data_start + 8 + png.len() as u32
+        };
+        sbix.extend_from_slice(&be32(offset));
+    }
+    sbix.extend_from_slice(&[0; 4]); // origin
+    sbix.extend_from_slice(b"png ");
+    sbix.extend_from_slice(&png);
+
+    // 'C' uses an SVG document, which contains multiple glyphs.
+    let doc = format!(
+        "<svg xmlns='http://www.w3.org/2000/svg'>
+            <rect id='glyph{}' y='-500' width='500' height='500' fill='green'/>
+            <rect id='glyph1' width='10' height='10'/>
+        </svg>",
+        face.glyph_index('C').unwrap().0
+    );
+    let mut svg = Vec::new();
+    svg.extend_from_slice(&be16(0)); // version
+    svg.extend_from_slice(&be32(10));
+    svg.extend_from_slice(&be32(0)); // reserved
+    svg.extend_from_slice(&be16(1));
+    svg.extend_from_slice(&glyph('C'));
+    svg.extend_from_slice(&glyph('C'));
+    svg.extend_from_slice(&be32(14));
+    svg.extend_from_slice(&be32(doc.len() as u32));
+    svg.extend_from_slice(doc.as_bytes());
+
+    let font = add_font_tables(
+        &data,
+        &[
+            (b"COLR", colr),
+            (b"CPAL", cpal),
+            (b"sbix", sbix),
+            (b"SVG ", svg),
+        ],
+    );
+
+    let mut opt = usvg::Options::default();
+    opt.fontdb.load_font_data(font);
+    let tree = usvg::Tree::from_str(
+        "<svg viewBox='0 0 100 100' xmlns='http://www.w3.org/2000/svg'>
+            <text x='10' y='50' font-family='Noto Sans' font-size='20' fill='blue'>ABCD</text>
+        </svg>",
+        &opt.to_ref(),
+    )
+    .unwrap();
+
+    let mut fills = Vec::new();
+    let mut images = Vec::new();
+    for node in tree.root.descendants() {
+        match *node.borrow() {
+            usvg::NodeKind::Path(ref path) => {
+                fills.push(match path.fill.as_ref().unwrap().paint {
+                    usvg::Paint::Color(c) => format!("{:?}", (c.red, c.green, c.blue)),
+                    usvg::Paint::LinearGradient(ref lg) => format!("{:?}", lg.stops.len()),
+                    _ => String::new(),
+                });
+            }
+            usvg::NodeKind::Image(ref image) => images.push(image.kind.clone()),
+            _ => {}
+        }
+    }
+
+    assert_eq!(fills, &["(255, 0, 0)", "(0, 0, 255)", "2"]);
+    assert_eq!(images.len(), 2);
+    assert!(matches!(images[0], usvg::ImageKind::PNG(_)));
+    match images[1] {
+        usvg::ImageKind::SVG(ref tree) => assert_eq!(
+            tree.root
+                .descendants()
+                .filter(|n| matches!(*n.borrow(), usvg::NodeKind::Path(_)))
+                .count(),
+            1
+        ),
+        _ => panic!("an SVG glyph expected"),
+    }
+}
+
+#[test]
+fn color_glyphs_limits() {
+    use rustybuzz::ttf_parser;
+
+    let data = std::fs::read("../tests/fonts/NotoSans-Regular.ttf").unwrap();
+    let face = ttf_parser::Face::parse(&data, 0).unwrap();
+    let glyph = |c| face.glyph_index(c).unwrap().0.to_be_bytes();
+    let be16 = |n: u16| n.to_be_bytes();
+    let be32 = |n: u32| n.to_be_bytes();
+
+    // 'D' uses layers that reference 'D' itself.
+    // 'E' uses a chain of 40 composites, where each one references the next one twice.
+    let composites = 40;
+    let layer_list = 56 + 8 * composites + 11;
+    let mut colr = Vec::new();
+    colr.extend_from_slice(&be16(1)); // version
+    colr.extend_from_slice(&be16(0)); // base glyph records
+    colr.extend_from_slice(&be32(0));
+    colr.extend_from_slice(&be32(0)); // layer records
+    colr.extend_from_slice(&be16(0));
+    colr.extend_from_slice(&be32(34)); // base glyph list
+    colr.extend_from_slice(&be32(layer_list)); // layer list
+    colr.extend_from_slice(&[0; 12]); // clip list and variations
+    colr.extend_from_slice(&be32(2));
+    colr.extend_from_slice(&glyph('D'));
+    colr.extend_from_slice(&be32(16));
+    colr.extend_from_slice(&glyph('E'));
+    colr.extend_from_slice(&be32(22));
+    colr.extend_from_slice(&[1, 2, 0, 0, 0, 0]); // PaintColrLayers
+    for _ in 0..composites {
+        colr.extend_from_slice(&[32, 0, 0, 8, 3, 0, 0, 8]); // PaintComposite
+    }
+    colr.extend_from_slice(&[10, 0, 0, 6]); // PaintGlyph
+    colr.extend_from_slice(&glyph('E'));
+    colr.extend_from_slice(&[2, 0xFF, 0xFF, 0x40, 0]); // PaintSolid
+    colr.extend_from_slice(&be32(2));
+    colr.extend_from_slice(&be32(12));
+    colr.extend_from_slice(&be32(12));
+    colr.push(11); // PaintColrGlyph
+    colr.extend_from_slice(&glyph('D'));
+
+    let mut opt = usvg::Options::default();
+    opt.fontdb.load_font_data(data.clone());
+    let expected = usvg::Tree::from_str(&text_svg("Noto Sans", "DE"), &opt.to_ref()).unwrap();
+
+    // Both glyphs fallback to outlines.
+    let mut opt = usvg::Options::default();
+    opt.fontdb
+        .load_font_data(add_font_tables(&data, &[(b"COLR", colr)]));
+    let tree = usvg::Tree::from_str(&text_svg("Noto Sans", "DE"), &opt.to_ref()).unwrap();
+    assert!(!first_path_data(&tree).is_empty());
+    assert_eq!(first_path_data(&tree), first_path_data(&expected));
+}
+
+#[test]
+fn font_variations() {
+    use rustybuzz::ttf_parser;
+
+    let data = std::fs::read("../tests/fonts/NotoSans-Regular.ttf").unwrap();
+    let face = ttf_parser::Face::parse(&data, 0).unwrap();
+    let glyph_id = face.glyph_index('I').unwrap().0;
+    let table = |tag| {
+        face.raw_face()
+            .table(ttf_parser::Tag::from_bytes(tag))
+            .unwrap()
+    };
+    let be16 = |n: u16| n.to_be_bytes();
+    let be32 = |n: u32| n.to_be_bytes();
+
+    // Get the number of points in the 'I' glyph.
+    let loca = table(b"loca");
+    let offset = if table(b"head")[51] == 0 {
+        let i = usize::from(glyph_id) * 2;
+        usize::from(u16::from_be_bytes([loca[i], loca[i + 1]])) * 2
+    } else {
+        let i = usize::from(glyph_id) * 4;
+        u32::from_be_bytes([loca[i], loca[i + 1], loca[i + 2], loca[i + 3]]) as usize
+    };
+    let glyf = &table(b"glyf")[offset..];
+    let contours = usize::from(u16::from_be_bytes([glyf[0], glyf[1]]));
+    let last_point = 10 + (contours - 1) * 2;
+    let points = u16::from_be_bytes([glyf[last_point], glyf[last_point + 1]]) + 1;
+    // Including phantom points.
+    let points = usize::from(points) + 4;
+
+    // A `wght` axis in the 100..400..900 range.
+    let mut fvar = Vec::new();
+    fvar.extend_from_slice(&be16(1)); // version
+    fvar.extend_from_slice(&be16(0));
+    fvar.extend_from_slice(&be16(16)); // axes offset
+    fvar.extend_from_slice(&be16(2)); // reserved
+    fvar.extend_from_slice(&be16(1)); // axes
+    fvar.extend_from_slice(&be16(20)); // axis size
+    fvar.extend_from_slice(&be16(0)); // instances
+    fvar.extend_from_slice(&be16(8)); // instance size
+    fvar.extend_from_slice(b"wght");
+    fvar.extend_from_slice(&be32(100 << 16));
+    fvar.extend_from_slice(&be32(400 << 16));
+    fvar.extend_from_slice(&be32(900 << 16));
+    fvar.extend_from_slice(&be16(0)); // flags
+    fvar.extend_from_slice(&be16(256)); // name ID
+
+    // At `wght` 900, all 'I' points are moved by 50 units to the right.
+    let mut variation = Vec::new();
+    variation.extend_from_slice(&be16(0x8001)); // shared points, 1 tuple
+    variation.extend_from_slice(&be16(10)); // data offset
+    variation.extend_from_slice(&[0; 2]); // data size, patched later
+    variation.extend_from_slice(&be16(0x8000)); // embedded peak tuple
+    variation.extend_from_slice(&be16(0x4000)); // 1.0
+    variation.push(0); // all points
+    for chunk in (0..points).collect::<Vec<_>>().chunks(64) {
+        variation.push(chunk.len() as u8 - 1);
+        variation.extend(chunk.iter().map(|_| 50));
+    }
+    for chunk in (0..points).collect::<Vec<_>>().chunks(64) {
+        variation.push(0x80 | (chunk.len() as u8 - 1)); // zero deltas
+    }
+    let data_size = (variation.len() - 10) as u16;
+    variation[4..6].copy_from_slice(&be16(data_size));
+    if variation.len() % 2 != 0 {
+        variation.push(0);
+    }
+
+    let num_glyphs = face.number_of_glyphs();
+    let mut gvar = Vec::new();
+    gvar.extend_from_slice(&be16(1)); // version
+    gvar.extend_from_slice(&be16(0));
+    gvar.extend_from_slice(&be16(1)); // axes
+    gvar.extend_from_slice(&be16(0)); // shared tuples
+    gvar.extend_from_slice(&be32(0));
+    gvar.extend_from_slice(&be16(num_glyphs));
+    gvar.extend_from_slice(&be16(0)); // short offsets
+    gvar.extend_from_slice(&be32(20 + (u32::from(num_glyphs) + 1) * 2));
+    for i in 0..=num_glyphs {
+        let offset = if i <= glyph_id { 0 } else { variation.len() };
+        gvar.extend_from_slice(&be16(offset as u16 / 2));
+    }
+    gvar.extend_from_slice(&variation);
+
+    let font = add_font_tables(&data, &[(b"fvar", fvar), (b"gvar", gvar)]);
+
+    let mut opt = usvg::Options::default();
+    opt.fontdb.load_font_data(font);
+    let render = |attrs: &str| {
+        let svg = format!(
+            "<svg viewBox='0 0 100 100' xmlns='http://www.w3.org/2000/svg'>
+                <text x='10' y='50' font-family='Noto Sans' font-size='20' {}>I</text>
+            </svg>",
+            attrs
+        );
+        let tree = usvg::Tree::from_str(&svg, &opt.to_ref()).unwrap();
+        for node in tree.root.descendants() {
+            if let usvg::NodeKind::Path(ref path) = *node.borrow() {
+                return path.data.bbox().unwrap().x();
+            }
+        }
+
+        unreachable!()
+    };
+
+    let default = render("");
+    assert_ne!(default, render("font-weight='900'"));
+    assert_eq!(
+        render("font-weight='900'"),
+        render("style='font-variation-settings: \"wght\" 900'")
+    );
+    assert!((render("font-weight='650'") - default - 0.5).abs() < 0.01);
+    // `font-variation-settings` overrides `font-weight`.
+    assert_eq!(
+        default,
+        render("font-weight='900' font-variation-settings=\"'wght' 400\"")
+    );
+    // Unknown axes are ignored.
+    assert_eq!(default, render("font-variation-settings='\"wdth\" 50'"));
+}
+
+#[test]
+fn synthetic_bold_and_oblique() {
+    fn bbox(opt: &usvg::Options, attrs: &str) -> usvg::PathBbox {
+        let svg = format!(
+            "<svg viewBox='0 0 100 100' xmlns='http://www.w3.org/2000/svg'>
+                <text x='10' y='50' font-family='Noto Serif' font-size='24' {}>I</text>
+            </svg>",
+            attrs
+        );
+        let tree = usvg::Tree::from_str(&svg, &opt.to_ref()).unwrap();
+        for node in tree.root.descendants() {
+            if let usvg::NodeKind::Path(ref path) = *node.borrow() {
+                return path.data.bbox().unwrap();
+            }
+        }
+
+        unreachable!()
+    }
+
+    // Noto Serif has only a regular face.
+    let mut opt = usvg::Options::default();
+    opt.fontdb
+        .load_font_file("../tests/fonts/NotoSerif-Regular.ttf")
+        .unwrap();
+
+    let regular = bbox(&opt, "");
+
+    // Glyphs are 1/24 em wider.
+    let bold = bbox(&opt, "font-weight='bold'");
+    assert!((bold.width() - regular.width() - 1.0).abs() < 0.01);
+    assert!((bold.x() - regular.x() + 0.5).abs() < 0.01);
+
+    // The top is moved to the right, while the baseline stays in place.
+    let oblique = bbox(&opt, "font-style='oblique 45deg'");
+    assert!((oblique.width() - regular.width() - regular.height()).abs() < 0.01);
+    assert!((oblique.x() - regular.x()).abs() < 0.01);
+
+    opt.synthetic_bold = false;
+    opt.synthetic_oblique = false;
+    let bbox = bbox(&opt, "font-weight='bold' font-style='italic'");
+    assert_eq!((regular.x(), regular.width()), (bbox.x(), bbox.width()));
+}
+
+#[test]
+fn measure_text() {
+    let mut opt = usvg::Options::default();
+    opt.fontdb.load_fonts_dir("../tests/fonts");
+
+    let font = usvg::FontProperties {
+        family: "Noto Sans".to_string(),
+        size: 20.0,
+        ..usvg::FontProperties::default()
+    };
+    let metrics = usvg::measure_text("Text", &font, &opt.to_ref(), true).unwrap();
+    assert_eq!(metrics.clusters.len(), 4);
+    assert!(metrics.ascent > 0.0 && metrics.descent < 0.0);
+
+    let advance: f64 = metrics.clusters.iter().map(|c| c.advance).sum();
+    assert!((metrics.advance - advance).abs() < 1e-6);
+
+    // Matches the rendered text, which starts at 10x50.
+    let tree = usvg::Tree::from_str(&text_svg("Noto Sans", "Text"), &opt.to_ref()).unwrap();
+    let mut rendered = None;
+    for node in tree.root.descendants() {
+        if let usvg::NodeKind::Path(ref path) = *node.borrow() {
+            rendered = path.data.bbox();
+        }
+    }
+    let rendered = rendered.unwrap();
+    let bbox = metrics.bbox.unwrap();
+    assert!((bbox.x() + 10.0 - rendered.x()).abs() < 1e-6);
+    assert!((bbox.y() + 50.0 - rendered.y()).abs() < 1e-6);
+    assert!((bbox.width() - rendered.width()).abs() < 1e-6);
+    assert!((bbox.height() - rendered.height()).abs() < 1e-6);
+
+    let mut outlines = usvg::PathData::new();
+    for cluster in &metrics.clusters {
+        outlines.push_path(cluster.outline.as_ref().unwrap());
+    }
+    let outlines_bbox = outlines.bbox().unwrap();
+    assert_eq!(
+        (outlines_bbox.x(), outlines_bbox.width()),
+        (bbox.x(), bbox.width())
+    );
+
+    // Spacing is included, except after the last cluster.
+    let spaced_font = usvg::FontProperties {
+        letter_spacing: 2.0,
+        ..font.clone()
+    };
+    let spaced = usvg::measure_text("Text", &spaced_font, &opt.to_ref(), false).unwrap();
+    assert!((spaced.advance - metrics.advance - 6.0).abs() < 1e-6);
+    assert!(spaced.clusters.iter().all(|c| c.outline.is_none()));
+
+    // Spaces are preserved and byte indices point to the original string.
+    let metrics = usvg::measure_text("a\r\n é", &font, &opt.to_ref(), false).unwrap();
+    let indices: Vec<_> = metrics.clusters.iter().map(|c| c.byte_idx).collect();
+    assert_eq!(indices, vec![0, 1, 2, 3, 4]);
+
+    // Characters that are not allowed in XML do not break the measuring.
+    let metrics = usvg::measure_text("a\u{0}\u{8}b\u{FFFF}c", &font, &opt.to_ref(), false).unwrap();
+    let indices: Vec<_> = metrics.clusters.iter().map(|c| c.byte_idx).collect();
+    assert_eq!(indices, vec![0, 1, 2, 3, 4, 7]);
+
+    // Line metrics are available even for an empty string.
+    let metrics = usvg::measure_text("", &font, &opt.to_ref(), false).unwrap();
+    assert_eq!(metrics.advance, 0.0);
+    assert!(metrics.bbox.is_none() && metrics.height() > 0.0);
+
+    let invalid_size = usvg::FontProperties { size: 0.0, ..font };
+    assert!(usvg::measure_text("Text", &invalid_size, &opt.to_ref(), false).is_none());
+}